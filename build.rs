@@ -0,0 +1,30 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "ffi")]
+    generate_c_header();
+}
+
+/// Regenerate include/maschine3_hal.h from the `ffi` module
+#[cfg(feature = "ffi")]
+fn generate_c_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    // Only parse the ffi module so crate-internal constants stay out of the header
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("cbindgen.toml should be valid");
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/ffi.rs", crate_dir))
+        .generate();
+
+    match bindings {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/include/maschine3_hal.h", crate_dir));
+        }
+        // Don't fail the build over the header; the library itself is fine
+        Err(e) => println!("cargo:warning=Failed to generate C header: {}", e),
+    }
+}
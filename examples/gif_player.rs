@@ -0,0 +1,39 @@
+use maschine3_hal::{DisplayAnimator, MK3Error, MaschineMK3};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🎞️  Maschine MK3 GIF Player");
+
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("Usage: cargo run --example gif_player --features gif -- <file.gif>");
+            return Ok(());
+        }
+    };
+
+    let device = match MaschineMK3::new() {
+        Ok(device) => Arc::new(Mutex::new(device)),
+        Err(MK3Error::DeviceNotFound) => {
+            println!("❌ No Maschine MK3 found");
+            return Ok(());
+        }
+        Err(e) => {
+            println!("❌ Connection error: {}", e);
+            return Ok(());
+        }
+    };
+
+    let bytes = std::fs::read(&path)?;
+    let mut animator = DisplayAnimator::new(Arc::clone(&device));
+
+    // Same animation on both screens, looping
+    animator.play_gif(0, &bytes, true)?;
+    animator.play_gif(1, &bytes, true)?;
+
+    println!("▶️  Playing {} - press Ctrl+C to stop", path);
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,101 @@
+use maschine3_hal::{InputEvent, MK3Error, MaschineLEDColor, MaschineMK3, PadEventType};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Hits measured per mode
+const HITS: usize = 16;
+
+/// Measures how long a pad hit takes from being decoded on the monitoring
+/// thread to its LED lighting up (the USB write returning), first with the
+/// default settings and then in low-latency mode.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("⏱️  Maschine MK3 Latency Check");
+    println!("⚠️  Make sure to close any NI software first!\n");
+
+    let mut device = match MaschineMK3::new() {
+        Ok(device) => {
+            println!("✅ Connected: {}", device.device_info()?);
+            device
+        }
+        Err(MK3Error::DeviceNotFound) => {
+            println!("❌ No Maschine MK3 found. Check connection.");
+            return Ok(());
+        }
+        Err(e) => {
+            println!("❌ Connection error: {}", e);
+            return Ok(());
+        }
+    };
+
+    device.clear_all_leds()?;
+    let default = measure(&mut device)?;
+
+    device.enable_low_latency();
+    let low_latency = measure(&mut device)?;
+
+    println!("\n📊 Hit to LED, over {} hits:", HITS);
+    report("default", &default);
+    report("low latency", &low_latency);
+    Ok(())
+}
+
+fn measure(device: &mut MaschineMK3) -> Result<Vec<Duration>, MK3Error> {
+    let mode = if device.is_low_latency() {
+        "low latency"
+    } else {
+        "default"
+    };
+    println!("\n🥁 {} mode: hit any pad {} times", mode, HITS);
+
+    // Stamp each event on the monitoring thread as soon as it is decoded
+    let (sender, receiver) = mpsc::channel();
+    device.start_input_monitoring(move |event| {
+        let _ = sender.send((Instant::now(), event));
+    })?;
+
+    let mut samples = Vec::with_capacity(HITS);
+    while samples.len() < HITS {
+        let Ok((decoded, event)) = receiver.recv() else {
+            break;
+        };
+        if let InputEvent::PadEvent {
+            pad_number,
+            event_type,
+            ..
+        } = event
+        {
+            match event_type {
+                PadEventType::Hit => {
+                    device.set_pad_led(pad_number, MaschineLEDColor::white(true))?;
+                    samples.push(decoded.elapsed());
+                    print!("{} ", samples.len());
+                }
+                PadEventType::HitRelease | PadEventType::TouchRelease => {
+                    device.set_pad_led(pad_number, MaschineLEDColor::black())?;
+                }
+                _ => {}
+            }
+        }
+    }
+    println!();
+
+    device.stop_input_monitoring()?;
+    device.clear_all_leds()?;
+    Ok(samples)
+}
+
+fn report(mode: &str, samples: &[Duration]) {
+    if samples.is_empty() {
+        return;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+    println!(
+        "   {:<12} mean {:>8.3}ms   median {:>8.3}ms   worst {:>8.3}ms",
+        mode,
+        mean.as_secs_f64() * 1000.0,
+        sorted[sorted.len() / 2].as_secs_f64() * 1000.0,
+        sorted[sorted.len() - 1].as_secs_f64() * 1000.0
+    );
+}
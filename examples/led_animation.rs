@@ -71,7 +71,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Light up pads in a wave pattern
         for pad in 0..16 {
-            let distance = ((pad as i32 - (i / 2) as i32).abs()) as f32;
+            let distance = ((pad as i32 - (i / 2)).abs()) as f32;
             let brightness = (1.0 - (distance / 8.0)).max(0.0);
 
             pad_leds.pad_leds[pad] = MaschineLEDColor::from_rgb_color(RgbColor::new(
@@ -114,16 +114,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("💗 Animation 5: Button pulse");
     for i in 0..30 {
         let brightness = ((i as f32 * 0.2).sin() * 127.0 + 127.0) as u8;
-        let mut leds = ButtonLedState::default();
 
         // Pulse all single-color LEDs
-        leds.play = brightness;
-        leds.rec = brightness;
-        leds.stop = brightness;
-        leds.volume = brightness;
-        leds.swing = brightness;
-        leds.tempo = brightness;
-        leds.notes = brightness;
+        let leds = ButtonLedState {
+            play: brightness,
+            rec: brightness,
+            stop: brightness,
+            volume: brightness,
+            swing: brightness,
+            tempo: brightness,
+            notes: brightness,
+            ..Default::default()
+        };
 
         device.write_button_leds(&leds)?;
         std::thread::sleep(Duration::from_millis(100));
@@ -193,7 +195,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         for led in 0..25 {
             let wave = ((time * 2.0 + led as f32 * 0.5).sin() + 1.0) / 2.0;
             let hue = (time * 50.0 + led as f32 * 14.4) % 360.0;
-            let color = hsv_to_rgb(hue, 1.0, wave as f32);
+            let color = hsv_to_rgb(hue, 1.0, wave);
             pad_leds.touch_strip_leds[led] = MaschineLEDColor::from_rgb_color(color);
         }
 
@@ -142,7 +142,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ];
 
     for (i, button) in group_buttons.iter().enumerate() {
-        device.set_button_led_color(button.clone(), rgb_colors[i])?;
+        device.set_button_led_color(*button, rgb_colors[i])?;
         std::thread::sleep(Duration::from_millis(200));
     }
 
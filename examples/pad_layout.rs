@@ -0,0 +1,76 @@
+use maschine3_hal::{
+    InputEvent, MK3Error, MaschineLEDColor, MaschineMK3, PadEventType, PadLedState,
+};
+use std::time::Duration;
+
+/// Hardware check of the pad LED layout: lights the grid row by row from the
+/// top-left, then lights whichever pad is hit. If the wrong pad lights up,
+/// `PAD_LED_LAYOUT` doesn't match the controller.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🥁 Maschine MK3 Pad Layout Check");
+    println!("⚠️  Make sure to close any NI software first!\n");
+
+    let mut device = match MaschineMK3::new() {
+        Ok(device) => {
+            println!("✅ Connected: {}", device.device_info()?);
+            device
+        }
+        Err(MK3Error::DeviceNotFound) => {
+            println!("❌ No Maschine MK3 found. Check connection.");
+            return Ok(());
+        }
+        Err(e) => {
+            println!("❌ Connection error: {}", e);
+            return Ok(());
+        }
+    };
+
+    device.clear_all_leds()?;
+
+    println!("\n➡️  Sweep: pads should light left to right, top row first");
+    for row in 0..4 {
+        for col in 0..4 {
+            device.update_leds(|leds| {
+                leds.pads = PadLedState::default();
+                leds.pads
+                    .set_by_grid(row, col, MaschineLEDColor::green(true));
+            })?;
+            println!("   row {}, column {}", row + 1, col + 1);
+            std::thread::sleep(Duration::from_millis(400));
+        }
+    }
+    device.clear_all_leds()?;
+
+    println!("\n🎮 Hit pads: the pad you hit should light up");
+    println!("   Press Ctrl+C to exit\n");
+
+    loop {
+        for event in device.poll_input_events()? {
+            match event {
+                InputEvent::PadEvent {
+                    pad_number,
+                    event_type: PadEventType::Hit,
+                    ..
+                } => {
+                    println!(
+                        "🔽 Pad {} (row {}, column {})",
+                        pad_number,
+                        pad_number / 4 + 1,
+                        pad_number % 4 + 1
+                    );
+                    device.set_pad_led(pad_number, MaschineLEDColor::white(true))?;
+                }
+                InputEvent::PadEvent {
+                    pad_number,
+                    event_type: PadEventType::HitRelease | PadEventType::TouchRelease,
+                    ..
+                } => {
+                    device.set_pad_led(pad_number, MaschineLEDColor::black())?;
+                }
+                _ => {}
+            }
+        }
+
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,54 @@
+use maschine3_hal::{DisplayCommand, DisplayPacket, MaschineMK3, RawPacket};
+use std::time::Duration;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("Loading raw display packet captures for pixel injection testing...");
+
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "wireshark_dumps/first.raw".to_string());
+    let data = std::fs::read(&path)?;
+    println!("\n=== Processing {} ===", path);
+    println!("File size: {} bytes", data.len());
+
+    let packet = DisplayPacket::parse(&data)?;
+    println!(
+        "📋 Display {}, region {:?}, {} pixels",
+        packet.display_id(),
+        packet.rect(),
+        packet.pixel_count()
+    );
+    for command in packet.commands() {
+        match command {
+            DisplayCommand::TransmitPixels { pixels } => {
+                println!("  transmit {} pixels", pixels.len())
+            }
+            DisplayCommand::RepeatPixels {
+                pixel1,
+                pixel2,
+                count,
+            } => println!(
+                "  repeat {:04X} {:04X} x{}",
+                pixel1.value, pixel2.value, count
+            ),
+            DisplayCommand::Blit => println!("  blit"),
+            DisplayCommand::EndTransmission => println!("  end"),
+        }
+    }
+
+    // Captures should survive a round trip through the builder unchanged
+    if packet.to_packet() != data {
+        println!("⚠️  Re-encoded packet differs from the capture");
+    }
+
+    let raw = RawPacket::new(data)?;
+    let device = MaschineMK3::new()?;
+    println!("Device initialized successfully");
+
+    std::thread::sleep(Duration::from_millis(2000));
+    device.send_raw_packet(&raw)?;
+    println!("✅ {} sent successfully", path);
+
+    println!("\n🎉 Pixel injection test complete");
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use maschine3_hal::MaschineMK3;
+use maschine3_hal::{MaschineMK3, RawPacket};
 use std::io::Result;
 use std::time::Instant;
 
@@ -137,12 +137,14 @@ fn create_single_row_packet(display_num: u8, time: f32) -> Vec<u8> {
     packet
 }
 
-fn paint_single_frame(device: &MaschineMK3, packet: &[u8]) -> Result<()> {
-    if let Err(e) = device.send_raw_data(packet) {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to send packet: {}", e),
-        ));
+fn paint_single_frame(device: &MaschineMK3, packet: Vec<u8>) -> Result<()> {
+    let packet = RawPacket::new(packet)
+        .map_err(|e| std::io::Error::other(format!("Invalid packet: {}", e)))?;
+    if let Err(e) = device.send_raw_packet(&packet) {
+        return Err(std::io::Error::other(format!(
+            "Failed to send packet: {}",
+            e
+        )));
     }
     Ok(())
 }
@@ -176,7 +178,7 @@ fn main() -> Result<()> {
         let packet = create_single_row_packet(0, elapsed);
 
         // Paint frame
-        if let Err(e) = paint_single_frame(&device, &packet) {
+        if let Err(e) = paint_single_frame(&device, packet) {
             eprintln!("Failed to paint frame: {}", e);
             break;
         }
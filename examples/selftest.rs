@@ -0,0 +1,67 @@
+use maschine3_hal::{MK3Error, MaschineMK3, SelfTestEvent, SelfTestStep};
+
+/// Guided check of every control, printing a calibration report at the end
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🩺 Maschine MK3 Self-Test");
+    println!("⚠️  Make sure to close any NI software first!\n");
+
+    let mut device = match MaschineMK3::new() {
+        Ok(device) => {
+            println!("✅ Connected: {}", device.device_info()?);
+            device
+        }
+        Err(MK3Error::DeviceNotFound) => {
+            println!("❌ No Maschine MK3 found. Check connection.");
+            return Ok(());
+        }
+        Err(e) => {
+            println!("❌ Connection error: {}", e);
+            return Ok(());
+        }
+    };
+
+    println!("\n🎮 Follow the prompts; a step is skipped if nothing happens for 15s\n");
+
+    let report = device.run_selftest(|event| match event {
+        SelfTestEvent::Prompt { step, index, total } => {
+            println!("[{}/{}] {}", index + 1, total, step.prompt());
+        }
+        SelfTestEvent::Passed(_) => println!("   ✅ OK"),
+        SelfTestEvent::TimedOut(_) => println!("   ❌ No response"),
+        SelfTestEvent::Unexpected { event, .. } => {
+            println!("   ⚠️  Unexpected: {}", event.description());
+        }
+        SelfTestEvent::PadHit { velocity, .. } => println!("   velocity {}", velocity),
+    })?;
+
+    println!("\n📋 Report");
+    println!("   Passed: {}", report.passed.len());
+    for step in &report.failed {
+        println!("   Failed: {:?}", step);
+    }
+    for (expected, event) in &report.unexpected {
+        println!("   While testing {:?}: {}", expected, event.description());
+    }
+    for (pad, calibration) in report.pads.iter().enumerate() {
+        match calibration {
+            Some(c) => println!(
+                "   {:?}: velocity {}-{}, max pressure {}",
+                SelfTestStep::Pad(pad as u8),
+                c.min_velocity,
+                c.max_velocity,
+                c.max_pressure
+            ),
+            None => println!("   {:?}: no hits", SelfTestStep::Pad(pad as u8)),
+        }
+    }
+
+    println!(
+        "\n{}",
+        if report.is_ok() {
+            "🎉 All controls OK"
+        } else {
+            "⚠️  Some controls need attention"
+        }
+    );
+    Ok(())
+}
@@ -23,27 +23,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🔍 Starting async monitoring...");
     println!("   Press Ctrl+C to stop\n");
 
-    // Use the callback-based API with LED feedback
-    device.start_input_monitoring(move |event| {
-        println!("{}", event.description());
-        
-        // Provide visual feedback
-        match event {
-            InputEvent::ButtonPressed(btn) => {
-                // Light up button when pressed (this won't work because we can't move device into closure)
-                // We'll show a better example in simple_test.rs
-                println!("  💡 {} button pressed!", btn.name());
-            }
-            InputEvent::PadEvent { pad_number, event_type: maschine3_hal::PadEventType::Hit, value } => {
-                println!("  🥁 Pad {} hit with velocity {}!", pad_number + 1, value);
-            }
-            _ => {}
-        }
-    })?;
+    // Events are queued by the monitoring thread, so the device stays
+    // available here for LED feedback
+    device.start_event_monitoring()?;
 
-    // Keep the program running
     println!("✨ Monitoring started! Interact with your device...");
     loop {
-        std::thread::sleep(Duration::from_secs(1));
+        while let Some(event) = device.recv_event_timeout(Duration::from_millis(100)) {
+            println!("{}", event.description());
+
+            // Provide visual feedback
+            match event {
+                InputEvent::ButtonPressed(btn) => {
+                    println!("  💡 {} button pressed!", btn.name());
+                    let _ = device.set_button_led(btn, 127);
+                }
+                InputEvent::ButtonReleased(btn) => {
+                    let _ = device.set_button_led(btn, 0);
+                }
+                InputEvent::PadEvent { pad_number, event_type: maschine3_hal::PadEventType::Hit, value } => {
+                    println!("  🥁 Pad {} hit with velocity {}!", pad_number + 1, value);
+                }
+                _ => {}
+            }
+        }
     }
-}
\ No newline at end of file
+}
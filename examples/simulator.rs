@@ -0,0 +1,43 @@
+use maschine3_hal::{InputEvent, MaschineDevice, MaschineLEDColor, PadEventType, SimulatedMK3};
+use std::io::BufRead;
+
+/// Drive the simulated controller from typed keys; displays go to
+/// `simulator_frames/display0.png` and `display1.png`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🖥️  Maschine MK3 Simulator");
+    println!("   Pads: 1234 / qwer / asdf / zxcv, Play: space, knobs: yuio / hjkl");
+    println!("   Type keys and press Enter, Ctrl+D to exit\n");
+
+    std::fs::create_dir_all("simulator_frames")?;
+    let mut device = SimulatedMK3::new();
+    device.set_png_output(Some("simulator_frames"));
+
+    for line in std::io::stdin().lock().lines() {
+        for key in line?.chars() {
+            device.key_down(key);
+            device.key_up(key);
+        }
+
+        for event in device.poll_input_events()? {
+            println!("{}", event.description());
+
+            if let InputEvent::PadEvent {
+                pad_number,
+                event_type: PadEventType::Hit,
+                ..
+            } = event
+            {
+                device.update_leds(|leds| {
+                    leds.set_all_pads(MaschineLEDColor::black());
+                    leds.set_pad(pad_number, MaschineLEDColor::green(true));
+                })?;
+
+                // Brightness of the left display follows the pad number
+                let level = (pad_number as u32 * 255 / 15) as u8;
+                device.send_display_rgb888(0, &vec![level; 480 * 272 * 3])?;
+            }
+        }
+    }
+
+    Ok(())
+}
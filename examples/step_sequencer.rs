@@ -0,0 +1,53 @@
+use maschine3_hal::{MK3Error, MaschineMK3, StepSequencer};
+use std::time::{Duration, Instant};
+
+/// 16-step sequencer on the pads; group buttons A-H switch pattern pages
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🥁 Maschine MK3 Step Sequencer");
+    println!("⚠️  Make sure to close any NI software first!\n");
+
+    let mut device = match MaschineMK3::new() {
+        Ok(device) => {
+            println!("✅ Connected: {}", device.device_info()?);
+            device
+        }
+        Err(MK3Error::DeviceNotFound) => {
+            println!("❌ No Maschine MK3 found. Check connection.");
+            return Ok(());
+        }
+        Err(e) => {
+            println!("❌ Connection error: {}", e);
+            return Ok(());
+        }
+    };
+
+    println!("\n🎮 Hit pads to toggle steps, press Group A-H to switch pages");
+    println!("   Press Ctrl+C to exit\n");
+
+    device.clear_all_leds()?;
+
+    let mut sequencer = StepSequencer::new();
+    // 16th notes at 120 BPM
+    let step_duration = Duration::from_millis(125);
+    let mut next_step = Instant::now();
+
+    loop {
+        let mut changed = false;
+        for event in device.poll_input_events()? {
+            changed |= sequencer.handle_event(&event);
+        }
+
+        if Instant::now() >= next_step {
+            next_step += step_duration;
+            let step = sequencer.tick();
+            if sequencer.pattern().steps[step] {
+                println!("🔊 Page {} step {}", sequencer.page() + 1, step + 1);
+            }
+            changed = true;
+        }
+
+        if changed {
+            device.update_leds(|leds| sequencer.render(leds))?;
+        }
+    }
+}
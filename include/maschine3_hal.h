@@ -0,0 +1,513 @@
+#ifndef MASCHINE3_HAL_H
+#define MASCHINE3_HAL_H
+
+/* Generated with cbindgen:0.27.0 */
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#if defined(MK3_RAW_POINTERS)
+#define MK3_INVALID_DEVICE NULL
+#endif
+
+#if !defined(MK3_RAW_POINTERS)
+// Version of the C ABI. Bump whenever a `#[repr(C)]` type or function
+// signature changes incompatibly. Version 2 replaced device pointers with
+// handles; `ffi-raw-pointers` builds keep version 1.
+#define MK3_ABI_VERSION 2
+#endif
+
+#if defined(MK3_RAW_POINTERS)
+#define MK3_ABI_VERSION 1
+#endif
+
+#define MK3_DISPLAY_WIDTH 480
+
+#define MK3_DISPLAY_HEIGHT 272
+
+#define MK3_TOUCH_STRIP_LED_COUNT 25
+
+#define MK3_OK 0
+
+#define MK3_ERROR_NULL_POINTER -1
+
+#define MK3_ERROR_DEVICE_NOT_FOUND -2
+
+#define MK3_ERROR_USB -3
+
+#define MK3_ERROR_INVALID_PACKET -4
+
+#define MK3_ERROR_DISCONNECTED -5
+
+#define MK3_ERROR_IO -6
+
+#define MK3_ERROR_INVALID_DATA -7
+
+#define MK3_ERROR_DISPLAY_STALLED -8
+
+#define MK3_ERROR_DISPLAY_UNAVAILABLE -9
+
+#define MK3_ERROR_DEVICE_BUSY -10
+
+// The device handle is unknown or was closed
+#define MK3_ERROR_INVALID_HANDLE -11
+
+// The library panicked; the details are logged
+#define MK3_ERROR_INTERNAL -12
+
+#define MK3_EVENT_BUTTON_PRESSED 1
+
+#define MK3_EVENT_BUTTON_RELEASED 2
+
+#define MK3_EVENT_BUTTON_HELD 3
+
+#define MK3_EVENT_KNOB_CHANGED 4
+
+#define MK3_EVENT_AUDIO_CHANGED 5
+
+#define MK3_EVENT_PAD 6
+
+#define MK3_EVENT_TOUCH_STRIP_GESTURE 7
+
+#define MK3_EVENT_STRIP_CHANGED 8
+
+#define MK3_EVENT_DEVICE_RESUMED 9
+
+#define MK3_EVENT_PAD_PRESSURE 10
+
+#define MK3_EVENT_BUTTON_DOUBLE_PRESSED 11
+
+#define MK3_EVENT_BUTTON_LONG_PRESSED 12
+
+#define MK3_EVENT_PACKETS_DROPPED 13
+
+#define MK3_EVENT_ENCODER_TURNED 14
+
+#define MK3_EVENT_PROTOCOL_WARNING 15
+
+#define MK3_EVENT_RAW_INPUT 16
+
+#define MK3_EVENT_KNOB_GESTURE 17
+
+#define MK3_EVENT_ACTION 18
+
+#define MK3_PAD_HIT 0
+
+#define MK3_PAD_TOUCH_RELEASE 1
+
+#define MK3_PAD_HIT_RELEASE 2
+
+#define MK3_PAD_AFTERTOUCH 3
+
+#define MK3_GESTURE_TAP 0
+
+#define MK3_GESTURE_DOUBLE_TAP 1
+
+#define MK3_GESTURE_SWIPE_LEFT 2
+
+#define MK3_GESTURE_SWIPE_RIGHT 3
+
+#define MK3_GESTURE_HOLD 4
+
+#define MK3_GESTURE_PINCH 5
+
+#define MK3_KNOB_TOUCH_TURN 0
+
+#define MK3_KNOB_TAP_TOUCH 1
+
+#define MK3_KNOB_TOUCH_RELEASE 2
+
+#define MK3_STRIP_RAW 0
+
+#define MK3_STRIP_PITCH_BEND 1
+
+#define MK3_STRIP_MOD_WHEEL 2
+
+#define MK3_STRIP_CROSSFADER 3
+
+#define MK3_WARNING_SHORT_PACKET 0
+
+#define MK3_DISPLAY_AVAILABLE 0
+
+#define MK3_DISPLAY_CLAIM_FAILED 1
+
+#define MK3_DISPLAY_WRONG_DRIVER 2
+
+#define MK3_DISPLAY_NOT_ATTEMPTED 3
+
+#define MK3_MONITORING_STOPPED 0
+
+#define MK3_MONITORING_RUNNING 1
+
+#define MK3_MONITORING_RESTARTING 2
+
+#define MK3_MONITORING_FAILED 3
+
+#define MK3_LED_ANIMATION_PULSE 0
+
+#define MK3_LED_ANIMATION_RAINBOW 1
+
+#define MK3_LED_ANIMATION_CHASE 2
+
+#define MK3_LED_TARGET_PADS 0
+
+#define MK3_LED_TARGET_TOUCH_STRIP 1
+
+#define MK3_LED_TARGET_ALL 2
+
+// Device behind an [`MK3DeviceRef`]; opaque to C
+typedef struct MK3Device MK3Device;
+
+#if !defined(MK3_RAW_POINTERS)
+// Device returned by [`mk3_open`] and passed to every other call.
+//
+// A handle from a registry that is checked on every call, so a stale or
+// made-up handle fails with `MK3_ERROR_INVALID_HANDLE` instead of touching
+// freed memory. Handles are never reused.
+typedef uint64_t MK3DeviceRef;
+#endif
+
+#if defined(MK3_RAW_POINTERS)
+// Device returned by [`mk3_open`] and passed to every other call: a raw
+// pointer, as before handles were introduced. Kept for hosts still being
+// migrated; a stale pointer is undefined behavior.
+typedef struct MK3Device *MK3DeviceRef;
+#endif
+
+// Flattened [`InputEvent`]. Which fields are meaningful depends on `kind`:
+//
+// - buttons: `element`
+// - knobs and audio controls: `element`, `value`, `delta`
+// - pads: `element` is the pad number, `detail` a `MK3_PAD_*` type, `value`
+//   the 12-bit velocity/pressure
+// - pad pressure: `element` is the pad number, `value` the pressure
+// - touch strip gestures: `detail` a `MK3_GESTURE_*` kind, `position` the
+//   gesture's position, distance or scale
+// - knob gestures: `element` is the knob, `detail` a `MK3_KNOB_*` kind;
+//   touch turns carry `value` and `delta` like knob changes, touch
+//   releases `value` 1 if the knob turned during the touch
+// - strip changes: `detail` a `MK3_STRIP_*` mode, `position` the value
+// - encoder turns: `element` is the main encoder, `delta` the detents
+//   turned, `value` the accelerated steps
+// - protocol warnings: `detail` a `MK3_WARNING_*` kind, `element` the
+//   packet type, `value` the packet length, `delta` the expected length of
+//   short packets (the bytes themselves aren't passed on)
+// - raw input: `element` is the packet type, `value` the packet length
+// - actions: `value` is the `ActionId`
+//
+// `element` is an [`InputElement`] ID (see [`InputElement::id`]).
+typedef struct CInputEvent {
+  uint32_t kind;
+  uint32_t element;
+  uint32_t detail;
+  int32_t value;
+  int32_t delta;
+  float position;
+} CInputEvent;
+
+// RGB color; converted to the nearest LED palette color when sent
+typedef struct CRgbColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+} CRgbColor;
+
+// Settings of [`mk3_start_led_animation`]. `color` is used by pulse and
+// chase; `period_ms` is the length of one cycle and must not be 0.
+typedef struct CLedAnimationParams {
+  // A `MK3_LED_TARGET_*` value
+  uint32_t target;
+  uint32_t period_ms;
+  struct CRgbColor color;
+} CLedAnimationParams;
+
+#if !defined(MK3_RAW_POINTERS)
+// What [`mk3_open`] returns when no device could be opened. cbindgen can't
+// express the null pointer, so the header defines it for raw-pointer builds
+// in `cbindgen.toml`.
+#define MK3_INVALID_DEVICE 0
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// ABI version of the loaded library; compare against `MK3_ABI_VERSION`
+uint32_t mk3_abi_version(void);
+
+// [`InputElement`] ID for a control name such as "GroupA" or "Knob 3" (see
+// [`InputElement::from_name`]), or `MK3_ERROR_INVALID_DATA` if the name is
+// unknown
+//
+// # Safety
+//
+// `name` must be null or point to a NUL-terminated string.
+int32_t mk3_element_from_name(const char *name);
+
+// Write the identifier of an [`InputElement`] ID ("GroupA") into `buffer`
+// as a NUL-terminated string. Returns the identifier's length without the
+// terminator, or a negative error code if the ID is unknown or `len` is too
+// small.
+//
+// # Safety
+//
+// `buffer` must point to `len` writable bytes.
+int32_t mk3_element_name(uint32_t element, char *buffer, uintptr_t len);
+
+// Connect to the first available Maschine MK3. Returns `MK3_INVALID_DEVICE`
+// on failure.
+MK3DeviceRef mk3_open(void);
+
+// Close a device returned by [`mk3_open`]. Closing `MK3_INVALID_DEVICE`, or
+// a handle that was already closed, is a no-op.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be null or a pointer returned
+// by `mk3_open` that has not been closed yet.
+void mk3_close(MK3DeviceRef device);
+
+// Fetch the next input event, reading from the device (waiting up to 100ms)
+// if none is queued, or from the monitoring thread's queue while
+// [`mk3_start_monitoring_with_rate`] is in effect. Returns 1 if an event was
+// written to `event`, 0 if no input arrived, or a negative error code.
+//
+// # Safety
+//
+// `event` must point to writable memory for one `CInputEvent`. In
+// `ffi-raw-pointers` builds `device` must be a live pointer from `mk3_open`.
+int32_t mk3_poll_event(MK3DeviceRef device, struct CInputEvent *event);
+
+// Read input on a background thread from now on, so [`mk3_poll_event`]
+// only takes queued events. The thread blocks on each read for up to
+// `poll_interval_ms` (at least 1): packets are delivered as soon as they
+// arrive either way, shorter intervals just let [`mk3_stop_monitoring`]
+// return sooner at the cost of more wakeups.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_start_monitoring_with_rate(MK3DeviceRef device, uint32_t poll_interval_ms);
+
+// Stop the thread started by [`mk3_start_monitoring_with_rate`]; events it
+// queued are still returned by [`mk3_poll_event`]. A no-op if monitoring
+// isn't running.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_stop_monitoring(MK3DeviceRef device);
+
+// What the thread started by [`mk3_start_monitoring_with_rate`] is doing:
+// one of the `MK3_MONITORING_*` results, or a negative error code. A
+// thread that panics or keeps failing restarts itself; after
+// `MK3_MONITORING_FAILED` no events arrive until monitoring is stopped and
+// started again. The reason is logged.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_monitoring_status(MK3DeviceRef device);
+
+// Set how long a button must be held before `MK3_EVENT_BUTTON_HELD` events
+// start (500ms by default). Applies to polling at once and to running
+// monitoring from its next packet.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_hold_threshold_ms(MK3DeviceRef device, uint32_t threshold_ms);
+
+// Report input packets that can't be parsed as
+// `MK3_EVENT_PROTOCOL_WARNING` events (`enabled` non-zero) instead of
+// skipping them silently. Applies to polling at once and to running
+// monitoring from its next packet.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_protocol_warnings(MK3DeviceRef device, int32_t enabled);
+
+// Tune the device for the shortest hit-to-LED path: 1ms input reads,
+// a raised priority for the monitoring thread and no LED write coalescing.
+// Call it before [`mk3_start_monitoring_with_rate`], whose interval then
+// replaces the 1ms read timeout; pass 1 to keep it.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_enable_low_latency(MK3DeviceRef device);
+
+// Set a pad LED (0-15) to the palette color nearest `color`
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_pad_led(MK3DeviceRef device, uint8_t pad_number, struct CRgbColor color);
+
+// Set a button LED brightness (0-127) by [`InputElement`] ID
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_button_led(MK3DeviceRef device, uint32_t element, uint8_t brightness);
+
+// Send a full 480x272 RGB888 frame (`len` must be 391680) to display 0 or 1
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes. In `ffi-raw-pointers` builds
+// `device` must be a live pointer from `mk3_open`.
+int32_t mk3_send_display_rgb888(MK3DeviceRef device,
+                                uint8_t display,
+                                const uint8_t *data,
+                                uintptr_t len);
+
+// Send full 480x272 RGB888 frames to both displays (`len` must be 391680
+// for each), converting the right one while the left one is sent. Only
+// the part of each frame that changed since the last call is transferred.
+//
+// # Safety
+//
+// `left` and `right` must each point to `len` readable bytes. In
+// `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_present_both(MK3DeviceRef device,
+                         const uint8_t *left,
+                         const uint8_t *right,
+                         uintptr_t len);
+
+// Set pads 0..`count` from `colors` (at most 16) with a single LED write
+//
+// # Safety
+//
+// `colors` must point to `count` readable `CRgbColor`s. In
+// `ffi-raw-pointers` builds `device` must be a live pointer from `mk3_open`.
+int32_t mk3_set_pad_leds(MK3DeviceRef device, const struct CRgbColor *colors, uintptr_t count);
+
+// Set several button LEDs with a single LED write. `elements` holds
+// [`InputElement`] IDs and `brightness` the matching 0-127 values. Nothing is
+// changed if any ID is invalid.
+//
+// # Safety
+//
+// `elements` and `brightness` must each point to `count` readable values.
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_button_led_batch(MK3DeviceRef device,
+                                 const uint32_t *elements,
+                                 const uint8_t *brightness,
+                                 uintptr_t count);
+
+// Write `w`x`h` RGB888 pixels (`len` must be `w * h * 3`) to a region of
+// display 0 or 1. `x` and `w` must be even.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes. In `ffi-raw-pointers` builds
+// `device` must be a live pointer from `mk3_open`.
+int32_t mk3_write_display_region_rgb888(MK3DeviceRef device,
+                                        uint8_t display,
+                                        uint16_t x,
+                                        uint16_t y,
+                                        uint16_t w,
+                                        uint16_t h,
+                                        const uint8_t *data,
+                                        uintptr_t len);
+
+// Whether the displays can be used: one of the `MK3_DISPLAY_*` results, or
+// a negative error code
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_display_status(MK3DeviceRef device);
+
+// Claim the display interface again without reopening the device, e.g.
+// after the user fixed the driver. Returns the new `MK3_DISPLAY_*` result,
+// or a negative error code.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_retry_display_claim(MK3DeviceRef device);
+
+// Flash every LED and both displays in a double-blink pattern for
+// `duration_ms`, with the serial number on the displays, so the user can
+// tell which controller this handle drives. Blocks until done, then
+// restores the LEDs and displays.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_identify(MK3DeviceRef device, uint32_t duration_ms);
+
+// Set the 25 touch strip LEDs, left to right, from `colors` with a single
+// LED write. A running touch strip animation draws over them on its next
+// update; stop it first with [`mk3_stop_animations`].
+//
+// # Safety
+//
+// `colors` must point to `MK3_TOUCH_STRIP_LED_COUNT` readable `CRgbColor`s.
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_touch_strip_leds(MK3DeviceRef device, const struct CRgbColor *colors);
+
+// Run a built-in `MK3_LED_ANIMATION_*` animation, replacing any already
+// running on the same LEDs. `params` may be null for a two-second white
+// cycle on the pads. Animations advance on every [`mk3_poll_event`] that
+// reads from the device, so the host doesn't send LED frames itself.
+//
+// # Safety
+//
+// `params` must be null or point to a readable `CLedAnimationParams`. In
+// `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_start_led_animation(MK3DeviceRef device,
+                                uint32_t kind,
+                                const struct CLedAnimationParams *params);
+
+// Stop all animations started with [`mk3_start_led_animation`], leaving
+// their LEDs as last drawn
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_stop_animations(MK3DeviceRef device);
+
+// Light the touch strip LEDs under the fingers in `color` (`enabled`
+// non-zero), `spread` LEDs either side of each, leaving a trail that fades
+// over `trail_ms`; `enabled` 0 turns it off. The LEDs follow the strip on
+// every [`mk3_poll_event`], or from the monitoring thread when it runs, so
+// the host doesn't forward strip input itself.
+//
+// # Safety
+//
+// In `ffi-raw-pointers` builds `device` must be a live pointer from
+// `mk3_open`.
+int32_t mk3_set_touch_strip_follow(MK3DeviceRef device,
+                                   int32_t enabled,
+                                   struct CRgbColor color,
+                                   uint8_t spread,
+                                   uint32_t trail_ms);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MASCHINE3_HAL_H */
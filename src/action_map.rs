@@ -0,0 +1,226 @@
+use crate::device::MaschineDevice;
+use crate::error::Result;
+use crate::input::{InputElement, InputEvent};
+use crate::led_group::LedValue;
+use crate::output::LedFrame;
+
+/// Brightness of a layer's modifier button while the layer is active
+const MODIFIER_BRIGHTNESS: u8 = 127;
+
+/// Logical action of an app, reported as [`InputEvent::Action`] by an
+/// [`ActionMap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActionId(pub u32);
+
+/// A button press that triggers an action, on the base layer or while a
+/// modifier button is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionBinding {
+    /// Button held for the layer, e.g. `Shift`; `None` for the base layer
+    pub modifier: Option<InputElement>,
+    pub button: InputElement,
+    pub action: ActionId,
+    /// What the button's LED shows while the layer is active; `None` leaves
+    /// the LED to the app
+    pub led: Option<LedValue>,
+}
+
+impl ActionBinding {
+    /// Binding on the base layer, without LED feedback
+    pub fn new(button: InputElement, action: ActionId) -> Self {
+        Self {
+            modifier: None,
+            button,
+            action,
+            led: None,
+        }
+    }
+
+    /// Only trigger while `modifier` is held
+    pub fn with_modifier(mut self, modifier: InputElement) -> Self {
+        self.modifier = Some(modifier);
+        self
+    }
+
+    /// Light the button while the binding's layer is active
+    pub fn with_led(mut self, value: impl Into<LedValue>) -> Self {
+        self.led = Some(value.into());
+        self
+    }
+}
+
+/// Remaps button presses to logical actions, with layers selected by held
+/// modifier buttons.
+///
+/// A press of a bound button on the active layer becomes an
+/// [`InputEvent::Action`]; its release, hold and press gestures are dropped.
+/// Everything else, the modifiers themselves included, passes through
+/// unchanged. The active layer is that of the modifier pressed last among
+/// those held, or the base layer when none is; bindings of other layers
+/// don't fire, so an unbound Shift+button arrives as a plain press.
+///
+/// Buttons bound with an LED show the active layer: lit as bound if they
+/// have a binding on it and off otherwise, with the modifier of an active
+/// layer lit as well.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{ActionBinding, ActionId, ActionMap, InputElement, InputEvent, MaschineMK3};
+///
+/// const PLAY: ActionId = ActionId(0);
+/// const RESTART: ActionId = ActionId(1);
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut actions = ActionMap::new();
+/// actions.bind(ActionBinding::new(InputElement::Play, PLAY).with_led(63));
+/// actions.bind(
+///     ActionBinding::new(InputElement::Play, RESTART)
+///         .with_modifier(InputElement::Shift)
+///         .with_led(127),
+/// );
+/// actions.show_leds(&mut device)?;
+///
+/// loop {
+///     let events = device.poll_input_events()?;
+///     for event in actions.handle_events(&mut device, &events)? {
+///         match event {
+///             InputEvent::Action(PLAY) => println!("Play"),
+///             InputEvent::Action(RESTART) => println!("Restart"),
+///             _ => {}
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    bindings: Vec<ActionBinding>,
+    /// Held layer modifiers, in the order they were pressed
+    held_modifiers: Vec<InputElement>,
+    /// Buttons whose last press triggered an action
+    mapped: Vec<InputElement>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a binding, replacing any for the same button on the same layer
+    pub fn bind(&mut self, binding: ActionBinding) {
+        self.unbind(binding.modifier, binding.button);
+        self.bindings.push(binding);
+    }
+
+    /// Remove the binding of a button on a layer. Returns false if there
+    /// was none.
+    pub fn unbind(&mut self, modifier: Option<InputElement>, button: InputElement) -> bool {
+        let before = self.bindings.len();
+        self.bindings
+            .retain(|binding| (binding.modifier, binding.button) != (modifier, button));
+        self.bindings.len() != before
+    }
+
+    pub fn bindings(&self) -> &[ActionBinding] {
+        &self.bindings
+    }
+
+    /// Modifier of the active layer, `None` for the base layer
+    pub fn active_layer(&self) -> Option<InputElement> {
+        self.held_modifiers.last().copied()
+    }
+
+    /// Binding a press of `button` triggers on the active layer
+    pub fn active_binding(&self, button: InputElement) -> Option<&ActionBinding> {
+        let layer = self.active_layer();
+        self.bindings
+            .iter()
+            .find(|binding| binding.modifier == layer && binding.button == button)
+    }
+
+    fn is_modifier(&self, element: &InputElement) -> bool {
+        self.bindings
+            .iter()
+            .any(|binding| binding.modifier.as_ref() == Some(element))
+    }
+
+    /// Remap one input event without touching the device. Returns `None`
+    /// for events of a mapped button other than its press.
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<InputEvent> {
+        match event {
+            InputEvent::ButtonPressed(element) if self.is_modifier(element) => {
+                self.held_modifiers.retain(|held| held != element);
+                self.held_modifiers.push(*element);
+            }
+            InputEvent::ButtonReleased(element) if self.is_modifier(element) => {
+                self.held_modifiers.retain(|held| held != element);
+            }
+            InputEvent::ButtonPressed(element) => {
+                self.mapped.retain(|mapped| mapped != element);
+                if let Some(action) = self.active_binding(*element).map(|binding| binding.action) {
+                    self.mapped.push(*element);
+                    return Some(InputEvent::Action(action));
+                }
+            }
+            // Long presses can be reported after the release, so a button
+            // stays mapped until its next press
+            InputEvent::ButtonReleased(element)
+            | InputEvent::ButtonHeld(element)
+            | InputEvent::ButtonDoublePressed(element)
+            | InputEvent::ButtonLongPressed(element)
+                if self.mapped.contains(element) =>
+            {
+                return None;
+            }
+            _ => {}
+        }
+        Some(event.clone())
+    }
+
+    /// Remap a batch of events, updating the bound LEDs if the active layer
+    /// changed
+    pub fn handle_events<D: MaschineDevice>(
+        &mut self,
+        device: &mut D,
+        events: &[InputEvent],
+    ) -> Result<Vec<InputEvent>> {
+        let before = self.active_layer();
+        let remapped: Vec<InputEvent> = events
+            .iter()
+            .filter_map(|event| self.handle_event(event))
+            .collect();
+
+        if self.active_layer() != before {
+            self.show_leds(device)?;
+        }
+        Ok(remapped)
+    }
+
+    /// Light the bound buttons for the active layer
+    pub fn show_leds<D: MaschineDevice>(&self, device: &mut D) -> Result<()> {
+        device.update_leds(|leds| self.write_leds(leds))
+    }
+
+    /// Write the LEDs of buttons bound with LED feedback into `leds`
+    pub fn write_leds(&self, leds: &mut LedFrame) {
+        let layer = self.active_layer();
+        for binding in self.bindings.iter().filter(|binding| binding.led.is_some()) {
+            let value = self
+                .active_binding(binding.button)
+                .and_then(|active| active.led)
+                .unwrap_or(LedValue::Brightness(0));
+            leds.set_button_value(binding.button, value);
+
+            if let Some(modifier) = binding.modifier {
+                let brightness = if layer == Some(modifier) {
+                    MODIFIER_BRIGHTNESS
+                } else {
+                    0
+                };
+                leds.set_button_value(modifier, brightness);
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A single full-screen frame of an animation (480x272 RGB888)
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub rgb_data: Vec<u8>,
+    pub duration: Duration,
+}
+
+impl AnimationFrame {
+    pub fn new(rgb_data: Vec<u8>, duration: Duration) -> Self {
+        Self { rgb_data, duration }
+    }
+}
+
+/// Where a playback thread takes its frames from
+trait FrameSource: Send + 'static {
+    /// Next frame to show, `None` once the animation is over
+    fn next_frame(&mut self) -> Option<&AnimationFrame>;
+}
+
+/// Frames decoded up front, see [`DisplayAnimator::play_frames`]
+struct FrameList {
+    frames: Vec<AnimationFrame>,
+    next: usize,
+    looped: bool,
+}
+
+impl FrameSource for FrameList {
+    fn next_frame(&mut self) -> Option<&AnimationFrame> {
+        if self.next == self.frames.len() {
+            if !self.looped {
+                return None;
+            }
+            self.next = 0;
+        }
+        self.next += 1;
+        self.frames.get(self.next - 1)
+    }
+}
+
+/// Animation running on one display
+struct Playback {
+    stop_signal: Arc<Mutex<bool>>,
+    thread: JoinHandle<()>,
+}
+
+/// Plays frame sequences on the displays from background threads.
+///
+/// Frames are sent through the dirty-region path, so only the part of the
+/// screen that changed between frames is transferred.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{AnimationFrame, DisplayAnimator, MaschineMK3};
+/// use std::sync::{Arc, Mutex};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = Arc::new(Mutex::new(MaschineMK3::new()?));
+/// let mut animator = DisplayAnimator::new(Arc::clone(&device));
+///
+/// // Blink the left display between black and white twice a second
+/// let black = AnimationFrame::new(vec![0; 480 * 272 * 3], Duration::from_millis(500));
+/// let white = AnimationFrame::new(vec![255; 480 * 272 * 3], Duration::from_millis(500));
+/// animator.play_frames(0, vec![black, white], true)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DisplayAnimator {
+    device: Arc<Mutex<MaschineMK3>>,
+    playbacks: [Option<Playback>; 2],
+}
+
+impl DisplayAnimator {
+    pub fn new(device: Arc<Mutex<MaschineMK3>>) -> Self {
+        Self {
+            device,
+            playbacks: [None, None],
+        }
+    }
+
+    /// Play a sequence of frames on a display, replacing any animation already
+    /// running there. Each frame must be 480x272 RGB888.
+    pub fn play_frames(
+        &mut self,
+        display_id: u8,
+        frames: Vec<AnimationFrame>,
+        looped: bool,
+    ) -> Result<()> {
+        if display_id > 1 {
+            return Err(MK3Error::InvalidData(
+                "Display number must be 0 or 1".to_string(),
+            ));
+        }
+
+        let frame_bytes =
+            MaschineMK3::DISPLAY_WIDTH as usize * MaschineMK3::DISPLAY_HEIGHT as usize * 3;
+        if frames.is_empty() {
+            return Err(MK3Error::InvalidData("Animation has no frames".to_string()));
+        }
+        if let Some(frame) = frames.iter().find(|f| f.rgb_data.len() != frame_bytes) {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes per frame, got {}",
+                frame_bytes,
+                frame.rgb_data.len()
+            )));
+        }
+
+        self.start(
+            display_id,
+            FrameList {
+                frames,
+                next: 0,
+                looped,
+            },
+        )
+    }
+
+    /// Play frames from `source` on a display from a new thread, replacing
+    /// any animation already running there
+    fn start<S: FrameSource>(&mut self, display_id: u8, mut source: S) -> Result<()> {
+        self.stop(display_id)?;
+
+        let stop_signal = Arc::new(Mutex::new(false));
+        let thread_stop = Arc::clone(&stop_signal);
+        let device = Arc::clone(&self.device);
+
+        let thread = thread::spawn(move || {
+            let mut next_frame_at = Instant::now();
+
+            loop {
+                if let Ok(stop) = thread_stop.lock() {
+                    if *stop {
+                        return;
+                    }
+                }
+
+                let Some(frame) = source.next_frame() else {
+                    return;
+                };
+
+                if let Ok(mut device) = device.lock() {
+                    // A failed frame is dropped; the next one resends the
+                    // whole changed area anyway
+                    let _ =
+                        device.write_display_framebuffer_rgb888_dirty(display_id, &frame.rgb_data);
+                }
+
+                // Schedule against the ideal timeline so transfer time doesn't
+                // accumulate into drift
+                next_frame_at += frame.duration;
+                let now = Instant::now();
+                if next_frame_at > now {
+                    thread::sleep(next_frame_at - now);
+                } else {
+                    next_frame_at = now;
+                }
+            }
+        });
+
+        self.playbacks[display_id as usize] = Some(Playback {
+            stop_signal,
+            thread,
+        });
+        Ok(())
+    }
+
+    /// Play an animated GIF on a display, scaled to 480x272. Frames are
+    /// decoded on the playback thread as they are shown, so only one is held
+    /// in memory however long the GIF is; a frame that fails to decode ends
+    /// the animation.
+    #[cfg(feature = "gif")]
+    pub fn play_gif(&mut self, display_id: u8, bytes: &[u8], looped: bool) -> Result<()> {
+        if display_id > 1 {
+            return Err(MK3Error::InvalidData(
+                "Display number must be 0 or 1".to_string(),
+            ));
+        }
+        let frames = GifFrames::new(bytes.into(), looped)?;
+        self.start(display_id, frames)
+    }
+
+    /// Check whether an animation is still running on a display
+    pub fn is_playing(&self, display_id: u8) -> bool {
+        match self.playbacks.get(display_id as usize) {
+            Some(Some(playback)) => !playback.thread.is_finished(),
+            _ => false,
+        }
+    }
+
+    /// Stop the animation on a display, leaving its last frame on screen
+    pub fn stop(&mut self, display_id: u8) -> Result<()> {
+        let Some(slot) = self.playbacks.get_mut(display_id as usize) else {
+            return Ok(());
+        };
+
+        if let Some(playback) = slot.take() {
+            if let Ok(mut stop) = playback.stop_signal.lock() {
+                *stop = true;
+            }
+            playback.thread.join().map_err(|_| {
+                MK3Error::InvalidData("Failed to join animation thread".to_string())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Stop animations on both displays
+    pub fn stop_all(&mut self) -> Result<()> {
+        self.stop(0)?;
+        self.stop(1)
+    }
+}
+
+/// GIF frames decoded one at a time, see [`DisplayAnimator::play_gif`]
+#[cfg(feature = "gif")]
+struct GifFrames {
+    bytes: Arc<[u8]>,
+    looped: bool,
+    decoder: gif::Decoder<std::io::Cursor<Arc<[u8]>>>,
+    /// RGBA image the frames are composited onto
+    canvas: Vec<u8>,
+    /// Whether the current pass through the GIF produced a frame, so an
+    /// empty GIF doesn't loop forever
+    decoded_in_pass: bool,
+    current: AnimationFrame,
+}
+
+#[cfg(feature = "gif")]
+impl GifFrames {
+    fn new(bytes: Arc<[u8]>, looped: bool) -> Result<Self> {
+        let decoder = Self::decoder(&bytes)?;
+        let canvas = vec![0u8; decoder.width() as usize * decoder.height() as usize * 4];
+        let frame_bytes =
+            MaschineMK3::DISPLAY_WIDTH as usize * MaschineMK3::DISPLAY_HEIGHT as usize * 3;
+        Ok(Self {
+            bytes,
+            looped,
+            decoder,
+            canvas,
+            decoded_in_pass: false,
+            current: AnimationFrame::new(vec![0; frame_bytes], Duration::ZERO),
+        })
+    }
+
+    fn decoder(bytes: &Arc<[u8]>) -> Result<gif::Decoder<std::io::Cursor<Arc<[u8]>>>> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        options
+            .read_info(std::io::Cursor::new(Arc::clone(bytes)))
+            .map_err(gif_error)
+    }
+
+    /// Composite the next frame from the decoder onto the canvas and render
+    /// it into `current`. Returns false at the end of the GIF.
+    fn decode_next(&mut self) -> Result<bool> {
+        use gif::DisposalMethod;
+
+        let canvas_width = self.decoder.width() as usize;
+        let canvas_height = self.decoder.height() as usize;
+        let Some(frame) = self.decoder.read_next_frame().map_err(gif_error)? else {
+            return Ok(false);
+        };
+        let canvas = &mut self.canvas;
+
+        let previous = match frame.dispose {
+            DisposalMethod::Previous => Some(canvas.clone()),
+            _ => None,
+        };
+
+        // Composite the frame onto the canvas, honouring transparency
+        let (left, top) = (frame.left as usize, frame.top as usize);
+        for row in 0..frame.height as usize {
+            let y = top + row;
+            if y >= canvas_height {
+                break;
+            }
+            for col in 0..frame.width as usize {
+                let x = left + col;
+                if x >= canvas_width {
+                    break;
+                }
+                let src = (row * frame.width as usize + col) * 4;
+                if frame.buffer[src + 3] == 0 {
+                    continue;
+                }
+                let dst = (y * canvas_width + x) * 4;
+                canvas[dst..dst + 4].copy_from_slice(&frame.buffer[src..src + 4]);
+            }
+        }
+
+        // GIF delays are in 1/100s; browsers treat 0 and 1 as "as fast as
+        // reasonable", which in practice is 100ms
+        let delay_ms = if frame.delay < 2 {
+            100
+        } else {
+            frame.delay as u64 * 10
+        };
+        self.current.duration = Duration::from_millis(delay_ms);
+        scale_rgba_to_display(
+            canvas,
+            canvas_width,
+            canvas_height,
+            &mut self.current.rgb_data,
+        );
+
+        match frame.dispose {
+            DisposalMethod::Background => {
+                for row in 0..frame.height as usize {
+                    let y = top + row;
+                    if y >= canvas_height {
+                        break;
+                    }
+                    let start = (y * canvas_width + left.min(canvas_width)) * 4;
+                    let end =
+                        (y * canvas_width + (left + frame.width as usize).min(canvas_width)) * 4;
+                    canvas[start..end].fill(0);
+                }
+            }
+            DisposalMethod::Previous => {
+                if let Some(previous) = previous {
+                    *canvas = previous;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "gif")]
+impl FrameSource for GifFrames {
+    fn next_frame(&mut self) -> Option<&AnimationFrame> {
+        loop {
+            match self.decode_next() {
+                Ok(true) => {
+                    self.decoded_in_pass = true;
+                    return Some(&self.current);
+                }
+                Ok(false) if self.looped && self.decoded_in_pass => {
+                    // Start the next pass from a fresh decoder and canvas
+                    self.decoder = Self::decoder(&self.bytes).ok()?;
+                    self.canvas.fill(0);
+                    self.decoded_in_pass = false;
+                }
+                Ok(false) => return None,
+                Err(e) => {
+                    log::warn!("Stopping GIF playback: {}", e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gif")]
+fn gif_error(e: gif::DecodingError) -> MK3Error {
+    MK3Error::InvalidData(format!("GIF decode error: {}", e))
+}
+
+/// Nearest-neighbour scale of an RGBA image into a 480x272 RGB888 frame
+#[cfg(feature = "gif")]
+fn scale_rgba_to_display(rgba: &[u8], width: usize, height: usize, rgb: &mut [u8]) {
+    let out_width = MaschineMK3::DISPLAY_WIDTH as usize;
+    let out_height = MaschineMK3::DISPLAY_HEIGHT as usize;
+
+    if width == 0 || height == 0 {
+        rgb.fill(0);
+        return;
+    }
+
+    for y in 0..out_height {
+        let src_y = y * height / out_height;
+        for x in 0..out_width {
+            let src_x = x * width / out_width;
+            let src = (src_y * width + src_x) * 4;
+            let dst = (y * out_width + x) * 3;
+            rgb[dst..dst + 3].copy_from_slice(&rgba[src..src + 3]);
+        }
+    }
+}
+
+impl Drop for DisplayAnimator {
+    fn drop(&mut self) {
+        let _ = self.stop_all();
+    }
+}
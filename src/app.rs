@@ -0,0 +1,376 @@
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::error::Result;
+use crate::font::{self, TextStyle};
+use crate::input::InputEvent;
+use crate::output::{DisplayRect, LedFrame, RgbColor};
+use std::time::{Duration, Instant};
+
+/// Frame rate of a new [`App`]
+pub const DEFAULT_FRAME_RATE: u32 = 30;
+
+/// What a mode asks the [`App`] to do after handling an event or update
+pub enum Transition {
+    /// Stay on the current mode
+    None,
+    /// Leave the event to the mode below; only meaningful from `on_event`
+    Ignore,
+    /// Open a mode on top of this one, e.g. a menu or settings page
+    Push(Box<dyn Mode>),
+    /// Close this mode and return to the one below
+    Pop,
+    /// Swap this mode for another without growing the stack
+    Replace(Box<dyn Mode>),
+    /// Stop the main loop
+    Quit,
+}
+
+/// One page of a controller app: it handles input while on top of the
+/// stack and draws the displays and LEDs.
+///
+/// Displays keep their contents between frames, so a mode can redraw only
+/// what changed. LEDs start from all off every frame.
+pub trait Mode {
+    /// Handle an input event. Returning [`Transition::Ignore`] passes the
+    /// event on to the mode below.
+    fn on_event(&mut self, event: &InputEvent) -> Transition;
+
+    /// Draw the displays for the coming frame
+    fn render(&mut self, displays: &mut Displays);
+
+    /// Set the LEDs for the coming frame
+    fn lights(&mut self, leds: &mut LedFrame);
+
+    /// Advance animations or timers once per frame, before drawing
+    fn update(&mut self, _dt: Duration) -> Transition {
+        Transition::None
+    }
+
+    /// Called when the mode becomes the top of the stack, including when
+    /// the mode above it is popped
+    fn on_enter(&mut self) {}
+
+    /// Called when the mode stops being the top of the stack
+    fn on_exit(&mut self) {}
+
+    /// Whether the mode below is drawn first, so this one only paints over
+    /// part of it, e.g. a dialog
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// Both display framebuffers (480x272 RGB888) as modes draw them.
+///
+/// A display is only sent to the device when it was drawn to since the last
+/// frame.
+pub struct Displays {
+    frames: [Vec<u8>; 2],
+    dirty: [bool; 2],
+}
+
+impl Default for Displays {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Displays {
+    pub const WIDTH: usize = MaschineMK3::DISPLAY_WIDTH as usize;
+    pub const HEIGHT: usize = MaschineMK3::DISPLAY_HEIGHT as usize;
+
+    /// Two black displays, both to be sent on the first frame
+    pub fn new() -> Self {
+        let frame = vec![0u8; Self::WIDTH * Self::HEIGHT * 3];
+        Self {
+            frames: [frame.clone(), frame],
+            dirty: [true; 2],
+        }
+    }
+
+    /// RGB888 framebuffer of a display (0 = left, 1 = right)
+    pub fn frame(&self, display: u8) -> &[u8] {
+        &self.frames[Self::index(display)]
+    }
+
+    /// Mutable framebuffer of a display, which is then sent on the next frame
+    pub fn frame_mut(&mut self, display: u8) -> &mut [u8] {
+        let index = Self::index(display);
+        self.dirty[index] = true;
+        &mut self.frames[index]
+    }
+
+    /// Fill a whole display with one color
+    pub fn fill(&mut self, display: u8, color: RgbColor) {
+        self.fill_rect(display, DisplayRect::full_screen(), color);
+    }
+
+    /// Fill part of a display; the rectangle is clipped to the screen
+    pub fn fill_rect(&mut self, display: u8, rect: DisplayRect, color: RgbColor) {
+        let rect = rect.clipped_to(&DisplayRect::full_screen());
+        let frame = self.frame_mut(display);
+        for y in rect.y as usize..rect.bottom() as usize {
+            let start = (y * Self::WIDTH + rect.x as usize) * 3;
+            for pixel in frame[start..start + rect.width as usize * 3].chunks_exact_mut(3) {
+                pixel.copy_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+    }
+
+    /// Draw a line of text with its top-left corner at (x, y)
+    pub fn draw_text(&mut self, display: u8, x: usize, y: usize, text: &str, style: &TextStyle) {
+        font::draw_text(self.frame_mut(display), Self::WIDTH, x, y, text, style);
+    }
+
+    /// Whether a display was drawn to since it was last sent
+    pub fn is_dirty(&self, display: u8) -> bool {
+        self.dirty[Self::index(display)]
+    }
+
+    /// Send the displays that were drawn to since the last call
+    pub fn present<D: MaschineDevice>(&mut self, device: &mut D) -> Result<()> {
+        for display in 0..2u8 {
+            let index = display as usize;
+            if self.dirty[index] {
+                device.send_display_rgb888(display, &self.frames[index])?;
+                self.dirty[index] = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send both displays on the next frame, e.g. after a device reset
+    pub fn invalidate(&mut self) {
+        self.dirty = [true; 2];
+    }
+
+    /// Out of range displays draw to the right one
+    fn index(display: u8) -> usize {
+        (display as usize).min(1)
+    }
+}
+
+/// Controller app built from a stack of [`Mode`]s.
+///
+/// Each frame the app polls input and routes every event to the top mode,
+/// down the stack for as long as modes return [`Transition::Ignore`]. It then
+/// updates the top mode, lets it set the LEDs and draw the displays, and
+/// sends what changed. [`run`](Self::run) repeats this at a fixed frame rate
+/// until a mode quits or the stack is empty; [`step`](Self::step) runs a
+/// single frame for apps with their own loop.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::app::{App, Displays, Mode, Transition};
+/// use maschine3_hal::{InputElement, InputEvent, LedFrame, MaschineLEDColor, MaschineMK3};
+/// use maschine3_hal::{RgbColor, TextStyle};
+///
+/// struct Home {
+///     hits: u32,
+/// }
+///
+/// impl Mode for Home {
+///     fn on_event(&mut self, event: &InputEvent) -> Transition {
+///         match event {
+///             InputEvent::PadEvent { .. } => self.hits += 1,
+///             InputEvent::ButtonPressed(InputElement::Stop) => return Transition::Quit,
+///             _ => return Transition::Ignore,
+///         }
+///         Transition::None
+///     }
+///
+///     fn render(&mut self, displays: &mut Displays) {
+///         displays.fill(0, RgbColor::black());
+///         let text = format!("Hits: {}", self.hits);
+///         displays.draw_text(0, 16, 16, &text, &TextStyle::default());
+///     }
+///
+///     fn lights(&mut self, leds: &mut LedFrame) {
+///         leds.set_pad((self.hits % 16) as u8, MaschineLEDColor::green(true));
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// App::new(Box::new(Home { hits: 0 })).run(&mut device)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct App {
+    stack: Vec<Box<dyn Mode>>,
+    displays: Displays,
+    frame_period: Duration,
+    last_frame: Option<Instant>,
+    running: bool,
+}
+
+impl App {
+    /// App starting on `root`, at [`DEFAULT_FRAME_RATE`]
+    pub fn new(root: Box<dyn Mode>) -> Self {
+        let mut app = Self {
+            stack: Vec::new(),
+            displays: Displays::new(),
+            frame_period: Duration::ZERO,
+            last_frame: None,
+            running: true,
+        };
+        app.set_frame_rate(DEFAULT_FRAME_RATE);
+        app.push(root);
+        app
+    }
+
+    /// Frames per second of [`run`](Self::run), at least 1
+    pub fn with_frame_rate(mut self, fps: u32) -> Self {
+        self.set_frame_rate(fps);
+        self
+    }
+
+    pub fn set_frame_rate(&mut self, fps: u32) {
+        self.frame_period = Duration::from_secs(1) / fps.max(1);
+    }
+
+    pub fn frame_period(&self) -> Duration {
+        self.frame_period
+    }
+
+    /// Modes on the stack, including the root
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// False once a mode quit or the last mode was popped
+    pub fn is_running(&self) -> bool {
+        self.running && !self.stack.is_empty()
+    }
+
+    pub fn displays(&self) -> &Displays {
+        &self.displays
+    }
+
+    pub fn displays_mut(&mut self) -> &mut Displays {
+        &mut self.displays
+    }
+
+    /// Open a mode on top of the stack
+    pub fn push(&mut self, mode: Box<dyn Mode>) {
+        if let Some(top) = self.stack.last_mut() {
+            top.on_exit();
+        }
+        self.stack.push(mode);
+        self.enter_top();
+    }
+
+    /// Close the top mode, returning it
+    pub fn pop(&mut self) -> Option<Box<dyn Mode>> {
+        let mut mode = self.stack.pop()?;
+        mode.on_exit();
+        self.enter_top();
+        Some(mode)
+    }
+
+    /// Stop the main loop after the current frame
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    /// Drive the device at the frame rate until the app stops
+    pub fn run<D: MaschineDevice>(&mut self, device: &mut D) -> Result<()> {
+        let mut next_frame = Instant::now();
+        while self.is_running() {
+            self.step(device)?;
+
+            next_frame += self.frame_period;
+            let now = Instant::now();
+            if next_frame > now {
+                std::thread::sleep(next_frame - now);
+            } else {
+                // Fell behind: pace from now rather than rushing to catch up
+                next_frame = now;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a single frame: route input, update, draw and send. Returns
+    /// whether the app is still running.
+    pub fn step<D: MaschineDevice>(&mut self, device: &mut D) -> Result<bool> {
+        for event in device.poll_input_events()? {
+            if matches!(event, InputEvent::DeviceResumed) {
+                self.displays.invalidate();
+            }
+            self.route(&event);
+            if !self.is_running() {
+                return Ok(false);
+            }
+        }
+
+        let now = Instant::now();
+        let dt = self
+            .last_frame
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+        self.last_frame = Some(now);
+        if let Some(top) = self.stack.last_mut() {
+            let transition = top.update(dt);
+            self.apply(transition);
+        }
+        if !self.is_running() {
+            return Ok(false);
+        }
+
+        let mut leds = LedFrame::default();
+        // Modes under a chain of overlays draw first
+        let base = self
+            .stack
+            .iter()
+            .rposition(|mode| !mode.is_overlay())
+            .unwrap_or(0);
+        for mode in &mut self.stack[base..] {
+            mode.lights(&mut leds);
+            mode.render(&mut self.displays);
+        }
+        device.set_led_frame(leds)?;
+        self.displays.present(device)?;
+        Ok(true)
+    }
+
+    /// Offer an event to the modes from the top down until one handles it
+    fn route(&mut self, event: &InputEvent) {
+        for index in (0..self.stack.len()).rev() {
+            let transition = self.stack[index].on_event(event);
+            if matches!(transition, Transition::Ignore) {
+                continue;
+            }
+            // A mode below the top can open modes or quit, but popping or
+            // replacing would remove the mode above it
+            let is_top = index + 1 == self.stack.len();
+            if is_top || matches!(transition, Transition::Push(_) | Transition::Quit) {
+                self.apply(transition);
+            }
+            return;
+        }
+    }
+
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None | Transition::Ignore => {}
+            Transition::Push(mode) => self.push(mode),
+            Transition::Pop => {
+                self.pop();
+            }
+            Transition::Replace(mode) => {
+                if let Some(mut old) = self.stack.pop() {
+                    old.on_exit();
+                }
+                self.stack.push(mode);
+                self.enter_top();
+            }
+            Transition::Quit => self.quit(),
+        }
+    }
+
+    fn enter_top(&mut self) {
+        if let Some(top) = self.stack.last_mut() {
+            top.on_enter();
+        }
+    }
+}
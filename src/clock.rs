@@ -0,0 +1,394 @@
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use crate::input::{InputElement, InputEvent};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Ticks per beat, matching MIDI clock (24 PPQN) so external sync is 1:1
+pub const TICKS_PER_BEAT: u32 = 24;
+
+/// Tempo range accepted from taps and `set_bpm`
+pub(crate) const MIN_BPM: f32 = 20.0;
+pub(crate) const MAX_BPM: f32 = 300.0;
+
+/// Where clock ticks come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockSource {
+    /// Generated by the clock's own thread at the current BPM
+    #[default]
+    Internal,
+    /// Supplied through [`Clock::external_tick`], e.g. from incoming MIDI clock
+    External,
+}
+
+/// Position passed to clock callbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTick {
+    /// Ticks since the clock was started or reset
+    pub tick: u64,
+    /// Beats since the clock was started or reset
+    pub beat: u64,
+    /// Tick within the current beat (0 on the beat)
+    pub tick_in_beat: u32,
+}
+
+impl ClockTick {
+    fn from_tick(tick: u64) -> Self {
+        Self {
+            tick,
+            beat: tick / TICKS_PER_BEAT as u64,
+            tick_in_beat: (tick % TICKS_PER_BEAT as u64) as u32,
+        }
+    }
+
+    pub fn is_beat(&self) -> bool {
+        self.tick_in_beat == 0
+    }
+}
+
+/// Derives a tempo from the spacing of taps
+#[derive(Debug, Clone)]
+pub struct TapTempo {
+    last_tap: Option<Instant>,
+    intervals: VecDeque<Duration>,
+}
+
+impl TapTempo {
+    /// Taps further apart than this start a new measurement
+    const MAX_GAP: Duration = Duration::from_secs(2);
+    /// Number of recent intervals averaged
+    const HISTORY: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            last_tap: None,
+            intervals: VecDeque::with_capacity(Self::HISTORY),
+        }
+    }
+
+    /// Register a tap. Returns the tempo once at least two taps are close
+    /// enough together.
+    pub fn tap(&mut self, now: Instant) -> Option<f32> {
+        let previous = self.last_tap.replace(now);
+        let interval = now.duration_since(previous?);
+
+        if interval > Self::MAX_GAP {
+            self.intervals.clear();
+            return None;
+        }
+
+        if self.intervals.len() == Self::HISTORY {
+            self.intervals.pop_front();
+        }
+        self.intervals.push_back(interval);
+
+        let average = self.intervals.iter().sum::<Duration>() / self.intervals.len() as u32;
+        Some((60.0 / average.as_secs_f32()).clamp(MIN_BPM, MAX_BPM))
+    }
+
+    pub fn reset(&mut self) {
+        self.last_tap = None;
+        self.intervals.clear();
+    }
+}
+
+impl Default for TapTempo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type ClockCallback = Box<dyn FnMut(ClockTick) + Send>;
+
+/// State shared between the clock and its tick thread
+struct ClockShared {
+    bpm: f32,
+    source: ClockSource,
+    next_tick: u64,
+    last_external_tick: Option<Instant>,
+    on_tick: Vec<ClockCallback>,
+    on_beat: Vec<ClockCallback>,
+}
+
+impl ClockShared {
+    fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f32(60.0 / (self.bpm * TICKS_PER_BEAT as f32))
+    }
+}
+
+/// Tempo clock with tap tempo, tick/beat callbacks and external sync.
+///
+/// Callbacks run on the clock's thread for internal ticks, or on the caller's
+/// thread for [`external_tick`](Self::external_tick).
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{Clock, MaschineMK3};
+/// use std::sync::{Arc, Mutex};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = Arc::new(Mutex::new(MaschineMK3::new()?));
+/// let mut clock = Clock::new(120.0);
+/// clock.on_beat(|tick| println!("Beat {}", tick.beat + 1));
+/// clock.flash_leds_on_beat(Arc::clone(&device));
+/// clock.start()?;
+///
+/// loop {
+///     let events = device.lock().unwrap().poll_input_events()?;
+///     for event in events {
+///         // Tap button presses adjust the tempo
+///         clock.handle_event(&event);
+///     }
+/// }
+/// # }
+/// ```
+pub struct Clock {
+    shared: Arc<Mutex<ClockShared>>,
+    tap_tempo: TapTempo,
+    stop_signal: Arc<Mutex<bool>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Clock {
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(ClockShared {
+                bpm: bpm.clamp(MIN_BPM, MAX_BPM),
+                source: ClockSource::Internal,
+                next_tick: 0,
+                last_external_tick: None,
+                on_tick: Vec::new(),
+                on_beat: Vec::new(),
+            })),
+            tap_tempo: TapTempo::new(),
+            stop_signal: Arc::new(Mutex::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Current tempo. With an external source this is estimated from the
+    /// incoming tick spacing.
+    pub fn bpm(&self) -> f32 {
+        self.shared.lock().map(|shared| shared.bpm).unwrap_or(0.0)
+    }
+
+    pub fn set_bpm(&mut self, bpm: f32) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.bpm = bpm.clamp(MIN_BPM, MAX_BPM);
+        }
+    }
+
+    pub fn source(&self) -> ClockSource {
+        self.shared
+            .lock()
+            .map(|shared| shared.source)
+            .unwrap_or_default()
+    }
+
+    /// Switch between the internal tick thread and external ticks
+    pub fn set_source(&mut self, source: ClockSource) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.source = source;
+            shared.last_external_tick = None;
+        }
+    }
+
+    /// Call `callback` on every tick (`TICKS_PER_BEAT` per beat)
+    pub fn on_tick<F>(&mut self, callback: F)
+    where
+        F: FnMut(ClockTick) + Send + 'static,
+    {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.on_tick.push(Box::new(callback));
+        }
+    }
+
+    /// Call `callback` on the first tick of every beat
+    pub fn on_beat<F>(&mut self, callback: F)
+    where
+        F: FnMut(ClockTick) + Send + 'static,
+    {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.on_beat.push(Box::new(callback));
+        }
+    }
+
+    /// Light the Tap and Play LEDs for the first quarter of every beat
+    pub fn flash_leds_on_beat(&mut self, device: Arc<Mutex<MaschineMK3>>) {
+        let mut lit = false;
+        self.on_tick(move |tick| {
+            let should_light = tick.tick_in_beat < TICKS_PER_BEAT / 4;
+            if should_light == lit {
+                return;
+            }
+            lit = should_light;
+
+            let brightness = if lit { 127 } else { 0 };
+            if let Ok(mut device) = device.lock() {
+                let _ = device.update_leds(|leds| {
+                    leds.set_button(InputElement::Tap, brightness);
+                    leds.set_button(InputElement::Play, brightness);
+                });
+            }
+        });
+    }
+
+    /// Register a tap for tap tempo and adopt the resulting BPM.
+    /// Returns the new tempo once enough taps have been made.
+    pub fn tap(&mut self) -> Option<f32> {
+        let bpm = self.tap_tempo.tap(Instant::now())?;
+        self.set_bpm(bpm);
+        Some(bpm)
+    }
+
+    /// Feed input events; presses of the Tap button drive tap tempo.
+    /// Returns the new tempo if it changed.
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<f32> {
+        match event {
+            InputEvent::ButtonPressed(InputElement::Tap) => self.tap(),
+            _ => None,
+        }
+    }
+
+    /// Start the internal tick thread. Ticks are only generated while the
+    /// source is [`ClockSource::Internal`].
+    pub fn start(&mut self) -> Result<()> {
+        if self.thread.is_some() {
+            return Err(MK3Error::InvalidData("Clock already running".to_string()));
+        }
+
+        if let Ok(mut stop) = self.stop_signal.lock() {
+            *stop = false;
+        }
+
+        let shared = Arc::clone(&self.shared);
+        let stop_signal = Arc::clone(&self.stop_signal);
+
+        self.thread = Some(thread::spawn(move || {
+            let mut next_tick_at = Instant::now();
+
+            loop {
+                if let Ok(stop) = stop_signal.lock() {
+                    if *stop {
+                        break;
+                    }
+                }
+
+                let (interval, internal) = match shared.lock() {
+                    Ok(state) => (state.tick_interval(), state.source == ClockSource::Internal),
+                    Err(_) => break,
+                };
+
+                if internal {
+                    Self::emit_tick(&shared);
+                }
+
+                // Schedule against the ideal timeline so callback time
+                // doesn't accumulate into drift
+                next_tick_at += interval;
+                let now = Instant::now();
+                if next_tick_at > now {
+                    thread::sleep(next_tick_at - now);
+                } else {
+                    next_tick_at = now;
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Stop the internal tick thread
+    pub fn stop(&mut self) -> Result<()> {
+        if let Ok(mut stop) = self.stop_signal.lock() {
+            *stop = true;
+        }
+
+        if let Some(thread) = self.thread.take() {
+            thread
+                .join()
+                .map_err(|_| MK3Error::InvalidData("Failed to join clock thread".to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// Move back to tick 0 (e.g. on a MIDI Start message)
+    pub fn reset(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.next_tick = 0;
+            shared.last_external_tick = None;
+        }
+    }
+
+    /// Advance the clock by one tick from an external source such as MIDI
+    /// clock. Ignored unless the source is [`ClockSource::External`]. The
+    /// tempo is estimated from the spacing of these calls.
+    pub fn external_tick(&self) {
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        if shared.source != ClockSource::External {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(previous) = shared.last_external_tick.replace(now) {
+            let interval = now.duration_since(previous).as_secs_f32();
+            if interval > 0.0 {
+                let bpm = (60.0 / (interval * TICKS_PER_BEAT as f32)).clamp(MIN_BPM, MAX_BPM);
+                // Smooth out jitter in the incoming ticks
+                shared.bpm += (bpm - shared.bpm) * 0.1;
+            }
+        }
+        drop(shared);
+
+        Self::emit_tick(&self.shared);
+    }
+
+    /// Advance one tick and run the callbacks. The lock isn't held while they
+    /// run, so a slow callback doesn't block tempo changes.
+    fn emit_tick(shared: &Arc<Mutex<ClockShared>>) {
+        let (tick, mut on_tick, mut on_beat) = match shared.lock() {
+            Ok(mut state) => {
+                let tick = ClockTick::from_tick(state.next_tick);
+                state.next_tick += 1;
+                (
+                    tick,
+                    std::mem::take(&mut state.on_tick),
+                    std::mem::take(&mut state.on_beat),
+                )
+            }
+            Err(_) => return,
+        };
+
+        for callback in &mut on_tick {
+            callback(tick);
+        }
+        if tick.is_beat() {
+            for callback in &mut on_beat {
+                callback(tick);
+            }
+        }
+
+        // Put the callbacks back ahead of any registered while they ran
+        if let Ok(mut state) = shared.lock() {
+            on_tick.append(&mut state.on_tick);
+            on_beat.append(&mut state.on_beat);
+            state.on_tick = on_tick;
+            state.on_beat = on_beat;
+        }
+    }
+}
+
+impl Drop for Clock {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
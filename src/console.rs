@@ -0,0 +1,273 @@
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::error::Result;
+use crate::font::{self, TextStyle, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::output::RgbColor;
+use std::fmt;
+
+/// Columns a tab advances to a multiple of
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    c: char,
+    fg: RgbColor,
+    bg: RgbColor,
+}
+
+/// Text terminal on one display, for logs and quick status output.
+///
+/// Text is written with [`print`](Self::print) or through `fmt::Write`, wraps
+/// at the right edge and scrolls at the bottom. `\n`, `\r`, `\t` and
+/// backspace are interpreted. Nothing reaches the hardware until
+/// [`present`](Self::present), which sends only the changed parts of the
+/// screen.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{DisplayConsole, MaschineMK3, RgbColor};
+/// use std::fmt::Write;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut console = DisplayConsole::new(0);
+///
+/// writeln!(console, "Connected: {}", device.device_info()?)?;
+/// console.set_fg(RgbColor::green());
+/// console.print("ready\n");
+/// console.present(&mut device)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DisplayConsole {
+    display_id: u8,
+    scale: usize,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    /// Column may equal `columns` after writing the last cell of a line; the
+    /// wrap happens when the next character arrives
+    cursor: (usize, usize),
+    fg: RgbColor,
+    bg: RgbColor,
+    cursor_visible: bool,
+    dirty: bool,
+}
+
+impl DisplayConsole {
+    /// Console with 16x16 pixel characters (30 columns by 17 rows)
+    pub fn new(display_id: u8) -> Self {
+        Self::with_scale(display_id, 2)
+    }
+
+    /// Console with characters magnified `scale` times from 8x8 pixels
+    pub fn with_scale(display_id: u8, scale: usize) -> Self {
+        let scale = scale.max(1);
+        let columns = MaschineMK3::DISPLAY_WIDTH as usize / (GLYPH_WIDTH * scale);
+        let rows = MaschineMK3::DISPLAY_HEIGHT as usize / (GLYPH_HEIGHT * scale);
+        let fg = RgbColor::white();
+        let bg = RgbColor::black();
+
+        Self {
+            display_id,
+            scale,
+            columns,
+            rows,
+            cells: vec![Cell { c: ' ', fg, bg }; columns * rows],
+            cursor: (0, 0),
+            fg,
+            bg,
+            cursor_visible: false,
+            dirty: true,
+        }
+    }
+
+    pub fn display_id(&self) -> u8 {
+        self.display_id
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Color of text written from now on
+    pub fn set_fg(&mut self, color: RgbColor) {
+        self.fg = color;
+    }
+
+    /// Background of text written from now on, and of cleared cells
+    pub fn set_bg(&mut self, color: RgbColor) {
+        self.bg = color;
+    }
+
+    pub fn set_colors(&mut self, fg: RgbColor, bg: RgbColor) {
+        self.fg = fg;
+        self.bg = bg;
+    }
+
+    /// Back to white on black
+    pub fn reset_colors(&mut self) {
+        self.set_colors(RgbColor::white(), RgbColor::black());
+    }
+
+    /// Current cursor position as (column, row)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor.0.min(self.columns - 1), self.cursor.1)
+    }
+
+    /// Move the cursor, clamped to the screen
+    pub fn set_cursor(&mut self, column: usize, row: usize) {
+        self.cursor = (column.min(self.columns - 1), row.min(self.rows - 1));
+        self.dirty |= self.cursor_visible;
+    }
+
+    /// Draw the cursor as an inverted cell
+    pub fn show_cursor(&mut self, visible: bool) {
+        self.dirty |= self.cursor_visible != visible;
+        self.cursor_visible = visible;
+    }
+
+    /// Clear the screen with the current background and home the cursor
+    pub fn clear(&mut self) {
+        let blank = self.blank();
+        self.cells.fill(blank);
+        self.cursor = (0, 0);
+        self.dirty = true;
+    }
+
+    /// Clear the cursor's row and move the cursor to its start
+    pub fn clear_line(&mut self) {
+        let blank = self.blank();
+        let row = self.cursor.1;
+        self.cells[row * self.columns..(row + 1) * self.columns].fill(blank);
+        self.cursor.0 = 0;
+        self.dirty = true;
+    }
+
+    /// Write text at the cursor
+    pub fn print(&mut self, text: &str) {
+        for c in text.chars() {
+            self.put_char(c);
+        }
+    }
+
+    /// Write text at the cursor followed by a newline
+    pub fn println(&mut self, text: &str) {
+        self.print(text);
+        self.put_char('\n');
+    }
+
+    /// Move every row up by `lines`, clearing the rows revealed at the bottom
+    pub fn scroll_up(&mut self, lines: usize) {
+        let lines = lines.min(self.rows);
+        let blank = self.blank();
+        self.cells.drain(..lines * self.columns);
+        self.cells
+            .extend(std::iter::repeat_n(blank, lines * self.columns));
+        self.cursor.1 = self.cursor.1.saturating_sub(lines);
+        self.dirty = true;
+    }
+
+    /// Whether the screen changed since the last `present`
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Render the console into a full-screen RGB888 frame
+    pub fn render(&self) -> Vec<u8> {
+        let width = MaschineMK3::DISPLAY_WIDTH as usize;
+        let height = MaschineMK3::DISPLAY_HEIGHT as usize;
+        let mut frame = vec![0u8; width * height * 3];
+        for pixel in frame.chunks_exact_mut(3) {
+            pixel.copy_from_slice(&[self.bg.r, self.bg.g, self.bg.b]);
+        }
+
+        let cursor = self.cursor_visible.then(|| self.cursor());
+        for (index, cell) in self.cells.iter().enumerate() {
+            let (column, row) = (index % self.columns, index / self.columns);
+            let (fg, bg) = if cursor == Some((column, row)) {
+                (cell.bg, cell.fg)
+            } else {
+                (cell.fg, cell.bg)
+            };
+            let style = TextStyle {
+                fg,
+                bg: Some(bg),
+                scale: self.scale,
+            };
+            font::draw_char(
+                &mut frame,
+                width,
+                column * GLYPH_WIDTH * self.scale,
+                row * GLYPH_HEIGHT * self.scale,
+                cell.c,
+                &style,
+            );
+        }
+        frame
+    }
+
+    /// Send the console to its display if it changed
+    pub fn present<D: MaschineDevice>(&mut self, device: &mut D) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        device.send_display_rgb888(self.display_id, &self.render())?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn blank(&self) -> Cell {
+        Cell {
+            c: ' ',
+            fg: self.fg,
+            bg: self.bg,
+        }
+    }
+
+    fn put_char(&mut self, c: char) {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => self.cursor.0 = 0,
+            '\t' => {
+                let next = (self.cursor.0 / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.cursor.0 = next.min(self.columns);
+            }
+            '\x08' => self.cursor.0 = self.cursor().0.saturating_sub(1),
+            c if c.is_control() => return,
+            c => {
+                if self.cursor.0 >= self.columns {
+                    self.new_line();
+                }
+                let (column, row) = self.cursor;
+                self.cells[row * self.columns + column] = Cell {
+                    c,
+                    fg: self.fg,
+                    bg: self.bg,
+                };
+                self.cursor.0 += 1;
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn new_line(&mut self) {
+        self.cursor.0 = 0;
+        if self.cursor.1 + 1 >= self.rows {
+            self.scroll_up(1);
+        }
+        self.cursor.1 += 1;
+    }
+}
+
+impl fmt::Write for DisplayConsole {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.print(s);
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! Coordinate conventions shared by the display, pad and touch strip APIs,
+//! and the types that carry them.
+//!
+//! - **Display pixels**: the origin is the top-left corner of each 480x272
+//!   display, x runs right and y down. Display 0 is the left screen, 1 the
+//!   right. [`DisplayPoint`] and [`DisplayRect`](crate::DisplayRect) hold
+//!   coordinates in this space and their checked constructors reject
+//!   anything off the screen. Rectangles are half-open:
+//!   [`right`](crate::DisplayRect::right) and
+//!   [`bottom`](crate::DisplayRect::bottom) are the first column and row
+//!   past the area.
+//! - **Pads**: numbered 0-15 row by row from the top-left pad, the same in
+//!   input events and LED APIs; [`PadIndex`] holds such a number. The
+//!   controller's LEDs are wired in another order, see [`PAD_LED_LAYOUT`],
+//!   which only the packet code deals with.
+//! - **Touch strip**: LEDs 0-24 and the finger position (0-1023) both run
+//!   left to right.
+
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use crate::output::PAD_LED_LAYOUT;
+
+/// Pixel on a display, see the [module docs](self) for the axes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DisplayPoint {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl DisplayPoint {
+    /// Top-left pixel
+    pub const ORIGIN: Self = Self { x: 0, y: 0 };
+
+    /// Point on the 480x272 display; an error if it lies outside
+    pub fn new(x: u16, y: u16) -> Result<Self> {
+        let point = Self { x, y };
+        if !point.is_on_screen() {
+            return Err(MK3Error::InvalidData(format!(
+                "Point ({}, {}) is outside the {}x{} display",
+                x,
+                y,
+                MaschineMK3::DISPLAY_WIDTH,
+                MaschineMK3::DISPLAY_HEIGHT
+            )));
+        }
+        Ok(point)
+    }
+
+    pub fn is_on_screen(&self) -> bool {
+        self.x < MaschineMK3::DISPLAY_WIDTH && self.y < MaschineMK3::DISPLAY_HEIGHT
+    }
+}
+
+/// Pad number 0-15, counted row by row from the top-left pad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PadIndex(u8);
+
+impl PadIndex {
+    pub const COUNT: u8 = 16;
+
+    /// `None` unless `pad` is 0-15
+    pub const fn new(pad: u8) -> Option<Self> {
+        if pad < Self::COUNT {
+            Some(Self(pad))
+        } else {
+            None
+        }
+    }
+
+    /// Pad at a grid position: row 0 is the top row, column 0 the left
+    /// column. `None` outside the 4x4 grid.
+    pub const fn from_grid(row: u8, col: u8) -> Option<Self> {
+        if row < 4 && col < 4 {
+            Some(Self(row * 4 + col))
+        } else {
+            None
+        }
+    }
+
+    /// Every pad in order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..Self::COUNT).map(Self)
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// Grid row, 0 being the top row
+    pub const fn row(self) -> u8 {
+        self.0 / 4
+    }
+
+    /// Grid column, 0 being the left column
+    pub const fn col(self) -> u8 {
+        self.0 % 4
+    }
+
+    /// Position of this pad's LED in the pad LED packet
+    pub const fn led_slot(self) -> u8 {
+        PAD_LED_LAYOUT[self.0 as usize]
+    }
+}
+
+impl TryFrom<u8> for PadIndex {
+    type Error = MK3Error;
+
+    fn try_from(pad: u8) -> Result<Self> {
+        Self::new(pad).ok_or_else(|| MK3Error::InvalidData("Pad number must be 0-15".to_string()))
+    }
+}
+
+impl From<PadIndex> for u8 {
+    fn from(pad: PadIndex) -> Self {
+        pad.0
+    }
+}
@@ -1,27 +1,963 @@
+use crate::coords::PadIndex;
+use crate::display_handle::DisplayHandle;
+use crate::encoder::EncoderAccelerationConfig;
 use crate::error::{MK3Error, Result};
-use crate::input::{InputElement, InputEvent, InputState, InputTracker, PadState};
-use crate::output::{DisplayPacket, MaschineLEDColor, Rgb565};
-use crate::{ButtonLedState, PadLedState};
+use crate::gesture::{KnobGestureConfig, TouchStripGestureConfig};
+use crate::health::{EndpointHealth, HealthMonitor, UsbEndpoint, UsbHealth};
+use crate::identify;
+use crate::input::{
+    ButtonGestureConfig, InputElement, InputEvent, InputState, InputTracker, PadPressureConfig,
+    PadState, ProtocolWarningKind, RawInputPacket,
+};
+use crate::led_animation::{LedAnimation, LedAnimator};
+use crate::led_group::{LedGroup, LedValue};
+#[cfg(feature = "png")]
+use crate::output::save_rgb888_png;
+use crate::output::{
+    ColorCorrection, ConversionOptions, CorrectionTable, DirtyStats, DisplayGraphics,
+    DisplayPacket, DisplayRect, LedCalibration, LedFrame, MaschineLEDColor, PacketCache,
+    RawPacket, Rgb565, Rgb565Converter, RgbColor,
+};
+use crate::pad_filter::PadFilterConfig;
+use crate::pad_flash::{PadFlashConfig, PadFlasher};
+use crate::process_lock::ProcessLock;
+use crate::recorder::OutputRecorder;
+#[cfg(feature = "scripting")]
+use crate::script::MacroScript;
+use crate::selftest::{self, SelfTestEvent, SelfTestReport};
+use crate::settings::DeviceSettings;
+use crate::splash::{self, SplashImage, SplashTransition};
+use crate::state::DeviceState;
+use crate::strip::{StripMode, STRIP_LED_COUNT};
+use crate::strip_follow::{StripFollowConfig, StripFollower};
+use crate::topology::{Backend, DeviceTopology, DisplayStatus, EndpointInfo, InterfaceInfo};
+use crate::{ButtonLedPacket, ButtonLedState, PadLedPacket, PadLedState};
 use rusb::{Context, Device, DeviceHandle, UsbContext};
-use std::sync::mpsc::{self, Receiver};
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+#[cfg(windows)]
+use crate::platform::{self, windows::DriverStatus};
 #[cfg(windows)]
 use hidapi::{HidApi, HidDevice};
 
 /// Native Instruments Maschine MK3 USB constants
-const VENDOR_ID: u16 = 0x17CC;
-const PRODUCT_ID: u16 = 0x1600;
+pub(crate) const VENDOR_ID: u16 = 0x17CC;
+pub(crate) const PRODUCT_ID: u16 = 0x1600;
 
 /// USB Interface and Endpoint constants
 const HID_INTERFACE: u8 = 4;
-const DISPLAY_INTERFACE: u8 = 5; // Back to original - Interface 5 with WinUSB
+pub(crate) const DISPLAY_INTERFACE: u8 = 5; // Back to original - Interface 5 with WinUSB
 const INPUT_ENDPOINT: u8 = 0x83;
 const OUTPUT_ENDPOINT: u8 = 0x03;
 const DISPLAY_ENDPOINT: u8 = 0x04; // Original endpoint 0x04 from interface 5
 
+/// Default read timeout for the input monitoring thread
+const DEFAULT_INPUT_READ_TIMEOUT: Duration = Duration::from_millis(250);
+/// Pause after a failed read before the monitoring thread retries
+const INPUT_ERROR_BACKOFF: Duration = Duration::from_millis(10);
+/// Read timeouts and error back-off in low-latency mode
+const LOW_LATENCY_INTERVAL: Duration = Duration::from_millis(1);
+/// How often a waiting [`EventStream`] checks whether monitoring failed
+const EVENTS_STATUS_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the monitoring thread tries to reopen a lost controller
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+/// Failed reads in a row after which the monitoring thread restarts
+const MONITOR_ERROR_LIMIT: u32 = 50;
+/// Restarts without a packet read in between before monitoring gives up
+const MONITOR_MAX_RESTARTS: u32 = 5;
+/// Pause before the first restart, doubled for each one after it
+const MONITOR_RESTART_BACKOFF: Duration = Duration::from_millis(100);
+/// Wall-clock time running this far ahead of monotonic time means the host slept
+const SUSPEND_DETECTION_GAP: Duration = Duration::from_secs(5);
+/// Read timeout of the monitoring thread while a touch strip trail fades
+const STRIP_FOLLOW_FRAME: Duration = Duration::from_millis(20);
+
+/// What the device shows once the connection is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownBehavior {
+    /// Leave LEDs lit and displays showing their last frame
+    #[default]
+    LeaveAsIs,
+    /// Turn off all LEDs and blank both displays
+    ClearAll,
+    /// Turn off all LEDs and show a logo on both displays
+    ShowLogo,
+    /// Fade the displays to black over the given time, e.g. to close a
+    /// [`show_splash`](MaschineMK3::show_splash) startup, then turn off all
+    /// LEDs. Only frames sent through the dirty-region path can fade; other
+    /// displays are blanked at the end.
+    FadeOut(Duration),
+}
+
+/// Timeouts for USB transfers, see [`MaschineMK3::set_timeouts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferTimeouts {
+    /// Input reads by [`MaschineMK3::poll_input_events`]. The monitoring
+    /// thread has its own, see [`MaschineMK3::set_input_read_timeout`].
+    pub input: Duration,
+    /// LED writes over USB. HID writes on Windows block until they finish.
+    pub led: Duration,
+    /// Display writes, per attempt when a stalled write is retried
+    pub display: Duration,
+}
+
+impl Default for TransferTimeouts {
+    fn default() -> Self {
+        Self {
+            input: Duration::from_millis(100),
+            led: Duration::from_millis(100),
+            display: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// How failed USB writes are retried, see [`MaschineMK3::set_retry_policy`].
+///
+/// Timeouts and stalls (`Pipe`) are what heavy bus load usually causes;
+/// `Busy` and `Interrupted` are always worth another try. A lost device
+/// (`NoDevice`) and other errors fail straight away and are left to
+/// [`MaschineMK3::check_resume`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first failed attempt; 0 turns retrying off
+    pub attempts: u32,
+    /// Pause before the first retry, doubled for each one after it
+    pub backoff: Duration,
+    /// Longest pause between two attempts
+    pub max_backoff: Duration,
+    /// Retry writes that timed out. Each attempt waits for the full
+    /// transfer timeout, so this multiplies how long a hung write blocks.
+    pub retry_timeouts: bool,
+    /// Retry writes the endpoint stalled, after clearing the stall
+    pub retry_stalls: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 2,
+            backoff: Duration::from_millis(2),
+            max_backoff: Duration::from_millis(20),
+            retry_timeouts: true,
+            retry_stalls: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Fail on the first error
+    pub fn disabled() -> Self {
+        Self {
+            attempts: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a write that failed with `error` is worth retrying
+    pub fn retries(&self, error: &rusb::Error) -> bool {
+        match error {
+            rusb::Error::Timeout => self.retry_timeouts,
+            rusb::Error::Pipe => self.retry_stalls,
+            rusb::Error::Busy | rusb::Error::Interrupted => true,
+            _ => false,
+        }
+    }
+
+    /// Pause before retry number `retry` (1 for the first)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let doublings = retry.saturating_sub(1).min(31);
+        self.backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+}
+
+/// Log a connection diagnostic unless [`ConnectionOptions::quiet`] is set
+macro_rules! connection_log {
+    ($options:expr, $level:ident, $($arg:tt)+) => {
+        if !$options.quiet {
+            log::$level!($($arg)+);
+        }
+    };
+}
+
+/// How [`MaschineMK3::new_with_options`] claims the controller's
+/// interfaces. The options are kept for
+/// [`reconnect`](MaschineMK3::reconnect) and the input monitoring thread.
+///
+/// ```no_run
+/// use maschine3_hal::{ConnectionOptions, MaschineMK3};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = ConnectionOptions::default()
+///     .require_display(true)
+///     .claim_retries(5, Duration::from_millis(200));
+/// let device = MaschineMK3::new_with_options(options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// Claim the display interface when connecting. Apps that only use input
+    /// and LEDs can leave it free and claim it later with
+    /// [`MaschineMK3::retry_display_claim`].
+    pub claim_display: bool,
+    /// Fail with [`MK3Error::DisplayUnavailable`] if the display interface
+    /// can't be claimed, instead of connecting without displays
+    pub require_display: bool,
+    /// If the display interface can't be claimed, try interface 3 instead.
+    /// On by default on Windows only.
+    pub try_alternate_display_interface: bool,
+    /// Attempts after the first at claiming an interface, e.g. while another
+    /// process is still letting go of the controller
+    pub claim_retries: u32,
+    /// Wait before each retry
+    pub retry_delay: Duration,
+    /// Detach a kernel driver bound to an interface before claiming it
+    /// (Linux; Windows has no kernel driver detachment)
+    pub detach_kernel_driver: bool,
+    /// Don't log connection diagnostics, even with a logger installed, see
+    /// [`MaschineMK3::new_quiet`]
+    pub quiet: bool,
+    /// Load and apply the unit's saved [`DeviceSettings`] once connected
+    pub load_settings: bool,
+    /// Take a lock shared with other processes using this crate before
+    /// claiming the controller, so a second instance fails with
+    /// [`MK3Error::DeviceBusy`] instead of sharing the interfaces
+    pub process_lock: bool,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            claim_display: true,
+            require_display: false,
+            try_alternate_display_interface: cfg!(windows),
+            claim_retries: 0,
+            retry_delay: Duration::from_millis(100),
+            detach_kernel_driver: true,
+            quiet: false,
+            load_settings: true,
+            process_lock: true,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    pub fn claim_display(mut self, claim: bool) -> Self {
+        self.claim_display = claim;
+        self
+    }
+
+    pub fn require_display(mut self, require: bool) -> Self {
+        self.require_display = require;
+        self
+    }
+
+    pub fn try_alternate_display_interface(mut self, try_alternate: bool) -> Self {
+        self.try_alternate_display_interface = try_alternate;
+        self
+    }
+
+    pub fn claim_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.claim_retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    pub fn detach_kernel_driver(mut self, detach: bool) -> Self {
+        self.detach_kernel_driver = detach;
+        self
+    }
+
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn load_settings(mut self, load: bool) -> Self {
+        self.load_settings = load;
+        self
+    }
+
+    pub fn process_lock(mut self, lock: bool) -> Self {
+        self.process_lock = lock;
+        self
+    }
+}
+
+/// Input packets processed and lost, see [`MaschineMK3::input_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputStats {
+    /// Button and pad packets processed
+    pub packets: u64,
+    /// Packets known to have been lost, as reported by
+    /// `InputEvent::PacketsDropped`
+    pub dropped: u64,
+}
+
+/// What the input monitoring thread is doing, see
+/// [`MaschineMK3::monitoring_status`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MonitoringStatus {
+    /// Monitoring isn't running
+    #[default]
+    Stopped,
+    Running,
+    /// The thread panicked or its reads kept failing. It is waiting to
+    /// reopen the controller and start over; `attempt` counts the restarts
+    /// since the last packet was read.
+    Restarting {
+        attempt: u32,
+        reason: String,
+    },
+    /// Restarting failed too often and the thread has ended. No more events
+    /// arrive until monitoring is stopped and started again.
+    Failed(String),
+}
+
+impl MonitoringStatus {
+    /// Whether events are being read
+    pub fn is_running(&self) -> bool {
+        *self == MonitoringStatus::Running
+    }
+}
+
+/// Blocking iterator over input events, see [`MaschineMK3::events`]
+pub struct EventStream<'a> {
+    device: &'a mut MaschineMK3,
+    error: Option<MK3Error>,
+}
+
+impl EventStream<'_> {
+    /// The device, e.g. to set LEDs between events. Iterate with
+    /// `while let Some(event) = events.next()` to call it in the loop.
+    pub fn device(&mut self) -> &mut MaschineMK3 {
+        self.device
+    }
+
+    /// Why monitoring couldn't be started, if the iterator had to start it
+    /// and failed
+    pub fn error(&self) -> Option<&MK3Error> {
+        self.error.as_ref()
+    }
+}
+
+impl Iterator for EventStream<'_> {
+    type Item = InputEvent;
+
+    fn next(&mut self) -> Option<InputEvent> {
+        loop {
+            let receiver = self.device.input_event_receiver.as_ref()?;
+            match receiver.recv_timeout(EVENTS_STATUS_INTERVAL) {
+                Ok(event) => return Some(event),
+                // The device keeps a sender for injected events, so the
+                // channel stays open after the thread gives up
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if let MonitoringStatus::Stopped | MonitoringStatus::Failed(_) =
+                        self.device.monitoring_status()
+                    {
+                        return None;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+/// Counters behind [`InputStats`], shared with the monitoring thread
+#[derive(Debug, Default)]
+struct InputCounters {
+    packets: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Dirty-region tracking for one display
+#[derive(Debug, Default)]
+struct DisplayCache {
+    /// Last frame sent, in `format`
+    framebuffer: Option<Vec<u8>>,
+    format: FrameFormat,
+    /// Area resent on the next write regardless of whether it changed
+    invalidated: DisplayRect,
+    /// Area changed since the last write, as reported by
+    /// [`MaschineMK3::mark_damaged`]
+    damaged: DisplayRect,
+    last_stats: DirtyStats,
+    /// Applied to RGB888 data on its way to this display
+    correction: Option<CorrectionTable>,
+    conversion: ConversionOptions,
+    /// Refresh interval while nothing else is sent, see
+    /// [`MaschineMK3::set_display_keep_alive`]
+    keep_alive: Option<Duration>,
+    /// Packets of recently sent dirty regions, reused when the same content
+    /// is redrawn
+    packets: PacketCache,
+}
+
+impl DisplayCache {
+    fn converter(&self) -> Rgb565Converter<'_> {
+        Rgb565Converter::new(self.conversion, self.correction.as_ref())
+    }
+
+    /// Cached frame as RGB888, widening it if it was sent as RGB565X
+    fn rgb888_framebuffer(&self) -> Option<Vec<u8>> {
+        let framebuffer = self.framebuffer.as_ref()?;
+        Some(match self.format {
+            FrameFormat::Rgb888 => framebuffer.clone(),
+            FrameFormat::Rgb565 => FrameFormat::rgb565_pixels(framebuffer)
+                .flat_map(|pixel| {
+                    let color = pixel.to_rgb();
+                    [color.r, color.g, color.b]
+                })
+                .collect(),
+        })
+    }
+}
+
+/// A dirty-region write worked out by `MaschineMK3::plan_dirty_write`,
+/// waiting for its packet to be built and sent
+#[derive(Debug)]
+struct DirtyWrite {
+    display_num: u8,
+    format: FrameFormat,
+    /// Region to send, aligned to pixel pairs
+    dirty: DisplayRect,
+    /// Packet cache key of the region's content
+    key: u64,
+    /// Packet sent for the same content before, until `packet` takes it
+    cached: Option<Vec<u8>>,
+    from_cache: bool,
+}
+
+impl DirtyWrite {
+    /// Packet for the region, reused from the cache or converted from
+    /// `frame` with the display's settings
+    fn packet(&mut self, cache: &DisplayCache, frame: &[u8]) -> Vec<u8> {
+        match self.cached.take() {
+            Some(packet) => packet,
+            None => {
+                let converter = cache.converter();
+                let pixels =
+                    MaschineMK3::region_to_rgb565(converter, self.format, frame, self.dirty);
+                DisplayPacket::region(self.display_num, self.dirty, pixels).to_packet()
+            }
+        }
+    }
+}
+
+/// Descriptor strings of the connected unit, read once per connection so
+/// the getters don't touch the bus
+#[derive(Debug, Clone)]
+struct DescriptorStrings {
+    vendor_id: u16,
+    product_id: u16,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial_number: std::result::Result<String, rusb::Error>,
+}
+
+impl DescriptorStrings {
+    fn read(handle: &DeviceHandle<Context>) -> Result<Self> {
+        let device_desc = handle.device().device_descriptor()?;
+        Ok(Self {
+            vendor_id: device_desc.vendor_id(),
+            product_id: device_desc.product_id(),
+            manufacturer: handle.read_manufacturer_string_ascii(&device_desc).ok(),
+            product: handle.read_product_string_ascii(&device_desc).ok(),
+            serial_number: handle.read_serial_number_string_ascii(&device_desc),
+        })
+    }
+}
+
+/// Pixel format of a display's cached framebuffer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum FrameFormat {
+    /// Three bytes per pixel, converted (and corrected) when sent
+    #[default]
+    Rgb888,
+    /// Native pixels, two little-endian bytes each, sent as they are
+    Rgb565,
+}
+
+impl FrameFormat {
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            FrameFormat::Rgb888 => 3,
+            FrameFormat::Rgb565 => 2,
+        }
+    }
+
+    fn rgb565_pixels(data: &[u8]) -> impl Iterator<Item = Rgb565> + '_ {
+        data.chunks_exact(2).map(|bytes| Rgb565 {
+            value: u16::from_le_bytes([bytes[0], bytes[1]]),
+        })
+    }
+}
+
+/// Operations shared by the hardware controller and the software simulator
+/// (`simulator` feature), so applications can be written against either
+pub trait MaschineDevice {
+    /// Input events since the last call
+    fn poll_input_events(&mut self) -> Result<Vec<InputEvent>>;
+
+    /// Current button, pad and touch strip LED state
+    fn led_frame(&self) -> LedFrame;
+
+    /// Replace the whole LED state
+    fn set_led_frame(&mut self, frame: LedFrame) -> Result<()>;
+
+    /// Send a full RGB888 frame (480x272x3 bytes) to a display
+    fn send_display_rgb888(&mut self, display_num: u8, rgb_data: &[u8]) -> Result<()>;
+
+    /// Last full frame sent to a display, if known
+    fn framebuffer_snapshot(&self, display_num: u8) -> Option<Vec<u8>>;
+
+    /// Stage LED changes on a copy of the current state and apply them at once
+    fn update_leds<F>(&mut self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut LedFrame),
+        Self: Sized,
+    {
+        let mut frame = self.led_frame();
+        update(&mut frame);
+        self.set_led_frame(frame)
+    }
+}
+
+/// Where a controller is plugged in, which stays the same when it resets
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UsbPort {
+    bus: u8,
+    ports: Vec<u8>,
+}
+
+impl UsbPort {
+    fn of(device: &Device<Context>) -> Self {
+        Self {
+            bus: device.bus_number(),
+            ports: device.port_numbers().unwrap_or_default(),
+        }
+    }
+
+    /// Name of the cross-process lock for the controller on this port
+    fn lock_name(&self) -> String {
+        let ports: Vec<String> = self.ports.iter().map(u8::to_string).collect();
+        format!("{}-{}", self.bus, ports.join("."))
+    }
+}
+
+/// LED packets as last sent, to skip identical writes. Behind a lock so the
+/// raw `&self` writers can update it too.
+#[derive(Debug, Default)]
+struct SentLedPackets {
+    buttons: Option<ButtonLedPacket>,
+    pads: Option<PadLedPacket>,
+}
+
+/// The pad LEDs as sent, shared with the monitoring thread so it can redraw
+/// the touch strip on top of them without the app polling
+#[derive(Debug, Default)]
+struct SharedPadLeds {
+    /// State the device last sent, unscaled, and the master brightness it
+    /// was sent with
+    sent: Option<(PadLedState, f32)>,
+    /// Touch strip colors last sent by either side, unscaled
+    shown_strip: [MaschineLEDColor; STRIP_LED_COUNT],
+    /// Touch strip colors the monitoring thread sent since the device last
+    /// sent the pads
+    thread_strip: Option<[MaschineLEDColor; STRIP_LED_COUNT]>,
+}
+
+impl SharedPadLeds {
+    /// Take over the strip the monitoring thread drew into `leds`, unless
+    /// the app changed the strip since the device last sent it. Returns
+    /// whether the thread had sent one, so the device's last packet is no
+    /// longer what is shown.
+    fn adopt_thread_strip(&mut self, leds: &mut PadLedState) -> bool {
+        let Some(strip) = self.thread_strip.take() else {
+            return false;
+        };
+        let unchanged = match &self.sent {
+            Some((sent, _)) => sent.touch_strip_leds == leds.touch_strip_leds,
+            None => true,
+        };
+        if unchanged {
+            leds.touch_strip_leds = strip;
+        }
+        true
+    }
+
+    fn record_sent(&mut self, leds: &PadLedState, brightness: f32) {
+        self.sent = Some((leds.clone(), brightness));
+        self.shown_strip = leds.touch_strip_leds;
+        self.thread_strip = None;
+    }
+
+    /// The last sent pad packet with the touch strip replaced by `strip`
+    fn strip_packet(&self, strip: [MaschineLEDColor; STRIP_LED_COUNT]) -> PadLedPacket {
+        let (mut leds, brightness) = self.sent.clone().unwrap_or((PadLedState::default(), 1.0));
+        leds.touch_strip_leds = strip;
+        if brightness < 1.0 {
+            leds = leds.scaled(brightness);
+        }
+        leds.to_packet()
+    }
+}
+
+/// Detects host sleep: the wall clock keeps running while the machine is
+/// suspended but the monotonic clock doesn't
+#[derive(Debug)]
+struct SleepDetector {
+    monotonic: Instant,
+    wall: SystemTime,
+}
+
+impl SleepDetector {
+    fn new() -> Self {
+        Self {
+            monotonic: Instant::now(),
+            wall: SystemTime::now(),
+        }
+    }
+
+    /// Whether the host slept since the previous call
+    fn slept(&mut self) -> bool {
+        let monotonic = self.monotonic.elapsed();
+        let wall = self.wall.elapsed().unwrap_or_default();
+        *self = Self::new();
+        wall > monotonic + SUSPEND_DETECTION_GAP
+    }
+}
+
+/// A setting changed while the monitoring thread runs, applied before it
+/// handles its next packet
+enum WorkerUpdate {
+    /// Reconfigure the input tracker, and what a restart resets it to
+    Tracker(Box<dyn Fn(&mut InputTracker) + Send>),
+    ReadTimeout(Duration),
+    LowLatency,
+    LedTimeout(Duration),
+    OutputRecorder(Option<OutputRecorder>),
+}
+
+/// The monitoring thread's state, run under a supervisor that restarts it
+struct InputWorker<F> {
+    handle: DeviceHandle<Context>,
+    context: Context,
+    usb_port: UsbPort,
+    options: ConnectionOptions,
+    tracker: InputTracker,
+    // What a restart resets the tracker to
+    initial_tracker: InputTracker,
+    read_timeout: Duration,
+    error_backoff: Duration,
+    low_latency: bool,
+    // Whether a packet was read since the last restart
+    read_packets: bool,
+    callback: F,
+    sender: Sender<InputEvent>,
+    stop_receiver: Receiver<()>,
+    updates: Receiver<WorkerUpdate>,
+    resume_pending: Arc<AtomicBool>,
+    counters: Arc<InputCounters>,
+    sync_requested: Arc<AtomicBool>,
+    health: Arc<HealthMonitor>,
+    strip_follower: Arc<Mutex<StripFollower>>,
+    pad_leds: Arc<Mutex<SharedPadLeds>>,
+    led_timeout: Duration,
+    output_recorder: Option<OutputRecorder>,
+    status: Arc<Mutex<MonitoringStatus>>,
+}
+
+impl<F> InputWorker<F>
+where
+    F: Fn(InputEvent) + Send + 'static,
+{
+    /// Run the read loop until stopped, restarting it with a fresh handle
+    /// and tracker when it panics or fails
+    fn supervise(mut self) {
+        if self.low_latency {
+            MaschineMK3::raise_input_thread_priority();
+        }
+
+        let mut restarts = 0;
+        let mut reopen_error = None;
+        loop {
+            let reason = match reopen_error.take() {
+                Some(reason) => reason,
+                None => match panic::catch_unwind(AssertUnwindSafe(|| self.run())) {
+                    Ok(None) => break,
+                    Ok(Some(reason)) => reason,
+                    Err(payload) => format!("Input thread panicked: {}", panic_message(&*payload)),
+                },
+            };
+
+            if std::mem::take(&mut self.read_packets) {
+                restarts = 0;
+            }
+            restarts += 1;
+            if restarts > MONITOR_MAX_RESTARTS {
+                log::error!(
+                    "Input monitoring stopped after {} restarts: {}",
+                    MONITOR_MAX_RESTARTS,
+                    reason
+                );
+                self.set_status(MonitoringStatus::Failed(reason));
+                return;
+            }
+            log::warn!(
+                "Restarting input monitoring (attempt {}): {}",
+                restarts,
+                reason
+            );
+            self.set_status(MonitoringStatus::Restarting {
+                attempt: restarts,
+                reason,
+            });
+
+            // Back off, waking at once on shutdown
+            let backoff = MONITOR_RESTART_BACKOFF * 2u32.pow(restarts - 1);
+            match self.stop_receiver.recv_timeout(backoff) {
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+
+            let reopened = MaschineMK3::find_device(&self.context, Some(&self.usb_port))
+                .and_then(|device| MaschineMK3::open_input_handle(&device, &self.options));
+            match reopened {
+                Ok(handle) => {
+                    self.handle = handle;
+                    self.tracker = self.initial_tracker.clone();
+                    self.set_status(MonitoringStatus::Running);
+                }
+                Err(error) => {
+                    reopen_error = Some(format!("Reopening the controller failed: {}", error))
+                }
+            }
+        }
+    }
+
+    /// Read and deliver packets until stopped (`None`) or until reads keep
+    /// failing, returning why
+    fn run(&mut self) -> Option<String> {
+        let mut buffer = vec![0u8; 64];
+        let mut sleep_detector = SleepDetector::new();
+        let mut failed_reads = 0;
+
+        loop {
+            // Block until a packet arrives or the timeout expires; the
+            // timeout bounds how long shutdown can take, and is shortened
+            // while a touch strip trail fades so it fades smoothly
+            let fading = self
+                .strip_follower
+                .lock()
+                .is_ok_and(|follower| follower.is_fading());
+            let timeout = if fading {
+                self.read_timeout.min(STRIP_FOLLOW_FRAME)
+            } else {
+                self.read_timeout
+            };
+            let result = self
+                .handle
+                .read_interrupt(INPUT_ENDPOINT, &mut buffer, timeout);
+
+            if MaschineMK3::should_stop(&self.stop_receiver) {
+                return None;
+            }
+            self.apply_updates();
+
+            if sleep_detector.slept() {
+                MaschineMK3::signal_resume(&self.resume_pending, &self.callback, &self.sender);
+            }
+
+            let bytes_read = match result {
+                Ok(bytes_read) => {
+                    self.health.record_transfer(UsbEndpoint::Input, bytes_read);
+                    failed_reads = 0;
+                    bytes_read
+                }
+                Err(rusb::Error::Timeout) => {
+                    failed_reads = 0;
+                    if fading {
+                        self.update_strip_leds(&[]);
+                    }
+                    continue;
+                }
+                Err(error) if MaschineMK3::is_connection_lost(&error) => {
+                    self.health.record_error(UsbEndpoint::Input, &error);
+                    // Wait for the controller to come back, then reopen it
+                    match self.stop_receiver.recv_timeout(RECONNECT_INTERVAL) {
+                        Err(mpsc::RecvTimeoutError::Timeout) => {}
+                        _ => return None,
+                    }
+                    let reopened = MaschineMK3::find_device(&self.context, Some(&self.usb_port))
+                        .and_then(|device| MaschineMK3::open_input_handle(&device, &self.options));
+                    if let Ok(handle) = reopened {
+                        self.handle = handle;
+                        MaschineMK3::signal_resume(
+                            &self.resume_pending,
+                            &self.callback,
+                            &self.sender,
+                        );
+                    }
+                    continue;
+                }
+                Err(error) => {
+                    self.health.record_error(UsbEndpoint::Input, &error);
+                    // Whatever the failed read would have returned is gone
+                    self.tracker.note_lost_packets(1);
+                    failed_reads += 1;
+                    if failed_reads >= MONITOR_ERROR_LIMIT {
+                        return Some(format!(
+                            "{} input reads failed in a row, the last with: {}",
+                            failed_reads, error
+                        ));
+                    }
+                    // Back off after an error, waking at once on shutdown
+                    match self.stop_receiver.recv_timeout(self.error_backoff) {
+                        Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                        _ => return None,
+                    }
+                }
+            };
+
+            if bytes_read == 0 {
+                continue;
+            }
+            self.read_packets = true;
+
+            // Process packet and get events
+            if self.sync_requested.swap(false, Ordering::Relaxed) {
+                self.tracker.request_state_sync();
+            }
+            let packet = &buffer[..bytes_read];
+            let events = match MaschineMK3::process_input_packet(
+                &mut self.tracker,
+                &self.counters,
+                packet,
+            ) {
+                Ok(events) => events,
+                Err(_) => continue,
+            };
+            if let Ok(mut follower) = self.strip_follower.lock() {
+                follower.touch(&self.tracker.touch_strip(), Instant::now());
+            }
+            self.update_strip_leds(&events);
+
+            // Send events through callback and channel
+            for event in events {
+                (self.callback)(event.clone());
+                let _ = self.sender.send(event);
+            }
+        }
+    }
+
+    /// Apply the settings the owning device changed since the last packet
+    fn apply_updates(&mut self) {
+        for update in self.updates.try_iter() {
+            match update {
+                WorkerUpdate::Tracker(update) => {
+                    update(&mut self.tracker);
+                    update(&mut self.initial_tracker);
+                }
+                WorkerUpdate::ReadTimeout(timeout) => self.read_timeout = timeout,
+                WorkerUpdate::LowLatency => {
+                    if !self.low_latency {
+                        MaschineMK3::raise_input_thread_priority();
+                    }
+                    self.low_latency = true;
+                    self.read_timeout = LOW_LATENCY_INTERVAL;
+                    self.error_backoff = LOW_LATENCY_INTERVAL;
+                }
+                WorkerUpdate::LedTimeout(timeout) => self.led_timeout = timeout,
+                WorkerUpdate::OutputRecorder(recorder) => self.output_recorder = recorder,
+            }
+        }
+    }
+
+    /// Draw the touch strip follow mode, or the strip mode feedback when
+    /// `events` changed the strip value, and send the pad LEDs if the strip
+    /// changed, so the strip follows input without the app polling or
+    /// calling `update_led_animations`. Failures are logged: LED output
+    /// mustn't stop input.
+    fn update_strip_leds(&mut self, events: &[InputEvent]) {
+        let mut pad_leds = self.pad_leds.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut strip = pad_leds.shown_strip;
+        let changed = match self.strip_follower.lock() {
+            // The follow mode owns the strip LEDs while it is on
+            Ok(mut follower) if follower.config().is_some() => {
+                follower.update(&mut strip, Instant::now())
+            }
+            Ok(_) => {
+                let feedback = events
+                    .iter()
+                    .any(|event| matches!(event, InputEvent::StripChanged { .. }))
+                    .then(|| self.tracker.strip_controller().led_feedback())
+                    .flatten();
+                match feedback {
+                    Some(leds) if leds != strip => {
+                        strip = leds;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Err(_) => false,
+        };
+        if !changed {
+            return;
+        }
+
+        let packet = pad_leds.strip_packet(strip);
+        #[cfg(feature = "verify-packets")]
+        crate::packet_verify::verify_output(UsbEndpoint::Led, &packet);
+        if let Some(recorder) = &self.output_recorder {
+            recorder.record(UsbEndpoint::Led, &packet);
+        }
+        match self
+            .handle
+            .write_interrupt(OUTPUT_ENDPOINT, &packet, self.led_timeout)
+        {
+            Ok(written) => {
+                self.health.record_transfer(UsbEndpoint::Led, written);
+                pad_leds.shown_strip = strip;
+                pad_leds.thread_strip = Some(strip);
+            }
+            Err(error) => {
+                self.health.record_error(UsbEndpoint::Led, &error);
+                log::warn!("Touch strip LED write failed: {}", error);
+            }
+        }
+    }
+
+    fn set_status(&self, status: MonitoringStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+}
+
+/// Message of a caught panic
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
 /// Main interface for communicating with a Maschine MK3 controller.
 /// 
 /// Provides methods for reading input events and controlling LEDs/display.
@@ -40,6 +976,10 @@ const DISPLAY_ENDPOINT: u8 = 0x04; // Original endpoint 0x04 from interface 5
 pub struct MaschineMK3 {
     device_handle: DeviceHandle<Context>,
     pub context: Context,
+    usb_port: UsbPort,
+    connection_options: ConnectionOptions,
+    // Held for the lifetime of the connection, including reconnects
+    _process_lock: Option<ProcessLock>,
     #[cfg(windows)]
     hid_device: Option<HidDevice>,
     #[cfg(windows)]
@@ -49,12 +989,78 @@ pub struct MaschineMK3 {
     current_button_leds: ButtonLedState,
     current_pad_leds: PadLedState,
     led_state_dirty: bool,
+    sent_led_packets: Mutex<SentLedPackets>,
+    differential_led_writes: bool,
+    // Minimum spacing of LED writes, and when the last one went out
+    led_write_interval: Option<Duration>,
+    last_led_write: Option<Instant>,
+    led_master_brightness: f32,
+    led_calibration: LedCalibration,
+
+    // Per-unit preferences, saved to settings_path by set_settings
+    settings: DeviceSettings,
+    settings_path: Option<PathBuf>,
+
+    // Per-display state for dirty-region updates
+    displays: [DisplayCache; 2],
+    // Time of the last packet written to each display, for the keep-alive
+    last_display_write: [Mutex<Instant>; 2],
+
+    timeouts: TransferTimeouts,
+    retry_policy: RetryPolicy,
+    // Display packets longer than this are sent in row bands
+    max_display_transfer: Option<usize>,
 
     // Input monitoring
     input_tracker: InputTracker,
     input_thread: Option<JoinHandle<()>>,
-    input_stop_signal: Arc<Mutex<bool>>,
+    // Dropping the sender tells the monitoring thread to stop
+    input_stop_sender: Option<mpsc::Sender<()>>,
+    // Settings changed while the monitoring thread runs
+    input_update_sender: Option<Sender<WorkerUpdate>>,
+    input_read_timeout: Duration,
+    // Raised priority and 1ms wakeups for the monitoring thread
+    low_latency: bool,
+    monitoring_status: Arc<Mutex<MonitoringStatus>>,
     input_event_receiver: Option<Receiver<InputEvent>>,
+    // Monitoring channel, for events given to inject_event
+    input_event_sender: Option<Sender<InputEvent>>,
+    // Injected events waiting for the next poll_input_events
+    injected_events: VecDeque<InputEvent>,
+    input_counters: Arc<InputCounters>,
+    health: Arc<HealthMonitor>,
+    // Copy of every LED and display packet, for golden-file tests
+    output_recorder: Option<OutputRecorder>,
+    // Set by request_state_sync for whichever reader handles the next packet
+    state_sync_requested: Arc<AtomicBool>,
+
+    // Result of claiming the display interface, for display_status()
+    display: DisplayStatus,
+    descriptors: DescriptorStrings,
+
+    // Suspend/resume handling
+    sleep_detector: SleepDetector,
+    // Set when a transfer fails the way it does after a suspend, or by the
+    // monitoring thread when it sees a resume
+    resume_pending: Arc<AtomicBool>,
+
+    // Velocity flashes on pad hits
+    pad_flash: Option<PadFlashConfig>,
+    pad_flasher: PadFlasher,
+
+    // Built-in pad and touch strip animations
+    led_animator: LedAnimator,
+    // Touch strip follow mode, fed by whichever thread reads input
+    strip_follower: Arc<Mutex<StripFollower>>,
+    // The pad LEDs as sent, for the monitoring thread to draw the strip on
+    shared_pad_leds: Arc<Mutex<SharedPadLeds>>,
+
+    // Macro script run by poll_input_events
+    #[cfg(feature = "scripting")]
+    script: Option<MacroScript>,
+
+    shutdown_behavior: ShutdownBehavior,
+    closed: bool,
 }
 
 impl MaschineMK3 {
@@ -79,115 +1085,68 @@ impl MaschineMK3 {
     /// # }
     /// ```
     pub fn new() -> Result<Self> {
-        let context = Context::new()?;
-        let device = Self::find_device(&context)?;
-        let mut device_handle = device.open()?;
+        Self::new_with_options(ConnectionOptions::default())
+    }
 
-        // Debug: print device configuration info
-        Self::debug_device_info(&device)?;
+    /// [`new`](Self::new), choosing how interfaces are claimed and what
+    /// happens when the display interface can't be
+    pub fn new_with_options(options: ConnectionOptions) -> Result<Self> {
+        Self::open(Context::new()?, None, options)
+    }
 
-        // Platform-specific interface claiming
-        #[cfg(windows)]
-        {
-            // Windows doesn't support automatic kernel driver detachment
-            Self::claim_interface_with_detach(&mut device_handle, HID_INTERFACE)?;
-        }
+    /// [`new`](Self::new) without logging connection diagnostics, for
+    /// applications like TUIs whose logger writes to the terminal. Failures
+    /// are still returned, and [`topology`](Self::topology) still tells
+    /// whether the displays are available.
+    pub fn new_quiet() -> Result<Self> {
+        Self::new_with_options(ConnectionOptions::default().quiet(true))
+    }
 
-        #[cfg(unix)]
-        {
-            // Linux: detach kernel drivers and claim interfaces
-            Self::detach_and_claim_interface(&mut device_handle, HID_INTERFACE)?;
+    /// Connect to every Maschine MK3 plugged in, e.g. for a
+    /// [`DeviceGroup`](crate::DeviceGroup). Fails if none is found or any of
+    /// them can't be opened.
+    ///
+    /// Each controller is tied to the USB port it was found on, so
+    /// [`reconnect`](Self::reconnect) finds the same one again. On Windows
+    /// LED writes go through the first controller's HID interface, so only
+    /// one controller per host is supported there.
+    pub fn open_all() -> Result<Vec<Self>> {
+        Self::open_all_with_options(ConnectionOptions::default())
+    }
+
+    /// [`open_all`](Self::open_all) with the same options for every controller
+    pub fn open_all_with_options(options: ConnectionOptions) -> Result<Vec<Self>> {
+        let context = Context::new()?;
+        let ports: Vec<UsbPort> = Self::find_devices(&context)?
+            .iter()
+            .map(UsbPort::of)
+            .collect();
+        if ports.is_empty() {
+            return Err(MK3Error::DeviceNotFound);
         }
 
-        // Platform-specific display interface handling
+        ports
+            .iter()
+            .map(|port| Self::open(context.clone(), Some(port), options))
+            .collect()
+    }
+
+    /// Open the controller on `port`, or the first one found
+    fn open(context: Context, port: Option<&UsbPort>, options: ConnectionOptions) -> Result<Self> {
+        let (process_lock, usb_port) = Self::lock_device(&context, port, &options)?;
+        let (device_handle, display) = Self::connect(&context, Some(&usb_port), &options)?;
+        let descriptors = DescriptorStrings::read(&device_handle)?;
+
+        // Platform-specific HID device initialization
         #[cfg(windows)]
-        {
-            // On Windows, try to claim display interface but don't fail if it doesn't work
-            match Self::claim_interface_with_detach(&mut device_handle, DISPLAY_INTERFACE) {
-                Ok(()) => println!(
-                    "✅ Display interface {} claimed successfully",
-                    DISPLAY_INTERFACE
-                ),
-                Err(e) => {
-                    println!(
-                        "⚠️  Could not claim display interface {}: {}",
-                        DISPLAY_INTERFACE, e
-                    );
-                    println!("   Trying alternative interface 3...");
-
-                    // Try Interface 3 as backup
-                    match Self::claim_interface_with_detach(&mut device_handle, 3) {
-                        Ok(()) => {
-                            println!("✅ Alternative interface 3 claimed successfully");
-                            // Update display endpoint to use Interface 3's bulk endpoint
-                            println!("   📝 Note: Using endpoint 0x02 instead of 0x04");
-                        }
-                        Err(e2) => {
-                            println!("⚠️  Alternative interface 3 also failed: {}", e2);
-                            println!("   💡 Consider installing WinUSB driver using Zadig");
-                            println!("   💡 Or use HID-only mode for input/LEDs");
-                        }
-                    }
-                }
-            }
-        }
-
-        #[cfg(unix)]
-        {
-            // On Linux, try to claim display interface
-            match Self::detach_and_claim_interface(&mut device_handle, DISPLAY_INTERFACE) {
-                Ok(()) => println!(
-                    "✅ Display interface {} claimed successfully",
-                    DISPLAY_INTERFACE
-                ),
-                Err(e) => {
-                    println!(
-                        "⚠️  Could not claim display interface {}: {}",
-                        DISPLAY_INTERFACE, e
-                    );
-                    println!("   💡 Check udev rules and user permissions");
-                }
-            }
-        }
-
-        // Platform-specific HID device initialization
-        #[cfg(windows)]
-        let (hid_device, hid_api) = {
-            match HidApi::new() {
-                Ok(api) => {
-                    let devices = api.device_list();
-                    let mut hid_dev = None;
-
-                    for device_info in devices {
-                        if device_info.vendor_id() == VENDOR_ID
-                            && device_info.product_id() == PRODUCT_ID
-                        {
-                            if device_info.interface_number() == 4 {
-                                match device_info.open_device(&api) {
-                                    Ok(dev) => {
-                                        hid_dev = Some(dev);
-                                        break;
-                                    }
-                                    Err(_) => {
-                                        // Silently continue to next device
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    (hid_dev, Some(api))
-                }
-                Err(_) => {
-                    // HID API not available, fall back to USB only
-                    (None, None)
-                }
-            }
-        };
+        let (hid_device, hid_api) = Self::open_hid_device();
 
-        Ok(Self {
+        let mut device = Self {
             device_handle,
             context,
+            usb_port,
+            connection_options: options,
+            _process_lock: process_lock,
             #[cfg(windows)]
             hid_device,
             #[cfg(windows)]
@@ -197,454 +1156,2397 @@ impl MaschineMK3 {
             current_button_leds: ButtonLedState::default(),
             current_pad_leds: PadLedState::default(),
             led_state_dirty: false,
+            sent_led_packets: Mutex::default(),
+            differential_led_writes: true,
+            led_write_interval: None,
+            last_led_write: None,
+            led_master_brightness: 1.0,
+            led_calibration: LedCalibration::default(),
+
+            settings: DeviceSettings::default(),
+            settings_path: None,
+
+            displays: [DisplayCache::default(), DisplayCache::default()],
+            last_display_write: [Mutex::new(Instant::now()), Mutex::new(Instant::now())],
+
+            timeouts: TransferTimeouts::default(),
+            retry_policy: RetryPolicy::default(),
+            max_display_transfer: None,
 
             // Initialize input monitoring
             input_tracker: InputTracker::new(),
             input_thread: None,
-            input_stop_signal: Arc::new(Mutex::new(false)),
+            input_stop_sender: None,
+            input_update_sender: None,
+            input_read_timeout: DEFAULT_INPUT_READ_TIMEOUT,
+            low_latency: false,
+            monitoring_status: Arc::default(),
+            input_counters: Arc::new(InputCounters::default()),
+            health: Arc::new(HealthMonitor::new()),
+            output_recorder: None,
+            state_sync_requested: Arc::new(AtomicBool::new(false)),
+
+            display,
+            descriptors,
+
+            sleep_detector: SleepDetector::new(),
+            resume_pending: Arc::new(AtomicBool::new(false)),
+
+            pad_flash: None,
+            pad_flasher: PadFlasher::default(),
+            led_animator: LedAnimator::default(),
+            strip_follower: Arc::default(),
+            shared_pad_leds: Arc::default(),
+
+            #[cfg(feature = "scripting")]
+            script: None,
+
+            shutdown_behavior: ShutdownBehavior::default(),
+            closed: false,
             input_event_receiver: None,
-        })
+            input_event_sender: None,
+            injected_events: VecDeque::new(),
+        };
+
+        if options.load_settings {
+            device.load_saved_settings()?;
+        }
+        Ok(device)
     }
 
-    /// Windows-specific: Claim interface without kernel driver detachment
-    #[cfg(windows)]
-    fn claim_interface_with_detach(
+    /// Find this unit's settings file and apply it if there is one. A file
+    /// that can't be read is logged and ignored rather than failing the
+    /// connection.
+    fn load_saved_settings(&mut self) -> Result<()> {
+        let options = self.connection_options;
+        let serial = match self.serial_number() {
+            Ok(serial) => serial,
+            Err(e) => {
+                connection_log!(options, warn, "No serial number, settings not loaded: {}", e);
+                return Ok(());
+            }
+        };
+        self.settings_path = DeviceSettings::path_for_serial(&serial);
+        let Some(path) = self.settings_path.clone().filter(|path| path.exists()) else {
+            return Ok(());
+        };
+
+        match DeviceSettings::load(&path) {
+            Ok(settings) => {
+                connection_log!(options, info, "Loaded settings from {}", path.display());
+                self.apply_settings(&settings)?;
+                self.settings = settings;
+            }
+            Err(e) => connection_log!(options, warn, "Ignoring settings file: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Pick the controller on `port`, or the first one no other process has
+    /// locked, and take its cross-process lock if enabled
+    fn lock_device(
+        context: &Context,
+        port: Option<&UsbPort>,
+        options: &ConnectionOptions,
+    ) -> Result<(Option<ProcessLock>, UsbPort)> {
+        let candidates: Vec<UsbPort> = Self::find_devices(context)?
+            .iter()
+            .map(UsbPort::of)
+            .filter(|candidate| port.is_none_or(|port| candidate == port))
+            .collect();
+        if !options.process_lock {
+            let port = candidates.into_iter().next().ok_or(MK3Error::DeviceNotFound)?;
+            return Ok((None, port));
+        }
+
+        let mut busy = None;
+        for candidate in candidates {
+            match ProcessLock::acquire(&candidate.lock_name()) {
+                Ok(lock) => return Ok((Some(lock), candidate)),
+                Err(e @ MK3Error::DeviceBusy(_)) => {
+                    connection_log!(options, debug, "Skipping controller: {}", e);
+                    busy.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(busy.unwrap_or(MK3Error::DeviceNotFound))
+    }
+
+    /// Find the controller, open it and claim its HID and display interfaces
+    fn connect(
+        context: &Context,
+        port: Option<&UsbPort>,
+        options: &ConnectionOptions,
+    ) -> Result<(DeviceHandle<Context>, DisplayStatus)> {
+        let device = Self::find_device(context, port)?;
+        let mut device_handle = device.open()?;
+
+        Self::claim_interface(&mut device_handle, HID_INTERFACE, options)?;
+
+        let display = if options.claim_display {
+            Self::claim_display(&mut device_handle, options)
+        } else {
+            DisplayStatus::NotAttempted
+        };
+
+        if options.require_display && !display.is_available() {
+            return Err(MK3Error::DisplayUnavailable {
+                reason: display.reason().unwrap_or_default(),
+                hint: display.hint().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok((device_handle, display))
+    }
+
+    /// Claim the display interface, or interface 3 if that is enabled and
+    /// the display interface fails
+    fn claim_display(
+        device_handle: &mut DeviceHandle<Context>,
+        options: &ConnectionOptions,
+    ) -> DisplayStatus {
+        match Self::claim_interface(device_handle, DISPLAY_INTERFACE, options) {
+            Ok(()) => DisplayStatus::Available {
+                interface: DISPLAY_INTERFACE,
+            },
+            Err(e) if options.try_alternate_display_interface => {
+                connection_log!(
+                    options,
+                    warn,
+                    "Could not claim display interface {}: {}; trying interface 3",
+                    DISPLAY_INTERFACE,
+                    e
+                );
+
+                match Self::claim_interface(device_handle, 3, options) {
+                    Ok(()) => {
+                        connection_log!(options, info, "Using interface 3 for the displays");
+                        DisplayStatus::Available { interface: 3 }
+                    }
+                    Err(e2) => {
+                        connection_log!(options, warn, "Could not claim interface 3: {}", e2);
+                        Self::display_claim_failure(
+                            device_handle,
+                            format!(
+                                "Could not claim display interface {} ({}) or 3 ({})",
+                                DISPLAY_INTERFACE, e, e2
+                            ),
+                        )
+                    }
+                }
+            }
+            Err(e) => {
+                connection_log!(
+                    options,
+                    warn,
+                    "Could not claim display interface {}: {}",
+                    DISPLAY_INTERFACE,
+                    e
+                );
+                Self::display_claim_failure(
+                    device_handle,
+                    format!(
+                        "Could not claim display interface {}: {}",
+                        DISPLAY_INTERFACE, e
+                    ),
+                )
+            }
+        }
+    }
+
+    /// Claim an interface, detaching its kernel driver first if that is
+    /// supported and enabled, with the retries `options` allow. Windows has
+    /// no kernel driver detachment.
+    fn claim_interface(
         handle: &mut DeviceHandle<Context>,
         interface: u8,
+        options: &ConnectionOptions,
     ) -> Result<()> {
-        println!("🔧 Attempting to claim interface {}", interface);
+        let mut attempt = 0;
+        loop {
+            #[cfg(unix)]
+            if options.detach_kernel_driver {
+                Self::detach_kernel_driver(handle, interface, options);
+            }
 
-        // Windows doesn't support kernel driver detachment
-        match handle.claim_interface(interface) {
-            Ok(()) => {
-                println!("✅ Successfully claimed interface {}", interface);
-                Ok(())
+            connection_log!(options, debug, "Claiming interface {}", interface);
+            match handle.claim_interface(interface) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < options.claim_retries => {
+                    attempt += 1;
+                    connection_log!(
+                        options,
+                        debug,
+                        "Could not claim interface {}: {}; retrying ({}/{})",
+                        interface,
+                        e,
+                        attempt,
+                        options.claim_retries
+                    );
+                    thread::sleep(options.retry_delay);
+                }
+                Err(rusb::Error::Busy) => {
+                    return Err(MK3Error::DeviceBusy(format!(
+                        "interface {} is claimed by another process or driver",
+                        interface
+                    )));
+                }
+                Err(e) => return Err(MK3Error::Usb(e)),
             }
-            Err(e) => {
-                println!("❌ Failed to claim interface {}: {:?}", interface, e);
-                Err(MK3Error::Usb(e))
+        }
+    }
+
+    /// Linux-specific: Tell a kernel driver holding the display interface
+    /// apart from other claim failures
+    #[cfg(unix)]
+    fn display_claim_failure(
+        device_handle: &DeviceHandle<Context>,
+        error: String,
+    ) -> DisplayStatus {
+        match device_handle.kernel_driver_active(DISPLAY_INTERFACE) {
+            Ok(true) => DisplayStatus::WrongDriver {
+                driver: None,
+                hint: "A kernel driver is bound to the display interface; enable \
+                       ConnectionOptions::detach_kernel_driver or unbind it"
+                    .to_string(),
+            },
+            _ => DisplayStatus::InterfaceClaimFailed {
+                error,
+                hint: Self::display_hint(),
+            },
+        }
+    }
+
+    /// Windows-specific: Tell a display interface without WinUSB apart from
+    /// other claim failures
+    #[cfg(windows)]
+    fn display_claim_failure(
+        _device_handle: &DeviceHandle<Context>,
+        error: String,
+    ) -> DisplayStatus {
+        match platform::windows::display_driver_status() {
+            Ok(DriverStatus::OtherDriver { service }) => DisplayStatus::WrongDriver {
+                hint: format!(
+                    "The display interface uses the {} driver; replace it with WinUSB",
+                    service
+                ),
+                driver: Some(service),
+            },
+            Ok(DriverStatus::NoDriver) => DisplayStatus::WrongDriver {
+                driver: None,
+                hint: "Install WinUSB for the display interface".to_string(),
+            },
+            Ok(DriverStatus::WinUsb) => DisplayStatus::InterfaceClaimFailed {
+                error,
+                hint: "WinUSB is installed; close other software using the controller".to_string(),
+            },
+            Ok(DriverStatus::NotConnected) | Err(_) => DisplayStatus::InterfaceClaimFailed {
+                error,
+                hint: "Check that WinUSB is bound to the display interface".to_string(),
+            },
+        }
+    }
+
+    /// Linux-specific: Suggest a fix for an unclaimable display interface
+    #[cfg(unix)]
+    fn display_hint() -> String {
+        "Check the udev rules (see LINUX_SETUP.md) and that your user is in the device's group"
+            .to_string()
+    }
+
+    /// Windows-specific: Open the HID interface used for LED writes, if available
+    #[cfg(windows)]
+    fn open_hid_device() -> (Option<HidDevice>, Option<HidApi>) {
+        match HidApi::new() {
+            Ok(api) => {
+                let devices = api.device_list();
+                let mut hid_dev = None;
+
+                for device_info in devices {
+                    if device_info.vendor_id() == VENDOR_ID
+                        && device_info.product_id() == PRODUCT_ID
+                    {
+                        if device_info.interface_number() == 4 {
+                            match device_info.open_device(&api) {
+                                Ok(dev) => {
+                                    hid_dev = Some(dev);
+                                    break;
+                                }
+                                Err(_) => {
+                                    // Silently continue to next device
+                                }
+                            }
+                        }
+                    }
+                }
+
+                (hid_dev, Some(api))
+            }
+            Err(_) => {
+                // HID API not available, fall back to USB only
+                (None, None)
             }
         }
     }
 
-    /// Linux-specific: Detach kernel driver and claim interface
+    /// Linux-specific: Detach the kernel driver bound to an interface, if
+    /// any. Failures are only logged, since claiming may work regardless.
     #[cfg(unix)]
-    fn detach_and_claim_interface(
+    fn detach_kernel_driver(
         handle: &mut DeviceHandle<Context>,
         interface: u8,
-    ) -> Result<()> {
-        println!("🔧 Attempting to detach kernel driver and claim interface {}", interface);
-
-        // Try to detach kernel driver if it's attached
+        options: &ConnectionOptions,
+    ) {
         match handle.kernel_driver_active(interface) {
-            Ok(true) => {
-                println!("📤 Detaching kernel driver from interface {}", interface);
-                match handle.detach_kernel_driver(interface) {
-                    Ok(()) => println!("✅ Kernel driver detached from interface {}", interface),
+            Ok(true) => match handle.detach_kernel_driver(interface) {
+                Ok(()) => connection_log!(
+                    options,
+                    debug,
+                    "Detached kernel driver from interface {}",
+                    interface
+                ),
+                Err(e) => connection_log!(
+                    options,
+                    warn,
+                    "Could not detach kernel driver from interface {}: {}",
+                    interface,
+                    e
+                ),
+            },
+            Ok(false) => {}
+            Err(e) => connection_log!(
+                options,
+                debug,
+                "Could not check kernel driver of interface {}: {}",
+                interface,
+                e
+            ),
+        }
+    }
+
+    /// Find the Maschine MK3 on `port`, or the first one if no port is given
+    fn find_device(context: &Context, port: Option<&UsbPort>) -> Result<Device<Context>> {
+        Self::find_devices(context)?
+            .into_iter()
+            .find(|device| port.is_none_or(|port| UsbPort::of(device) == *port))
+            .ok_or(MK3Error::DeviceNotFound)
+    }
+
+    /// Every Maschine MK3 on the bus
+    fn find_devices(context: &Context) -> Result<Vec<Device<Context>>> {
+        let mut found = Vec::new();
+
+        for device in context.devices()?.iter() {
+            let device_desc = device.device_descriptor()?;
+
+            if device_desc.vendor_id() == VENDOR_ID && device_desc.product_id() == PRODUCT_ID {
+                found.push(device);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Read input data from the device
+    fn read_input(&self, timeout: Duration) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; 64]; // Max packet size
+
+        match self
+            .device_handle
+            .read_interrupt(INPUT_ENDPOINT, &mut buffer, timeout)
+        {
+            Ok(bytes_read) => {
+                self.health.record_transfer(UsbEndpoint::Input, bytes_read);
+                buffer.truncate(bytes_read);
+                Ok(buffer)
+            }
+            Err(rusb::Error::Timeout) => Ok(Vec::new()), // No data available
+            Err(e) => Err(self.note_transfer_error(UsbEndpoint::Input, e)),
+        }
+    }
+
+    /// Whether a transfer error means the connection was lost, e.g. because
+    /// the controller was reset during a host suspend
+    fn is_connection_lost(error: &rusb::Error) -> bool {
+        matches!(
+            error,
+            rusb::Error::NoDevice | rusb::Error::Io | rusb::Error::Pipe
+        )
+    }
+
+    /// Count a failed transfer and flag it for reconnection by
+    /// [`check_resume`](Self::check_resume) if it looks like a lost
+    /// connection. Writes also count timeouts, which is how display writes
+    /// fail after a resume.
+    fn note_transfer_error(&self, endpoint: UsbEndpoint, error: rusb::Error) -> MK3Error {
+        self.health.record_error(endpoint, &error);
+        let is_write = endpoint != UsbEndpoint::Input;
+        if Self::is_connection_lost(&error) || (is_write && error == rusb::Error::Timeout) {
+            self.resume_pending.store(true, Ordering::Relaxed);
+        }
+        MK3Error::Usb(error)
+    }
+
+    /// Run a USB write, retrying the errors the retry policy allows after
+    /// its backoff. `recover` runs before each retry with the error and the
+    /// retry number; if it fails the write is given up. Returns the last
+    /// error and how many retries were made.
+    fn with_retries<T>(
+        &self,
+        endpoint: UsbEndpoint,
+        mut write: impl FnMut() -> rusb::Result<T>,
+        mut recover: impl FnMut(&rusb::Error, u32) -> rusb::Result<()>,
+    ) -> std::result::Result<T, (rusb::Error, u32)> {
+        let policy = self.retry_policy;
+        let mut retries = 0;
+
+        loop {
+            let error = match write() {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            if retries >= policy.attempts || !policy.retries(&error) {
+                return Err((error, retries));
+            }
+
+            retries += 1;
+            thread::sleep(policy.delay(retries));
+            if recover(&error, retries).is_err() {
+                return Err((error, retries));
+            }
+            self.health.record_error(endpoint, &error);
+            self.health.record_retry(endpoint);
+        }
+    }
+
+    /// LED interrupt write with retries; a stall is cleared before retrying
+    fn write_led_transfer(&self, data: &[u8], timeout: Duration) -> Result<()> {
+        let handle = &self.device_handle;
+        let written = self.with_retries(
+            UsbEndpoint::Led,
+            || handle.write_interrupt(OUTPUT_ENDPOINT, data, timeout),
+            |error, _| match error {
+                rusb::Error::Pipe => handle.clear_halt(OUTPUT_ENDPOINT),
+                _ => Ok(()),
+            },
+        );
+        match written {
+            Ok(_) => {
+                self.health.record_transfer(UsbEndpoint::Led, data.len());
+                Ok(())
+            }
+            Err((e, _)) => Err(self.note_transfer_error(UsbEndpoint::Led, e)),
+        }
+    }
+
+    /// Write LED data to the device
+    fn write_leds(&self, data: &[u8]) -> Result<()> {
+        self.write_leds_timeout(data, self.timeouts.led)
+    }
+
+    /// Write a raw LED packet with a timeout for this call only
+    pub fn write_leds_timeout(&self, data: &[u8], timeout: Duration) -> Result<()> {
+        #[cfg(feature = "verify-packets")]
+        crate::packet_verify::verify_output(UsbEndpoint::Led, data);
+        self.record_output(UsbEndpoint::Led, data);
+
+        #[cfg(windows)]
+        {
+            // Windows: Use HID API for LED communication (interface 4 requires HID driver)
+            if let Some(ref hid_dev) = self.hid_device {
+                match hid_dev.write(data) {
+                    Ok(_) => {
+                        self.health.record_transfer(UsbEndpoint::Led, data.len());
+                        return Ok(());
+                    }
                     Err(e) => {
-                        println!("⚠️  Failed to detach kernel driver: {:?}", e);
-                        // Continue anyway - might still work
+                        log::warn!("HID LED write failed: {}", e);
+                        self.health.record_error(UsbEndpoint::Led, &e);
+                        self.resume_pending.store(true, Ordering::Relaxed);
+                        return Err(MK3Error::Io(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            e,
+                        )));
                     }
                 }
             }
-            Ok(false) => {
-                println!("✅ No kernel driver attached to interface {}", interface);
-            }
-            Err(e) => {
-                println!("⚠️  Could not check kernel driver status: {:?}", e);
-                // Continue anyway
+
+            // Fallback to USB interrupt transfer if HID failed
+            self.write_led_transfer(data, timeout)
+        }
+
+        #[cfg(unix)]
+        {
+            // Linux: Use direct USB interrupt transfer
+            self.write_led_transfer(data, timeout)
+        }
+    }
+
+    /// Write display data to the device.
+    ///
+    /// A write that times out or stalls usually leaves the endpoint wedged
+    /// until it is cleared, so the endpoint is reset and the whole packet
+    /// resent as the [retry policy](Self::set_retry_policy) allows (twice by
+    /// default). If that doesn't help,
+    /// [`MK3Error::DisplayStalled`] is returned and the connection is flagged
+    /// for [`check_resume`](Self::check_resume) to reopen.
+    pub fn write_display(&self, data: &[u8]) -> Result<()> {
+        self.write_display_timeout(data, self.timeouts.display)
+    }
+
+    /// [`write_display`](Self::write_display) with a timeout for this call only.
+    ///
+    /// Packets longer than the [`max_display_transfer`](Self::set_max_display_transfer)
+    /// are sent as several smaller ones, with the timeout applying to each.
+    /// Data that doesn't parse as a display packet is sent as it is.
+    pub fn write_display_timeout(&self, data: &[u8], timeout: Duration) -> Result<()> {
+        #[cfg(feature = "verify-packets")]
+        crate::packet_verify::verify_output(UsbEndpoint::Display, data);
+        self.record_output(UsbEndpoint::Display, data);
+
+        if let Some(max_len) = self.max_display_transfer.filter(|&max_len| data.len() > max_len) {
+            if let Ok(packet) = DisplayPacket::parse(data) {
+                for band in packet.split_rows(max_len) {
+                    self.write_display_transfer(&band.to_packet(), timeout)?;
+                }
+                return Ok(());
             }
         }
+        self.write_display_transfer(data, timeout)
+    }
 
-        // Claim the interface
-        match handle.claim_interface(interface) {
-            Ok(()) => {
-                println!("✅ Successfully claimed interface {}", interface);
+    /// One display bulk transfer, with the stall recovery described on
+    /// [`write_display`](Self::write_display)
+    fn write_display_transfer(&self, data: &[u8], timeout: Duration) -> Result<()> {
+        let handle = &self.device_handle;
+        let written = self.with_retries(
+            UsbEndpoint::Display,
+            || handle.write_bulk(DISPLAY_ENDPOINT, data, timeout),
+            |error, retry| match error {
+                rusb::Error::Timeout | rusb::Error::Pipe => self.reset_display_endpoint(retry),
+                _ => Ok(()),
+            },
+        );
+        match written {
+            Ok(_) => {
+                self.health.record_transfer(UsbEndpoint::Display, data.len());
+                self.note_display_write(data);
                 Ok(())
             }
-            Err(e) => {
-                println!("❌ Failed to claim interface {}: {:?}", interface, e);
-                Err(MK3Error::Usb(e))
+            Err((error @ (rusb::Error::Timeout | rusb::Error::Pipe), attempts)) => {
+                self.note_transfer_error(UsbEndpoint::Display, error);
+                Err(MK3Error::DisplayStalled { attempts, error })
             }
+            Err((error, _)) => Err(self.note_transfer_error(UsbEndpoint::Display, error)),
+        }
+    }
+
+    /// Remember when a display last received a packet, from the display
+    /// number in the packet header
+    fn note_display_write(&self, data: &[u8]) {
+        let written = data
+            .get(2)
+            .and_then(|&display_num| self.last_display_write.get(display_num as usize));
+        if let Some(Ok(mut last_write)) = written.map(Mutex::lock) {
+            *last_write = Instant::now();
+        }
+    }
+
+    /// Clear a stalled display endpoint. Later attempts also reselect the
+    /// interface's alternate setting, which resets the endpoint's data
+    /// toggle on both ends.
+    fn reset_display_endpoint(&self, attempt: u32) -> rusb::Result<()> {
+        if attempt > 1 {
+            self.device_handle
+                .set_alternate_setting(DISPLAY_INTERFACE, 0)?;
         }
+        self.device_handle.clear_halt(DISPLAY_ENDPOINT)
+    }
+
+    /// Write button LED state
+    pub fn write_button_leds(&self, state: &ButtonLedState) -> Result<()> {
+        let packet = state.to_packet();
+        self.write_leds(&packet)?;
+        self.sent_led_packets().buttons = Some(packet);
+        Ok(())
+    }
+
+    /// Write pad LED state
+    pub fn write_pad_leds(&self, state: &PadLedState) -> Result<()> {
+        let packet = state.to_packet();
+        let mut shared = Self::lock_shared_pad_leds(&self.shared_pad_leds);
+        self.write_leds(&packet)?;
+        shared.record_sent(state, 1.0);
+        self.sent_led_packets().pads = Some(packet);
+        Ok(())
+    }
+
+    /// Held while pad LEDs are sent, so they and the monitoring thread's
+    /// touch strip writes don't overtake each other
+    fn lock_shared_pad_leds(shared: &Mutex<SharedPadLeds>) -> MutexGuard<'_, SharedPadLeds> {
+        // A panic while it was held leaves at worst a stale strip
+        shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn sent_led_packets(&self) -> MutexGuard<'_, SentLedPackets> {
+        // Only a cache: a panic while it was held can't leave it inconsistent
+        self.sent_led_packets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Write a display packet to a specific display
+    pub fn write_display_packet(&self, packet: &DisplayPacket) -> Result<()> {
+        let data = packet.to_packet();
+        self.write_display(&data)
+    }
+
+    /// Send a display packet built by hand, after
+    /// [`RawPacket::new`](crate::RawPacket::new) checked it is well formed
+    pub fn send_raw_packet(&self, packet: &RawPacket) -> Result<()> {
+        self.write_display(packet.as_bytes())
+    }
+
+    /// Send raw data directly to the device (for testing/debugging).
+    ///
+    /// Nothing is checked: malformed data can leave the displays unusable
+    /// until the controller is reconnected. Prefer
+    /// [`send_raw_packet`](Self::send_raw_packet).
+    #[cfg(feature = "unsafe_raw")]
+    pub fn send_raw_data(&self, data: &[u8]) -> Result<()> {
+        self.record_output(UsbEndpoint::Display, data);
+        let timeout = self.timeouts.display;
+
+        // Try display endpoint first (bulk transfer)
+        match self
+            .device_handle
+            .write_bulk(DISPLAY_ENDPOINT, data, timeout)
+        {
+            Ok(_) => {
+                self.health.record_transfer(UsbEndpoint::Display, data.len());
+                self.note_display_write(data);
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("Display endpoint failed: {}; trying the HID endpoint", e);
+                self.health.record_error(UsbEndpoint::Display, &e);
+
+                // Fallback to HID endpoint (interrupt transfer)
+                match self
+                    .device_handle
+                    .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
+                {
+                    Ok(_) => {
+                        self.health.record_transfer(UsbEndpoint::Led, data.len());
+                        Ok(())
+                    }
+                    Err(e2) => {
+                        self.health.record_error(UsbEndpoint::Led, &e2);
+                        Err(MK3Error::Usb(e2))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serial number from the USB device descriptor, unique per unit. Read
+    /// when the connection was opened; the error is that of the read.
+    pub fn serial_number(&self) -> Result<String> {
+        Ok(self.descriptors.serial_number.clone()?)
+    }
+
+    /// Product string from the USB device descriptor, if it could be read
+    pub fn product_name(&self) -> Option<&str> {
+        self.descriptors.product.as_deref()
+    }
+
+    /// Manufacturer string from the USB device descriptor, if it could be
+    /// read
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.descriptors.manufacturer.as_deref()
+    }
+
+    /// Get device information for debugging. Built from the descriptors
+    /// read at connect time, so it is cheap to call from a UI.
+    pub fn device_info(&self) -> Result<String> {
+        let descriptors = &self.descriptors;
+        Ok(format!(
+            "Maschine MK3 - Manufacturer: {}, Product: {}, VID: 0x{:04X}, PID: 0x{:04X}",
+            self.manufacturer().unwrap_or("Unknown"),
+            self.product_name().unwrap_or("Unknown"),
+            descriptors.vendor_id,
+            descriptors.product_id
+        ))
+    }
+
+    /// Whether frames can be sent to the displays
+    pub fn is_display_available(&self) -> bool {
+        self.display.is_available()
+    }
+
+    /// Whether the displays can be used, and if not why, with a hint to
+    /// show the user
+    pub fn display_status(&self) -> DisplayStatus {
+        self.display.clone()
+    }
+
+    /// Claim the display interface again, e.g. after the user installed
+    /// WinUSB or closed the software holding it, without reconnecting.
+    /// Returns the new status; displays that are already available are left
+    /// alone. A successful claim resends the next dirty-region frames in
+    /// full.
+    pub fn retry_display_claim(&mut self) -> Result<DisplayStatus> {
+        if self.display.is_available() {
+            return Ok(self.display.clone());
+        }
+
+        self.display = Self::claim_display(&mut self.device_handle, &self.connection_options);
+        if self.display.is_available() {
+            for display_num in 0..2 {
+                self.invalidate_display(display_num)?;
+            }
+        }
+        Ok(self.display.clone())
+    }
+
+    /// Describe the controller's USB interfaces and endpoints, which were
+    /// claimed, which backends carry input and LED traffic, and whether the
+    /// displays can be used (with the reason if not)
+    pub fn topology(&self) -> Result<DeviceTopology> {
+        let device = self.device_handle.device();
+        let device_desc = device.device_descriptor()?;
+        let config_desc = device.active_config_descriptor()?;
+
+        let display_interface = match self.display {
+            DisplayStatus::Available { interface } => Some(interface),
+            _ => None,
+        };
+
+        let interfaces = config_desc
+            .interfaces()
+            .filter_map(|interface| {
+                let interface_desc = interface.descriptors().next()?;
+                let number = interface_desc.interface_number();
+                Some(InterfaceInfo {
+                    number,
+                    class_code: interface_desc.class_code(),
+                    sub_class_code: interface_desc.sub_class_code(),
+                    protocol_code: interface_desc.protocol_code(),
+                    endpoints: interface_desc
+                        .endpoint_descriptors()
+                        .map(|endpoint| EndpointInfo {
+                            address: endpoint.address(),
+                            direction: endpoint.direction(),
+                            transfer_type: endpoint.transfer_type(),
+                        })
+                        .collect(),
+                    claimed: number == HID_INTERFACE || Some(number) == display_interface,
+                })
+            })
+            .collect();
+
+        #[cfg(windows)]
+        let led_backend = if self.hid_device.is_some() {
+            Backend::Hid
+        } else {
+            Backend::Rusb
+        };
+        #[cfg(not(windows))]
+        let led_backend = Backend::Rusb;
+
+        Ok(DeviceTopology {
+            vendor_id: device_desc.vendor_id(),
+            product_id: device_desc.product_id(),
+            bus_number: device.bus_number(),
+            address: device.address(),
+            interfaces,
+            input_backend: Backend::Rusb,
+            led_backend,
+            display: (&self.display).into(),
+        })
+    }
+
+    /// Display dimensions
+    pub const DISPLAY_WIDTH: u16 = 480;
+    pub const DISPLAY_HEIGHT: u16 = 272;
+    /// Width of the surface formed by both displays side by side, see
+    /// [`write_span_rgb888_dirty`](Self::write_span_rgb888_dirty)
+    pub const SPAN_WIDTH: u16 = Self::DISPLAY_WIDTH * 2;
+
+    /// Send optimized full-screen image to display (30 FPS capable)
+    pub fn send_display_image(&self, display_num: u8, pixels: Vec<Rgb565>) -> Result<()> {
+        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+
+        if pixels.len() != num_pixels {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} pixels, got {}",
+                num_pixels,
+                pixels.len()
+            )));
+        }
+
+        let packet = DisplayPacket::full_screen_optimized(display_num, pixels);
+        self.write_display_packet(&packet)
+    }
+
+    /// Handles to the left and right display of a shared device, for
+    /// rendering each screen on its own thread
+    pub fn displays(device: &Arc<Mutex<Self>>) -> (DisplayHandle, DisplayHandle) {
+        (
+            DisplayHandle::new(Arc::clone(device), 0),
+            DisplayHandle::new(Arc::clone(device), 1),
+        )
+    }
+
+    /// Send RGB888 image to display (converts to RGB565X)
+    pub fn send_display_rgb888(&self, display_num: u8, rgb_data: &[u8]) -> Result<()> {
+        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+
+        if rgb_data.len() != num_pixels * 3 {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes, got {}",
+                num_pixels * 3,
+                rgb_data.len()
+            )));
+        }
+
+        Self::check_display_num(display_num)?;
+        let converter = self.displays[display_num as usize].converter();
+        let rows = rgb_data.chunks_exact(Self::DISPLAY_WIDTH as usize * 3);
+        let pixels = Self::convert_rgb888_rows(converter, DisplayRect::full_screen(), rows);
+
+        self.send_display_image(display_num, pixels)
+    }
+
+    /// Clear display with solid color
+    pub fn clear_display(&self, display_num: u8, red: u8, green: u8, blue: u8) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+        let color = match &self.displays[display_num as usize].correction {
+            Some(table) => {
+                let [red, green, blue] = table.apply([red, green, blue]);
+                Rgb565::new(red, green, blue)
+            }
+            None => Rgb565::new(red, green, blue),
+        };
+        let pixels = vec![color; num_pixels];
+        self.send_display_image(display_num, pixels)
+    }
+
+    /// Send a region of pixels to a display. `rect.x` and `rect.width` must
+    /// be even.
+    pub fn write_display_region(
+        &self,
+        display_num: u8,
+        rect: DisplayRect,
+        pixels: Vec<Rgb565>,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        Self::check_display_region(rect)?;
+
+        if pixels.len() != rect.area() {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} pixels, got {}",
+                rect.area(),
+                pixels.len()
+            )));
+        }
+
+        let packet = DisplayPacket::region(display_num, rect, pixels);
+        self.write_display_packet(&packet)
+    }
+
+    /// [`write_display_region`](Self::write_display_region) for pixels
+    /// generated on the fly: they are encoded into the packet as the
+    /// iterator yields them, without an intermediate `Vec`. The iterator has
+    /// to yield exactly `rect.area()` pixels, row by row, and as there
+    /// `rect.x` and `rect.width` must be even.
+    pub fn write_display_region_iter(
+        &self,
+        display_num: u8,
+        rect: DisplayRect,
+        pixels: impl IntoIterator<Item = Rgb565>,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        Self::check_display_region(rect)?;
+
+        let data = DisplayPacket::encode_region(display_num, rect, pixels)?;
+        self.write_display(&data)
+    }
+
+    /// Write RGB888 data (`rect.width * rect.height * 3` bytes, row-major) to a
+    /// region of a display. As with [`write_display_region`](Self::write_display_region),
+    /// `rect.x` and `rect.width` must be even.
+    pub fn write_display_region_rgb888(
+        &self,
+        display_num: u8,
+        rect: DisplayRect,
+        rgb_data: &[u8],
+    ) -> Result<()> {
+        if rgb_data.len() != rect.area() * 3 {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes, got {}",
+                rect.area() * 3,
+                rgb_data.len()
+            )));
+        }
+
+        Self::check_display_num(display_num)?;
+        let converter = self.displays[display_num as usize].converter();
+        let rows = rgb_data.chunks_exact((rect.width as usize * 3).max(3));
+        let pixels = Self::convert_rgb888_rows(converter, rect, rows);
+        self.write_display_region(display_num, rect, pixels)
+    }
+
+    /// Send a full RGB888 frame, transferring only the region that changed since
+    /// the previous call for this display.
+    ///
+    /// Returns the region that was sent, or `None` if the frame was unchanged.
+    /// The first frame sent to a display is always transferred in full, and
+    /// areas marked with [`invalidate_region`](Self::invalidate_region) are
+    /// resent even if they didn't change.
+    pub fn write_display_framebuffer_rgb888_dirty(
+        &mut self,
+        display_num: u8,
+        rgb_data: &[u8],
+    ) -> Result<Option<DisplayRect>> {
+        self.write_framebuffer_dirty(display_num, FrameFormat::Rgb888, rgb_data, None)
+    }
+
+    /// Record that `rect` of a display's frame changed, for
+    /// [`flush_damage`](Self::flush_damage). Damage from several calls is
+    /// merged into one bounding rectangle.
+    pub fn mark_damaged(&mut self, display_num: u8, rect: DisplayRect) -> Result<()> {
+        Self::check_display_num(display_num)?;
+
+        let rect = rect.clipped_to(&DisplayRect::full_screen());
+        let cache = &mut self.displays[display_num as usize];
+        cache.damaged = cache.damaged.union(&rect);
+        Ok(())
+    }
+
+    /// Send the damaged part of a full RGB888 frame without comparing it to
+    /// the previous one, for renderers that already know what they redrew.
+    ///
+    /// Works like
+    /// [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty),
+    /// except that only the area given to [`mark_damaged`](Self::mark_damaged)
+    /// since the last write (plus invalidated areas) is sent. Changes outside
+    /// it are not noticed. The first frame is still sent in full.
+    pub fn flush_damage(
+        &mut self,
+        display_num: u8,
+        rgb_data: &[u8],
+    ) -> Result<Option<DisplayRect>> {
+        Self::check_display_num(display_num)?;
+        let damaged = self.displays[display_num as usize].damaged;
+        self.write_framebuffer_dirty(display_num, FrameFormat::Rgb888, rgb_data, Some(damaged))
+    }
+
+    /// Send a 960x272 RGB888 frame spanning both displays, left display
+    /// first, through the dirty-region path of each.
+    ///
+    /// Returns the region sent to each display in its own coordinates. A
+    /// change crossing the seam is sent to both.
+    pub fn write_span_rgb888_dirty(&mut self, rgb_data: &[u8]) -> Result<[Option<DisplayRect>; 2]> {
+        let [left, right] = Self::split_span_frame(rgb_data)?;
+        self.present_both(&left, &right)
+    }
+
+    /// [`mark_damaged`](Self::mark_damaged) in the coordinates of the
+    /// 960x272 surface spanning both displays
+    pub fn mark_span_damaged(&mut self, rect: DisplayRect) -> Result<()> {
+        for (display_num, part) in Self::split_span_rect(rect).into_iter().enumerate() {
+            if !part.is_empty() {
+                self.mark_damaged(display_num as u8, part)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [`flush_damage`](Self::flush_damage) for a 960x272 RGB888 frame
+    /// spanning both displays
+    pub fn flush_span_damage(&mut self, rgb_data: &[u8]) -> Result<[Option<DisplayRect>; 2]> {
+        let [left, right] = Self::split_span_frame(rgb_data)?;
+        let damaged = self.displays.each_ref().map(|cache| Some(cache.damaged));
+        self.present_pair(FrameFormat::Rgb888, [&left, &right], damaged)
+    }
+
+    /// [`write_display_region_rgb888`](Self::write_display_region_rgb888) in
+    /// the coordinates of the 960x272 surface spanning both displays. Parts
+    /// of `rect` on each side of the seam go to their display.
+    pub fn write_span_region_rgb888(&mut self, rect: DisplayRect, rgb_data: &[u8]) -> Result<()> {
+        if rgb_data.len() != rect.area() * 3 {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes, got {}",
+                rect.area() * 3,
+                rgb_data.len()
+            )));
+        }
+        let span = DisplayRect::new(0, 0, Self::SPAN_WIDTH, Self::DISPLAY_HEIGHT);
+        if rect.clipped_to(&span) != rect {
+            return Err(MK3Error::InvalidData(format!(
+                "Region {:?} is outside the 960x272 span",
+                rect
+            )));
+        }
+
+        let row_bytes = rect.width as usize * 3;
+        let mut column = rect.x;
+        for (display_num, part) in Self::split_span_rect(rect).into_iter().enumerate() {
+            if part.is_empty() {
+                continue;
+            }
+            let start = (column - rect.x) as usize * 3;
+            let part_bytes = part.width as usize * 3;
+            let data: Vec<u8> = rgb_data
+                .chunks_exact(row_bytes)
+                .flat_map(|row| &row[start..start + part_bytes])
+                .copied()
+                .collect();
+            self.write_display_region_rgb888(display_num as u8, part, &data)?;
+            column += part.width;
+        }
+        Ok(())
+    }
+
+    /// Split a 960x272 RGB888 frame into the left and right display frames
+    fn split_span_frame(rgb_data: &[u8]) -> Result<[Vec<u8>; 2]> {
+        let row_bytes = Self::DISPLAY_WIDTH as usize * 3;
+        let expected = row_bytes * 2 * Self::DISPLAY_HEIGHT as usize;
+        if rgb_data.len() != expected {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes for a 960x272 span, got {}",
+                expected,
+                rgb_data.len()
+            )));
+        }
+
+        let mut halves = [
+            Vec::with_capacity(expected / 2),
+            Vec::with_capacity(expected / 2),
+        ];
+        for row in rgb_data.chunks_exact(row_bytes * 2) {
+            halves[0].extend_from_slice(&row[..row_bytes]);
+            halves[1].extend_from_slice(&row[row_bytes..]);
+        }
+        Ok(halves)
+    }
+
+    /// Parts of a span rectangle on the left and right display, in each
+    /// display's coordinates (empty if it doesn't reach that display)
+    fn split_span_rect(rect: DisplayRect) -> [DisplayRect; 2] {
+        let left = rect.clipped_to(&DisplayRect::full_screen());
+        let mut right = rect.clipped_to(&DisplayRect::new(
+            Self::DISPLAY_WIDTH,
+            0,
+            Self::DISPLAY_WIDTH,
+            Self::DISPLAY_HEIGHT,
+        ));
+        right.x -= Self::DISPLAY_WIDTH;
+        [left, right]
+    }
+
+    /// [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty)
+    /// for frames already in the display's RGB565X format (480x272 pixels,
+    /// row-major), which are sent without conversion. Color correction and
+    /// dithering only apply to RGB888 data.
+    ///
+    /// The frame is cached at two bytes per pixel. Switching a display
+    /// between this and the RGB888 variant resends the next frame in full.
+    pub fn write_display_framebuffer_rgb565_dirty(
+        &mut self,
+        display_num: u8,
+        pixels: &[Rgb565],
+    ) -> Result<Option<DisplayRect>> {
+        let data: Vec<u8> = pixels
+            .iter()
+            .flat_map(|pixel| pixel.value.to_le_bytes())
+            .collect();
+        self.write_framebuffer_dirty(display_num, FrameFormat::Rgb565, &data, None)
+    }
+
+    /// Send what changed of a full frame: `damaged` if the caller knows it,
+    /// otherwise the difference from the cached frame
+    fn write_framebuffer_dirty(
+        &mut self,
+        display_num: u8,
+        format: FrameFormat,
+        frame: &[u8],
+        damaged: Option<DisplayRect>,
+    ) -> Result<Option<DisplayRect>> {
+        self.check_resume()?;
+        let Some(mut write) = self.plan_dirty_write(display_num, format, frame, damaged)? else {
+            return Ok(None);
+        };
+        let packet = write.packet(&self.displays[display_num as usize], frame);
+        let result = self.write_display(&packet);
+        self.finish_dirty_write(write, frame, packet, result)
+    }
+
+    /// Work out what a dirty write of `frame` has to send, or `None` if
+    /// nothing changed
+    fn plan_dirty_write(
+        &mut self,
+        display_num: u8,
+        format: FrameFormat,
+        frame: &[u8],
+        damaged: Option<DisplayRect>,
+    ) -> Result<Option<DirtyWrite>> {
+        Self::check_display_num(display_num)?;
+
+        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+        let bpp = format.bytes_per_pixel();
+        if frame.len() != num_pixels * bpp {
+            return Err(MK3Error::InvalidData(match format {
+                FrameFormat::Rgb888 => {
+                    format!("Expected {} RGB bytes, got {}", num_pixels * 3, frame.len())
+                }
+                FrameFormat::Rgb565 => {
+                    format!("Expected {} pixels, got {}", num_pixels, frame.len() / 2)
+                }
+            }));
+        }
+
+        let cache = &mut self.displays[display_num as usize];
+        let changed = match (&cache.framebuffer, damaged) {
+            (Some(_), Some(damaged)) if cache.format == format => damaged,
+            (Some(previous), None) if cache.format == format => {
+                Self::find_dirty_rect(previous, frame, bpp).unwrap_or_default()
+            }
+            _ => DisplayRect::full_screen(),
+        };
+
+        let dirty = changed.union(&cache.invalidated);
+        if dirty.is_empty() {
+            cache.last_stats = DirtyStats::default();
+            return Ok(None);
+        }
+        let dirty = dirty.aligned_to_pixel_pairs();
+
+        // Redrawing content sent before (e.g. switching back to a page)
+        // reuses its packet instead of converting it again
+        let key = PacketCache::key(dirty, frame, Self::DISPLAY_WIDTH as usize, bpp);
+        let cached = cache.packets.take(key);
+        Ok(Some(DirtyWrite {
+            display_num,
+            format,
+            dirty,
+            key,
+            from_cache: cached.is_some(),
+            cached,
+        }))
+    }
+
+    /// Update the display's cache after the packet of a dirty write was
+    /// sent with `result`
+    fn finish_dirty_write(
+        &mut self,
+        write: DirtyWrite,
+        frame: &[u8],
+        packet: Vec<u8>,
+        result: Result<()>,
+    ) -> Result<Option<DisplayRect>> {
+        let cache = &mut self.displays[write.display_num as usize];
+        cache.packets.insert(write.key, packet);
+        result?;
+
+        match &mut cache.framebuffer {
+            Some(previous) if cache.format == write.format => previous.copy_from_slice(frame),
+            slot => *slot = Some(frame.to_vec()),
+        }
+        cache.format = write.format;
+        cache.invalidated = DisplayRect::default();
+        cache.damaged = DisplayRect::default();
+        cache.last_stats = DirtyStats {
+            from_cache: write.from_cache,
+            ..DirtyStats::for_region(write.dirty)
+        };
+
+        Ok(Some(write.dirty))
+    }
+
+    /// Send full RGB888 frames to both displays, left first, through the
+    /// dirty-region path of each.
+    ///
+    /// Works like two calls to
+    /// [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty),
+    /// but the right frame is converted on another thread while the left
+    /// one is, and while the left packet is transferred, so its packet
+    /// follows straight after. If the left transfer fails the right frame
+    /// isn't sent.
+    ///
+    /// Returns the region sent to each display.
+    pub fn present_both(&mut self, left: &[u8], right: &[u8]) -> Result<[Option<DisplayRect>; 2]> {
+        self.present_pair(FrameFormat::Rgb888, [left, right], [None, None])
+    }
+
+    /// Dirty writes of a frame per display, the right display's packet
+    /// built while the left one's is built and sent
+    fn present_pair(
+        &mut self,
+        format: FrameFormat,
+        frames: [&[u8]; 2],
+        damaged: [Option<DisplayRect>; 2],
+    ) -> Result<[Option<DisplayRect>; 2]> {
+        self.check_resume()?;
+        let mut left = self.plan_dirty_write(0, format, frames[0], damaged[0])?;
+        let mut right = match self.plan_dirty_write(1, format, frames[1], damaged[1]) {
+            Ok(right) => right,
+            Err(e) => {
+                // Return the packet the left plan took from the cache
+                if let Some(DirtyWrite {
+                    key,
+                    cached: Some(packet),
+                    ..
+                }) = left
+                {
+                    self.displays[0].packets.insert(key, packet);
+                }
+                return Err(e);
+            }
+        };
+
+        let device = &*self;
+        let (left_sent, right_packet) = thread::scope(|scope| {
+            let right_packet = right.as_mut().map(|write| {
+                let cache = &device.displays[1];
+                scope.spawn(move || write.packet(cache, frames[1]))
+            });
+            let left_sent = left.as_mut().map(|write| {
+                let packet = write.packet(&device.displays[0], frames[0]);
+                let result = device.write_display(&packet);
+                (packet, result)
+            });
+            let right_packet = right_packet.map(|job| match job.join() {
+                Ok(packet) => packet,
+                Err(payload) => panic::resume_unwind(payload),
+            });
+            (left_sent, right_packet)
+        });
+
+        let mut sent = [None, None];
+        if let (Some(write), Some((packet, result))) = (left, left_sent) {
+            match self.finish_dirty_write(write, frames[0], packet, result) {
+                Ok(region) => sent[0] = region,
+                Err(e) => {
+                    // Keep the unsent right packet for the next write
+                    if let (Some(write), Some(packet)) = (right, right_packet) {
+                        self.displays[1].packets.insert(write.key, packet);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        if let (Some(write), Some(packet)) = (right, right_packet) {
+            let result = self.write_display(&packet);
+            sent[1] = self.finish_dirty_write(write, frames[1], packet, result)?;
+        }
+        Ok(sent)
+    }
+
+    /// Scroll the contents of a region by `dx`/`dy` pixels (positive values move
+    /// content right/down). The area uncovered by the scroll is cleared to black.
+    ///
+    /// The display's blit command has no source-rectangle support, so the
+    /// scroll is applied to the framebuffer cached by
+    /// [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty)
+    /// (or its RGB565 variant) and only the affected region is resent. A
+    /// frame must have been sent through one of them first.
+    pub fn scroll_region(
+        &mut self,
+        display_num: u8,
+        rect: DisplayRect,
+        dx: i16,
+        dy: i16,
+    ) -> Result<()> {
+        let rect = rect.clipped_to(&DisplayRect::full_screen());
+        if rect.is_empty() {
+            return Ok(());
+        }
+
+        self.modify_cached_framebuffer(display_num, rect, |framebuffer, bpp| {
+            let source = Self::read_region(framebuffer, rect, bpp);
+            let row_bytes = Self::DISPLAY_WIDTH as usize * bpp;
+
+            for row in 0..rect.height as i32 {
+                for col in 0..rect.width as i32 {
+                    let src_row = row - dy as i32;
+                    let src_col = col - dx as i32;
+                    let inside = (0..rect.height as i32).contains(&src_row)
+                        && (0..rect.width as i32).contains(&src_col);
+
+                    let dst = (rect.y as usize + row as usize) * row_bytes
+                        + (rect.x as usize + col as usize) * bpp;
+                    if inside {
+                        let src =
+                            (src_row as usize * rect.width as usize + src_col as usize) * bpp;
+                        framebuffer[dst..dst + bpp].copy_from_slice(&source[src..src + bpp]);
+                    } else {
+                        framebuffer[dst..dst + bpp].fill(0);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Copy a region of a display to another position on the same display.
+    /// Overlapping source and destination areas are handled correctly.
+    ///
+    /// Like [`scroll_region`](Self::scroll_region), this operates on the
+    /// cached framebuffer and only resends the destination area.
+    pub fn copy_region(
+        &mut self,
+        display_num: u8,
+        source: DisplayRect,
+        dst_x: u16,
+        dst_y: u16,
+    ) -> Result<()> {
+        let source = source.clipped_to(&DisplayRect::full_screen());
+        let dest = DisplayRect::new(dst_x, dst_y, source.width, source.height)
+            .clipped_to(&DisplayRect::full_screen());
+        if dest.is_empty() {
+            return Ok(());
+        }
+
+        self.modify_cached_framebuffer(display_num, dest, |framebuffer, bpp| {
+            let pixels = Self::read_region(framebuffer, source, bpp);
+            let row_bytes = Self::DISPLAY_WIDTH as usize * bpp;
+            let src_row_bytes = source.width as usize * bpp;
+            let copy_bytes = dest.width as usize * bpp;
+
+            for row in 0..dest.height as usize {
+                let dst = (dest.y as usize + row) * row_bytes + dest.x as usize * bpp;
+                let src = row * src_row_bytes;
+                framebuffer[dst..dst + copy_bytes]
+                    .copy_from_slice(&pixels[src..src + copy_bytes]);
+            }
+        })
+    }
+
+    /// Apply an edit to a display's cached framebuffer, which is passed with
+    /// its bytes per pixel, and resend `rect`. If the transfer fails the
+    /// cache is dropped so the next dirty write resends the whole frame.
+    fn modify_cached_framebuffer<F>(
+        &mut self,
+        display_num: u8,
+        rect: DisplayRect,
+        edit: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut [u8], usize),
+    {
+        Self::check_display_num(display_num)?;
+
+        let cache = &mut self.displays[display_num as usize];
+        let Some(framebuffer) = cache.framebuffer.as_mut() else {
+            return Err(MK3Error::InvalidData(format!(
+                "No framebuffer cached for display {}; send a frame first",
+                display_num
+            )));
+        };
+
+        edit(framebuffer, cache.format.bytes_per_pixel());
+
+        let rect = rect.union(&cache.invalidated).aligned_to_pixel_pairs();
+        let converter = Rgb565Converter::new(cache.conversion, cache.correction.as_ref());
+        let pixels = Self::region_to_rgb565(converter, cache.format, framebuffer, rect);
+        let result = self.write_display_region(display_num, rect, pixels);
+
+        let cache = &mut self.displays[display_num as usize];
+        match result {
+            Ok(()) => {
+                cache.invalidated = DisplayRect::default();
+                cache.last_stats = DirtyStats::for_region(rect);
+            }
+            Err(_) => cache.framebuffer = None,
+        }
+        result
+    }
+
+    /// Copy of the last frame sent to a display through the dirty-region
+    /// path (including scroll and copy edits) as RGB888, or `None` if there
+    /// isn't one
+    pub fn framebuffer_snapshot(&self, display_num: u8) -> Option<Vec<u8>> {
+        self.displays.get(display_num as usize)?.rgb888_framebuffer()
+    }
+
+    /// Save the cached frame of a display as a 480x272 PNG
+    #[cfg(feature = "png")]
+    pub fn save_display_png<P: AsRef<std::path::Path>>(
+        &self,
+        display_num: u8,
+        path: P,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        let Some(rgb_data) = self.displays[display_num as usize].rgb888_framebuffer() else {
+            return Err(MK3Error::InvalidData(format!(
+                "No framebuffer cached for display {}; send a frame first",
+                display_num
+            )));
+        };
+
+        save_rgb888_png(path.as_ref(), &rgb_data)
+    }
+
+    /// Force the next dirty-region write to resend the whole display, e.g.
+    /// after a reconnect or after writing to it with
+    /// [`send_raw_packet`](Self::send_raw_packet) or the other non-caching methods
+    pub fn invalidate_display(&mut self, display_num: u8) -> Result<()> {
+        self.invalidate_region(display_num, DisplayRect::full_screen())
+    }
+
+    /// Force the next dirty-region write to resend `rect`, even if its
+    /// contents match the cached frame
+    pub fn invalidate_region(&mut self, display_num: u8, rect: DisplayRect) -> Result<()> {
+        Self::check_display_num(display_num)?;
+
+        let rect = rect.clipped_to(&DisplayRect::full_screen());
+        let cache = &mut self.displays[display_num as usize];
+        cache.invalidated = cache.invalidated.union(&rect);
+        Ok(())
+    }
+
+    /// What the last dirty-region write to a display transferred
+    pub fn dirty_stats(&self, display_num: u8) -> Result<DirtyStats> {
+        Self::check_display_num(display_num)?;
+        Ok(self.displays[display_num as usize].last_stats)
+    }
+
+    /// Copy the bytes inside `rect` out of a full frame, row by row
+    fn read_region(frame: &[u8], rect: DisplayRect, bpp: usize) -> Vec<u8> {
+        let row_bytes = Self::DISPLAY_WIDTH as usize * bpp;
+        let mut region = Vec::with_capacity(rect.area() * bpp);
+
+        for y in rect.y as usize..rect.bottom() as usize {
+            let start = y * row_bytes + rect.x as usize * bpp;
+            region.extend_from_slice(&frame[start..start + rect.width as usize * bpp]);
+        }
+
+        region
+    }
+
+    /// Bounding rectangle of all pixels that differ between two frames with
+    /// `bpp` bytes per pixel
+    fn find_dirty_rect(previous: &[u8], current: &[u8], bpp: usize) -> Option<DisplayRect> {
+        let row_bytes = Self::DISPLAY_WIDTH as usize * bpp;
+        let mut dirty: Option<(usize, usize, usize, usize)> = None; // (x0, y0, x1, y1) inclusive
+
+        for (y, (old_row, new_row)) in previous
+            .chunks_exact(row_bytes)
+            .zip(current.chunks_exact(row_bytes))
+            .enumerate()
+        {
+            if old_row == new_row {
+                continue;
+            }
+
+            let first = old_row
+                .chunks_exact(bpp)
+                .zip(new_row.chunks_exact(bpp))
+                .position(|(a, b)| a != b)
+                .unwrap_or(0);
+            let last = old_row
+                .chunks_exact(bpp)
+                .zip(new_row.chunks_exact(bpp))
+                .rposition(|(a, b)| a != b)
+                .unwrap_or(first);
+
+            dirty = Some(match dirty {
+                Some((x0, y0, x1, _)) => (x0.min(first), y0, x1.max(last), y),
+                None => (first, y, last, y),
+            });
+        }
+
+        dirty.map(|(x0, y0, x1, y1)| {
+            DisplayRect::new(
+                x0 as u16,
+                y0 as u16,
+                (x1 - x0 + 1) as u16,
+                (y1 - y0 + 1) as u16,
+            )
+        })
+    }
+
+    /// Convert the pixels inside `rect` of a full RGB888 frame to RGB565X
+    fn rgb888_region_to_rgb565(
+        converter: Rgb565Converter,
+        rgb_data: &[u8],
+        rect: DisplayRect,
+    ) -> Vec<Rgb565> {
+        let row_bytes = Self::DISPLAY_WIDTH as usize * 3;
+        let rows = (rect.y as usize..rect.bottom() as usize).map(|y| {
+            let start = y * row_bytes + rect.x as usize * 3;
+            &rgb_data[start..start + rect.width as usize * 3]
+        });
+        Self::convert_rgb888_rows(converter, rect, rows)
+    }
+
+    /// Pixels inside `rect` of a full frame in `format`, converted to RGB565X
+    /// if it is RGB888
+    fn region_to_rgb565(
+        converter: Rgb565Converter,
+        format: FrameFormat,
+        frame: &[u8],
+        rect: DisplayRect,
+    ) -> Vec<Rgb565> {
+        match format {
+            FrameFormat::Rgb888 => Self::rgb888_region_to_rgb565(converter, frame, rect),
+            FrameFormat::Rgb565 => {
+                let region = Self::read_region(frame, rect, 2);
+                FrameFormat::rgb565_pixels(&region).collect()
+            }
+        }
+    }
+
+    /// Convert the RGB888 rows of `rect`, top to bottom, to RGB565X
+    fn convert_rgb888_rows<'a>(
+        mut converter: Rgb565Converter,
+        rect: DisplayRect,
+        rows: impl Iterator<Item = &'a [u8]>,
+    ) -> Vec<Rgb565> {
+        let mut pixels = Vec::with_capacity(rect.area());
+        for (row, rgb_data) in rows.enumerate() {
+            converter.convert_row(rgb_data, rect.x as usize, rect.y as usize + row, &mut pixels);
+        }
+        pixels
+    }
+
+    fn check_display_num(display_num: u8) -> Result<()> {
+        if display_num > 1 {
+            return Err(MK3Error::InvalidData(
+                "Display number must be 0 or 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_display_region(rect: DisplayRect) -> Result<()> {
+        DisplayRect::checked(rect.origin(), rect.width, rect.height)?;
+        // Region packets count pixels in pairs, an odd region would lose its
+        // last pixel and shift every row after it
+        if !rect.x.is_multiple_of(2) || !rect.width.is_multiple_of(2) {
+            return Err(MK3Error::InvalidData(format!(
+                "Region {:?} must start on an even column and have an even width, \
+                 see DisplayRect::aligned_to_pixel_pairs",
+                rect
+            )));
+        }
+        Ok(())
+    }
+
+    // === Suspend/Resume ===
+
+    /// Recover from a host suspend or controller reset: if the host slept or
+    /// a transfer failed like it does after a resume, reopen the connection
+    /// with [`reconnect`](Self::reconnect). Returns `true` if it reconnected.
+    ///
+    /// Called automatically by [`poll_input_events`](Self::poll_input_events)
+    /// (which then reports `InputEvent::DeviceResumed`), by LED updates and by
+    /// [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty).
+    /// If the controller isn't back yet the error is returned and the next
+    /// call tries again.
+    pub fn check_resume(&mut self) -> Result<bool> {
+        let slept = self.sleep_detector.slept();
+        if !slept && !self.resume_pending.swap(false, Ordering::Relaxed) {
+            return Ok(false);
+        }
+
+        if let Err(e) = self.reconnect() {
+            self.resume_pending.store(true, Ordering::Relaxed);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// Reopen the controller, reclaim its interfaces and resend the LED
+    /// state and cached display frames, which the controller forgets on a
+    /// reset. The monitoring thread reopens its own connection separately.
+    pub fn reconnect(&mut self) -> Result<()> {
+        let state = self.save_state();
+
+        // Release the old claims so the new handle can take them if the
+        // controller kept its address
+        let _ = self.device_handle.release_interface(HID_INTERFACE);
+        if let DisplayStatus::Available { interface } = self.display {
+            let _ = self.device_handle.release_interface(interface);
+        }
+
+        let (device_handle, display) =
+            Self::connect(&self.context, Some(&self.usb_port), &self.connection_options)?;
+        self.descriptors = DescriptorStrings::read(&device_handle)?;
+        self.device_handle = device_handle;
+        self.display = display;
+
+        #[cfg(windows)]
+        {
+            let (hid_device, hid_api) = Self::open_hid_device();
+            self.hid_device = hid_device;
+            self._hid_api = hid_api;
+        }
+
+        *self.sent_led_packets() = SentLedPackets::default();
+        self.restore_state(&state)
+    }
+
+    // === Input Management ===
+
+    /// Start monitoring input on a background thread without a callback.
+    /// Events are read with [`try_recv_event`](Self::try_recv_event),
+    /// [`recv_event_timeout`](Self::recv_event_timeout),
+    /// [`queued_events`](Self::queued_events) or [`events`](Self::events).
+    pub fn start_event_monitoring(&mut self) -> Result<()> {
+        self.start_input_monitoring(|_| {})
+    }
+
+    /// Start monitoring input with a callback (non-blocking).
+    /// Events are also queued for the `recv` methods below.
+    pub fn start_input_monitoring<F>(&mut self, callback: F) -> Result<()>
+    where
+        F: Fn(InputEvent) + Send + 'static,
+    {
+        if self.input_thread.is_some() {
+            return Err(MK3Error::InvalidData(
+                "Input monitoring already running".to_string(),
+            ));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        self.input_event_receiver = Some(receiver);
+        self.input_event_sender = Some(sender.clone());
+
+        // Open a second handle for the thread
+        let options = self.connection_options;
+        let handle = Self::open_input_handle(&self.device_handle.device(), &options)?;
+
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        self.input_stop_sender = Some(stop_sender);
+        let (update_sender, updates) = mpsc::channel();
+        self.input_update_sender = Some(update_sender);
+        let error_backoff = if self.low_latency {
+            LOW_LATENCY_INTERVAL
+        } else {
+            INPUT_ERROR_BACKOFF
+        };
+        self.set_monitoring_status(MonitoringStatus::Running);
+
+        let worker = InputWorker {
+            handle,
+            context: self.context.clone(),
+            usb_port: self.usb_port.clone(),
+            options,
+            // Start from the polling tracker so its configuration carries over
+            tracker: self.input_tracker.clone(),
+            initial_tracker: self.input_tracker.clone(),
+            read_timeout: self.input_read_timeout,
+            error_backoff,
+            low_latency: self.low_latency,
+            read_packets: false,
+            callback,
+            sender,
+            stop_receiver,
+            updates,
+            resume_pending: Arc::clone(&self.resume_pending),
+            counters: Arc::clone(&self.input_counters),
+            sync_requested: Arc::clone(&self.state_sync_requested),
+            health: Arc::clone(&self.health),
+            strip_follower: Arc::clone(&self.strip_follower),
+            pad_leds: Arc::clone(&self.shared_pad_leds),
+            led_timeout: self.timeouts.led,
+            output_recorder: self.output_recorder.clone(),
+            status: Arc::clone(&self.monitoring_status),
+        };
+        self.input_thread = Some(thread::spawn(move || worker.supervise()));
+        Ok(())
+    }
+
+    /// Deliver an event as if the controller had sent it, e.g. to test an
+    /// application without pressing anything.
+    ///
+    /// While input is monitored the event joins the monitoring channel (the
+    /// monitoring callback doesn't see it); otherwise it is returned first
+    /// by the next [`poll_input_events`](Self::poll_input_events), which
+    /// still reads the controller. Injected events skip input tracking, so
+    /// they don't change [`pad_pressure`](Self::pad_pressure) or produce
+    /// holds, double presses or gestures of their own.
+    pub fn inject_event(&mut self, event: InputEvent) {
+        match &self.input_event_sender {
+            Some(sender) => {
+                let _ = sender.send(event);
+            }
+            None => self.injected_events.push_back(event),
+        }
+    }
+
+    /// Whether input monitoring was started and not stopped. See
+    /// [`monitoring_status`](Self::monitoring_status) for whether the thread
+    /// is actually reading.
+    pub fn is_input_monitoring(&self) -> bool {
+        self.input_thread.is_some()
     }
 
-    /// Find the first Maschine MK3 device
-    fn find_device(context: &Context) -> Result<Device<Context>> {
-        let devices = context.devices()?;
+    /// What the monitoring thread is doing. A thread that panics or whose
+    /// reads keep failing reopens the controller and starts over, waiting
+    /// longer before each restart; after five restarts without reading a
+    /// packet it gives up with `MonitoringStatus::Failed`.
+    pub fn monitoring_status(&self) -> MonitoringStatus {
+        self.monitoring_status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
 
-        for device in devices.iter() {
-            let device_desc = device.device_descriptor()?;
+    fn set_monitoring_status(&self, status: MonitoringStatus) {
+        if let Ok(mut current) = self.monitoring_status.lock() {
+            *current = status;
+        }
+    }
 
-            if device_desc.vendor_id() == VENDOR_ID && device_desc.product_id() == PRODUCT_ID {
-                return Ok(device);
-            }
+    /// Stop input monitoring
+    pub fn stop_input_monitoring(&mut self) -> Result<()> {
+        // Disconnecting the channel wakes the thread if it is backing off;
+        // a blocking read returns within the configured read timeout
+        self.input_stop_sender = None;
+        self.input_update_sender = None;
+
+        if let Some(handle) = self.input_thread.take() {
+            handle.join().map_err(|_| {
+                MK3Error::InvalidData("Failed to join monitoring thread".to_string())
+            })?;
         }
 
-        Err(MK3Error::DeviceNotFound)
+        self.input_event_receiver = None;
+        self.input_event_sender = None;
+        self.set_monitoring_status(MonitoringStatus::Stopped);
+
+        Ok(())
     }
 
-    /// Debug device configuration information
-    fn debug_device_info(device: &Device<Context>) -> Result<()> {
-        let device_desc = device.device_descriptor()?;
-        println!(
-            "📱 Device found: VID:0x{:04X} PID:0x{:04X}",
-            device_desc.vendor_id(),
-            device_desc.product_id()
-        );
+    /// Set the timeouts used for input reads and LED and display writes.
+    /// Real-time apps may want short ones so a hung transfer doesn't stall a
+    /// frame; slow hubs may need longer ones than the defaults.
+    pub fn set_timeouts(&mut self, timeouts: TransferTimeouts) {
+        self.timeouts = timeouts;
+        self.send_worker_update(WorkerUpdate::LedTimeout(timeouts.led));
+    }
 
-        let config_desc = device.config_descriptor(0)?;
-        println!(
-            "🔧 Configuration: {} interfaces",
-            config_desc.num_interfaces()
-        );
+    pub fn timeouts(&self) -> TransferTimeouts {
+        self.timeouts
+    }
 
-        for interface in config_desc.interfaces() {
-            println!("   Interface {}", interface.number());
+    /// Set how LED and display writes that fail with a transient error
+    /// are retried; `RetryPolicy::disabled()` reports every failure at once
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
 
-            for interface_desc in interface.descriptors() {
-                println!(
-                    "     Class: 0x{:02X}, Subclass: 0x{:02X}, Protocol: 0x{:02X}",
-                    interface_desc.class_code(),
-                    interface_desc.sub_class_code(),
-                    interface_desc.protocol_code()
-                );
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
 
-                for endpoint in interface_desc.endpoint_descriptors() {
-                    println!(
-                        "       Endpoint: 0x{:02X} ({:?})",
-                        endpoint.address(),
-                        endpoint.transfer_type()
-                    );
-                }
-            }
-        }
-        Ok(())
+    /// Largest display transfer to attempt, for hubs and host controllers
+    /// that reject or stall on full-screen packets (about 261 KB). Longer
+    /// packets are split into region packets of whole rows, see
+    /// [`DisplayPacket::split_rows`]; `None` (the default) sends every
+    /// packet as it is.
+    pub fn set_max_display_transfer(&mut self, max_len: Option<usize>) {
+        self.max_display_transfer = max_len;
     }
 
-    /// Read input data from the device
-    fn read_input(&self) -> Result<Vec<u8>> {
-        let mut buffer = vec![0u8; 64]; // Max packet size
-        let timeout = Duration::from_millis(100);
+    pub fn max_display_transfer(&self) -> Option<usize> {
+        self.max_display_transfer
+    }
 
-        match self
-            .device_handle
-            .read_interrupt(INPUT_ENDPOINT, &mut buffer, timeout)
-        {
-            Ok(bytes_read) => {
-                buffer.truncate(bytes_read);
-                Ok(buffer)
-            }
-            Err(rusb::Error::Timeout) => Ok(Vec::new()), // No data available
-            Err(e) => Err(MK3Error::Usb(e)),
-        }
+    /// Set how long the monitoring thread blocks on each input read (250ms by
+    /// default). Packets are delivered as soon as they arrive regardless of
+    /// this value; it only bounds how long
+    /// [`stop_input_monitoring`](Self::stop_input_monitoring) may wait.
+    pub fn set_input_read_timeout(&mut self, timeout: Duration) {
+        self.input_read_timeout = timeout;
+        self.send_worker_update(WorkerUpdate::ReadTimeout(timeout));
     }
 
-    /// Write LED data to the device
-    fn write_leds(&self, data: &[u8]) -> Result<()> {
+    /// Tune input handling for the shortest path from a hit to the app and
+    /// the LEDs, for drumming and other latency-sensitive apps:
+    ///
+    /// - Input reads time out after 1ms, both in
+    ///   [`poll_input_events`](Self::poll_input_events) and the monitoring
+    ///   thread, so pad flashes, animations and the strip follow mode run
+    ///   every millisecond and a poll loop is never held up by an idle read.
+    /// - The monitoring thread runs at raised priority so other load doesn't
+    ///   delay it: `SCHED_FIFO` (or a lower nice value) on Linux, given the
+    ///   rights (see the README), and time-critical on Windows. A failure is
+    ///   logged and monitoring runs at normal priority.
+    /// - The monitoring thread retries 1ms after a failed read instead of
+    ///   10ms.
+    /// - LED writes go out at once instead of being coalesced by
+    ///   [`set_led_write_interval`](Self::set_led_write_interval).
+    ///
+    /// Packets are still read at the input endpoint's polling interval,
+    /// which the host schedules from the controller's USB descriptor. The
+    /// cost is CPU time: an idle monitoring thread wakes up to 1000 times a
+    /// second. A running monitoring thread switches over after its next read.
+    pub fn enable_low_latency(&mut self) {
+        self.low_latency = true;
+        self.timeouts.input = LOW_LATENCY_INTERVAL;
+        self.input_read_timeout = LOW_LATENCY_INTERVAL;
+        self.led_write_interval = None;
+        self.send_worker_update(WorkerUpdate::LowLatency);
+    }
+
+    pub fn is_low_latency(&self) -> bool {
+        self.low_latency
+    }
+
+    /// Raise the priority of the calling monitoring thread, logging failures
+    fn raise_input_thread_priority() {
+        #[cfg(target_os = "linux")]
+        let result = crate::platform::linux::raise_thread_priority();
         #[cfg(windows)]
-        {
-            // Windows: Use HID API for LED communication (interface 4 requires HID driver)
-            if let Some(ref hid_dev) = self.hid_device {
-                match hid_dev.write(data) {
-                    Ok(_) => return Ok(()),
-                    Err(e) => {
-                        eprintln!("HID LED write failed: {}", e);
-                        return Err(MK3Error::Io(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            e,
-                        )));
-                    }
-                }
-            }
+        let result = crate::platform::windows::raise_thread_priority();
+        #[cfg(not(any(target_os = "linux", windows)))]
+        let result: std::io::Result<()> = Err(std::io::ErrorKind::Unsupported.into());
 
-            // Fallback to USB interrupt transfer if HID failed
-            let timeout = Duration::from_millis(100);
-            match self
-                .device_handle
-                .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
-            {
-                Ok(_) => Ok(()),
-                Err(e) => Err(MK3Error::Usb(e)),
-            }
+        if let Err(error) = result {
+            log::warn!(
+                "Input thread runs at normal priority, raising it failed: {}",
+                error
+            );
         }
+    }
 
-        #[cfg(unix)]
-        {
-            // Linux: Use direct USB interrupt transfer
-            let timeout = Duration::from_millis(100);
-            match self
-                .device_handle
-                .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
-            {
-                Ok(_) => Ok(()),
-                Err(e) => Err(MK3Error::Usb(e)),
-            }
-        }
+    /// Open a handle with the HID interface claimed, for the monitoring thread
+    fn open_input_handle(
+        device: &Device<Context>,
+        options: &ConnectionOptions,
+    ) -> Result<DeviceHandle<Context>> {
+        let mut handle = device.open()?;
+        Self::claim_interface(&mut handle, HID_INTERFACE, options)?;
+        Ok(handle)
     }
 
-    /// Write display data to the device
-    pub fn write_display(&self, data: &[u8]) -> Result<()> {
-        let timeout = Duration::from_millis(1000); // Longer timeout for display data
-        self.device_handle
-            .write_bulk(DISPLAY_ENDPOINT, data, timeout)?;
-        Ok(())
+    /// Report a resume from the monitoring thread and have the owning device
+    /// reconnect its output interfaces on its next LED or display update
+    fn signal_resume<F>(resume_pending: &AtomicBool, callback: &F, sender: &Sender<InputEvent>)
+    where
+        F: Fn(InputEvent),
+    {
+        resume_pending.store(true, Ordering::Relaxed);
+        callback(InputEvent::DeviceResumed);
+        let _ = sender.send(InputEvent::DeviceResumed);
     }
 
-    /// Write button LED state
-    pub fn write_button_leds(&self, state: &ButtonLedState) -> Result<()> {
-        let packet = state.to_packet();
-        self.write_leds(&packet)
+    /// Whether the owning device has asked the monitoring thread to stop
+    fn should_stop(stop_receiver: &Receiver<()>) -> bool {
+        !matches!(stop_receiver.try_recv(), Err(mpsc::TryRecvError::Empty))
     }
 
-    /// Write pad LED state
-    pub fn write_pad_leds(&self, state: &PadLedState) -> Result<()> {
-        let packet = state.to_packet();
-        self.write_leds(&packet)
+    /// Take the next queued event from the monitoring thread without blocking.
+    /// Returns `None` if no event is pending or monitoring isn't running.
+    pub fn try_recv_event(&self) -> Option<InputEvent> {
+        self.input_event_receiver.as_ref()?.try_recv().ok()
     }
 
-    /// Write a display packet to a specific display
-    pub fn write_display_packet(&self, packet: &DisplayPacket) -> Result<()> {
-        let data = packet.to_packet();
-        self.write_display(&data)
+    /// Wait up to `timeout` for the next event from the monitoring thread.
+    /// Returns `None` on timeout or if monitoring isn't running.
+    pub fn recv_event_timeout(&self, timeout: Duration) -> Option<InputEvent> {
+        self.input_event_receiver.as_ref()?.recv_timeout(timeout).ok()
     }
 
-    /// Send raw data directly to the device (for testing/debugging)
-    pub fn send_raw_data(&self, data: &[u8]) -> Result<()> {
-        let timeout = Duration::from_millis(1000);
+    /// Iterate over events as they arrive, blocking until the next one,
+    /// for tools that just react to input:
+    ///
+    /// ```no_run
+    /// use maschine3_hal::MaschineMK3;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut device = MaschineMK3::new()?;
+    /// for event in device.events() {
+    ///     println!("{}", event.description());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Input monitoring is started without a callback if it isn't running;
+    /// if that fails the iterator yields nothing and
+    /// [`EventStream::error`] says why. Monitoring keeps running when the
+    /// iterator is dropped. The iterator ends once monitoring has
+    /// [failed](MonitoringStatus::Failed) and the queued events are read;
+    /// while the thread is restarting it keeps waiting.
+    pub fn events(&mut self) -> EventStream<'_> {
+        let error = if self.input_thread.is_none() {
+            self.start_event_monitoring().err()
+        } else {
+            None
+        };
+        EventStream {
+            device: self,
+            error,
+        }
+    }
 
-        // Try display endpoint first (bulk transfer)
-        match self
-            .device_handle
-            .write_bulk(DISPLAY_ENDPOINT, data, timeout)
-        {
-            Ok(_) => {
-                //println!("✅ Sent {} bytes via display endpoint (bulk)", data.len());
-                Ok(())
-            }
-            Err(e) => {
-                println!("⚠️  Display endpoint failed: {}, trying HID endpoint...", e);
+    /// Iterate over all events currently queued by the monitoring thread.
+    /// The iterator ends when the queue is empty; it never blocks.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use maschine3_hal::MaschineMK3;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut device = MaschineMK3::new()?;
+    /// device.start_event_monitoring()?;
+    ///
+    /// loop {
+    ///     for event in device.queued_events() {
+    ///         println!("{}", event.description());
+    ///     }
+    ///     std::thread::sleep(Duration::from_millis(16));
+    /// }
+    /// # }
+    /// ```
+    pub fn queued_events(&self) -> impl Iterator<Item = InputEvent> + '_ {
+        self.input_event_receiver
+            .as_ref()
+            .into_iter()
+            .flat_map(Receiver::try_iter)
+    }
 
-                // Fallback to HID endpoint (interrupt transfer)
-                match self
-                    .device_handle
-                    .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
-                {
-                    Ok(_) => {
-                        println!("✅ Sent {} bytes via HID endpoint (interrupt)", data.len());
-                        Ok(())
-                    }
-                    Err(e2) => {
-                        println!("❌ Both endpoints failed");
-                        Err(MK3Error::Usb(e2))
-                    }
-                }
-            }
-        }
+    /// Change the input tracker's configuration, in a running monitoring
+    /// thread too. Input settings apply to polling at once and to the
+    /// monitoring thread from the next packet it reads.
+    fn configure_tracker(&mut self, update: impl Fn(&mut InputTracker) + Send + 'static) {
+        update(&mut self.input_tracker);
+        self.send_worker_update(WorkerUpdate::Tracker(Box::new(update)));
     }
 
-    /// Get device information for debugging
-    pub fn device_info(&self) -> Result<String> {
-        let device = self.device_handle.device();
-        let device_desc = device.device_descriptor()?;
-        let handle = &self.device_handle;
+    fn send_worker_update(&self, update: WorkerUpdate) {
+        if let Some(sender) = &self.input_update_sender {
+            let _ = sender.send(update);
+        }
+    }
 
-        let manufacturer = handle
-            .read_manufacturer_string_ascii(&device_desc)
-            .unwrap_or_else(|_| "Unknown".to_string());
+    /// Configure the thresholds used for touch strip gesture events
+    pub fn set_touch_strip_gesture_config(&mut self, config: TouchStripGestureConfig) {
+        self.configure_tracker(move |tracker| {
+            tracker.set_touch_strip_gesture_config(config.clone())
+        });
+    }
 
-        let product = handle
-            .read_product_string_ascii(&device_desc)
-            .unwrap_or_else(|_| "Unknown".to_string());
+    /// Configure how short a touch without turning must be for a
+    /// `KnobGesture::TapTouch`
+    pub fn set_knob_gesture_config(&mut self, config: KnobGestureConfig) {
+        self.configure_tracker(move |tracker| tracker.set_knob_gesture_config(config));
+    }
 
-        Ok(format!(
-            "Maschine MK3 - Manufacturer: {}, Product: {}, VID: 0x{:04X}, PID: 0x{:04X}",
-            manufacturer,
-            product,
-            device_desc.vendor_id(),
-            device_desc.product_id()
-        ))
+    pub fn knob_gesture_config(&self) -> KnobGestureConfig {
+        self.input_tracker.knob_gesture_config()
     }
 
-    /// Display dimensions
-    pub const DISPLAY_WIDTH: u16 = 480;
-    pub const DISPLAY_HEIGHT: u16 = 272;
+    /// Configure the timing of `ButtonHeld`, `ButtonDoublePressed` and
+    /// `ButtonLongPressed` events
+    pub fn set_button_gesture_config(&mut self, config: ButtonGestureConfig) {
+        self.configure_tracker(move |tracker| tracker.set_button_gesture_config(config));
+    }
 
-    /// Send optimized full-screen image to display (30 FPS capable)
-    pub fn send_display_image(&self, display_num: u8, pixels: Vec<Rgb565>) -> Result<()> {
-        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+    pub fn button_gesture_config(&self) -> ButtonGestureConfig {
+        self.input_tracker.button_gesture_config()
+    }
 
-        if pixels.len() != num_pixels {
-            return Err(MK3Error::InvalidData(format!(
-                "Expected {} pixels, got {}",
-                num_pixels,
-                pixels.len()
-            )));
-        }
+    /// Configure how turn speed scales the steps of `EncoderTurned` events;
+    /// `EncoderAccelerationConfig::disabled()` gives one step per detent
+    pub fn set_encoder_acceleration(&mut self, config: EncoderAccelerationConfig) {
+        self.configure_tracker(move |tracker| tracker.set_encoder_acceleration(config));
+    }
 
-        let packet = DisplayPacket::full_screen_optimized(display_num, pixels);
-        self.send_raw_data(&packet.to_packet())
+    pub fn encoder_acceleration(&self) -> EncoderAccelerationConfig {
+        self.input_tracker.encoder_acceleration()
     }
 
-    /// Send RGB888 image to display (converts to RGB565X)
-    pub fn send_display_rgb888(&self, display_num: u8, rgb_data: &[u8]) -> Result<()> {
-        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+    /// Report input packets that can't be parsed (shorter than their
+    /// layout) as `InputEvent::ProtocolWarning` with their bytes, instead of
+    /// skipping them silently. Packets of types that aren't decoded always
+    /// arrive as `InputEvent::RawInput`.
+    pub fn set_protocol_warnings(&mut self, enabled: bool) {
+        self.configure_tracker(move |tracker| tracker.set_protocol_warnings(enabled));
+    }
 
-        if rgb_data.len() != num_pixels * 3 {
-            return Err(MK3Error::InvalidData(format!(
-                "Expected {} RGB bytes, got {}",
-                num_pixels * 3,
-                rgb_data.len()
-            )));
-        }
+    /// Configure pad debouncing, crosstalk suppression and per-pad velocity
+    /// thresholds; `PadFilterConfig::disabled()` turns filtering off
+    pub fn set_pad_filter_config(&mut self, config: PadFilterConfig) {
+        self.configure_tracker(move |tracker| tracker.set_pad_filter_config(config.clone()));
+    }
 
-        // Convert RGB888 to RGB565X
-        let mut pixels = Vec::with_capacity(num_pixels);
-        for chunk in rgb_data.chunks_exact(3) {
-            pixels.push(Rgb565::new(chunk[0], chunk[1], chunk[2]));
-        }
+    /// Stream `InputEvent::PadPressure` events with the given rate limiting,
+    /// or stop with `None`
+    pub fn set_pad_pressure_config(&mut self, config: Option<PadPressureConfig>) {
+        self.configure_tracker(move |tracker| tracker.set_pad_pressure_config(config));
+    }
 
-        self.send_display_image(display_num, pixels)
+    /// Latest pressure of a pad (0-4095) seen by
+    /// [`poll_input_events`](Self::poll_input_events), 0 when released
+    pub fn pad_pressure(&self, pad_number: u8) -> u16 {
+        self.input_tracker.pad_pressure(pad_number)
     }
 
-    /// Clear display with solid color
-    pub fn clear_display(&self, display_num: u8, red: u8, green: u8, blue: u8) -> Result<()> {
-        let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
-        let color = Rgb565::new(red, green, blue);
-        let pixels = vec![color; num_pixels];
-        self.send_display_image(display_num, pixels)
+    /// Set how the touch strip is interpreted. In any mode other than
+    /// `StripMode::Raw`, `InputEvent::StripChanged` events are emitted and the
+    /// strip LEDs show the current value while polling with
+    /// [`poll_input_events`](Self::poll_input_events) or monitoring input.
+    pub fn set_strip_mode(&mut self, mode: StripMode) -> Result<()> {
+        self.configure_tracker(move |tracker| tracker.strip_controller_mut().set_mode(mode));
+        self.apply_strip_feedback()
     }
 
-    // === Input Management ===
+    /// Color used for the strip mode LED feedback
+    pub fn set_strip_feedback_color(&mut self, color: MaschineLEDColor) -> Result<()> {
+        self.configure_tracker(move |tracker| {
+            tracker.strip_controller_mut().set_feedback_color(color)
+        });
+        self.apply_strip_feedback()
+    }
 
-    /// Start monitoring input with a callback (non-blocking)
-    pub fn start_input_monitoring<F>(&mut self, callback: F) -> Result<()>
-    where
-        F: Fn(InputEvent) + Send + 'static,
-    {
-        if self.input_thread.is_some() {
-            return Err(MK3Error::InvalidData(
-                "Input monitoring already running".to_string(),
-            ));
+    /// Poll for input events (blocking with timeout). LED and display
+    /// updates made along the way (strip feedback, animations, pad flashes,
+    /// held back LED writes, the keep-alive, a loaded macro script) are
+    /// logged when they fail instead of failing the poll, so no events are
+    /// lost; failed writes show up in [`usb_health`](Self::usb_health).
+    pub fn poll_input_events(&mut self) -> Result<Vec<InputEvent>> {
+        if self.check_resume()? {
+            return Ok(vec![InputEvent::DeviceResumed]);
         }
 
-        let (sender, receiver) = mpsc::channel();
-        self.input_event_receiver = Some(receiver);
+        let data = match self.read_input(self.timeouts.input) {
+            Ok(data) => data,
+            Err(MK3Error::Usb(error)) if !Self::is_connection_lost(&error) => {
+                // Whatever the failed read would have returned is gone
+                self.input_tracker.note_lost_packets(1);
+                return Err(MK3Error::Usb(error));
+            }
+            Err(e) => return Err(e),
+        };
 
-        // Clone the device handle for the thread
-        let device = self.device_handle.device();
-        let mut thread_device_handle = device.open()?;
-        
-        #[cfg(windows)]
-        Self::claim_interface_with_detach(&mut thread_device_handle, HID_INTERFACE)?;
-        
-        #[cfg(unix)]
-        Self::detach_and_claim_interface(&mut thread_device_handle, HID_INTERFACE)?;
+        let mut events = if data.is_empty() {
+            Vec::new()
+        } else {
+            if self.state_sync_requested.swap(false, Ordering::Relaxed) {
+                self.input_tracker.request_state_sync();
+            }
+            let events =
+                Self::process_input_packet(&mut self.input_tracker, &self.input_counters, &data)?;
+            if let Ok(mut follower) = self.strip_follower.lock() {
+                follower.touch(&self.input_tracker.touch_strip(), Instant::now());
+            }
+            events
+        };
+        // Injected before this read, so they come first
+        events.splice(0..0, self.injected_events.drain(..));
 
-        let stop_signal = Arc::clone(&self.input_stop_signal);
-        let mut tracker = InputTracker::new();
+        // The events are already read; failed output mustn't lose them
+        if let Err(e) = self.update_leds_after_poll(&events) {
+            log::warn!("LED update after polling failed: {}", e);
+        }
+        if let Err(e) = self.service_display_keep_alive() {
+            log::warn!("Display keep-alive failed: {}", e);
+        }
 
-        let handle = thread::spawn(move || {
-            loop {
-                // Check stop signal
-                if let Ok(stop) = stop_signal.lock() {
-                    if *stop {
-                        break;
-                    }
-                }
+        #[cfg(feature = "scripting")]
+        if let Err(e) = self.run_script(&events) {
+            log::warn!("Macro script failed: {}", e);
+        }
 
-                // Read input from device
-                let data = {
-                    let mut buffer = vec![0u8; 64];
-                    let timeout = Duration::from_millis(100);
-                    match thread_device_handle.read_interrupt(INPUT_ENDPOINT, &mut buffer, timeout)
-                    {
-                        Ok(bytes_read) => {
-                            buffer.truncate(bytes_read);
-                            buffer
-                        }
-                        Err(rusb::Error::Timeout) => Vec::new(),
-                        Err(_) => {
-                            thread::sleep(Duration::from_millis(10));
-                            continue;
-                        }
-                    }
-                };
+        Ok(events)
+    }
 
-                if data.is_empty() {
-                    thread::sleep(Duration::from_millis(10));
-                    continue;
-                }
+    /// The LED side effects of a poll
+    fn update_leds_after_poll(&mut self, events: &[InputEvent]) -> Result<()> {
+        if events
+            .iter()
+            .any(|event| matches!(event, InputEvent::StripChanged { .. }))
+        {
+            self.apply_strip_feedback()?;
+        }
+        self.update_led_animations()?;
+        self.update_pad_flashes(events)?;
+        self.send_pending_led_writes()
+    }
 
-                // Process packet and get events
-                let events = match Self::process_input_packet(&mut tracker, &data) {
-                    Ok(events) => events,
-                    Err(_) => continue,
-                };
+    /// Flash pads in their velocity color on every hit, fading back to the
+    /// pad's own color, or stop with `None`. Flashes advance each time
+    /// [`poll_input_events`](Self::poll_input_events) returns, so poll
+    /// with a short read timeout for a smooth decay.
+    pub fn set_pad_hit_flash(&mut self, config: Option<PadFlashConfig>) -> Result<()> {
+        self.pad_flash = config;
+        if config.is_none() && self.pad_flasher.is_active() {
+            // Let the running flashes finish immediately
+            let finish = PadFlashConfig {
+                hold: Duration::ZERO,
+                decay: Duration::ZERO,
+                ..PadFlashConfig::default()
+            };
+            self.apply_pad_flashes(&finish, &[])?;
+        }
+        Ok(())
+    }
 
-                // Send events through callback and channel
-                for event in events {
-                    callback(event.clone());
-                    let _ = sender.send(event);
-                }
+    /// Run a built-in animation on the pads and/or touch strip, replacing
+    /// any already running on the same LEDs. Like pad flashes, animations
+    /// advance each time [`poll_input_events`](Self::poll_input_events)
+    /// returns, so poll with a short read timeout for smooth motion; apps
+    /// using the monitoring threads call
+    /// [`update_led_animations`](Self::update_led_animations) instead.
+    ///
+    /// The animation owns its LEDs while it runs: colors set there by the
+    /// app are drawn over on the next update.
+    pub fn start_led_animation(&mut self, animation: LedAnimation) -> Result<()> {
+        self.led_animator.start(animation, Instant::now());
+        self.update_led_animations()
+    }
 
-                thread::sleep(Duration::from_millis(10));
-            }
-        });
+    /// Stop all built-in animations, leaving their LEDs as last drawn
+    pub fn stop_led_animations(&mut self) {
+        self.led_animator.stop();
+    }
 
-        self.input_thread = Some(handle);
-        Ok(())
+    pub fn has_led_animations(&self) -> bool {
+        self.led_animator.is_active()
     }
 
-    /// Stop input monitoring
-    pub fn stop_input_monitoring(&mut self) -> Result<()> {
-        if let Ok(mut stop) = self.input_stop_signal.lock() {
-            *stop = true;
+    /// Draw the running animations and the touch strip follow mode for the
+    /// current time and send the LEDs if they changed
+    pub fn update_led_animations(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let animated = self.led_animator.update(&mut self.current_pad_leds, now);
+        let followed = Self::lock_strip_follower(&self.strip_follower)?
+            .update(&mut self.current_pad_leds.touch_strip_leds, now);
+        if animated || followed {
+            self.led_state_dirty = true;
+            self.write_led_state()?;
         }
+        Ok(())
+    }
 
-        if let Some(handle) = self.input_thread.take() {
-            handle.join().map_err(|_| {
-                MK3Error::InvalidData("Failed to join monitoring thread".to_string())
-            })?;
+    /// Light the touch strip LEDs under the fingers, `spread` LEDs either
+    /// side of each, leaving a trail that fades after the finger moves on,
+    /// or stop with `None`. Finger positions are taken from every packet by
+    /// the thread reading input, so apps don't wire strip input to LEDs
+    /// themselves: [`poll_input_events`](Self::poll_input_events) sends the
+    /// LEDs for the packet it just read, and the monitoring thread sends them
+    /// itself as packets arrive and while trails fade. The strip it drew is
+    /// taken into the LED state on the device's next LED write.
+    ///
+    /// While on, the follow mode owns the strip LEDs and draws over the strip
+    /// mode feedback, strip animations and colors set by the app. Turning it
+    /// off clears the strip and shows the strip mode feedback again.
+    pub fn set_touch_strip_follow(&mut self, config: Option<StripFollowConfig>) -> Result<()> {
+        let was_on = {
+            let mut follower = Self::lock_strip_follower(&self.strip_follower)?;
+            let was_on = follower.config().is_some();
+            follower.set_config(config);
+            was_on
+        };
+
+        if config.is_some() {
+            return self.update_led_animations();
+        }
+        if was_on {
+            // Whatever the monitoring thread drew is cleared too
+            if Self::lock_shared_pad_leds(&self.shared_pad_leds)
+                .thread_strip
+                .take()
+                .is_some()
+            {
+                self.sent_led_packets().pads = None;
+            }
+            self.current_pad_leds.touch_strip_leds = [MaschineLEDColor::black(); STRIP_LED_COUNT];
+            self.led_state_dirty = true;
+            self.write_led_state()?;
         }
+        self.apply_strip_feedback()
+    }
 
-        self.input_event_receiver = None;
+    pub fn touch_strip_follow(&self) -> Option<StripFollowConfig> {
+        Self::lock_strip_follower(&self.strip_follower)
+            .ok()?
+            .config()
+    }
+
+    fn lock_strip_follower(
+        follower: &Mutex<StripFollower>,
+    ) -> Result<MutexGuard<'_, StripFollower>> {
+        follower
+            .lock()
+            .map_err(|_| MK3Error::InvalidData("Touch strip follow lock poisoned".to_string()))
+    }
+
+    fn update_pad_flashes(&mut self, events: &[InputEvent]) -> Result<()> {
+        match self.pad_flash {
+            Some(config) => self.apply_pad_flashes(&config, events),
+            None => Ok(()),
+        }
+    }
 
-        // Reset stop signal for future use
-        if let Ok(mut stop) = self.input_stop_signal.lock() {
-            *stop = false;
+    fn apply_pad_flashes(&mut self, config: &PadFlashConfig, events: &[InputEvent]) -> Result<()> {
+        let now = Instant::now();
+        if self
+            .pad_flasher
+            .update(config, &mut self.current_pad_leds.pad_leds, events, now)
+        {
+            self.led_state_dirty = true;
+            self.write_led_state()?;
         }
+        Ok(())
+    }
+
+    /// Load a Rhai macro script (see [`crate::script`]) whose `on_event`
+    /// `poll_input_events` calls for every event it returns, replacing any
+    /// loaded script.
+    /// Events from the monitoring threads are not scripted.
+    #[cfg(feature = "scripting")]
+    pub fn load_script<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        self.script = Some(MacroScript::load(path)?);
+        Ok(())
+    }
 
-        Ok(())
+    /// Install an already compiled script, or remove the current one with `None`
+    #[cfg(feature = "scripting")]
+    pub fn set_script(&mut self, script: Option<MacroScript>) {
+        self.script = script;
     }
 
-    /// Poll for input events (blocking with timeout)
-    pub fn poll_input_events(&mut self) -> Result<Vec<InputEvent>> {
-        let data = self.read_input()?;
+    #[cfg(feature = "scripting")]
+    fn run_script(&mut self, events: &[InputEvent]) -> Result<()> {
+        let Some(mut script) = self.script.take() else {
+            return Ok(());
+        };
+        let result = events
+            .iter()
+            .try_for_each(|event| script.handle_event(self, event));
+        self.script = Some(script);
+        result
+    }
 
-        if data.is_empty() {
-            return Ok(Vec::new());
+    /// Show the strip mode's value on the touch strip LEDs
+    fn apply_strip_feedback(&mut self) -> Result<()> {
+        // The follow mode owns the strip LEDs while it is on
+        if Self::lock_strip_follower(&self.strip_follower)?
+            .config()
+            .is_some()
+        {
+            return Ok(());
         }
-
-        Self::process_input_packet(&mut self.input_tracker, &data)
+        if let Some(leds) = self.input_tracker.strip_controller().led_feedback() {
+            self.current_pad_leds.touch_strip_leds = leds;
+            self.led_state_dirty = true;
+            self.write_led_state()?;
+        }
+        Ok(())
     }
 
     /// Process a raw input packet and return events
-    fn process_input_packet(tracker: &mut InputTracker, data: &[u8]) -> Result<Vec<InputEvent>> {
+    fn process_input_packet(
+        tracker: &mut InputTracker,
+        counters: &InputCounters,
+        data: &[u8],
+    ) -> Result<Vec<InputEvent>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
 
-        match data[0] {
-            0x01 if data.len() >= 42 => {
+        // Malformed packets are skipped, and reported if the tracker has
+        // protocol warnings on; types that aren't decoded are passed on raw
+        let events = match data[0] {
+            0x01 if data.len() < 42 => {
+                let kind = ProtocolWarningKind::ShortPacket {
+                    packet_type: 0x01,
+                    expected: 42,
+                };
+                return Ok(tracker.protocol_warning(kind, data).into_iter().collect());
+            }
+            0x01 => {
                 let input_state = InputState::from_button_packet(data)?;
-                Ok(tracker.update(input_state))
+                tracker.update(input_state)
             }
             0x02 => {
                 let pad_state = PadState::from_pad_packet(data)?;
-                Ok(tracker.update_pads(pad_state))
+                tracker.update_pads(pad_state)
+            }
+            packet_type => {
+                return Ok(vec![InputEvent::RawInput(RawInputPacket {
+                    packet_type,
+                    data: data.to_vec(),
+                })]);
+            }
+        };
+
+        counters.packets.fetch_add(1, Ordering::Relaxed);
+        for event in &events {
+            if let InputEvent::PacketsDropped { count } = event {
+                counters.dropped.fetch_add(*count as u64, Ordering::Relaxed);
             }
-            _ => Ok(Vec::new()),
+        }
+        Ok(events)
+    }
+
+    /// Report the complete input state with the next button packet, through
+    /// polling or the monitoring thread, whichever reads it. See
+    /// [`InputTracker::request_state_sync`] for what is reported.
+    pub fn request_state_sync(&self) {
+        self.state_sync_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Input packets processed and lost so far, by polling and the
+    /// monitoring thread together
+    pub fn input_stats(&self) -> InputStats {
+        InputStats {
+            packets: self.input_counters.packets.load(Ordering::Relaxed),
+            dropped: self.input_counters.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn connection_options(&self) -> ConnectionOptions {
+        self.connection_options
+    }
+
+    /// Transfer, error and retry counts by endpoint, with throughput and
+    /// error rate over the last ten seconds
+    pub fn usb_health(&self) -> UsbHealth {
+        self.health.snapshot()
+    }
+
+    /// Call `callback` when an endpoint's error rate over the last ten
+    /// seconds rises above `error_rate` (0.0-1.0), e.g. to alert before the
+    /// controller becomes unusable. It fires once per crossing, from
+    /// whichever thread made the failing transfer, and only once the
+    /// endpoint has seen a few transfers in the window.
+    pub fn set_usb_health_alert<F>(&mut self, error_rate: f64, callback: F)
+    where
+        F: Fn(UsbEndpoint, &EndpointHealth) + Send + Sync + 'static,
+    {
+        self.health.set_alert(Some((error_rate, Arc::new(callback))));
+    }
+
+    pub fn clear_usb_health_alert(&mut self) {
+        self.health.set_alert(None);
+    }
+
+    /// Copy every LED and display packet sent from now on into `recorder`,
+    /// whether or not the transfer succeeds. `None` stops recording.
+    pub fn set_output_recorder(&mut self, recorder: Option<OutputRecorder>) {
+        self.output_recorder = recorder.clone();
+        self.send_worker_update(WorkerUpdate::OutputRecorder(recorder));
+    }
+
+    pub fn output_recorder(&self) -> Option<&OutputRecorder> {
+        self.output_recorder.as_ref()
+    }
+
+    fn record_output(&self, endpoint: UsbEndpoint, data: &[u8]) {
+        if let Some(recorder) = &self.output_recorder {
+            recorder.record(endpoint, data);
         }
     }
 
@@ -652,98 +3554,8 @@ impl MaschineMK3 {
 
     /// Set individual button LED brightness
     pub fn set_button_led(&mut self, button: InputElement, brightness: u8) -> Result<()> {
-        match button {
-            InputElement::Play => self.current_button_leds.play = brightness,
-            InputElement::Rec => self.current_button_leds.rec = brightness,
-            InputElement::Stop => self.current_button_leds.stop = brightness,
-            InputElement::Restart => self.current_button_leds.restart = brightness,
-            InputElement::Erase => self.current_button_leds.erase = brightness,
-            InputElement::Tap => self.current_button_leds.tap = brightness,
-            InputElement::Follow => self.current_button_leds.follow = brightness,
-            InputElement::ChannelMidi => self.current_button_leds.channel_midi = brightness,
-            InputElement::Arranger => self.current_button_leds.arranger = brightness,
-            InputElement::ArrowLeft => self.current_button_leds.arrow_left = brightness,
-            InputElement::ArrowRight => self.current_button_leds.arrow_right = brightness,
-            InputElement::FileSave => self.current_button_leds.file_save = brightness,
-            InputElement::Settings => self.current_button_leds.settings = brightness,
-            InputElement::Macro => self.current_button_leds.macro_set = brightness,
-            InputElement::Auto => self.current_button_leds.auto = brightness,
-            InputElement::Plugin => self.current_button_leds.plugin_instance = brightness,
-            InputElement::Mixer => self.current_button_leds.mixer = brightness,
-            InputElement::Sampling => self.current_button_leds.sampler = brightness,
-            InputElement::Volume => self.current_button_leds.volume = brightness,
-            InputElement::Swing => self.current_button_leds.swing = brightness,
-            InputElement::NoteRepeat => self.current_button_leds.note_repeat = brightness,
-            InputElement::Tempo => self.current_button_leds.tempo = brightness,
-            InputElement::Lock => self.current_button_leds.lock = brightness,
-            InputElement::Pitch => self.current_button_leds.pitch = brightness,
-            InputElement::Mod => self.current_button_leds.mod_ = brightness,
-            InputElement::Perform => self.current_button_leds.perform = brightness,
-            InputElement::Notes => self.current_button_leds.notes = brightness,
-            InputElement::Shift => self.current_button_leds.shift = brightness,
-            InputElement::FixedVel => self.current_button_leds.fixed_vel = brightness,
-            InputElement::PadMode => self.current_button_leds.pad_mode = brightness,
-            InputElement::Keyboard => self.current_button_leds.keyboard = brightness,
-            InputElement::Chords => self.current_button_leds.chords = brightness,
-            InputElement::Step => self.current_button_leds.step = brightness,
-            InputElement::Scene => self.current_button_leds.scene = brightness,
-            InputElement::Pattern => self.current_button_leds.pattern = brightness,
-            InputElement::Events => self.current_button_leds.events = brightness,
-            InputElement::Variation => self.current_button_leds.variation = brightness,
-            InputElement::Duplicate => self.current_button_leds.duplicate = brightness,
-            InputElement::Select => self.current_button_leds.select = brightness,
-            InputElement::Solo => self.current_button_leds.solo = brightness,
-            InputElement::Mute => self.current_button_leds.mute = brightness,
-            InputElement::DisplayButton1 => self.current_button_leds.display_button_1 = brightness,
-            InputElement::DisplayButton2 => self.current_button_leds.display_button_2 = brightness,
-            InputElement::DisplayButton3 => self.current_button_leds.display_button_3 = brightness,
-            InputElement::DisplayButton4 => self.current_button_leds.display_button_4 = brightness,
-            InputElement::DisplayButton5 => self.current_button_leds.display_button_5 = brightness,
-            InputElement::DisplayButton6 => self.current_button_leds.display_button_6 = brightness,
-            InputElement::DisplayButton7 => self.current_button_leds.display_button_7 = brightness,
-            InputElement::DisplayButton8 => self.current_button_leds.display_button_8 = brightness,
-            // For RGB LEDs, convert brightness to grayscale color
-            InputElement::GroupA => {
-                self.current_button_leds.group_a = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupB => {
-                self.current_button_leds.group_b = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupC => {
-                self.current_button_leds.group_c = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupD => {
-                self.current_button_leds.group_d = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupE => {
-                self.current_button_leds.group_e = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupF => {
-                self.current_button_leds.group_f = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupG => {
-                self.current_button_leds.group_g = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::GroupH => {
-                self.current_button_leds.group_h = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::BrowserPlugin => {
-                self.current_button_leds.browser_plugin =
-                    MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::EncoderUp => {
-                self.current_button_leds.nav_up = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::EncoderLeft => {
-                self.current_button_leds.nav_left = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::EncoderRight => {
-                self.current_button_leds.nav_right = MaschineLEDColor::from_brightness(brightness)
-            }
-            InputElement::EncoderDown => {
-                self.current_button_leds.nav_down = MaschineLEDColor::from_brightness(brightness)
-            }
-            _ => return Ok(()), // Elements that don't have LEDs
+        if !self.current_button_leds.set_brightness(&button, brightness) {
+            return Ok(()); // Elements that don't have LEDs
         }
         self.led_state_dirty = true;
         self.write_led_state()?;
@@ -756,21 +3568,8 @@ impl MaschineMK3 {
         button: InputElement,
         color: MaschineLEDColor,
     ) -> Result<()> {
-        match button {
-            InputElement::GroupA => self.current_button_leds.group_a = color,
-            InputElement::GroupB => self.current_button_leds.group_b = color,
-            InputElement::GroupC => self.current_button_leds.group_c = color,
-            InputElement::GroupD => self.current_button_leds.group_d = color,
-            InputElement::GroupE => self.current_button_leds.group_e = color,
-            InputElement::GroupF => self.current_button_leds.group_f = color,
-            InputElement::GroupG => self.current_button_leds.group_g = color,
-            InputElement::GroupH => self.current_button_leds.group_h = color,
-            InputElement::BrowserPlugin => self.current_button_leds.browser_plugin = color,
-            InputElement::EncoderUp => self.current_button_leds.nav_up = color,
-            InputElement::EncoderLeft => self.current_button_leds.nav_left = color,
-            InputElement::EncoderRight => self.current_button_leds.nav_right = color,
-            InputElement::EncoderDown => self.current_button_leds.nav_down = color,
-            _ => return Ok(()), // Elements that don't have RGB LEDs
+        if !self.current_button_leds.set_color(&button, color) {
+            return Ok(()); // Elements that don't have RGB LEDs
         }
         self.led_state_dirty = true;
         self.write_led_state()?;
@@ -779,37 +3578,219 @@ impl MaschineMK3 {
 
     /// Set individual pad LED color
     pub fn set_pad_led(&mut self, pad_number: u8, color: MaschineLEDColor) -> Result<()> {
-        if pad_number > 15 {
-            return Err(MK3Error::InvalidData("Pad number must be 0-15".to_string()));
+        let pad = PadIndex::try_from(pad_number)?;
+        if self.current_pad_leds.pad(pad) != color {
+            self.current_pad_leds.set_pad(pad, color);
+            self.led_state_dirty = true;
+            self.write_led_state()?;
         }
+        Ok(())
+    }
+
+    /// Set a pad LED to the palette color closest to `color` after the pad
+    /// calibration, see [`set_led_calibration`](Self::set_led_calibration).
+    ///
+    /// The pads have no direct RGB addressing: the LED reports carry one
+    /// palette byte per LED (17 hues, dim or bright), so arbitrary colors
+    /// can only be approximated.
+    pub fn set_pad_led_rgb(&mut self, pad_number: u8, color: RgbColor) -> Result<()> {
+        let color = self.led_calibration.pad_color(color);
+        self.set_pad_led(pad_number, color)
+    }
 
-        let old_color = self.current_pad_leds.pad_leds[pad_number as usize];
-        if old_color != color {
-            self.current_pad_leds.pad_leds[pad_number as usize] = color;
+    /// Set a button LED from an RGB color after the button calibration. RGB
+    /// buttons get the closest palette color, single-color ones a matching
+    /// brightness.
+    pub fn set_button_led_rgb(&mut self, button: InputElement, color: RgbColor) -> Result<()> {
+        let led_color = self.led_calibration.button_color(color);
+        if self.current_button_leds.set_color(&button, led_color) {
             self.led_state_dirty = true;
-            self.write_led_state()?;
+            return self.write_led_state();
+        }
+        let brightness = self.led_calibration.button_brightness(color);
+        self.set_button_led(button, brightness)
+    }
+
+    /// Correct RGB colors given to [`set_pad_led_rgb`](Self::set_pad_led_rgb)
+    /// and [`set_button_led_rgb`](Self::set_button_led_rgb) before they are
+    /// matched to the LED palette, to tune for one unit's LEDs
+    pub fn set_led_calibration(&mut self, calibration: LedCalibration) {
+        self.led_calibration = calibration;
+    }
+
+    pub fn led_calibration(&self) -> &LedCalibration {
+        &self.led_calibration
+    }
+
+    /// Settings applied at connection or by the last
+    /// [`set_settings`](Self::set_settings). Changes made through the
+    /// individual setters aren't reflected here.
+    pub fn settings(&self) -> &DeviceSettings {
+        &self.settings
+    }
+
+    /// Apply settings to the device and save them to
+    /// [`settings_path`](Self::settings_path), so they are loaded again the
+    /// next time this unit connects
+    pub fn set_settings(&mut self, settings: DeviceSettings) -> Result<()> {
+        self.apply_settings(&settings)?;
+        if let Some(path) = &self.settings_path {
+            settings.save(path)?;
         }
+        self.settings = settings;
         Ok(())
     }
 
-    /// Set all button LEDs to the same brightness
-    pub fn set_all_button_leds(&mut self, brightness: u8) -> Result<()> {
-        let mut changed = false;
+    /// File [`set_settings`](Self::set_settings) writes to: by default the
+    /// one for this unit's serial number in the platform config directory,
+    /// see [`DeviceSettings::path_for_serial`]. `None` if the serial couldn't
+    /// be read or settings loading was turned off in [`ConnectionOptions`].
+    pub fn settings_path(&self) -> Option<&Path> {
+        self.settings_path.as_deref()
+    }
+
+    /// Keep settings in another file, or only in memory with `None`. The
+    /// file isn't read; use [`DeviceSettings::load`] and
+    /// [`set_settings`](Self::set_settings) for that.
+    pub fn set_settings_path(&mut self, path: Option<PathBuf>) {
+        self.settings_path = path;
+    }
 
-        // Set all brightness-based LEDs
-        if self.current_button_leds.play != brightness {
-            self.current_button_leds.play = brightness;
-            changed = true;
+    fn apply_settings(&mut self, settings: &DeviceSettings) -> Result<()> {
+        self.led_calibration = LedCalibration {
+            pads: settings.pad_calibration,
+            buttons: settings.button_calibration,
+            touch_strip: settings.touch_strip_calibration,
+            palette: self.led_calibration.palette.clone(),
+        };
+        for (display, correction) in settings.display_correction.iter().enumerate() {
+            self.set_display_color_correction(display as u8, *correction)?;
+        }
+        self.set_pad_filter_config(settings.pad_filter.clone());
+        if settings.led_brightness != self.led_master_brightness {
+            self.set_led_master_brightness(settings.led_brightness)?;
         }
-        // Add more brightness-based buttons as needed
+        Ok(())
+    }
 
-        if changed {
-            self.led_state_dirty = true;
-            self.write_led_state()?;
+    /// Color correct everything sent to a display as RGB888 from now on, or
+    /// stop with `None`. Frames already on screen aren't redrawn, and the
+    /// cached framebuffers keep the uncorrected colors.
+    pub fn set_display_color_correction(
+        &mut self,
+        display_num: u8,
+        correction: Option<ColorCorrection>,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        let cache = &mut self.displays[display_num as usize];
+        cache.correction = correction.map(CorrectionTable::new);
+        cache.packets.clear();
+        Ok(())
+    }
+
+    pub fn display_color_correction(&self, display_num: u8) -> Option<ColorCorrection> {
+        let cache = self.displays.get(display_num as usize)?;
+        cache.correction.as_ref().map(|table| *table.correction())
+    }
+
+    /// How RGB888 data sent to a display from now on is reduced to RGB565X,
+    /// e.g. to dither gradients instead of showing bands
+    pub fn set_display_conversion(
+        &mut self,
+        display_num: u8,
+        options: ConversionOptions,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        let cache = &mut self.displays[display_num as usize];
+        cache.conversion = options;
+        cache.packets.clear();
+        Ok(())
+    }
+
+    pub fn display_conversion(&self, display_num: u8) -> Option<ConversionOptions> {
+        self.displays.get(display_num as usize).map(|cache| cache.conversion)
+    }
+
+    /// Resend a tiny region of a display whenever nothing has been sent to
+    /// it for `interval`, for firmware that blanks the screens when display
+    /// packets stop arriving. `None` turns it off (the default).
+    ///
+    /// The refresh rewrites the top-left pixel pair from the cached
+    /// framebuffer, so it only runs once a frame has gone through the
+    /// dirty-region API and never changes what is shown. It is sent from
+    /// [`poll_input_events`](Self::poll_input_events), which logs a failed
+    /// refresh instead of losing the events it read. Apps that don't poll,
+    /// e.g. because they use input monitoring or only draw now and then,
+    /// must call [`service_display_keep_alive`](Self::service_display_keep_alive)
+    /// from their own loop or timer, at least as often as `interval`.
+    pub fn set_display_keep_alive(
+        &mut self,
+        display_num: u8,
+        interval: Option<Duration>,
+    ) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        self.displays[display_num as usize].keep_alive = interval;
+        Ok(())
+    }
+
+    pub fn display_keep_alive(&self, display_num: u8) -> Option<Duration> {
+        self.displays.get(display_num as usize)?.keep_alive
+    }
+
+    /// Keep the packets of the last `entries` regions sent to a display
+    /// through [`write_display_framebuffer_rgb888_dirty`](Self::write_display_framebuffer_rgb888_dirty),
+    /// so redrawing any of them again, e.g. when toggling between pages,
+    /// skips the RGB565 conversion. A full-screen packet takes about 260 KB.
+    /// Defaults to [`DEFAULT_PACKET_CACHE_ENTRIES`](crate::output::DEFAULT_PACKET_CACHE_ENTRIES);
+    /// 0 turns the cache off.
+    pub fn set_display_packet_cache(&mut self, display_num: u8, entries: usize) -> Result<()> {
+        Self::check_display_num(display_num)?;
+        self.displays[display_num as usize]
+            .packets
+            .set_capacity(entries);
+        Ok(())
+    }
+
+    pub fn display_packet_cache(&self, display_num: u8) -> Option<usize> {
+        let cache = self.displays.get(display_num as usize)?;
+        Some(cache.packets.capacity())
+    }
+
+    /// Send the keep-alive refresh to every display that is due one
+    pub fn service_display_keep_alive(&self) -> Result<()> {
+        let keep_alive_rect = DisplayRect::new(0, 0, 2, 1);
+
+        for (display_num, cache) in self.displays.iter().enumerate() {
+            let (Some(interval), Some(framebuffer)) = (cache.keep_alive, &cache.framebuffer)
+            else {
+                continue;
+            };
+            let idle = match self.last_display_write[display_num].lock() {
+                Ok(last_write) => last_write.elapsed(),
+                Err(_) => continue,
+            };
+            if idle < interval {
+                continue;
+            }
+
+            let pixels = Self::region_to_rgb565(
+                cache.converter(),
+                cache.format,
+                framebuffer,
+                keep_alive_rect,
+            );
+            self.write_display_region(display_num as u8, keep_alive_rect, pixels)?;
         }
         Ok(())
     }
 
+    /// Set all button LEDs to the same brightness (RGB buttons get a grayscale color)
+    pub fn set_all_button_leds(&mut self, brightness: u8) -> Result<()> {
+        self.current_button_leds.set_all_brightness(brightness);
+        self.led_state_dirty = true;
+        self.write_led_state()
+    }
+
     /// Set all pad LEDs to the same color
     pub fn set_all_pad_leds(&mut self, color: MaschineLEDColor) -> Result<()> {
         let mut changed = false;
@@ -828,6 +3809,50 @@ impl MaschineMK3 {
         Ok(())
     }
 
+    /// Stage several LED changes and send them together.
+    ///
+    /// The closure receives the current LED state; after it returns, at most
+    /// one button packet (0x80) and one pad/touch strip packet (0x81) are
+    /// written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use maschine3_hal::{InputElement, MaschineLEDColor, MaschineMK3};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut device = MaschineMK3::new()?;
+    /// device.update_leds(|leds| {
+    ///     for pad in 0..16 {
+    ///         leds.set_pad(pad, MaschineLEDColor::new(pad, true));
+    ///     }
+    ///     leds.set_button(InputElement::Play, 127);
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_leds<F>(&mut self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut LedFrame),
+    {
+        let mut frame = LedFrame {
+            buttons: self.current_button_leds.clone(),
+            pads: self.current_pad_leds.clone(),
+        };
+        update(&mut frame);
+
+        self.current_button_leds = frame.buttons;
+        self.current_pad_leds = frame.pads;
+        self.led_state_dirty = true;
+        self.write_led_state()
+    }
+
+    /// Set every LED of a group to a color or brightness in one write
+    pub fn set_group(&mut self, group: &LedGroup, value: impl Into<LedValue>) -> Result<()> {
+        let value = value.into();
+        self.update_leds(|leds| group.apply(leds, value))
+    }
+
     /// Turn off all LEDs (set to black/0 brightness)
     pub fn clear_all_leds(&mut self) -> Result<()> {
         self.current_button_leds = ButtonLedState::default();
@@ -854,74 +3879,413 @@ impl MaschineMK3 {
 
     /// Force send LED changes even if no changes detected
     pub fn flush_led_changes(&mut self) -> Result<()> {
+        self.send_led_packets(true)
+    }
+
+    /// Enable or disable differential LED writes (enabled by default).
+    ///
+    /// When enabled, a button (0x80) or pad (0x81) packet is only sent if it
+    /// differs from the last one written, so changing a single pad sends just
+    /// the pad packet. When disabled, every LED update sends both packets.
+    pub fn set_differential_led_writes(&mut self, enabled: bool) {
+        self.differential_led_writes = enabled;
+    }
+
+    /// Space LED writes at least `interval` apart, or send every change at
+    /// once with `None` (the default).
+    ///
+    /// Changes made within the interval are kept and go out with the next
+    /// LED update or [`poll_input_events`](Self::poll_input_events) call
+    /// after it, so feedback logic that relights pads on every quick re-hit
+    /// costs one write per interval instead of one per hit. Apps using the
+    /// monitoring threads call
+    /// [`send_pending_led_writes`](Self::send_pending_led_writes) instead;
+    /// [`flush_led_changes`](Self::flush_led_changes) sends at once.
+    pub fn set_led_write_interval(&mut self, interval: Option<Duration>) {
+        self.led_write_interval = interval;
+    }
+
+    /// LED packets (0-2) whose state was set but hasn't been sent to the
+    /// device yet, because of [`set_led_write_interval`](Self::set_led_write_interval)
+    /// or a failed write. What `get_pad_led_color` and friends return is
+    /// already the new state.
+    pub fn pending_led_writes(&self) -> usize {
+        let (button_packet, pad_packet) = self.led_packets();
+        let sent = self.sent_led_packets();
+        usize::from(sent.buttons != Some(button_packet))
+            + usize::from(sent.pads != Some(pad_packet))
+    }
+
+    /// Send LED changes held back by the write interval if it has passed
+    pub fn send_pending_led_writes(&mut self) -> Result<()> {
+        if self.led_state_dirty && !self.led_writes_deferred() {
+            self.write_led_state()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the write interval hasn't passed since the last LED write
+    fn led_writes_deferred(&self) -> bool {
+        match (self.led_write_interval, self.last_led_write) {
+            (Some(interval), Some(last)) => last.elapsed() < interval,
+            _ => false,
+        }
+    }
+
+    /// Walk the user through pressing every button, turning every knob,
+    /// hitting every pad and sliding the touch strip, checking that each
+    /// control reports on the expected packet bits. Pad hits are measured for
+    /// per-pad velocity calibration. Progress is passed to `report`; the
+    /// control under test is lit where it has an LED and the LED state is
+    /// restored afterwards.
+    ///
+    /// Input monitoring must not be running, since the self-test reads input
+    /// itself.
+    pub fn run_selftest<F>(&mut self, report: F) -> Result<SelfTestReport>
+    where
+        F: FnMut(SelfTestEvent),
+    {
+        if self.input_thread.is_some() {
+            return Err(MK3Error::InvalidData(
+                "Stop input monitoring before running the self-test".to_string(),
+            ));
+        }
+        selftest::run(self, report)
+    }
+
+    /// Capture the current LED state, master brightness and cached display
+    /// frames so they can be restored on a new connection after a USB reset
+    /// or reconnect
+    pub fn save_state(&self) -> DeviceState {
+        DeviceState {
+            buttons: self.current_button_leds.clone(),
+            pads: self.current_pad_leds.clone(),
+            led_master_brightness: self.led_master_brightness,
+            displays: self
+                .displays
+                .each_ref()
+                .map(|display| display.rgb888_framebuffer()),
+        }
+    }
+
+    /// Apply a saved state, sending every LED packet and display frame in full
+    pub(crate) fn restore_state(&mut self, state: &DeviceState) -> Result<()> {
+        self.current_button_leds = state.buttons.clone();
+        self.current_pad_leds = state.pads.clone();
+        self.led_master_brightness = state.led_master_brightness.clamp(0.0, 1.0);
+        self.flush_led_changes()?;
+
+        for (display_num, frame) in state.displays.iter().enumerate() {
+            if let Some(frame) = frame {
+                self.invalidate_display(display_num as u8)?;
+                self.write_display_framebuffer_rgb888_dirty(display_num as u8, frame)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flash every LED and both displays so that a user with several
+    /// controllers can tell which one this handle drives: two quick flashes
+    /// a second for `duration`, with the [serial number](Self::serial_number)
+    /// shown large on the displays. Blocks until done, then restores the
+    /// LEDs and the display frames sent through the dirty-region path;
+    /// displays that weren't are left black.
+    pub fn identify(&mut self, duration: Duration) -> Result<()> {
+        let state = self.save_state();
+        let serial = self
+            .serial_number()
+            .unwrap_or_else(|_| "MASCHINE MK3".to_string());
+        let result = self.run_identify(&serial, duration);
+
+        // Restore even if the pattern was cut short
+        let restored = self.restore_state(&state).and_then(|()| {
+            if !self.is_display_available() {
+                return Ok(());
+            }
+            let num_pixels = Self::DISPLAY_WIDTH as usize * Self::DISPLAY_HEIGHT as usize;
+            let black = vec![0; num_pixels * 3];
+            for (display_num, frame) in state.displays.iter().enumerate() {
+                if frame.is_none() {
+                    self.write_display_framebuffer_rgb888_dirty(display_num as u8, &black)?;
+                }
+            }
+            Ok(())
+        });
+        result.and(restored)
+    }
+
+    /// Show the identify pattern until `duration` has passed, writing only
+    /// when it turns on or off
+    fn run_identify(&mut self, serial: &str, duration: Duration) -> Result<()> {
+        let frames = [false, true].map(|lit| identify::frame(serial, lit));
+        let start = Instant::now();
+        let mut shown = None;
+        while start.elapsed() < duration {
+            let lit = identify::is_lit(start.elapsed());
+            if shown != Some(lit) {
+                let color = if lit {
+                    MaschineLEDColor::white(true)
+                } else {
+                    MaschineLEDColor::black()
+                };
+                self.update_leds(|leds| {
+                    leds.buttons.set_all_brightness(if lit { 127 } else { 0 });
+                    leds.set_all_pads(color);
+                    leds.pads.touch_strip_leds = [color; STRIP_LED_COUNT];
+                })?;
+                if self.is_display_available() {
+                    let frame = &frames[lit as usize];
+                    self.present_both(frame, frame)?;
+                }
+                shown = Some(lit);
+            }
+            let remaining = duration.saturating_sub(start.elapsed());
+            thread::sleep(identify::POLL_INTERVAL.min(remaining));
+        }
+        Ok(())
+    }
+
+    /// Show a startup image on both displays, fading it in from black if
+    /// asked. Blocks until the fade is done. The image is sent through the
+    /// dirty-region path, so [`ShutdownBehavior::FadeOut`] can fade it out
+    /// again.
+    pub fn show_splash(&mut self, image: &SplashImage, transition: SplashTransition) -> Result<()> {
+        let duration = match transition {
+            SplashTransition::Cut => Duration::ZERO,
+            SplashTransition::FadeIn(duration) => duration,
+        };
+        let steps = splash::fade_steps(duration);
+        self.run_fade(steps, |step| {
+            let frame = splash::faded(image.rgb888(), step as f32 / steps as f32);
+            [Some(frame.clone()), Some(frame)]
+        })
+    }
+
+    /// Fade the cached display frames down to black
+    fn fade_out(&mut self, duration: Duration) -> Result<()> {
+        let frames = [self.framebuffer_snapshot(0), self.framebuffer_snapshot(1)];
+        let steps = splash::fade_steps(duration);
+        self.run_fade(steps, |step| {
+            let level = 1.0 - step as f32 / steps as f32;
+            frames
+                .each_ref()
+                .map(|frame| frame.as_ref().map(|frame| splash::faded(frame, level)))
+        })
+    }
+
+    /// Send `frame(step)` to the displays for steps 1 to `steps`, at the fade
+    /// frame rate
+    fn run_fade<F>(&mut self, steps: u32, frame: F) -> Result<()>
+    where
+        F: Fn(u32) -> [Option<Vec<u8>>; 2],
+    {
+        let period = Duration::from_secs(1) / splash::FADE_FPS;
+        let mut next_frame = Instant::now();
+        for step in 1..=steps {
+            for (display_num, rgb) in frame(step).iter().enumerate() {
+                if let Some(rgb) = rgb {
+                    self.write_display_framebuffer_rgb888_dirty(display_num as u8, rgb)?;
+                }
+            }
+            if step < steps {
+                next_frame += period;
+                let now = Instant::now();
+                if next_frame > now {
+                    thread::sleep(next_frame - now);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Choose what the LEDs and displays show when the device is closed or dropped
+    pub fn set_shutdown_behavior(&mut self, behavior: ShutdownBehavior) {
+        self.shutdown_behavior = behavior;
+    }
+
+    /// Close the connection, applying the configured [`ShutdownBehavior`].
+    ///
+    /// Dropping the device does the same but ignores any errors; use this to
+    /// find out whether the shutdown actually succeeded.
+    pub fn close(mut self) -> Result<()> {
+        self.shutdown()
+    }
+
+    /// Stop monitoring, apply the shutdown behavior and release the interfaces.
+    /// Every step is attempted even if an earlier one fails; the first error is
+    /// returned.
+    fn shutdown(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        let mut results = vec![self.stop_input_monitoring()];
+
+        match self.shutdown_behavior {
+            ShutdownBehavior::LeaveAsIs => {}
+            ShutdownBehavior::ClearAll => {
+                results.push(self.clear_all_leds());
+                for display_num in 0..2 {
+                    results.push(self.clear_display(display_num, 0, 0, 0));
+                }
+            }
+            ShutdownBehavior::FadeOut(duration) => {
+                results.push(self.fade_out(duration));
+                results.push(self.clear_all_leds());
+                for display_num in 0..2 {
+                    results.push(self.clear_display(display_num, 0, 0, 0));
+                }
+            }
+            ShutdownBehavior::ShowLogo => {
+                results.push(self.clear_all_leds());
+                for display_num in 0..2 {
+                    let logo = DisplayGraphics::logo(Self::DISPLAY_WIDTH, Self::DISPLAY_HEIGHT);
+                    results.push(self.send_display_image(display_num, logo));
+                }
+            }
+        }
+
+        results.push(
+            self.device_handle
+                .release_interface(HID_INTERFACE)
+                .map_err(MK3Error::Usb),
+        );
+        // Only the display interface that was actually claimed, which may be
+        // the fallback one
+        if let DisplayStatus::Available { interface } = self.display {
+            results.push(
+                self.device_handle
+                    .release_interface(interface)
+                    .map_err(MK3Error::Usb),
+            );
+        }
+
+        results.into_iter().collect()
+    }
+
+    /// Dim every LED by a global factor (0.0 - 1.0, default 1.0) without
+    /// changing the stored LED state. Single-color LEDs scale smoothly; RGB
+    /// LEDs switch to dim below 0.5.
+    pub fn set_led_master_brightness(&mut self, brightness: f32) -> Result<()> {
+        self.led_master_brightness = brightness.clamp(0.0, 1.0);
         self.write_led_state()
     }
 
+    pub fn led_master_brightness(&self) -> f32 {
+        self.led_master_brightness
+    }
+
     /// Read raw input data (for debugging purposes)
     pub fn read_raw_input(&self) -> Result<Vec<u8>> {
-        self.read_input()
+        self.read_input(self.timeouts.input)
+    }
+
+    /// Read raw input data, waiting up to `timeout`. Returns an empty packet
+    /// if nothing arrived in time.
+    pub fn read_raw_input_timeout(&self, timeout: Duration) -> Result<Vec<u8>> {
+        self.read_input(timeout)
     }
 
     // === Helper methods ===
 
     fn write_led_state(&mut self) -> Result<()> {
-        let button_packet = self.current_button_leds.to_packet();
-        self.write_led_data(&button_packet)?;
+        self.send_led_packets(false)
+    }
+
+    /// Send the tracked LED state, skipping packets identical to the last one
+    /// sent unless `force` is set or differential writes are disabled
+    fn send_led_packets(&mut self, force: bool) -> Result<()> {
+        // A reconnect resends the LED state itself
+        if self.check_resume()? {
+            return Ok(());
+        }
+
+        // Held back changes stay dirty for send_pending_led_writes
+        if !force && self.led_writes_deferred() {
+            return Ok(());
+        }
 
-        let pad_packet = self.current_pad_leds.to_packet();
-        self.write_led_data(&pad_packet)?;
+        let mut shared = Self::lock_shared_pad_leds(&self.shared_pad_leds);
+        if shared.adopt_thread_strip(&mut self.current_pad_leds) {
+            self.sent_led_packets().pads = None;
+        }
+        let always_send = force || !self.differential_led_writes;
+        let (button_packet, pad_packet) = self.led_packets();
+
+        if always_send || self.sent_led_packets().buttons != Some(button_packet) {
+            self.write_leds(&button_packet)?;
+            self.sent_led_packets().buttons = Some(button_packet);
+            self.last_led_write = Some(Instant::now());
+        }
+
+        if always_send || self.sent_led_packets().pads != Some(pad_packet) {
+            self.write_leds(&pad_packet)?;
+            shared.record_sent(&self.current_pad_leds, self.led_master_brightness);
+            self.sent_led_packets().pads = Some(pad_packet);
+            self.last_led_write = Some(Instant::now());
+        }
 
         self.led_state_dirty = false;
         Ok(())
     }
 
-    fn write_led_data(&self, data: &[u8]) -> Result<()> {
-        #[cfg(windows)]
-        {
-            if let Some(ref hid_dev) = self.hid_device {
-                match hid_dev.write(data) {
-                    Ok(_) => return Ok(()),
-                    Err(e) => {
-                        eprintln!("HID LED write failed: {}", e);
-                        return Err(MK3Error::Io(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            e,
-                        )));
-                    }
-                }
-            }
-
-            let timeout = Duration::from_millis(100);
-            match self
-                .device_handle
-                .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
-            {
-                Ok(_) => Ok(()),
-                Err(e) => Err(MK3Error::Usb(e)),
-            }
+    /// Button and pad packets for the tracked LED state
+    fn led_packets(&self) -> (ButtonLedPacket, PadLedPacket) {
+        let master = self.led_master_brightness;
+        if master < 1.0 {
+            (
+                self.current_button_leds.scaled(master).to_packet(),
+                self.current_pad_leds.scaled(master).to_packet(),
+            )
+        } else {
+            (
+                self.current_button_leds.to_packet(),
+                self.current_pad_leds.to_packet(),
+            )
         }
+    }
+}
 
-        #[cfg(unix)]
-        {
-            let timeout = Duration::from_millis(100);
-            match self
-                .device_handle
-                .write_interrupt(OUTPUT_ENDPOINT, data, timeout)
-            {
-                Ok(_) => Ok(()),
-                Err(e) => Err(MK3Error::Usb(e)),
-            }
+impl MaschineDevice for MaschineMK3 {
+    fn poll_input_events(&mut self) -> Result<Vec<InputEvent>> {
+        MaschineMK3::poll_input_events(self)
+    }
+
+    fn led_frame(&self) -> LedFrame {
+        LedFrame {
+            buttons: self.current_button_leds.clone(),
+            pads: self.current_pad_leds.clone(),
         }
     }
+
+    fn set_led_frame(&mut self, frame: LedFrame) -> Result<()> {
+        MaschineMK3::update_leds(self, |leds| *leds = frame)
+    }
+
+    /// Sent through the dirty-region path so the frame is cached for
+    /// `framebuffer_snapshot`
+    fn send_display_rgb888(&mut self, display_num: u8, rgb_data: &[u8]) -> Result<()> {
+        self.write_display_framebuffer_rgb888_dirty(display_num, rgb_data)
+            .map(|_| ())
+    }
+
+    fn framebuffer_snapshot(&self, display_num: u8) -> Option<Vec<u8>> {
+        MaschineMK3::framebuffer_snapshot(self, display_num)
+    }
+
+    fn update_leds<F>(&mut self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut LedFrame),
+    {
+        MaschineMK3::update_leds(self, update)
+    }
 }
 
 impl Drop for MaschineMK3 {
     fn drop(&mut self) {
-        // Stop input monitoring
-        let _ = self.stop_input_monitoring();
-
-        // Release interfaces on cleanup
-        let _ = self.device_handle.release_interface(HID_INTERFACE);
-        let _ = self.device_handle.release_interface(DISPLAY_INTERFACE);
+        // Errors can't be reported from drop; `close()` returns them
+        let _ = self.shutdown();
     }
 }
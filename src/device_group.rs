@@ -0,0 +1,225 @@
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::error::{MK3Error, Result};
+use crate::output::LedFrame;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One controller in a [`DeviceGroup`]
+struct Member {
+    device: Arc<Mutex<MaschineMK3>>,
+    offset: Duration,
+}
+
+/// Several controllers driven in lockstep, for installations with more than
+/// one MK3.
+///
+/// LED and display frames are written to every controller in parallel, one
+/// thread per controller, with the writes released together so the units
+/// update at the same moment. Frames can be rendered per controller from a
+/// shared animation clock: each controller sees the clock delayed by its own
+/// offset, so giving them increasing offsets makes an animation chase from
+/// one unit to the next.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{DeviceGroup, LedFrame, MaschineLEDColor};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut group = DeviceGroup::open_all()?;
+/// for index in 0..group.len() {
+///     group.set_offset(index, Duration::from_millis(250) * index as u32);
+/// }
+///
+/// loop {
+///     group.update_leds(|_, time| {
+///         let mut frame = LedFrame::default();
+///         let lit = time.as_millis() / 250 % 16;
+///         frame.set_pad(lit as u8, MaschineLEDColor::red(true));
+///         frame
+///     })?;
+///     std::thread::sleep(Duration::from_millis(33));
+/// }
+/// # }
+/// ```
+pub struct DeviceGroup {
+    members: Vec<Member>,
+    started: Instant,
+}
+
+impl DeviceGroup {
+    /// Group of already opened controllers, all with no offset
+    pub fn new(devices: Vec<MaschineMK3>) -> Self {
+        Self::from_shared(
+            devices
+                .into_iter()
+                .map(|device| Arc::new(Mutex::new(device)))
+                .collect(),
+        )
+    }
+
+    /// Group of controllers that are also used elsewhere, e.g. by a
+    /// [`DisplayAnimator`](crate::DisplayAnimator)
+    pub fn from_shared(devices: Vec<Arc<Mutex<MaschineMK3>>>) -> Self {
+        Self {
+            members: devices
+                .into_iter()
+                .map(|device| Member {
+                    device,
+                    offset: Duration::ZERO,
+                })
+                .collect(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Group of every controller plugged in, see [`MaschineMK3::open_all`]
+    pub fn open_all() -> Result<Self> {
+        Ok(Self::new(MaschineMK3::open_all()?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn device(&self, index: usize) -> Option<Arc<Mutex<MaschineMK3>>> {
+        self.members
+            .get(index)
+            .map(|member| Arc::clone(&member.device))
+    }
+
+    /// Delay of a controller's view of the animation clock. Returns false if
+    /// there is no such controller.
+    pub fn set_offset(&mut self, index: usize, offset: Duration) -> bool {
+        match self.members.get_mut(index) {
+            Some(member) => {
+                member.offset = offset;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn offset(&self, index: usize) -> Option<Duration> {
+        self.members.get(index).map(|member| member.offset)
+    }
+
+    /// Time since the group was created or the clock was reset
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Animation time a controller sees: the shared clock minus its offset,
+    /// held at zero until the offset has passed
+    pub fn time(&self, index: usize) -> Option<Duration> {
+        let offset = self.offset(index)?;
+        Some(self.elapsed().saturating_sub(offset))
+    }
+
+    pub fn reset_clock(&mut self) {
+        self.started = Instant::now();
+    }
+
+    /// Show the same LED state on every controller
+    pub fn set_led_frame(&self, frame: &LedFrame) -> Result<()> {
+        self.update_leds(|_, _| frame.clone())
+    }
+
+    /// Render an LED frame for each controller from its index and animation
+    /// time, then write them all at once
+    pub fn update_leds<F>(&self, render: F) -> Result<()>
+    where
+        F: Fn(usize, Duration) -> LedFrame + Sync,
+    {
+        self.in_lockstep(render, |device, frame| device.set_led_frame(frame))
+    }
+
+    /// Send the same 480x272 RGB888 frame to a display of every controller
+    pub fn send_display_rgb888(&self, display_num: u8, rgb_data: &[u8]) -> Result<()> {
+        self.update_display(display_num, |_, _| rgb_data.to_vec())
+    }
+
+    /// Render a 480x272 RGB888 frame for a display of each controller from
+    /// its index and animation time, then send them all at once. Only the
+    /// part of each display that changed is transferred.
+    pub fn update_display<F>(&self, display_num: u8, render: F) -> Result<()>
+    where
+        F: Fn(usize, Duration) -> Vec<u8> + Sync,
+    {
+        self.in_lockstep(render, |device, frame| {
+            device
+                .write_display_framebuffer_rgb888_dirty(display_num, &frame)
+                .map(|_| ())
+        })
+    }
+
+    /// Render on one thread per controller, then write every frame from
+    /// its own thread, releasing the writes together once all controllers
+    /// are locked. Returns the first error, after all controllers have been
+    /// written.
+    fn in_lockstep<T, R, A>(&self, render: R, apply: A) -> Result<()>
+    where
+        T: Send,
+        R: Fn(usize, Duration) -> T + Sync,
+        A: Fn(&mut MaschineMK3, T) -> Result<()> + Sync,
+    {
+        let elapsed = self.elapsed();
+        let frames: Vec<T> = thread::scope(|scope| {
+            let renderers: Vec<_> = self
+                .members
+                .iter()
+                .enumerate()
+                .map(|(index, member)| {
+                    let render = &render;
+                    scope.spawn(move || render(index, elapsed.saturating_sub(member.offset)))
+                })
+                .collect();
+            renderers
+                .into_iter()
+                .map(|renderer| {
+                    renderer
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+
+        let barrier = Barrier::new(self.members.len());
+        thread::scope(|scope| {
+            let writers: Vec<_> = self
+                .members
+                .iter()
+                .zip(frames)
+                .enumerate()
+                .map(|(index, (member, frame))| {
+                    let (apply, barrier) = (&apply, &barrier);
+                    scope.spawn(move || {
+                        let device = member.device.lock();
+                        barrier.wait();
+                        let mut device = device.map_err(|_| {
+                            MK3Error::InvalidData(format!("Device {} lock poisoned", index))
+                        })?;
+                        apply(&mut device, frame)
+                    })
+                })
+                .collect();
+
+            // Join every writer before reporting the first error
+            let results: Vec<Result<()>> = writers
+                .into_iter()
+                .map(|writer| {
+                    writer.join().unwrap_or_else(|_| {
+                        Err(MK3Error::InvalidData("Device write panicked".to_string()))
+                    })
+                })
+                .collect();
+            results.into_iter().collect()
+        })
+    }
+}
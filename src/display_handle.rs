@@ -0,0 +1,122 @@
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use crate::output::{DisplayRect, Rgb565};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// One display of a shared controller, from
+/// [`MaschineMK3::displays`](crate::MaschineMK3::displays).
+///
+/// Handles are `Send` and `Clone`, so each screen can be rendered on its
+/// own thread. Writes lock the device for the length of one transfer: both
+/// displays share a USB endpoint, so transfers take turns while rendering
+/// and color conversion of the next frame carry on in parallel.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::MaschineMK3;
+/// use std::sync::{Arc, Mutex};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = Arc::new(Mutex::new(MaschineMK3::new()?));
+/// let (left, right) = MaschineMK3::displays(&device);
+///
+/// let threads = [left, right].map(|display| {
+///     std::thread::spawn(move || -> maschine3_hal::error::Result<()> {
+///         for frame in 0..100u32 {
+///             let shade = (frame * 2 + display.display_id() as u32 * 128) as u8;
+///             display.write_framebuffer_rgb888_dirty(&vec![shade; 480 * 272 * 3])?;
+///         }
+///         Ok(())
+///     })
+/// });
+/// for thread in threads {
+///     thread.join().expect("render thread panicked")?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DisplayHandle {
+    device: Arc<Mutex<MaschineMK3>>,
+    display_id: u8,
+}
+
+impl DisplayHandle {
+    pub(crate) fn new(device: Arc<Mutex<MaschineMK3>>, display_id: u8) -> Self {
+        Self { device, display_id }
+    }
+
+    /// 0 for the left display, 1 for the right
+    pub fn display_id(&self) -> u8 {
+        self.display_id
+    }
+
+    /// Send a full 480x272 RGB888 frame, see
+    /// [`MaschineMK3::send_display_rgb888`]
+    pub fn send_rgb888(&self, rgb_data: &[u8]) -> Result<()> {
+        self.lock()?.send_display_rgb888(self.display_id, rgb_data)
+    }
+
+    /// Send a full frame of RGB565 pixels, see
+    /// [`MaschineMK3::send_display_image`]
+    pub fn send_image(&self, pixels: Vec<Rgb565>) -> Result<()> {
+        self.lock()?.send_display_image(self.display_id, pixels)
+    }
+
+    /// Send only what changed since the last frame, returning the region
+    /// sent, see [`MaschineMK3::write_display_framebuffer_rgb888_dirty`]
+    pub fn write_framebuffer_rgb888_dirty(&self, rgb_data: &[u8]) -> Result<Option<DisplayRect>> {
+        self.lock()?
+            .write_display_framebuffer_rgb888_dirty(self.display_id, rgb_data)
+    }
+
+    /// Record a changed area for [`flush_damage`](Self::flush_damage), see
+    /// [`MaschineMK3::mark_damaged`]
+    pub fn mark_damaged(&self, rect: DisplayRect) -> Result<()> {
+        self.lock()?.mark_damaged(self.display_id, rect)
+    }
+
+    /// Send the damaged part of a frame without diffing it, see
+    /// [`MaschineMK3::flush_damage`]
+    pub fn flush_damage(&self, rgb_data: &[u8]) -> Result<Option<DisplayRect>> {
+        self.lock()?.flush_damage(self.display_id, rgb_data)
+    }
+
+    /// Write RGB888 pixels to a region, see
+    /// [`MaschineMK3::write_display_region_rgb888`]
+    pub fn write_region_rgb888(&self, rect: DisplayRect, rgb_data: &[u8]) -> Result<()> {
+        self.lock()?
+            .write_display_region_rgb888(self.display_id, rect, rgb_data)
+    }
+
+    /// Write RGB565 pixels to a region, see
+    /// [`MaschineMK3::write_display_region`]
+    pub fn write_region(&self, rect: DisplayRect, pixels: Vec<Rgb565>) -> Result<()> {
+        self.lock()?
+            .write_display_region(self.display_id, rect, pixels)
+    }
+
+    /// Write RGB565 pixels from an iterator to a region, see
+    /// [`MaschineMK3::write_display_region_iter`]
+    pub fn write_region_iter(
+        &self,
+        rect: DisplayRect,
+        pixels: impl IntoIterator<Item = Rgb565>,
+    ) -> Result<()> {
+        self.lock()?
+            .write_display_region_iter(self.display_id, rect, pixels)
+    }
+
+    /// Fill the display with one color
+    pub fn clear(&self, red: u8, green: u8, blue: u8) -> Result<()> {
+        self.lock()?
+            .clear_display(self.display_id, red, green, blue)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, MaschineMK3>> {
+        self.device
+            .lock()
+            .map_err(|_| MK3Error::InvalidData("Device lock poisoned".to_string()))
+    }
+}
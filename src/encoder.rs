@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+/// Detents after which the main encoder's 4-bit position wraps
+const ENCODER_POSITIONS: i32 = 16;
+
+/// A turn starting this long after the previous one starts at normal speed
+const IDLE_RESET: Duration = Duration::from_millis(200);
+
+/// How turn speed scales the steps of an `InputEvent::EncoderTurned`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccelerationCurve {
+    /// One step per detent at any speed
+    Off,
+    /// The multiplier grows by `gain` for every detent per second above the
+    /// threshold
+    Linear { gain: f32 },
+    /// The multiplier is the speed over the threshold raised to `exponent`,
+    /// so faster turns gain more than with `Linear`
+    Power { exponent: f32 },
+}
+
+/// Settings for main encoder acceleration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderAccelerationConfig {
+    pub curve: AccelerationCurve,
+    /// Speed in detents per second below which every detent is one step
+    pub threshold: f32,
+    /// Largest number of steps per detent
+    pub max_multiplier: f32,
+}
+
+impl Default for EncoderAccelerationConfig {
+    fn default() -> Self {
+        Self {
+            curve: AccelerationCurve::Linear { gain: 0.15 },
+            threshold: 8.0,
+            max_multiplier: 8.0,
+        }
+    }
+}
+
+impl EncoderAccelerationConfig {
+    /// One step per detent
+    pub fn disabled() -> Self {
+        Self {
+            curve: AccelerationCurve::Off,
+            ..Self::default()
+        }
+    }
+
+    /// Steps per detent at `speed` detents per second
+    pub fn multiplier(&self, speed: f32) -> f32 {
+        let threshold = self.threshold.max(f32::EPSILON);
+        if speed <= threshold {
+            return 1.0;
+        }
+        let multiplier = match self.curve {
+            AccelerationCurve::Off => 1.0,
+            AccelerationCurve::Linear { gain } => 1.0 + (speed - threshold) * gain,
+            AccelerationCurve::Power { exponent } => (speed / threshold).powf(exponent),
+        };
+        multiplier.clamp(1.0, self.max_multiplier.max(1.0))
+    }
+}
+
+/// Turns main encoder detents into accelerated steps from how fast they
+/// arrive
+#[derive(Debug, Clone, Default)]
+pub struct EncoderAccelerator {
+    config: EncoderAccelerationConfig,
+    /// Time and direction of the last turn
+    last_turn: Option<(Instant, i32)>,
+    /// Smoothed speed in detents per second
+    speed: f32,
+}
+
+impl EncoderAccelerator {
+    pub fn new(config: EncoderAccelerationConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &EncoderAccelerationConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: EncoderAccelerationConfig) {
+        self.config = config;
+    }
+
+    /// Accelerated steps for `detents` turned at `now`. Never fewer than
+    /// `detents`; changing direction or pausing drops back to normal speed.
+    pub fn update(&mut self, detents: i32, now: Instant) -> i32 {
+        if detents == 0 {
+            return 0;
+        }
+
+        let direction = detents.signum();
+        self.speed = match self.last_turn {
+            Some((at, last_direction)) if last_direction == direction => {
+                let elapsed = now.duration_since(at);
+                if elapsed >= IDLE_RESET {
+                    0.0
+                } else {
+                    // Packets can arrive back to back, so bound the interval
+                    let seconds = elapsed.as_secs_f32().max(0.002);
+                    let instant = detents.unsigned_abs() as f32 / seconds;
+                    (self.speed + instant) / 2.0
+                }
+            }
+            _ => 0.0,
+        };
+        self.last_turn = Some((now, direction));
+
+        let steps = (detents as f32 * self.config.multiplier(self.speed)).round() as i32;
+        if steps.abs() < detents.abs() {
+            detents
+        } else {
+            steps
+        }
+    }
+}
+
+/// Detents turned for a `KnobChanged` delta of the main encoder, whose 4-bit
+/// position wraps around
+pub(crate) fn encoder_detents(delta: i32) -> i32 {
+    (delta + ENCODER_POSITIONS / 2).rem_euclid(ENCODER_POSITIONS) - ENCODER_POSITIONS / 2
+}
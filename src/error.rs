@@ -19,6 +19,15 @@ pub enum MK3Error {
 
     #[error("Invalid data {0}")]
     InvalidData(String),
+
+    #[error("Display endpoint still failing after {attempts} recovery attempts: {error}")]
+    DisplayStalled { attempts: u32, error: rusb::Error },
+
+    #[error("Display interface unavailable: {reason}. {hint}")]
+    DisplayUnavailable { reason: String, hint: String },
+
+    #[error("Device is in use: {0}")]
+    DeviceBusy(String),
 }
 
 pub type Result<T> = std::result::Result<T, MK3Error>;
@@ -0,0 +1,1182 @@
+//! C API for non-Rust hosts (Unity, C++ engines).
+//!
+//! Enabled with the `ffi` feature, which also builds a `cdylib` and generates
+//! `include/maschine3_hal.h` with cbindgen. Functions return `MK3_OK` (0) or a
+//! negative `MK3_ERROR_*` code unless documented otherwise.
+//!
+//! Panics never cross into the host: they are logged with a backtrace
+//! through the `log` facade and reported as `MK3_ERROR_INTERNAL`. A device
+//! that panicked mid-call stays usable, though its state may be off until
+//! the next full LED or display write.
+//!
+//! Hosts should compare [`mk3_abi_version`] against the `MK3_ABI_VERSION` in
+//! the header they were built with and refuse to run on a mismatch.
+
+use crate::action_map::ActionId;
+use crate::device::{MaschineMK3, MonitoringStatus};
+use crate::error::MK3Error;
+use crate::gesture::{KnobGesture, TouchStripGesture};
+use crate::input::{InputElement, InputEvent, PadEventType, ProtocolWarningKind};
+use crate::led_animation::{LedAnimation, LedAnimationKind, LedTarget};
+use crate::output::{DisplayRect, MaschineLEDColor};
+use crate::strip::{StripMode, STRIP_LED_COUNT};
+use crate::strip_follow::StripFollowConfig;
+use crate::topology::DisplayStatus;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+#[cfg(not(feature = "ffi-raw-pointers"))]
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::ffi::{c_char, CStr};
+use std::mem::{align_of, size_of};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Once;
+use std::time::Duration;
+#[cfg(not(feature = "ffi-raw-pointers"))]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Version of the C ABI. Bump whenever a `#[repr(C)]` type or function
+/// signature changes incompatibly. Version 2 replaced device pointers with
+/// handles; `ffi-raw-pointers` builds keep version 1.
+#[cfg(not(feature = "ffi-raw-pointers"))]
+pub const MK3_ABI_VERSION: u32 = 2;
+#[cfg(feature = "ffi-raw-pointers")]
+pub const MK3_ABI_VERSION: u32 = 1;
+
+// Literals so cbindgen can emit them; checked against the device below
+pub const MK3_DISPLAY_WIDTH: u16 = 480;
+pub const MK3_DISPLAY_HEIGHT: u16 = 272;
+pub const MK3_TOUCH_STRIP_LED_COUNT: usize = 25;
+
+pub const MK3_OK: i32 = 0;
+pub const MK3_ERROR_NULL_POINTER: i32 = -1;
+pub const MK3_ERROR_DEVICE_NOT_FOUND: i32 = -2;
+pub const MK3_ERROR_USB: i32 = -3;
+pub const MK3_ERROR_INVALID_PACKET: i32 = -4;
+pub const MK3_ERROR_DISCONNECTED: i32 = -5;
+pub const MK3_ERROR_IO: i32 = -6;
+pub const MK3_ERROR_INVALID_DATA: i32 = -7;
+pub const MK3_ERROR_DISPLAY_STALLED: i32 = -8;
+pub const MK3_ERROR_DISPLAY_UNAVAILABLE: i32 = -9;
+pub const MK3_ERROR_DEVICE_BUSY: i32 = -10;
+/// The device handle is unknown or was closed
+pub const MK3_ERROR_INVALID_HANDLE: i32 = -11;
+/// The library panicked; the details are logged
+pub const MK3_ERROR_INTERNAL: i32 = -12;
+
+// `CInputEvent::kind` values
+pub const MK3_EVENT_BUTTON_PRESSED: u32 = 1;
+pub const MK3_EVENT_BUTTON_RELEASED: u32 = 2;
+pub const MK3_EVENT_BUTTON_HELD: u32 = 3;
+pub const MK3_EVENT_KNOB_CHANGED: u32 = 4;
+pub const MK3_EVENT_AUDIO_CHANGED: u32 = 5;
+pub const MK3_EVENT_PAD: u32 = 6;
+pub const MK3_EVENT_TOUCH_STRIP_GESTURE: u32 = 7;
+pub const MK3_EVENT_STRIP_CHANGED: u32 = 8;
+pub const MK3_EVENT_DEVICE_RESUMED: u32 = 9;
+pub const MK3_EVENT_PAD_PRESSURE: u32 = 10;
+pub const MK3_EVENT_BUTTON_DOUBLE_PRESSED: u32 = 11;
+pub const MK3_EVENT_BUTTON_LONG_PRESSED: u32 = 12;
+pub const MK3_EVENT_PACKETS_DROPPED: u32 = 13;
+pub const MK3_EVENT_ENCODER_TURNED: u32 = 14;
+pub const MK3_EVENT_PROTOCOL_WARNING: u32 = 15;
+pub const MK3_EVENT_RAW_INPUT: u32 = 16;
+pub const MK3_EVENT_KNOB_GESTURE: u32 = 17;
+pub const MK3_EVENT_ACTION: u32 = 18;
+
+// `CInputEvent::detail` values for MK3_EVENT_PAD
+pub const MK3_PAD_HIT: u32 = 0;
+pub const MK3_PAD_TOUCH_RELEASE: u32 = 1;
+pub const MK3_PAD_HIT_RELEASE: u32 = 2;
+pub const MK3_PAD_AFTERTOUCH: u32 = 3;
+
+// `CInputEvent::detail` values for MK3_EVENT_TOUCH_STRIP_GESTURE
+pub const MK3_GESTURE_TAP: u32 = 0;
+pub const MK3_GESTURE_DOUBLE_TAP: u32 = 1;
+pub const MK3_GESTURE_SWIPE_LEFT: u32 = 2;
+pub const MK3_GESTURE_SWIPE_RIGHT: u32 = 3;
+pub const MK3_GESTURE_HOLD: u32 = 4;
+pub const MK3_GESTURE_PINCH: u32 = 5;
+
+// `CInputEvent::detail` values for MK3_EVENT_KNOB_GESTURE
+pub const MK3_KNOB_TOUCH_TURN: u32 = 0;
+pub const MK3_KNOB_TAP_TOUCH: u32 = 1;
+pub const MK3_KNOB_TOUCH_RELEASE: u32 = 2;
+
+// `CInputEvent::detail` values for MK3_EVENT_STRIP_CHANGED
+pub const MK3_STRIP_RAW: u32 = 0;
+pub const MK3_STRIP_PITCH_BEND: u32 = 1;
+pub const MK3_STRIP_MOD_WHEEL: u32 = 2;
+pub const MK3_STRIP_CROSSFADER: u32 = 3;
+
+// `CInputEvent::detail` values for MK3_EVENT_PROTOCOL_WARNING
+pub const MK3_WARNING_SHORT_PACKET: u32 = 0;
+
+// `mk3_display_status` and `mk3_retry_display_claim` results
+pub const MK3_DISPLAY_AVAILABLE: i32 = 0;
+pub const MK3_DISPLAY_CLAIM_FAILED: i32 = 1;
+pub const MK3_DISPLAY_WRONG_DRIVER: i32 = 2;
+pub const MK3_DISPLAY_NOT_ATTEMPTED: i32 = 3;
+
+// `mk3_monitoring_status` results
+pub const MK3_MONITORING_STOPPED: i32 = 0;
+pub const MK3_MONITORING_RUNNING: i32 = 1;
+pub const MK3_MONITORING_RESTARTING: i32 = 2;
+pub const MK3_MONITORING_FAILED: i32 = 3;
+
+// `mk3_start_led_animation` kinds
+pub const MK3_LED_ANIMATION_PULSE: u32 = 0;
+pub const MK3_LED_ANIMATION_RAINBOW: u32 = 1;
+pub const MK3_LED_ANIMATION_CHASE: u32 = 2;
+
+// `CLedAnimationParams::target` values
+pub const MK3_LED_TARGET_PADS: u32 = 0;
+pub const MK3_LED_TARGET_TOUCH_STRIP: u32 = 1;
+pub const MK3_LED_TARGET_ALL: u32 = 2;
+
+/// Device behind an [`MK3DeviceRef`]; opaque to C
+pub struct MK3Device {
+    device: MaschineMK3,
+    pending_events: VecDeque<InputEvent>,
+}
+
+/// Device returned by [`mk3_open`] and passed to every other call.
+///
+/// A handle from a registry that is checked on every call, so a stale or
+/// made-up handle fails with `MK3_ERROR_INVALID_HANDLE` instead of touching
+/// freed memory. Handles are never reused.
+#[cfg(not(feature = "ffi-raw-pointers"))]
+pub type MK3DeviceRef = u64;
+
+/// Device returned by [`mk3_open`] and passed to every other call: a raw
+/// pointer, as before handles were introduced. Kept for hosts still being
+/// migrated; a stale pointer is undefined behavior.
+#[cfg(feature = "ffi-raw-pointers")]
+pub type MK3DeviceRef = *mut MK3Device;
+
+/// What [`mk3_open`] returns when no device could be opened. cbindgen can't
+/// express the null pointer, so the header defines it for raw-pointer builds
+/// in `cbindgen.toml`.
+#[cfg(not(feature = "ffi-raw-pointers"))]
+pub const MK3_INVALID_DEVICE: MK3DeviceRef = 0;
+#[cfg(feature = "ffi-raw-pointers")]
+pub const MK3_INVALID_DEVICE: MK3DeviceRef = ptr::null_mut();
+
+/// Open devices by handle. Each device has its own lock so calls on
+/// different devices don't wait for each other.
+#[cfg(not(feature = "ffi-raw-pointers"))]
+struct Registry {
+    next: MK3DeviceRef,
+    devices: BTreeMap<MK3DeviceRef, Arc<Mutex<MK3Device>>>,
+}
+
+#[cfg(not(feature = "ffi-raw-pointers"))]
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next: 1,
+    devices: BTreeMap::new(),
+});
+
+/// The registry stays consistent even if a thread panicked holding it
+#[cfg(not(feature = "ffi-raw-pointers"))]
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(not(feature = "ffi-raw-pointers"))]
+fn register(device: MK3Device) -> MK3DeviceRef {
+    let mut registry = registry();
+    let handle = registry.next;
+    registry.next += 1;
+    registry
+        .devices
+        .insert(handle, Arc::new(Mutex::new(device)));
+    handle
+}
+
+#[cfg(feature = "ffi-raw-pointers")]
+fn register(device: MK3Device) -> MK3DeviceRef {
+    Box::into_raw(Box::new(device))
+}
+
+/// Forget a handle. A call still running on the device finishes first; the
+/// device is closed when it returns.
+#[cfg(not(feature = "ffi-raw-pointers"))]
+unsafe fn unregister(device: MK3DeviceRef) {
+    let removed = registry().devices.remove(&device);
+    drop(removed);
+}
+
+#[cfg(feature = "ffi-raw-pointers")]
+unsafe fn unregister(device: MK3DeviceRef) {
+    if !device.is_null() {
+        drop(Box::from_raw(device));
+    }
+}
+
+/// Run `f` on a device, or fail with `MK3_ERROR_INVALID_HANDLE` if the handle
+/// isn't open
+#[cfg(not(feature = "ffi-raw-pointers"))]
+unsafe fn with_device<F>(device: MK3DeviceRef, f: F) -> i32
+where
+    F: FnOnce(&mut MK3Device) -> i32,
+{
+    // Look up under the registry lock, then release it before the call
+    let Some(device) = registry().devices.get(&device).cloned() else {
+        return MK3_ERROR_INVALID_HANDLE;
+    };
+    let mut device = device.lock().unwrap_or_else(PoisonError::into_inner);
+    f(&mut device)
+}
+
+/// Run `f` on a device, or fail with `MK3_ERROR_NULL_POINTER` for null
+#[cfg(feature = "ffi-raw-pointers")]
+unsafe fn with_device<F>(device: MK3DeviceRef, f: F) -> i32
+where
+    F: FnOnce(&mut MK3Device) -> i32,
+{
+    match device.as_mut() {
+        Some(device) => f(device),
+        None => MK3_ERROR_NULL_POINTER,
+    }
+}
+
+/// Flattened [`InputEvent`]. Which fields are meaningful depends on `kind`:
+///
+/// - buttons: `element`
+/// - knobs and audio controls: `element`, `value`, `delta`
+/// - pads: `element` is the pad number, `detail` a `MK3_PAD_*` type, `value`
+///   the 12-bit velocity/pressure
+/// - pad pressure: `element` is the pad number, `value` the pressure
+/// - touch strip gestures: `detail` a `MK3_GESTURE_*` kind, `position` the
+///   gesture's position, distance or scale
+/// - knob gestures: `element` is the knob, `detail` a `MK3_KNOB_*` kind;
+///   touch turns carry `value` and `delta` like knob changes, touch
+///   releases `value` 1 if the knob turned during the touch
+/// - strip changes: `detail` a `MK3_STRIP_*` mode, `position` the value
+/// - encoder turns: `element` is the main encoder, `delta` the detents
+///   turned, `value` the accelerated steps
+/// - protocol warnings: `detail` a `MK3_WARNING_*` kind, `element` the
+///   packet type, `value` the packet length, `delta` the expected length of
+///   short packets (the bytes themselves aren't passed on)
+/// - raw input: `element` is the packet type, `value` the packet length
+/// - actions: `value` is the `ActionId`
+///
+/// `element` is an [`InputElement`] ID (see [`InputElement::id`]).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CInputEvent {
+    pub kind: u32,
+    pub element: u32,
+    pub detail: u32,
+    pub value: i32,
+    pub delta: i32,
+    pub position: f32,
+}
+
+/// RGB color; converted to the nearest LED palette color when sent
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CRgbColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Settings of [`mk3_start_led_animation`]. `color` is used by pulse and
+/// chase; `period_ms` is the length of one cycle and must not be 0.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CLedAnimationParams {
+    /// A `MK3_LED_TARGET_*` value
+    pub target: u32,
+    pub period_ms: u32,
+    pub color: CRgbColor,
+}
+
+impl Default for CLedAnimationParams {
+    fn default() -> Self {
+        let animation = LedAnimation::default();
+        let (r, g, b) = animation.color.to_rgb();
+        Self {
+            target: MK3_LED_TARGET_PADS,
+            period_ms: animation.period.as_millis() as u32,
+            color: CRgbColor { r, g, b },
+        }
+    }
+}
+
+// Layout checks: changing any of these is an ABI break and needs a new
+// MK3_ABI_VERSION
+const _: () = assert!(size_of::<CInputEvent>() == 24);
+const _: () = assert!(align_of::<CInputEvent>() == 4);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, kind) == 0);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, element) == 4);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, detail) == 8);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, value) == 12);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, delta) == 16);
+const _: () = assert!(std::mem::offset_of!(CInputEvent, position) == 20);
+const _: () = assert!(size_of::<CRgbColor>() == 3);
+const _: () = assert!(align_of::<CRgbColor>() == 1);
+const _: () = assert!(size_of::<CLedAnimationParams>() == 12);
+const _: () = assert!(align_of::<CLedAnimationParams>() == 4);
+const _: () = assert!(std::mem::offset_of!(CLedAnimationParams, target) == 0);
+const _: () = assert!(std::mem::offset_of!(CLedAnimationParams, period_ms) == 4);
+const _: () = assert!(std::mem::offset_of!(CLedAnimationParams, color) == 8);
+const _: () = assert!(MK3_TOUCH_STRIP_LED_COUNT == STRIP_LED_COUNT);
+const _: () = assert!(MK3_DISPLAY_WIDTH == MaschineMK3::DISPLAY_WIDTH);
+const _: () = assert!(MK3_DISPLAY_HEIGHT == MaschineMK3::DISPLAY_HEIGHT);
+
+impl From<&InputEvent> for CInputEvent {
+    fn from(event: &InputEvent) -> Self {
+        let mut c_event = CInputEvent::default();
+
+        match event {
+            InputEvent::ButtonPressed(element) => {
+                c_event.kind = MK3_EVENT_BUTTON_PRESSED;
+                c_event.element = element.id();
+            }
+            InputEvent::ButtonReleased(element) => {
+                c_event.kind = MK3_EVENT_BUTTON_RELEASED;
+                c_event.element = element.id();
+            }
+            InputEvent::ButtonHeld(element) => {
+                c_event.kind = MK3_EVENT_BUTTON_HELD;
+                c_event.element = element.id();
+            }
+            InputEvent::ButtonDoublePressed(element) => {
+                c_event.kind = MK3_EVENT_BUTTON_DOUBLE_PRESSED;
+                c_event.element = element.id();
+            }
+            InputEvent::ButtonLongPressed(element) => {
+                c_event.kind = MK3_EVENT_BUTTON_LONG_PRESSED;
+                c_event.element = element.id();
+            }
+            InputEvent::KnobChanged {
+                element,
+                value,
+                delta,
+            } => {
+                c_event.kind = MK3_EVENT_KNOB_CHANGED;
+                c_event.element = element.id();
+                c_event.value = *value as i32;
+                c_event.delta = *delta;
+            }
+            InputEvent::AudioChanged {
+                element,
+                value,
+                delta,
+            } => {
+                c_event.kind = MK3_EVENT_AUDIO_CHANGED;
+                c_event.element = element.id();
+                c_event.value = *value as i32;
+                c_event.delta = *delta;
+            }
+            InputEvent::PadEvent {
+                pad_number,
+                event_type,
+                value,
+            } => {
+                c_event.kind = MK3_EVENT_PAD;
+                c_event.element = *pad_number as u32;
+                c_event.detail = match event_type {
+                    PadEventType::Hit => MK3_PAD_HIT,
+                    PadEventType::TouchRelease => MK3_PAD_TOUCH_RELEASE,
+                    PadEventType::HitRelease => MK3_PAD_HIT_RELEASE,
+                    PadEventType::Aftertouch => MK3_PAD_AFTERTOUCH,
+                };
+                c_event.value = *value as i32;
+            }
+            InputEvent::TouchStripGesture(gesture) => {
+                c_event.kind = MK3_EVENT_TOUCH_STRIP_GESTURE;
+                (c_event.detail, c_event.position) = match *gesture {
+                    TouchStripGesture::Tap { position } => (MK3_GESTURE_TAP, position),
+                    TouchStripGesture::DoubleTap { position } => {
+                        (MK3_GESTURE_DOUBLE_TAP, position)
+                    }
+                    TouchStripGesture::SwipeLeft { distance } => (MK3_GESTURE_SWIPE_LEFT, distance),
+                    TouchStripGesture::SwipeRight { distance } => {
+                        (MK3_GESTURE_SWIPE_RIGHT, distance)
+                    }
+                    TouchStripGesture::Hold { position } => (MK3_GESTURE_HOLD, position),
+                    TouchStripGesture::Pinch { scale } => (MK3_GESTURE_PINCH, scale),
+                };
+            }
+            InputEvent::KnobGesture(gesture) => {
+                c_event.kind = MK3_EVENT_KNOB_GESTURE;
+                c_event.element = gesture.knob().id();
+                match *gesture {
+                    KnobGesture::TouchTurn { value, delta, .. } => {
+                        c_event.detail = MK3_KNOB_TOUCH_TURN;
+                        c_event.value = value as i32;
+                        c_event.delta = delta;
+                    }
+                    KnobGesture::TapTouch { .. } => c_event.detail = MK3_KNOB_TAP_TOUCH,
+                    KnobGesture::TouchRelease { turned, .. } => {
+                        c_event.detail = MK3_KNOB_TOUCH_RELEASE;
+                        c_event.value = turned as i32;
+                    }
+                }
+            }
+            InputEvent::StripChanged { mode, value } => {
+                c_event.kind = MK3_EVENT_STRIP_CHANGED;
+                c_event.detail = match mode {
+                    StripMode::Raw => MK3_STRIP_RAW,
+                    StripMode::PitchBend => MK3_STRIP_PITCH_BEND,
+                    StripMode::ModWheel => MK3_STRIP_MOD_WHEEL,
+                    StripMode::Crossfader => MK3_STRIP_CROSSFADER,
+                };
+                c_event.position = *value;
+            }
+            InputEvent::DeviceResumed => {
+                c_event.kind = MK3_EVENT_DEVICE_RESUMED;
+            }
+            InputEvent::PadPressure { pad, value } => {
+                c_event.kind = MK3_EVENT_PAD_PRESSURE;
+                c_event.element = *pad as u32;
+                c_event.value = *value as i32;
+            }
+            InputEvent::PacketsDropped { count } => {
+                c_event.kind = MK3_EVENT_PACKETS_DROPPED;
+                c_event.value = *count as i32;
+            }
+            InputEvent::EncoderTurned { detents, steps } => {
+                c_event.kind = MK3_EVENT_ENCODER_TURNED;
+                c_event.element = InputElement::MainEncoder.id();
+                c_event.value = *steps;
+                c_event.delta = *detents;
+            }
+            InputEvent::ProtocolWarning { kind, raw } => {
+                c_event.kind = MK3_EVENT_PROTOCOL_WARNING;
+                c_event.value = raw.len() as i32;
+                match *kind {
+                    ProtocolWarningKind::ShortPacket {
+                        packet_type,
+                        expected,
+                    } => {
+                        c_event.detail = MK3_WARNING_SHORT_PACKET;
+                        c_event.element = packet_type as u32;
+                        c_event.delta = expected as i32;
+                    }
+                }
+            }
+            InputEvent::RawInput(packet) => {
+                c_event.kind = MK3_EVENT_RAW_INPUT;
+                c_event.element = packet.packet_type as u32;
+                c_event.value = packet.data.len() as i32;
+            }
+            InputEvent::Action(ActionId(id)) => {
+                c_event.kind = MK3_EVENT_ACTION;
+                c_event.value = *id as i32;
+            }
+        }
+
+        c_event
+    }
+}
+
+impl From<CRgbColor> for MaschineLEDColor {
+    fn from(color: CRgbColor) -> Self {
+        MaschineLEDColor::from_rgb(color.r, color.g, color.b)
+    }
+}
+
+fn error_code(error: &MK3Error) -> i32 {
+    match error {
+        MK3Error::Usb(_) => MK3_ERROR_USB,
+        MK3Error::DeviceNotFound => MK3_ERROR_DEVICE_NOT_FOUND,
+        MK3Error::InvalidPacket => MK3_ERROR_INVALID_PACKET,
+        MK3Error::DeviceDisconnected => MK3_ERROR_DISCONNECTED,
+        MK3Error::Io(_) => MK3_ERROR_IO,
+        MK3Error::InvalidData(_) => MK3_ERROR_INVALID_DATA,
+        MK3Error::DisplayStalled { .. } => MK3_ERROR_DISPLAY_STALLED,
+        MK3Error::DisplayUnavailable { .. } => MK3_ERROR_DISPLAY_UNAVAILABLE,
+        MK3Error::DeviceBusy(_) => MK3_ERROR_DEVICE_BUSY,
+    }
+}
+
+thread_local! {
+    /// Where the last panic on this thread happened, captured by the hook
+    /// installed in `contain`
+    static PANIC_TRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run the body of an exported function, turning a panic into `fallback`
+/// instead of unwinding into the host
+fn contain<T, F: FnOnce() -> T>(function: &str, fallback: T, body: F) -> T {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        // Backtraces are only available at the panic site, so record one
+        // there and leave the rest to the previous hook
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let trace = format!("{}\n{}", info, Backtrace::force_capture());
+            PANIC_TRACE.with(|slot| *slot.borrow_mut() = Some(trace));
+            previous(info);
+        }));
+    });
+
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(_) => {
+            let trace = PANIC_TRACE.with(|slot| slot.borrow_mut().take());
+            log::error!(
+                "Panic in {}: {}",
+                function,
+                trace.as_deref().unwrap_or("no details")
+            );
+            fallback
+        }
+    }
+}
+
+fn to_code(result: crate::error::Result<()>) -> i32 {
+    match result {
+        Ok(()) => MK3_OK,
+        Err(e) => error_code(&e),
+    }
+}
+
+/// ABI version of the loaded library; compare against `MK3_ABI_VERSION`
+#[no_mangle]
+pub extern "C" fn mk3_abi_version() -> u32 {
+    MK3_ABI_VERSION
+}
+
+/// [`InputElement`] ID for a control name such as "GroupA" or "Knob 3" (see
+/// [`InputElement::from_name`]), or `MK3_ERROR_INVALID_DATA` if the name is
+/// unknown
+///
+/// # Safety
+///
+/// `name` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_element_from_name(name: *const c_char) -> i32 {
+    contain("mk3_element_from_name", MK3_ERROR_INTERNAL, || {
+        if name.is_null() {
+            return MK3_ERROR_NULL_POINTER;
+        }
+        let Ok(name) = CStr::from_ptr(name).to_str() else {
+            return MK3_ERROR_INVALID_DATA;
+        };
+        match InputElement::from_name(name) {
+            Some(element) => element.id() as i32,
+            None => MK3_ERROR_INVALID_DATA,
+        }
+    })
+}
+
+/// Write the identifier of an [`InputElement`] ID ("GroupA") into `buffer`
+/// as a NUL-terminated string. Returns the identifier's length without the
+/// terminator, or a negative error code if the ID is unknown or `len` is too
+/// small.
+///
+/// # Safety
+///
+/// `buffer` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_element_name(
+    element: u32,
+    buffer: *mut c_char,
+    len: usize,
+) -> i32 {
+    contain("mk3_element_name", MK3_ERROR_INTERNAL, || {
+        if buffer.is_null() {
+            return MK3_ERROR_NULL_POINTER;
+        }
+        let Some(element) = InputElement::from_id(element) else {
+            return MK3_ERROR_INVALID_DATA;
+        };
+        let name = element.to_string();
+        if name.len() >= len {
+            return MK3_ERROR_INVALID_DATA;
+        }
+        ptr::copy_nonoverlapping(name.as_ptr(), buffer.cast::<u8>(), name.len());
+        *buffer.add(name.len()) = 0;
+        name.len() as i32
+    })
+}
+
+/// Connect to the first available Maschine MK3. Returns `MK3_INVALID_DEVICE`
+/// on failure.
+#[no_mangle]
+pub extern "C" fn mk3_open() -> MK3DeviceRef {
+    contain("mk3_open", MK3_INVALID_DEVICE, || {
+        match MaschineMK3::new() {
+            Ok(device) => register(MK3Device {
+                device,
+                pending_events: VecDeque::new(),
+            }),
+            Err(_) => MK3_INVALID_DEVICE,
+        }
+    })
+}
+
+/// Close a device returned by [`mk3_open`]. Closing `MK3_INVALID_DEVICE`, or
+/// a handle that was already closed, is a no-op.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be null or a pointer returned
+/// by `mk3_open` that has not been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_close(device: MK3DeviceRef) {
+    contain("mk3_close", (), || {
+        unregister(device);
+    })
+}
+
+/// Fetch the next input event, reading from the device (waiting up to 100ms)
+/// if none is queued, or from the monitoring thread's queue while
+/// [`mk3_start_monitoring_with_rate`] is in effect. Returns 1 if an event was
+/// written to `event`, 0 if no input arrived, or a negative error code.
+///
+/// # Safety
+///
+/// `event` must point to writable memory for one `CInputEvent`. In
+/// `ffi-raw-pointers` builds `device` must be a live pointer from `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_poll_event(device: MK3DeviceRef, event: *mut CInputEvent) -> i32 {
+    contain("mk3_poll_event", MK3_ERROR_INTERNAL, || {
+        let Some(event) = event.as_mut() else {
+            return MK3_ERROR_NULL_POINTER;
+        };
+
+        with_device(device, |device| {
+            if device.pending_events.is_empty() {
+                if device.device.is_input_monitoring() {
+                    let wait = device.device.timeouts().input;
+                    let events = device.device.recv_event_timeout(wait);
+                    device.pending_events.extend(events);
+                    device.pending_events.extend(device.device.queued_events());
+                    // Polling no longer reads from the device, so keep the
+                    // animations and held back LED writes going from here
+                    let leds = device.device.update_led_animations();
+                    if let Err(e) = leds.and_then(|()| device.device.send_pending_led_writes()) {
+                        return error_code(&e);
+                    }
+                } else {
+                    match device.device.poll_input_events() {
+                        Ok(events) => device.pending_events.extend(events),
+                        Err(e) => return error_code(&e),
+                    }
+                }
+            }
+
+            match device.pending_events.pop_front() {
+                Some(next) => {
+                    *event = CInputEvent::from(&next);
+                    1
+                }
+                None => 0,
+            }
+        })
+    })
+}
+
+/// Read input on a background thread from now on, so [`mk3_poll_event`]
+/// only takes queued events. The thread blocks on each read for up to
+/// `poll_interval_ms` (at least 1): packets are delivered as soon as they
+/// arrive either way, shorter intervals just let [`mk3_stop_monitoring`]
+/// return sooner at the cost of more wakeups.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_start_monitoring_with_rate(
+    device: MK3DeviceRef,
+    poll_interval_ms: u32,
+) -> i32 {
+    contain("mk3_start_monitoring_with_rate", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if poll_interval_ms == 0 {
+                return MK3_ERROR_INVALID_DATA;
+            }
+            let interval = Duration::from_millis(poll_interval_ms.into());
+            device.device.set_input_read_timeout(interval);
+            to_code(device.device.start_event_monitoring())
+        })
+    })
+}
+
+/// Stop the thread started by [`mk3_start_monitoring_with_rate`]; events it
+/// queued are still returned by [`mk3_poll_event`]. A no-op if monitoring
+/// isn't running.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_stop_monitoring(device: MK3DeviceRef) -> i32 {
+    contain("mk3_stop_monitoring", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let queued: Vec<InputEvent> = device.device.queued_events().collect();
+            device.pending_events.extend(queued);
+            to_code(device.device.stop_input_monitoring())
+        })
+    })
+}
+
+/// What the thread started by [`mk3_start_monitoring_with_rate`] is doing:
+/// one of the `MK3_MONITORING_*` results, or a negative error code. A
+/// thread that panics or keeps failing restarts itself; after
+/// `MK3_MONITORING_FAILED` no events arrive until monitoring is stopped and
+/// started again. The reason is logged.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_monitoring_status(device: MK3DeviceRef) -> i32 {
+    contain("mk3_monitoring_status", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| match device.device.monitoring_status() {
+            MonitoringStatus::Stopped => MK3_MONITORING_STOPPED,
+            MonitoringStatus::Running => MK3_MONITORING_RUNNING,
+            MonitoringStatus::Restarting { .. } => MK3_MONITORING_RESTARTING,
+            MonitoringStatus::Failed(_) => MK3_MONITORING_FAILED,
+        })
+    })
+}
+
+/// Set how long a button must be held before `MK3_EVENT_BUTTON_HELD` events
+/// start (500ms by default). Applies to polling at once and to running
+/// monitoring from its next packet.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_hold_threshold_ms(device: MK3DeviceRef, threshold_ms: u32) -> i32 {
+    contain("mk3_set_hold_threshold_ms", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let mut config = device.device.button_gesture_config();
+            config.hold_threshold = Duration::from_millis(threshold_ms.into());
+            device.device.set_button_gesture_config(config);
+            MK3_OK
+        })
+    })
+}
+
+/// Report input packets that can't be parsed as
+/// `MK3_EVENT_PROTOCOL_WARNING` events (`enabled` non-zero) instead of
+/// skipping them silently. Applies to polling at once and to running
+/// monitoring from its next packet.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_protocol_warnings(device: MK3DeviceRef, enabled: i32) -> i32 {
+    contain("mk3_set_protocol_warnings", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            device.device.set_protocol_warnings(enabled != 0);
+            MK3_OK
+        })
+    })
+}
+
+/// Tune the device for the shortest hit-to-LED path: 1ms input reads,
+/// a raised priority for the monitoring thread and no LED write coalescing.
+/// Call it before [`mk3_start_monitoring_with_rate`], whose interval then
+/// replaces the 1ms read timeout; pass 1 to keep it.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_enable_low_latency(device: MK3DeviceRef) -> i32 {
+    contain("mk3_enable_low_latency", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            device.device.enable_low_latency();
+            MK3_OK
+        })
+    })
+}
+
+/// Set a pad LED (0-15) to the palette color nearest `color`
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_pad_led(
+    device: MK3DeviceRef,
+    pad_number: u8,
+    color: CRgbColor,
+) -> i32 {
+    contain("mk3_set_pad_led", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            to_code(device.device.set_pad_led(pad_number, color.into()))
+        })
+    })
+}
+
+/// Set a button LED brightness (0-127) by [`InputElement`] ID
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_button_led(
+    device: MK3DeviceRef,
+    element: u32,
+    brightness: u8,
+) -> i32 {
+    contain("mk3_set_button_led", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let Some(element) = InputElement::from_id(element) else {
+                return MK3_ERROR_INVALID_DATA;
+            };
+            to_code(device.device.set_button_led(element, brightness))
+        })
+    })
+}
+
+/// Send a full 480x272 RGB888 frame (`len` must be 391680) to display 0 or 1
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes. In `ffi-raw-pointers` builds
+/// `device` must be a live pointer from `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_send_display_rgb888(
+    device: MK3DeviceRef,
+    display: u8,
+    data: *const u8,
+    len: usize,
+) -> i32 {
+    contain("mk3_send_display_rgb888", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if data.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+            let data = std::slice::from_raw_parts(data, len);
+            to_code(device.device.send_display_rgb888(display, data))
+        })
+    })
+}
+
+/// Send full 480x272 RGB888 frames to both displays (`len` must be 391680
+/// for each), converting the right one while the left one is sent. Only
+/// the part of each frame that changed since the last call is transferred.
+///
+/// # Safety
+///
+/// `left` and `right` must each point to `len` readable bytes. In
+/// `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_present_both(
+    device: MK3DeviceRef,
+    left: *const u8,
+    right: *const u8,
+    len: usize,
+) -> i32 {
+    contain("mk3_present_both", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if left.is_null() || right.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+            let left = std::slice::from_raw_parts(left, len);
+            let right = std::slice::from_raw_parts(right, len);
+            to_code(device.device.present_both(left, right).map(|_| ()))
+        })
+    })
+}
+
+/// Set pads 0..`count` from `colors` (at most 16) with a single LED write
+///
+/// # Safety
+///
+/// `colors` must point to `count` readable `CRgbColor`s. In
+/// `ffi-raw-pointers` builds `device` must be a live pointer from `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_pad_leds(
+    device: MK3DeviceRef,
+    colors: *const CRgbColor,
+    count: usize,
+) -> i32 {
+    contain("mk3_set_pad_leds", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if colors.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+            if count > 16 {
+                return MK3_ERROR_INVALID_DATA;
+            }
+
+            let colors = std::slice::from_raw_parts(colors, count);
+            to_code(device.device.update_leds(|leds| {
+                for (pad_number, color) in colors.iter().enumerate() {
+                    leds.set_pad(pad_number as u8, (*color).into());
+                }
+            }))
+        })
+    })
+}
+
+/// Set several button LEDs with a single LED write. `elements` holds
+/// [`InputElement`] IDs and `brightness` the matching 0-127 values. Nothing is
+/// changed if any ID is invalid.
+///
+/// # Safety
+///
+/// `elements` and `brightness` must each point to `count` readable values.
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_button_led_batch(
+    device: MK3DeviceRef,
+    elements: *const u32,
+    brightness: *const u8,
+    count: usize,
+) -> i32 {
+    contain("mk3_set_button_led_batch", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if elements.is_null() || brightness.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+
+            let elements = std::slice::from_raw_parts(elements, count);
+            let brightness = std::slice::from_raw_parts(brightness, count);
+            let Some(elements) = elements
+                .iter()
+                .map(|&id| InputElement::from_id(id))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return MK3_ERROR_INVALID_DATA;
+            };
+
+            to_code(device.device.update_leds(|leds| {
+                for (element, &value) in elements.into_iter().zip(brightness) {
+                    leds.set_button(element, value);
+                }
+            }))
+        })
+    })
+}
+
+/// Write `w`x`h` RGB888 pixels (`len` must be `w * h * 3`) to a region of
+/// display 0 or 1. `x` and `w` must be even.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes. In `ffi-raw-pointers` builds
+/// `device` must be a live pointer from `mk3_open`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn mk3_write_display_region_rgb888(
+    device: MK3DeviceRef,
+    display: u8,
+    x: u16,
+    y: u16,
+    w: u16,
+    h: u16,
+    data: *const u8,
+    len: usize,
+) -> i32 {
+    contain("mk3_write_display_region_rgb888", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if data.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+
+            let data = std::slice::from_raw_parts(data, len);
+            let rect = DisplayRect::new(x, y, w, h);
+            to_code(device.device.write_display_region_rgb888(display, rect, data))
+        })
+    })
+}
+
+fn display_status_code(status: &DisplayStatus) -> i32 {
+    match status {
+        DisplayStatus::Available { .. } => MK3_DISPLAY_AVAILABLE,
+        DisplayStatus::InterfaceClaimFailed { .. } => MK3_DISPLAY_CLAIM_FAILED,
+        DisplayStatus::WrongDriver { .. } => MK3_DISPLAY_WRONG_DRIVER,
+        DisplayStatus::NotAttempted => MK3_DISPLAY_NOT_ATTEMPTED,
+    }
+}
+
+/// Whether the displays can be used: one of the `MK3_DISPLAY_*` results, or
+/// a negative error code
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_display_status(device: MK3DeviceRef) -> i32 {
+    contain("mk3_display_status", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            display_status_code(&device.device.display_status())
+        })
+    })
+}
+
+/// Claim the display interface again without reopening the device, e.g.
+/// after the user fixed the driver. Returns the new `MK3_DISPLAY_*` result,
+/// or a negative error code.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_retry_display_claim(device: MK3DeviceRef) -> i32 {
+    contain("mk3_retry_display_claim", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| match device.device.retry_display_claim() {
+            Ok(status) => display_status_code(&status),
+            Err(e) => error_code(&e),
+        })
+    })
+}
+
+/// Flash every LED and both displays in a double-blink pattern for
+/// `duration_ms`, with the serial number on the displays, so the user can
+/// tell which controller this handle drives. Blocks until done, then
+/// restores the LEDs and displays.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_identify(device: MK3DeviceRef, duration_ms: u32) -> i32 {
+    contain("mk3_identify", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let duration = Duration::from_millis(duration_ms.into());
+            to_code(device.device.identify(duration))
+        })
+    })
+}
+
+/// Set the 25 touch strip LEDs, left to right, from `colors` with a single
+/// LED write. A running touch strip animation draws over them on its next
+/// update; stop it first with [`mk3_stop_animations`].
+///
+/// # Safety
+///
+/// `colors` must point to `MK3_TOUCH_STRIP_LED_COUNT` readable `CRgbColor`s.
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_touch_strip_leds(
+    device: MK3DeviceRef,
+    colors: *const CRgbColor,
+) -> i32 {
+    contain("mk3_set_touch_strip_leds", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            if colors.is_null() {
+                return MK3_ERROR_NULL_POINTER;
+            }
+
+            let colors = std::slice::from_raw_parts(colors, MK3_TOUCH_STRIP_LED_COUNT);
+            to_code(device.device.update_leds(|leds| {
+                for (index, color) in colors.iter().enumerate() {
+                    leds.set_touch_strip(index as u8, (*color).into());
+                }
+            }))
+        })
+    })
+}
+
+/// Run a built-in `MK3_LED_ANIMATION_*` animation, replacing any already
+/// running on the same LEDs. `params` may be null for a two-second white
+/// cycle on the pads. Animations advance on every [`mk3_poll_event`] that
+/// reads from the device, so the host doesn't send LED frames itself.
+///
+/// # Safety
+///
+/// `params` must be null or point to a readable `CLedAnimationParams`. In
+/// `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_start_led_animation(
+    device: MK3DeviceRef,
+    kind: u32,
+    params: *const CLedAnimationParams,
+) -> i32 {
+    contain("mk3_start_led_animation", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let params = params.as_ref().copied().unwrap_or_default();
+            let kind = match kind {
+                MK3_LED_ANIMATION_PULSE => LedAnimationKind::Pulse,
+                MK3_LED_ANIMATION_RAINBOW => LedAnimationKind::Rainbow,
+                MK3_LED_ANIMATION_CHASE => LedAnimationKind::Chase,
+                _ => return MK3_ERROR_INVALID_DATA,
+            };
+            let target = match params.target {
+                MK3_LED_TARGET_PADS => LedTarget::Pads,
+                MK3_LED_TARGET_TOUCH_STRIP => LedTarget::TouchStrip,
+                MK3_LED_TARGET_ALL => LedTarget::All,
+                _ => return MK3_ERROR_INVALID_DATA,
+            };
+            if params.period_ms == 0 {
+                return MK3_ERROR_INVALID_DATA;
+            }
+
+            to_code(device.device.start_led_animation(LedAnimation {
+                kind,
+                target,
+                color: params.color.into(),
+                period: Duration::from_millis(params.period_ms.into()),
+            }))
+        })
+    })
+}
+
+/// Stop all animations started with [`mk3_start_led_animation`], leaving
+/// their LEDs as last drawn
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_stop_animations(device: MK3DeviceRef) -> i32 {
+    contain("mk3_stop_animations", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            device.device.stop_led_animations();
+            MK3_OK
+        })
+    })
+}
+
+/// Light the touch strip LEDs under the fingers in `color` (`enabled`
+/// non-zero), `spread` LEDs either side of each, leaving a trail that fades
+/// over `trail_ms`; `enabled` 0 turns it off. The LEDs follow the strip on
+/// every [`mk3_poll_event`], or from the monitoring thread when it runs, so
+/// the host doesn't forward strip input itself.
+///
+/// # Safety
+///
+/// In `ffi-raw-pointers` builds `device` must be a live pointer from
+/// `mk3_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mk3_set_touch_strip_follow(
+    device: MK3DeviceRef,
+    enabled: i32,
+    color: CRgbColor,
+    spread: u8,
+    trail_ms: u32,
+) -> i32 {
+    contain("mk3_set_touch_strip_follow", MK3_ERROR_INTERNAL, || {
+        with_device(device, |device| {
+            let config = (enabled != 0).then(|| StripFollowConfig {
+                color: color.into(),
+                spread,
+                trail: Duration::from_millis(trail_ms.into()),
+            });
+            to_code(device.device.set_touch_strip_follow(config))
+        })
+    })
+}
@@ -0,0 +1,526 @@
+use crate::error::{MK3Error, Result};
+use crate::input::{InputElement, InputEvent, PadEventType};
+
+/// Knob travel, in raw steps, that sweeps an axis across its whole range at
+/// a sensitivity of 1.0
+const KNOB_STEPS_PER_SWEEP: f32 = 1024.0;
+
+/// Gamepad button an input can be mapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// A on Xbox layouts, cross on PlayStation
+    South,
+    East,
+    North,
+    West,
+    LeftShoulder,
+    RightShoulder,
+    /// Digital left trigger
+    LeftTrigger,
+    /// Digital right trigger
+    RightTrigger,
+    Select,
+    Start,
+    /// Guide / home button
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    /// Extra buttons 1-40 beyond the standard layout
+    Extra(u8),
+}
+
+impl GamepadButton {
+    /// Linux input event code, or `None` for an extra button out of range
+    fn code(self) -> Option<u16> {
+        let code = match self {
+            GamepadButton::South => 0x130,
+            GamepadButton::East => 0x131,
+            GamepadButton::North => 0x133,
+            GamepadButton::West => 0x134,
+            GamepadButton::LeftShoulder => 0x136,
+            GamepadButton::RightShoulder => 0x137,
+            GamepadButton::LeftTrigger => 0x138,
+            GamepadButton::RightTrigger => 0x139,
+            GamepadButton::Select => 0x13a,
+            GamepadButton::Start => 0x13b,
+            GamepadButton::Mode => 0x13c,
+            GamepadButton::LeftThumb => 0x13d,
+            GamepadButton::RightThumb => 0x13e,
+            GamepadButton::DpadUp => 0x220,
+            GamepadButton::DpadDown => 0x221,
+            GamepadButton::DpadLeft => 0x222,
+            GamepadButton::DpadRight => 0x223,
+            GamepadButton::Extra(n @ 1..=40) => 0x2c0 + n as u16 - 1,
+            GamepadButton::Extra(_) => return None,
+        };
+        Some(code)
+    }
+}
+
+/// Gamepad axis a knob or the touch strip can be mapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX,
+        GamepadAxis::LeftY,
+        GamepadAxis::RightX,
+        GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    /// Values the axis reports. Every axis starts at zero, the center of a
+    /// stick and the rest position of a trigger.
+    pub fn range(self) -> (i32, i32) {
+        match self {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => (0, 1023),
+            _ => (-32768, 32767),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Linux absolute axis code
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn code(self) -> u16 {
+        match self {
+            GamepadAxis::LeftX => 0x00,
+            GamepadAxis::LeftY => 0x01,
+            GamepadAxis::LeftTrigger => 0x02,
+            GamepadAxis::RightX => 0x03,
+            GamepadAxis::RightY => 0x04,
+            GamepadAxis::RightTrigger => 0x05,
+        }
+    }
+}
+
+/// Which controls drive which gamepad buttons and axes
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadMapping {
+    /// Button for each pad (0-15). A pad is held from its hit until its
+    /// release.
+    pub pads: [Option<GamepadButton>; 16],
+    /// Buttons for controller buttons
+    pub buttons: Vec<(InputElement, GamepadButton)>,
+    /// Axis for each of knobs 1-8. Knobs are endless, so they move their
+    /// axis by how far they turn and stop at its ends.
+    pub knobs: [Option<GamepadAxis>; 8],
+    /// Axis the touch strip position sets, left end to right end
+    pub strip: Option<GamepadAxis>,
+    /// How far a knob turn moves its axis; at 1.0 one full turn sweeps the
+    /// whole range
+    pub knob_sensitivity: f32,
+}
+
+impl Default for GamepadMapping {
+    /// Pads on the sixteen standard buttons, Play on the guide button and
+    /// knobs 1-6 on the sticks and triggers
+    fn default() -> Self {
+        use GamepadButton::*;
+
+        Self {
+            pads: [
+                South,
+                East,
+                West,
+                North,
+                LeftShoulder,
+                RightShoulder,
+                LeftTrigger,
+                RightTrigger,
+                Select,
+                Start,
+                LeftThumb,
+                RightThumb,
+                DpadUp,
+                DpadDown,
+                DpadLeft,
+                DpadRight,
+            ]
+            .map(Some),
+            buttons: vec![(InputElement::Play, Mode)],
+            knobs: [
+                Some(GamepadAxis::LeftX),
+                Some(GamepadAxis::LeftY),
+                Some(GamepadAxis::RightX),
+                Some(GamepadAxis::RightY),
+                Some(GamepadAxis::LeftTrigger),
+                Some(GamepadAxis::RightTrigger),
+                None,
+                None,
+            ],
+            strip: None,
+            knob_sensitivity: 1.0,
+        }
+    }
+}
+
+impl GamepadMapping {
+    fn mapped_buttons(&self) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.pads
+            .iter()
+            .flatten()
+            .copied()
+            .chain(self.buttons.iter().map(|(_, button)| *button))
+    }
+
+    fn mapped_axes(&self) -> impl Iterator<Item = GamepadAxis> + '_ {
+        GamepadAxis::ALL
+            .into_iter()
+            .filter(|axis| self.knobs.contains(&Some(*axis)) || self.strip == Some(*axis))
+    }
+}
+
+/// Gamepad input produced from a controller event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadEvent {
+    Button {
+        button: GamepadButton,
+        pressed: bool,
+    },
+    Axis {
+        axis: GamepadAxis,
+        value: i32,
+    },
+}
+
+const KNOBS: [InputElement; 8] = [
+    InputElement::Knob1,
+    InputElement::Knob2,
+    InputElement::Knob3,
+    InputElement::Knob4,
+    InputElement::Knob5,
+    InputElement::Knob6,
+    InputElement::Knob7,
+    InputElement::Knob8,
+];
+
+/// Translates controller events to gamepad events through a
+/// [`GamepadMapping`], keeping track of where each axis is
+#[derive(Debug, Clone)]
+pub struct GamepadMapper {
+    mapping: GamepadMapping,
+    axes: [f32; 6],
+}
+
+impl GamepadMapper {
+    pub fn new(mapping: GamepadMapping) -> Self {
+        Self {
+            axes: [0.0; 6],
+            mapping,
+        }
+    }
+
+    pub fn mapping(&self) -> &GamepadMapping {
+        &self.mapping
+    }
+
+    /// Current value of an axis
+    pub fn axis(&self, axis: GamepadAxis) -> i32 {
+        self.axes[axis.index()].round() as i32
+    }
+
+    /// Gamepad event for a controller event, if the control is mapped
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<GamepadEvent> {
+        match event {
+            InputEvent::PadEvent {
+                pad_number,
+                event_type,
+                ..
+            } => {
+                let button = (*self.mapping.pads.get(*pad_number as usize)?)?;
+                let pressed = match event_type {
+                    PadEventType::Hit => true,
+                    PadEventType::HitRelease | PadEventType::TouchRelease => false,
+                    PadEventType::Aftertouch => return None,
+                };
+                Some(GamepadEvent::Button { button, pressed })
+            }
+            InputEvent::ButtonPressed(element) | InputEvent::ButtonReleased(element) => {
+                let (_, button) = self
+                    .mapping
+                    .buttons
+                    .iter()
+                    .find(|(mapped, _)| mapped == element)?;
+                Some(GamepadEvent::Button {
+                    button: *button,
+                    pressed: matches!(event, InputEvent::ButtonPressed(_)),
+                })
+            }
+            InputEvent::KnobChanged { element, delta, .. } => {
+                let knob = KNOBS.iter().position(|knob| knob == element)?;
+                let axis = self.mapping.knobs[knob]?;
+                let (min, max) = axis.range();
+                let step =
+                    (max - min) as f32 * self.mapping.knob_sensitivity / KNOB_STEPS_PER_SWEEP;
+                let position = &mut self.axes[axis.index()];
+                *position = (*position + *delta as f32 * step).clamp(min as f32, max as f32);
+                Some(GamepadEvent::Axis {
+                    axis,
+                    value: self.axis(axis),
+                })
+            }
+            InputEvent::StripChanged { value, .. } => {
+                let axis = self.mapping.strip?;
+                let (min, max) = axis.range();
+                self.axes[axis.index()] = min as f32 + value.clamp(0.0, 1.0) * (max - min) as f32;
+                Some(GamepadEvent::Axis {
+                    axis,
+                    value: self.axis(axis),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The controller exposed to the system as a virtual gamepad, so games and
+/// accessibility tools can use it like any other pad.
+///
+/// Created through uinput, so it needs Linux and write access to
+/// `/dev/uinput` (usually membership of the `input` group or a udev rule).
+/// Other platforms get an error from [`new`](Self::new).
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineMK3, VirtualGamepad};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut gamepad = VirtualGamepad::new("Maschine MK3 Gamepad")?;
+///
+/// loop {
+///     let events = device.poll_input_events()?;
+///     gamepad.handle_events(&events)?;
+/// }
+/// # }
+/// ```
+pub struct VirtualGamepad {
+    mapper: GamepadMapper,
+    #[cfg(target_os = "linux")]
+    device: uinput::UinputDevice,
+}
+
+impl VirtualGamepad {
+    /// Create a gamepad with the default mapping
+    pub fn new(name: &str) -> Result<Self> {
+        Self::with_mapping(name, GamepadMapping::default())
+    }
+
+    /// Create a gamepad offering exactly the buttons and axes in `mapping`
+    pub fn with_mapping(name: &str, mapping: GamepadMapping) -> Result<Self> {
+        let mut keys = Vec::new();
+        for button in mapping.mapped_buttons() {
+            let code = button
+                .code()
+                .ok_or_else(|| MK3Error::InvalidData(format!("No gamepad button {:?}", button)))?;
+            if !keys.contains(&code) {
+                keys.push(code);
+            }
+        }
+        let axes: Vec<GamepadAxis> = mapping.mapped_axes().collect();
+
+        #[cfg(target_os = "linux")]
+        {
+            let mapper = GamepadMapper::new(mapping);
+            let device = uinput::UinputDevice::create(name, &keys, &axes)?;
+            Ok(Self { mapper, device })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (name, keys, axes);
+            Err(MK3Error::InvalidData(
+                "Virtual gamepads are only supported on Linux (uinput)".to_string(),
+            ))
+        }
+    }
+
+    pub fn mapping(&self) -> &GamepadMapping {
+        self.mapper.mapping()
+    }
+
+    /// Forward one controller event. Returns what the gamepad reported, if
+    /// the control is mapped.
+    pub fn handle_event(&mut self, event: &InputEvent) -> Result<Option<GamepadEvent>> {
+        let Some(gamepad_event) = self.mapper.handle_event(event) else {
+            return Ok(None);
+        };
+        self.emit(gamepad_event)?;
+        self.sync()?;
+        Ok(Some(gamepad_event))
+    }
+
+    /// Forward a batch of controller events as a single gamepad report
+    pub fn handle_events(&mut self, events: &[InputEvent]) -> Result<()> {
+        let mut changed = false;
+        for event in events {
+            if let Some(gamepad_event) = self.mapper.handle_event(event) {
+                self.emit(gamepad_event)?;
+                changed = true;
+            }
+        }
+        if changed {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn emit(&mut self, event: GamepadEvent) -> Result<()> {
+        match event {
+            GamepadEvent::Button { button, pressed } => {
+                if let Some(code) = button.code() {
+                    self.device.emit(uinput::EV_KEY, code, pressed as i32)?;
+                }
+            }
+            GamepadEvent::Axis { axis, value } => {
+                self.device.emit(uinput::EV_ABS, axis.code(), value)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sync(&mut self) -> Result<()> {
+        Ok(self.device.emit(uinput::EV_SYN, uinput::SYN_REPORT, 0)?)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn emit(&mut self, _event: GamepadEvent) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Minimal uinput device, through the legacy `uinput_user_dev` setup that
+/// every kernel with uinput supports
+#[cfg(target_os = "linux")]
+mod uinput {
+    use super::GamepadAxis;
+    use crate::device::{PRODUCT_ID, VENDOR_ID};
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+
+    pub(super) const EV_SYN: u16 = 0x00;
+    pub(super) const EV_KEY: u16 = 0x01;
+    pub(super) const EV_ABS: u16 = 0x03;
+    pub(super) const SYN_REPORT: u16 = 0;
+
+    const BUS_USB: u16 = 0x03;
+
+    // _IO('U', 1), _IO('U', 2) and _IOW('U', 100-103, int)
+    const UI_DEV_CREATE: u32 = 0x5501;
+    const UI_DEV_DESTROY: u32 = 0x5502;
+    const UI_SET_EVBIT: u32 = 0x4004_5564;
+    const UI_SET_KEYBIT: u32 = 0x4004_5565;
+    const UI_SET_ABSBIT: u32 = 0x4004_5567;
+
+    pub(super) struct UinputDevice {
+        file: File,
+    }
+
+    impl UinputDevice {
+        pub(super) fn create(name: &str, keys: &[u16], axes: &[GamepadAxis]) -> io::Result<Self> {
+            let file = OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open("/dev/uinput")?;
+            let device = Self { file };
+
+            device.ioctl(UI_SET_EVBIT, EV_KEY as libc::c_int)?;
+            for &key in keys {
+                device.ioctl(UI_SET_KEYBIT, key as libc::c_int)?;
+            }
+            if !axes.is_empty() {
+                device.ioctl(UI_SET_EVBIT, EV_ABS as libc::c_int)?;
+            }
+            for axis in axes {
+                device.ioctl(UI_SET_ABSBIT, axis.code() as libc::c_int)?;
+            }
+
+            // SAFETY: uinput_user_dev is plain integers and arrays, for which
+            // all zeroes is valid
+            let mut setup: libc::uinput_user_dev = unsafe { std::mem::zeroed() };
+            let name = name.as_bytes();
+            for (slot, byte) in setup
+                .name
+                .iter_mut()
+                .zip(name.iter().take(libc::UINPUT_MAX_NAME_SIZE - 1))
+            {
+                *slot = *byte as libc::c_char;
+            }
+            setup.id = libc::input_id {
+                bustype: BUS_USB,
+                vendor: VENDOR_ID,
+                product: PRODUCT_ID,
+                version: 1,
+            };
+            for axis in axes {
+                let (min, max) = axis.range();
+                setup.absmin[axis.code() as usize] = min;
+                setup.absmax[axis.code() as usize] = max;
+            }
+            write_struct(&device.file, &setup)?;
+
+            device.ioctl(UI_DEV_CREATE, 0)?;
+            Ok(device)
+        }
+
+        pub(super) fn emit(&self, kind: u16, code: u16, value: i32) -> io::Result<()> {
+            // SAFETY: input_event is plain integers; a zero timestamp makes
+            // the kernel fill in the current time
+            let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+            event.type_ = kind;
+            event.code = code;
+            event.value = value;
+            write_struct(&self.file, &event)
+        }
+
+        fn ioctl(&self, request: u32, argument: libc::c_int) -> io::Result<()> {
+            // SAFETY: every request used here takes an int argument by value
+            let result = unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, argument) };
+            if result < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for UinputDevice {
+        fn drop(&mut self) {
+            let _ = self.ioctl(UI_DEV_DESTROY, 0);
+        }
+    }
+
+    /// Write a C struct to the device in one call, as uinput requires
+    fn write_struct<T>(mut file: &File, value: &T) -> io::Result<()> {
+        // SAFETY: `value` is a live, fully initialized `T` of that many bytes
+        let bytes = unsafe {
+            std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>())
+        };
+        file.write_all(bytes)
+    }
+}
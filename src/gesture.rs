@@ -0,0 +1,380 @@
+use crate::encoder::encoder_detents;
+use crate::input::{InputElement, InputState, TouchStripState};
+use std::time::{Duration, Instant};
+
+/// High-level gestures recognized on the touch strip.
+///
+/// Positions and distances are normalized to the strip length (0.0 - 1.0).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchStripGesture {
+    /// Short touch without significant movement
+    Tap { position: f32 },
+    /// Second tap following a tap within the double-tap window
+    DoubleTap { position: f32 },
+    /// Quick movement towards the left end of the strip
+    SwipeLeft { distance: f32 },
+    /// Quick movement towards the right end of the strip
+    SwipeRight { distance: f32 },
+    /// Finger kept still on the strip for the hold duration
+    Hold { position: f32 },
+    /// Two fingers moving apart (scale > 1.0) or together (scale < 1.0),
+    /// relative to their distance when the second finger landed
+    Pinch { scale: f32 },
+}
+
+impl TouchStripGesture {
+    /// Get a human-readable description of this gesture
+    pub fn description(&self) -> String {
+        match self {
+            TouchStripGesture::Tap { position } => format!("tap at {:.2}", position),
+            TouchStripGesture::DoubleTap { position } => format!("double tap at {:.2}", position),
+            TouchStripGesture::SwipeLeft { distance } => format!("swipe left ({:.2})", distance),
+            TouchStripGesture::SwipeRight { distance } => format!("swipe right ({:.2})", distance),
+            TouchStripGesture::Hold { position } => format!("hold at {:.2}", position),
+            TouchStripGesture::Pinch { scale } => format!("pinch (scale {:.2})", scale),
+        }
+    }
+}
+
+/// Thresholds used by the touch strip gesture recognizer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchStripGestureConfig {
+    /// Longest touch that still counts as a tap
+    pub tap_max_duration: Duration,
+    /// Largest movement (normalized) that still counts as a tap or hold
+    pub tap_max_movement: f32,
+    /// Maximum time between two taps for a double tap
+    pub double_tap_window: Duration,
+    /// Minimum travel (normalized) for a swipe
+    pub swipe_min_distance: f32,
+    /// Longest touch that still counts as a swipe
+    pub swipe_max_duration: Duration,
+    /// How long a finger must stay still before a hold is reported
+    pub hold_duration: Duration,
+    /// Minimum change in pinch scale before a new pinch event is reported
+    pub pinch_threshold: f32,
+}
+
+impl Default for TouchStripGestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_max_duration: Duration::from_millis(200),
+            tap_max_movement: 0.03,
+            double_tap_window: Duration::from_millis(300),
+            swipe_min_distance: 0.25,
+            swipe_max_duration: Duration::from_millis(500),
+            hold_duration: Duration::from_millis(600),
+            pinch_threshold: 0.1,
+        }
+    }
+}
+
+/// State of the current touch, from first finger down to last finger up
+#[derive(Debug, Clone)]
+struct TouchSession {
+    started: Instant,
+    start_position: f32,
+    last_position: f32,
+    max_movement: f32,
+    hold_reported: bool,
+    multi_finger: bool,
+    pinch_start_distance: Option<f32>,
+    last_pinch_scale: f32,
+}
+
+/// Turns raw touch strip finger data into [`TouchStripGesture`]s
+#[derive(Debug, Clone, Default)]
+pub struct TouchStripGestureRecognizer {
+    config: TouchStripGestureConfig,
+    session: Option<TouchSession>,
+    last_tap: Option<Instant>,
+}
+
+impl TouchStripGestureRecognizer {
+    pub fn new(config: TouchStripGestureConfig) -> Self {
+        Self {
+            config,
+            session: None,
+            last_tap: None,
+        }
+    }
+
+    pub fn config(&self) -> &TouchStripGestureConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: TouchStripGestureConfig) {
+        self.config = config;
+    }
+
+    /// Feed the latest touch strip state and return any gestures it completes
+    pub fn update(&mut self, strip: &TouchStripState, now: Instant) -> Vec<TouchStripGesture> {
+        let mut gestures = Vec::new();
+        let first = strip.finger_1.normalized_position();
+        let second = strip.finger_2.normalized_position();
+
+        match (self.session.as_mut(), first) {
+            (None, Some(position)) => {
+                self.session = Some(TouchSession {
+                    started: now,
+                    start_position: position,
+                    last_position: position,
+                    max_movement: 0.0,
+                    hold_reported: false,
+                    multi_finger: second.is_some(),
+                    pinch_start_distance: second.map(|p| (p - position).abs()),
+                    last_pinch_scale: 1.0,
+                });
+            }
+            (Some(session), Some(position)) => {
+                session.last_position = position;
+                session.max_movement = session
+                    .max_movement
+                    .max((position - session.start_position).abs());
+
+                if let Some(second) = second {
+                    session.multi_finger = true;
+                    let distance = (second - position).abs();
+                    match session.pinch_start_distance {
+                        Some(start) if start > f32::EPSILON => {
+                            let scale = distance / start;
+                            if (scale - session.last_pinch_scale).abs()
+                                >= self.config.pinch_threshold
+                            {
+                                session.last_pinch_scale = scale;
+                                gestures.push(TouchStripGesture::Pinch { scale });
+                            }
+                        }
+                        _ => session.pinch_start_distance = Some(distance),
+                    }
+                } else {
+                    session.pinch_start_distance = None;
+                    session.last_pinch_scale = 1.0;
+                }
+
+                if !session.hold_reported
+                    && !session.multi_finger
+                    && session.max_movement <= self.config.tap_max_movement
+                    && now.duration_since(session.started) >= self.config.hold_duration
+                {
+                    session.hold_reported = true;
+                    gestures.push(TouchStripGesture::Hold { position });
+                }
+            }
+            (Some(_), None) => {
+                if let Some(session) = self.session.take() {
+                    if let Some(gesture) = self.finish_session(&session, now) {
+                        gestures.push(gesture);
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+
+        gestures
+    }
+
+    /// Classify a completed touch as a tap, double tap or swipe
+    fn finish_session(
+        &mut self,
+        session: &TouchSession,
+        now: Instant,
+    ) -> Option<TouchStripGesture> {
+        if session.multi_finger || session.hold_reported {
+            return None;
+        }
+
+        let duration = now.duration_since(session.started);
+        let displacement = session.last_position - session.start_position;
+
+        if displacement.abs() >= self.config.swipe_min_distance
+            && duration <= self.config.swipe_max_duration
+        {
+            self.last_tap = None;
+            return Some(if displacement < 0.0 {
+                TouchStripGesture::SwipeLeft {
+                    distance: -displacement,
+                }
+            } else {
+                TouchStripGesture::SwipeRight {
+                    distance: displacement,
+                }
+            });
+        }
+
+        if duration <= self.config.tap_max_duration
+            && session.max_movement <= self.config.tap_max_movement
+        {
+            let position = session.start_position;
+            let is_double = matches!(
+                self.last_tap,
+                Some(tapped_at) if now.duration_since(tapped_at) <= self.config.double_tap_window
+            );
+
+            if is_double {
+                self.last_tap = None;
+                return Some(TouchStripGesture::DoubleTap { position });
+            }
+            self.last_tap = Some(now);
+            return Some(TouchStripGesture::Tap { position });
+        }
+
+        None
+    }
+}
+
+/// Knobs with touch sensors, with the element reporting each one's touch
+const TOUCH_KNOBS: [(InputElement, InputElement); 9] = [
+    (InputElement::Knob1, InputElement::Knob1Touched),
+    (InputElement::Knob2, InputElement::Knob2Touched),
+    (InputElement::Knob3, InputElement::Knob3Touched),
+    (InputElement::Knob4, InputElement::Knob4Touched),
+    (InputElement::Knob5, InputElement::Knob5Touched),
+    (InputElement::Knob6, InputElement::Knob6Touched),
+    (InputElement::Knob7, InputElement::Knob7Touched),
+    (InputElement::Knob8, InputElement::Knob8Touched),
+    (InputElement::MainEncoder, InputElement::MainKnobTouched),
+];
+
+/// Knob touches combined with the turns made during them.
+///
+/// A touch starts with the `ButtonPressed` event of the knob's touch
+/// element (e.g. [`InputElement::Knob1Touched`]), which is the moment to
+/// show the parameter under the knob. `knob` is the knob itself, e.g.
+/// [`InputElement::Knob1`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnobGesture {
+    /// The knob turned while touched. `value` and `delta` are those of the
+    /// `KnobChanged` event; for the main encoder `delta` is in detents.
+    TouchTurn {
+        knob: InputElement,
+        value: u16,
+        delta: i32,
+    },
+    /// Short touch without turning, reported before its `TouchRelease`
+    TapTouch { knob: InputElement },
+    /// The finger left the knob; `turned` tells whether it turned during
+    /// the touch
+    TouchRelease { knob: InputElement, turned: bool },
+}
+
+impl KnobGesture {
+    pub fn knob(&self) -> InputElement {
+        match *self {
+            KnobGesture::TouchTurn { knob, .. }
+            | KnobGesture::TapTouch { knob }
+            | KnobGesture::TouchRelease { knob, .. } => knob,
+        }
+    }
+
+    /// Get a human-readable description of this gesture
+    pub fn description(&self) -> String {
+        match self {
+            KnobGesture::TouchTurn { knob, value, delta } => {
+                format!("{} touch-turned → {} (Δ{})", knob.name(), value, delta)
+            }
+            KnobGesture::TapTouch { knob } => format!("{} tapped", knob.name()),
+            KnobGesture::TouchRelease { knob, turned } => {
+                let how = if *turned {
+                    "after turning"
+                } else {
+                    "untouched"
+                };
+                format!("{} released {}", knob.name(), how)
+            }
+        }
+    }
+}
+
+/// Thresholds used by the knob gesture recognizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnobGestureConfig {
+    /// Longest touch without turning that still counts as a tap
+    pub tap_max_duration: Duration,
+}
+
+impl Default for KnobGestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_max_duration: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Touch on one knob, from the finger landing to it leaving
+#[derive(Debug, Clone, Copy)]
+struct KnobTouch {
+    started: Instant,
+    turned: bool,
+}
+
+/// Combines knob touch bits and value changes into [`KnobGesture`]s
+#[derive(Debug, Clone, Default)]
+pub struct KnobGestureRecognizer {
+    config: KnobGestureConfig,
+    touches: [Option<KnobTouch>; 9],
+    /// Knob values of the last update
+    values: Option<[u16; 9]>,
+}
+
+impl KnobGestureRecognizer {
+    pub fn new(config: KnobGestureConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> KnobGestureConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: KnobGestureConfig) {
+        self.config = config;
+    }
+
+    /// Feed the latest button packet state and return the gestures it
+    /// makes. Turns are only reported from the second update on, once
+    /// there are values to compare with.
+    pub fn update(&mut self, state: &InputState, now: Instant) -> Vec<KnobGesture> {
+        let mut gestures = Vec::new();
+        let values = TOUCH_KNOBS.map(|(knob, _)| state.get_value(&knob));
+
+        for (index, (knob, touch_element)) in TOUCH_KNOBS.into_iter().enumerate() {
+            let touch = &mut self.touches[index];
+            if !state.get_button(&touch_element) {
+                if let Some(ended) = touch.take() {
+                    let turned = ended.turned;
+                    if !turned && now.duration_since(ended.started) <= self.config.tap_max_duration
+                    {
+                        gestures.push(KnobGesture::TapTouch { knob });
+                    }
+                    gestures.push(KnobGesture::TouchRelease { knob, turned });
+                }
+                continue;
+            }
+
+            // A turn in the packet that starts the touch counts as well
+            let current = touch.get_or_insert(KnobTouch {
+                started: now,
+                turned: false,
+            });
+            let previous = self.values.map(|values| values[index]);
+            if let Some(previous) = previous.filter(|&previous| previous != values[index]) {
+                let mut delta = values[index] as i32 - previous as i32;
+                if knob == InputElement::MainEncoder {
+                    delta = encoder_detents(delta);
+                }
+                current.turned = true;
+                gestures.push(KnobGesture::TouchTurn {
+                    knob,
+                    value: values[index],
+                    delta,
+                });
+            }
+        }
+
+        self.values = Some(values);
+        gestures
+    }
+}
@@ -0,0 +1,213 @@
+use crate::device::MaschineDevice;
+use crate::error::Result;
+use crate::input::{InputElement, InputEvent};
+use crate::output::{LedFrame, MaschineLEDColor};
+
+/// Group buttons, and so groups per bank
+pub const GROUPS_PER_BANK: usize = 8;
+
+const GROUP_BUTTONS: [InputElement; GROUPS_PER_BANK] = [
+    InputElement::GroupA,
+    InputElement::GroupB,
+    InputElement::GroupC,
+    InputElement::GroupD,
+    InputElement::GroupE,
+    InputElement::GroupF,
+    InputElement::GroupG,
+    InputElement::GroupH,
+];
+
+/// Palette indices groups are colored with by default, cycling every 8
+const DEFAULT_GROUP_COLORS: [u8; GROUPS_PER_BANK] = [0, 1, 3, 5, 7, 9, 10, 11];
+
+/// Something a [`GroupSelector`] did in response to input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupEvent {
+    /// `group` (counting across banks, so 9 is B in the second bank) was
+    /// selected
+    GroupChanged { group: usize },
+    /// The group buttons now show bank `bank`
+    BankChanged { bank: usize },
+}
+
+/// Selection state of the group row, modeled on the official software.
+///
+/// Groups are laid out in banks of eight on buttons A-H. Pressing a group
+/// button selects that group in the bank in view; holding Shift turns the
+/// row into a bank selector, where button A shows the first bank, B the
+/// second and so on. The selected group's button is lit brightly in its
+/// color and the other groups in the bank dimly; while Shift is held the
+/// buttons show the available banks instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{GroupEvent, GroupSelector, MaschineMK3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut groups = GroupSelector::new(12);
+/// groups.show_leds(&mut device)?;
+///
+/// loop {
+///     let events = device.poll_input_events()?;
+///     for change in groups.handle_events(&mut device, &events)? {
+///         if let GroupEvent::GroupChanged { group } = change {
+///             println!("Group {} selected", group);
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GroupSelector {
+    colors: Vec<MaschineLEDColor>,
+    selected: usize,
+    bank: usize,
+    shift_held: bool,
+}
+
+impl GroupSelector {
+    /// Selector for `count` groups (at least one), with group 0 selected
+    pub fn new(count: usize) -> Self {
+        let colors = (0..count.max(1))
+            .map(|group| MaschineLEDColor::new(DEFAULT_GROUP_COLORS[group % GROUPS_PER_BANK], true))
+            .collect();
+
+        Self {
+            colors,
+            selected: 0,
+            bank: 0,
+            shift_held: false,
+        }
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn bank_count(&self) -> usize {
+        self.colors.len().div_ceil(GROUPS_PER_BANK)
+    }
+
+    /// Selected group, counting across banks
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Bank shown on the group buttons
+    pub fn bank(&self) -> usize {
+        self.bank
+    }
+
+    /// Button of the selected group, if its bank is in view
+    pub fn selected_button(&self) -> Option<InputElement> {
+        let slot = self.selected.checked_sub(self.bank * GROUPS_PER_BANK)?;
+        GROUP_BUTTONS.get(slot).cloned()
+    }
+
+    /// Select a group and bring its bank into view. Returns false if it
+    /// doesn't exist.
+    pub fn select(&mut self, group: usize) -> bool {
+        if group >= self.colors.len() {
+            return false;
+        }
+        self.selected = group;
+        self.bank = group / GROUPS_PER_BANK;
+        true
+    }
+
+    /// Show a bank without changing the selection. Returns false if it
+    /// doesn't exist.
+    pub fn set_bank(&mut self, bank: usize) -> bool {
+        if bank >= self.bank_count() {
+            return false;
+        }
+        self.bank = bank;
+        true
+    }
+
+    pub fn color(&self, group: usize) -> Option<MaschineLEDColor> {
+        self.colors.get(group).copied()
+    }
+
+    /// Color a group's button is lit in; the selected group is shown bright
+    /// and the others dim regardless of `color.bright`
+    pub fn set_color(&mut self, group: usize, color: MaschineLEDColor) -> bool {
+        match self.colors.get_mut(group) {
+            Some(slot) => {
+                *slot = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle one input event without touching the device
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<GroupEvent> {
+        match event {
+            InputEvent::ButtonPressed(InputElement::Shift) => {
+                self.shift_held = true;
+                None
+            }
+            InputEvent::ButtonReleased(InputElement::Shift) => {
+                self.shift_held = false;
+                None
+            }
+            InputEvent::ButtonPressed(element) => {
+                let slot = GROUP_BUTTONS.iter().position(|button| button == element)?;
+                if self.shift_held {
+                    if slot == self.bank || !self.set_bank(slot) {
+                        return None;
+                    }
+                    Some(GroupEvent::BankChanged { bank: slot })
+                } else {
+                    let group = self.bank * GROUPS_PER_BANK + slot;
+                    if group == self.selected || !self.select(group) {
+                        return None;
+                    }
+                    Some(GroupEvent::GroupChanged { group })
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a batch of events, updating the group LEDs if anything they
+    /// show changed
+    pub fn handle_events<D: MaschineDevice>(
+        &mut self,
+        device: &mut D,
+        events: &[InputEvent],
+    ) -> Result<Vec<GroupEvent>> {
+        let before = (self.selected, self.bank, self.shift_held);
+        let changes: Vec<GroupEvent> = events
+            .iter()
+            .filter_map(|event| self.handle_event(event))
+            .collect();
+
+        if (self.selected, self.bank, self.shift_held) != before {
+            self.show_leds(device)?;
+        }
+        Ok(changes)
+    }
+
+    /// Light the group buttons for the current state
+    pub fn show_leds<D: MaschineDevice>(&self, device: &mut D) -> Result<()> {
+        device.update_leds(|leds| self.write_leds(leds))
+    }
+
+    /// Write the group button LEDs into `leds`
+    pub fn write_leds(&self, leds: &mut LedFrame) {
+        for (slot, button) in GROUP_BUTTONS.iter().enumerate() {
+            let color = if self.shift_held {
+                (slot < self.bank_count()).then(|| MaschineLEDColor::white(slot == self.bank))
+            } else {
+                let group = self.bank * GROUPS_PER_BANK + slot;
+                self.color(group)
+                    .map(|color| MaschineLEDColor::new(color.index, group == self.selected))
+            };
+            leds.set_button_color(*button, color.unwrap_or_else(MaschineLEDColor::black));
+        }
+    }
+}
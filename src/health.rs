@@ -0,0 +1,259 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Rolling window throughput and error rates are measured over, in
+/// one-second buckets
+const WINDOW_SECONDS: usize = 10;
+
+/// Transfers an endpoint needs in the window before its error rate can
+/// raise an alert, so a single failure on a quiet endpoint doesn't
+const MIN_ALERT_TRANSFERS: u64 = 10;
+
+/// Endpoint a transfer went to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsbEndpoint {
+    /// Button, knob and pad reports
+    Input,
+    /// Button and pad LED writes, over HID on Windows
+    Led,
+    /// Display bulk writes
+    Display,
+}
+
+impl UsbEndpoint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UsbEndpoint::Input => "Input",
+            UsbEndpoint::Led => "LED",
+            UsbEndpoint::Display => "Display",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Transfer counters for one endpoint, see [`UsbHealth`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndpointHealth {
+    /// Transfers that completed, since the device was opened
+    pub transfers: u64,
+    /// Transfers that failed. Input reads that time out without data don't
+    /// count; each failed attempt of a retried display write does.
+    pub errors: u64,
+    /// Display writes resent after resetting a stalled endpoint
+    pub retries: u64,
+    /// Bytes moved by completed transfers
+    pub bytes: u64,
+    /// When the last error happened
+    pub last_error_at: Option<SystemTime>,
+    pub last_error: Option<String>,
+    /// Throughput over the last ten seconds
+    pub bytes_per_second: f64,
+    /// Share of transfers over the last ten seconds that failed (0.0-1.0)
+    pub error_rate: f64,
+}
+
+/// USB transfer statistics by endpoint, see
+/// [`MaschineMK3::usb_health`](crate::MaschineMK3::usb_health)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsbHealth {
+    pub input: EndpointHealth,
+    pub led: EndpointHealth,
+    pub display: EndpointHealth,
+}
+
+impl UsbHealth {
+    pub fn endpoint(&self, endpoint: UsbEndpoint) -> &EndpointHealth {
+        match endpoint {
+            UsbEndpoint::Input => &self.input,
+            UsbEndpoint::Led => &self.led,
+            UsbEndpoint::Display => &self.display,
+        }
+    }
+
+    /// Errors across all endpoints since the device was opened
+    pub fn total_errors(&self) -> u64 {
+        self.input.errors + self.led.errors + self.display.errors
+    }
+}
+
+/// Called with the endpoint and its health when its error rate crosses the
+/// alert threshold
+pub(crate) type HealthAlertCallback = Arc<dyn Fn(UsbEndpoint, &EndpointHealth) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    second: u64,
+    transfers: u64,
+    errors: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct EndpointRecord {
+    totals: EndpointHealth,
+    buckets: [Bucket; WINDOW_SECONDS],
+    /// Whether the alert has fired and not yet cleared
+    alerting: bool,
+}
+
+impl EndpointRecord {
+    fn health(&self, second: u64, elapsed: Duration) -> EndpointHealth {
+        let recent = self
+            .buckets
+            .iter()
+            .filter(|bucket| second - bucket.second < WINDOW_SECONDS as u64);
+        let (transfers, errors, bytes) = recent.fold((0, 0, 0), |(t, e, b), bucket| {
+            (t + bucket.transfers, e + bucket.errors, b + bucket.bytes)
+        });
+        let span = elapsed.as_secs_f64().clamp(1.0, WINDOW_SECONDS as f64);
+
+        EndpointHealth {
+            bytes_per_second: bytes as f64 / span,
+            error_rate: if transfers + errors > 0 {
+                errors as f64 / (transfers + errors) as f64
+            } else {
+                0.0
+            },
+            ..self.totals.clone()
+        }
+    }
+
+    fn window_transfers(&self, second: u64) -> u64 {
+        self.buckets
+            .iter()
+            .filter(|bucket| second - bucket.second < WINDOW_SECONDS as u64)
+            .map(|bucket| bucket.transfers + bucket.errors)
+            .sum()
+    }
+}
+
+/// Bucket for `second`, emptied first if it still holds an older second
+fn current_bucket(buckets: &mut [Bucket; WINDOW_SECONDS], second: u64) -> &mut Bucket {
+    let bucket = &mut buckets[second as usize % WINDOW_SECONDS];
+    if bucket.second != second {
+        *bucket = Bucket {
+            second,
+            ..Bucket::default()
+        };
+    }
+    bucket
+}
+
+struct HealthAlert {
+    error_rate: f64,
+    callback: HealthAlertCallback,
+}
+
+/// Transfer bookkeeping shared by a device and its input monitoring thread
+pub(crate) struct HealthMonitor {
+    started: Instant,
+    endpoints: Mutex<[EndpointRecord; 3]>,
+    alert: Mutex<Option<HealthAlert>>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            endpoints: Mutex::new(Default::default()),
+            alert: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn record_transfer(&self, endpoint: UsbEndpoint, bytes: usize) {
+        self.record(endpoint, |totals, bucket| {
+            totals.transfers += 1;
+            totals.bytes += bytes as u64;
+            bucket.transfers += 1;
+            bucket.bytes += bytes as u64;
+        });
+    }
+
+    pub(crate) fn record_error(&self, endpoint: UsbEndpoint, error: &dyn fmt::Display) {
+        self.record(endpoint, |totals, bucket| {
+            totals.errors += 1;
+            totals.last_error_at = Some(SystemTime::now());
+            totals.last_error = Some(error.to_string());
+            bucket.errors += 1;
+        });
+    }
+
+    pub(crate) fn record_retry(&self, endpoint: UsbEndpoint) {
+        if let Ok(mut endpoints) = self.endpoints.lock() {
+            endpoints[endpoint.index()].totals.retries += 1;
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> UsbHealth {
+        let elapsed = self.started.elapsed();
+        let second = elapsed.as_secs();
+        let Ok(endpoints) = self.endpoints.lock() else {
+            return UsbHealth::default();
+        };
+
+        UsbHealth {
+            input: endpoints[UsbEndpoint::Input.index()].health(second, elapsed),
+            led: endpoints[UsbEndpoint::Led.index()].health(second, elapsed),
+            display: endpoints[UsbEndpoint::Display.index()].health(second, elapsed),
+        }
+    }
+
+    pub(crate) fn set_alert(&self, alert: Option<(f64, HealthAlertCallback)>) {
+        if let Ok(mut current) = self.alert.lock() {
+            *current = alert.map(|(error_rate, callback)| HealthAlert {
+                error_rate,
+                callback,
+            });
+        }
+        if let Ok(mut endpoints) = self.endpoints.lock() {
+            for record in endpoints.iter_mut() {
+                record.alerting = false;
+            }
+        }
+    }
+
+    /// Update one endpoint, then check it against the alert threshold. The
+    /// callback runs without any lock held.
+    fn record<F>(&self, endpoint: UsbEndpoint, update: F)
+    where
+        F: FnOnce(&mut EndpointHealth, &mut Bucket),
+    {
+        let elapsed = self.started.elapsed();
+        let second = elapsed.as_secs();
+        let threshold = match self.alert.lock() {
+            Ok(alert) => alert
+                .as_ref()
+                .map(|alert| (alert.error_rate, Arc::clone(&alert.callback))),
+            Err(_) => None,
+        };
+
+        let fire = {
+            let Ok(mut endpoints) = self.endpoints.lock() else {
+                return;
+            };
+            let record = &mut endpoints[endpoint.index()];
+            update(
+                &mut record.totals,
+                current_bucket(&mut record.buckets, second),
+            );
+
+            let Some((error_rate, callback)) = threshold else {
+                return;
+            };
+            let health = record.health(second, elapsed);
+            let over = record.window_transfers(second) >= MIN_ALERT_TRANSFERS
+                && health.error_rate > error_rate;
+            let rising = over && !record.alerting;
+            record.alerting = over;
+            rising.then_some((callback, health))
+        };
+
+        if let Some((callback, health)) = fire {
+            callback(endpoint, &health);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::device::MaschineMK3;
+use crate::font::{self, TextStyle, GLYPH_HEIGHT};
+use crate::output::RgbColor;
+use std::time::Duration;
+
+/// Length of one cycle of the identify pattern
+const PERIOD: Duration = Duration::from_millis(1000);
+
+/// Length of each of the two flashes that open a cycle, and of the gap
+/// between them
+const FLASH: Duration = Duration::from_millis(120);
+
+/// Largest text scale for the serial number
+const MAX_SCALE: usize = 6;
+
+/// How often the pattern is checked for a change
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Whether the pattern is lit `elapsed` after it started: two quick flashes,
+/// then dark for the rest of the second. The double flash stands out from
+/// the steady or single-blink patterns apps tend to use.
+pub(crate) fn is_lit(elapsed: Duration) -> bool {
+    let phase = elapsed.as_millis() % PERIOD.as_millis();
+    let flash = FLASH.as_millis();
+    phase < flash || (2 * flash..3 * flash).contains(&phase)
+}
+
+/// RGB888 display frame with `serial` centered as large as it fits, black
+/// on white while lit and white on black otherwise, so it can be read
+/// throughout
+pub(crate) fn frame(serial: &str, lit: bool) -> Vec<u8> {
+    let width = MaschineMK3::DISPLAY_WIDTH as usize;
+    let height = MaschineMK3::DISPLAY_HEIGHT as usize;
+    let (fg, bg) = if lit {
+        (RgbColor::black(), RgbColor::white())
+    } else {
+        (RgbColor::white(), RgbColor::black())
+    };
+
+    let mut frame = [bg.r, bg.g, bg.b].repeat(width * height);
+    let scale = (width * 9 / 10 / font::text_width(serial, 1).max(1)).clamp(1, MAX_SCALE);
+    let x = width.saturating_sub(font::text_width(serial, scale)) / 2;
+    let y = (height - GLYPH_HEIGHT * scale) / 2;
+    font::draw_text(
+        &mut frame,
+        width,
+        x,
+        y,
+        serial,
+        &TextStyle {
+            fg,
+            bg: None,
+            scale,
+        },
+    );
+    frame
+}
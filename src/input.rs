@@ -1,8 +1,20 @@
+use crate::action_map::ActionId;
+use crate::coords::PadIndex;
+use crate::encoder::{encoder_detents, EncoderAccelerationConfig, EncoderAccelerator};
 use crate::error::{MK3Error, Result};
+use crate::gesture::{
+    KnobGesture, KnobGestureConfig, KnobGestureRecognizer, TouchStripGesture,
+    TouchStripGestureConfig, TouchStripGestureRecognizer,
+};
+use crate::pad_filter::{PadFilterConfig, PadHitFilter};
+use crate::strip::{StripController, StripMode};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 /// Represents the state of all buttons on the Maschine MK3
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct ButtonState {
     // Transport controls
     pub play: bool,
@@ -87,7 +99,7 @@ pub struct ButtonState {
 }
 
 /// Represents the state of all knobs on the Maschine MK3
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct KnobState {
     pub knob_1: u16, // 10-bit resolution (0-1023)
     pub knob_2: u16,
@@ -112,13 +124,13 @@ pub struct KnobState {
 }
 
 /// Represents touch strip data
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct TouchStripState {
     pub finger_1: TouchData,
     pub finger_2: TouchData,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct TouchData {
     pub data_a: u8,
     pub data_b: u8,
@@ -126,16 +138,122 @@ pub struct TouchData {
     pub data_d: u8,
 }
 
+impl TouchData {
+    /// Largest raw position value reported by the strip
+    pub const MAX_POSITION: u16 = 1023;
+
+    /// Whether this finger is currently on the strip (data A is non-zero while touched)
+    pub fn is_touched(&self) -> bool {
+        self.data_a > 0
+    }
+
+    /// Raw finger position (0-1023, left to right), or `None` when not touched.
+    /// Read little-endian from data C/D, 10-bit like the knobs. This layout
+    /// hasn't been confirmed from a capture, see the notes on the touch strip
+    /// in `docs/MaschineMK3-HIDInput.md`.
+    pub fn position(&self) -> Option<u16> {
+        if !self.is_touched() {
+            return None;
+        }
+        Some((((self.data_d as u16 & 0x03) << 8) | self.data_c as u16).min(Self::MAX_POSITION))
+    }
+
+    /// Finger position normalized to 0.0 (left) - 1.0 (right)
+    pub fn normalized_position(&self) -> Option<f32> {
+        self.position()
+            .map(|position| position as f32 / Self::MAX_POSITION as f32)
+    }
+}
+
+/// Raw bytes reported for both touch strip fingers (A, B, C, D per finger)
+pub type TouchStripRawData = ((u8, u8, u8, u8), (u8, u8, u8, u8));
+
 /// Represents audio controls
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct AudioState {
     pub mic_gain: u16,
     pub headphone_volume: u16,
     pub master_volume: u16,
 }
 
+/// Which way an audio control was turned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioDirection {
+    Up,
+    Down,
+}
+
+/// Audio control reading on a 0.0-1.0 scale, from
+/// [`InputEvent::audio_value`].
+///
+/// The controls are analog pots reported with 12 bits (0-4095), the same
+/// resolution as the pads. They act on the analog inputs and outputs after
+/// conversion, and NI doesn't publish their gain law, so the controller
+/// itself has no known dB mapping; [`to_db`](Self::to_db) maps onto a range
+/// the app chooses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioValue {
+    pub element: InputElement,
+    pub raw: u16,
+    /// Position from 0.0 (fully down) to 1.0 (fully up)
+    pub normalized: f32,
+    /// Change since the previous reading on the same scale, positive when
+    /// turned up
+    pub delta: f32,
+}
+
+impl AudioValue {
+    /// Largest raw value of the mic gain, headphone and master volume
+    /// controls
+    pub const MAX_RAW: u16 = 4095;
+
+    /// Reading from a raw value and the raw change since the previous one
+    pub fn from_raw(element: InputElement, raw: u16, raw_delta: i32) -> Self {
+        let max = Self::MAX_RAW as f32;
+        Self {
+            element,
+            raw,
+            normalized: raw.min(Self::MAX_RAW) as f32 / max,
+            delta: raw_delta as f32 / max,
+        }
+    }
+
+    /// Direction of the change, `None` when the value didn't move (e.g. in
+    /// a state sync snapshot)
+    pub fn direction(&self) -> Option<AudioDirection> {
+        if self.delta > 0.0 {
+            Some(AudioDirection::Up)
+        } else if self.delta < 0.0 {
+            Some(AudioDirection::Down)
+        } else {
+            None
+        }
+    }
+
+    /// Position as a percentage (0-100)
+    pub fn percent(&self) -> f32 {
+        self.normalized * 100.0
+    }
+
+    /// Level in dB for a control spanning `min_db..=max_db` evenly in dB,
+    /// e.g. `-60.0..=0.0` for a volume fader. Fully down is silence
+    /// (`f32::NEG_INFINITY`).
+    pub fn to_db(&self, min_db: f32, max_db: f32) -> f32 {
+        if self.raw == 0 {
+            return f32::NEG_INFINITY;
+        }
+        min_db + self.normalized * (max_db - min_db)
+    }
+
+    /// Linear amplitude factor matching [`to_db`](Self::to_db), 0.0 when
+    /// fully down
+    pub fn to_gain(&self, min_db: f32, max_db: f32) -> f32 {
+        10f32.powf(self.to_db(min_db, max_db) / 20.0)
+    }
+}
+
 /// Enumeration of all input elements for event-based input
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InputElement {
     // Buttons
     Play,
@@ -230,6 +348,64 @@ pub enum InputElement {
 }
 
 impl InputElement {
+    /// Every input element in declaration order. An element's position in this
+    /// list is its numeric ID, as used by the C API.
+    pub const ALL: [InputElement; 86] = [
+        InputElement::Play, InputElement::Rec, InputElement::Stop, InputElement::Restart,
+        InputElement::Erase, InputElement::Tap, InputElement::Follow, InputElement::GroupA,
+        InputElement::GroupB, InputElement::GroupC, InputElement::GroupD, InputElement::GroupE,
+        InputElement::GroupF, InputElement::GroupG, InputElement::GroupH, InputElement::Notes,
+        InputElement::Volume, InputElement::Swing, InputElement::Tempo, InputElement::NoteRepeat,
+        InputElement::Lock, InputElement::PadMode, InputElement::Keyboard, InputElement::Chords,
+        InputElement::Step, InputElement::FixedVel, InputElement::Scene, InputElement::Pattern,
+        InputElement::Events, InputElement::Variation, InputElement::Duplicate,
+        InputElement::Select, InputElement::Solo, InputElement::Mute, InputElement::Pitch,
+        InputElement::Mod, InputElement::Perform, InputElement::Shift, InputElement::EncoderPush,
+        InputElement::EncoderUp, InputElement::EncoderDown, InputElement::EncoderLeft,
+        InputElement::EncoderRight, InputElement::DisplayButton1, InputElement::DisplayButton2,
+        InputElement::DisplayButton3, InputElement::DisplayButton4, InputElement::DisplayButton5,
+        InputElement::DisplayButton6, InputElement::DisplayButton7, InputElement::DisplayButton8,
+        InputElement::ChannelMidi, InputElement::Arranger, InputElement::BrowserPlugin,
+        InputElement::ArrowLeft, InputElement::ArrowRight, InputElement::FileSave,
+        InputElement::Settings, InputElement::Macro, InputElement::Plugin, InputElement::Mixer,
+        InputElement::Sampling, InputElement::Auto, InputElement::PedalConnected,
+        InputElement::MicrophoneConnected, InputElement::Knob1, InputElement::Knob2,
+        InputElement::Knob3, InputElement::Knob4, InputElement::Knob5, InputElement::Knob6,
+        InputElement::Knob7, InputElement::Knob8, InputElement::MainEncoder,
+        InputElement::Knob1Touched, InputElement::Knob2Touched, InputElement::Knob3Touched,
+        InputElement::Knob4Touched, InputElement::Knob5Touched, InputElement::Knob6Touched,
+        InputElement::Knob7Touched, InputElement::Knob8Touched, InputElement::MainKnobTouched,
+        InputElement::MicGain, InputElement::HeadphoneVolume, InputElement::MasterVolume,
+    ];
+
+    /// Stable numeric ID of this element (its index in [`InputElement::ALL`])
+    pub fn id(&self) -> u32 {
+        *self as u32
+    }
+
+    /// Look up an element by its numeric ID
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.get(id as usize).cloned()
+    }
+
+    /// Look up an element by name. Accepts the identifier written by
+    /// `Display` ("GroupA", "Knob3") as well as the display name from
+    /// [`InputElement::name`] ("Group A"), ignoring case, spaces and
+    /// punctuation.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let wanted = normalize_name(name);
+        if wanted.is_empty() {
+            return None;
+        }
+        Self::ALL
+            .iter()
+            .find(|element| {
+                normalize_name(&element.to_string()) == wanted
+                    || normalize_name(element.name()) == wanted
+            })
+            .cloned()
+    }
+
     /// Get the display name for this input element
     pub fn name(&self) -> &'static str {
         match self {
@@ -323,27 +499,60 @@ impl InputElement {
     }
 
     pub fn has_color(&self) -> bool {
-        match self {
-            InputElement::GroupA => true,
-            InputElement::GroupB => true,
-            InputElement::GroupC => true,
-            InputElement::GroupD => true,
-            InputElement::GroupE => true,
-            InputElement::GroupF => true,
-            InputElement::GroupG => true,
-            InputElement::GroupH => true,
-            InputElement::BrowserPlugin => true,
-            InputElement::EncoderUp => true,
-            InputElement::EncoderLeft => true,
-            InputElement::EncoderRight => true,
-            InputElement::EncoderDown => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            InputElement::GroupA
+                | InputElement::GroupB
+                | InputElement::GroupC
+                | InputElement::GroupD
+                | InputElement::GroupE
+                | InputElement::GroupF
+                | InputElement::GroupG
+                | InputElement::GroupH
+                | InputElement::BrowserPlugin
+                | InputElement::EncoderUp
+                | InputElement::EncoderLeft
+                | InputElement::EncoderRight
+                | InputElement::EncoderDown
+        )
+    }
+}
+
+/// Writes the element's identifier ("GroupA", "Knob3"), which
+/// [`InputElement::from_name`] and `FromStr` parse back
+impl fmt::Display for InputElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for InputElement {
+    type Err = MK3Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_name(s)
+            .ok_or_else(|| MK3Error::InvalidData(format!("Unknown input element '{}'", s)))
     }
 }
 
+/// Jack detection reports as buttons but isn't pressed by hand
+fn has_button_gestures(element: &InputElement) -> bool {
+    !matches!(
+        element,
+        InputElement::PedalConnected | InputElement::MicrophoneConnected
+    )
+}
+
+/// Lowercase alphanumerics only, so "Group A", "group_a" and "GroupA" match
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
 /// Pad event types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PadEventType {
     Hit,           // 0x1 - Initial pad hit with velocity
     TouchRelease,  // 0x2 - Release from touch-only (no initial hit)
@@ -351,6 +560,53 @@ pub enum PadEventType {
     Aftertouch,    // 0x4 - Pressure/aftertouch data
 }
 
+impl PadEventType {
+    /// Decode the phase nibble (high 4 bits of an entry's second byte)
+    pub fn from_phase_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0x1 => Some(PadEventType::Hit),
+            0x2 => Some(PadEventType::TouchRelease),
+            0x3 => Some(PadEventType::HitRelease),
+            0x4 => Some(PadEventType::Aftertouch),
+            _ => None,
+        }
+    }
+
+    /// The phase nibble this event type is sent with
+    pub fn phase_bits(&self) -> u8 {
+        match self {
+            PadEventType::Hit => 0x1,
+            PadEventType::TouchRelease => 0x2,
+            PadEventType::HitRelease => 0x3,
+            PadEventType::Aftertouch => 0x4,
+        }
+    }
+}
+
+/// Why an input packet was skipped, see `InputEvent::ProtocolWarning`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtocolWarningKind {
+    /// A known packet type, but shorter than its layout
+    ShortPacket { packet_type: u8, expected: usize },
+}
+
+/// Input packet of a type the crate doesn't decode (anything but the 0x01
+/// button and 0x02 pad packets), passed on as it arrived
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawInputPacket {
+    /// First byte of the packet
+    pub packet_type: u8,
+    /// The whole packet, including the type byte
+    pub data: Vec<u8>,
+}
+
+impl RawInputPacket {
+    /// The bytes after the type
+    pub fn payload(&self) -> &[u8] {
+        self.data.get(1..).unwrap_or_default()
+    }
+}
+
 /// Input event types
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputEvent {
@@ -372,19 +628,223 @@ pub enum InputEvent {
         event_type: PadEventType,
         value: u16,  // 12-bit velocity/pressure (0-4095)
     },
+    TouchStripGesture(TouchStripGesture),
+    /// Touch and turn of a knob combined, see [`KnobGesture`]. Reported
+    /// after the knob's `KnobChanged` and touch events.
+    KnobGesture(KnobGesture),
+    /// Touch strip value in the active strip mode (see `StripMode` for ranges)
+    StripChanged {
+        mode: StripMode,
+        value: f32,
+    },
+    /// The connection was reopened after a host suspend or controller reset;
+    /// LEDs and display frames sent through the device have been restored
+    DeviceResumed,
+    /// Continuous pad pressure (0-4095), rate-limited by `PadPressureConfig`.
+    /// A pad's stream ends with a value of 0 when it is released.
+    PadPressure { pad: u8, value: u16 },
+    /// Second press of a button within `ButtonGestureConfig::double_press_window`,
+    /// reported after its `ButtonPressed`
+    ButtonDoublePressed(InputElement),
+    /// Button held for `ButtonGestureConfig::long_press_threshold`, reported
+    /// once per press
+    ButtonLongPressed(InputElement),
+    /// At least `count` input packets were lost, from a failed read or a pad
+    /// release without its hit. Pad and button state may be stale until
+    /// the next full button packet.
+    PacketsDropped { count: u32 },
+    /// The main encoder turned `detents` clicks (positive clockwise),
+    /// reported after its `KnobChanged`. `steps` is the turn after
+    /// acceleration (see `EncoderAccelerationConfig`), for scrolling.
+    EncoderTurned { detents: i32, steps: i32 },
+    /// An input packet of a type the crate doesn't decode, e.g. status
+    /// reports not yet reverse engineered
+    RawInput(RawInputPacket),
+    /// An input packet that couldn't be parsed was skipped; `raw` holds its
+    /// bytes. Only reported after `InputTracker::set_protocol_warnings`,
+    /// e.g. to collect packets from features not yet decoded.
+    ProtocolWarning {
+        kind: ProtocolWarningKind,
+        raw: Vec<u8>,
+    },
+    /// Logical action a button press was remapped to by an `ActionMap`
+    Action(ActionId),
+}
+
+/// Elements reported as button presses and releases
+const BUTTON_ELEMENTS: &[InputElement] = &[
+    InputElement::Play,
+    InputElement::Rec,
+    InputElement::Stop,
+    InputElement::Restart,
+    InputElement::Erase,
+    InputElement::Tap,
+    InputElement::Follow,
+    InputElement::GroupA,
+    InputElement::GroupB,
+    InputElement::GroupC,
+    InputElement::GroupD,
+    InputElement::GroupE,
+    InputElement::GroupF,
+    InputElement::GroupG,
+    InputElement::GroupH,
+    InputElement::Notes,
+    InputElement::Volume,
+    InputElement::Swing,
+    InputElement::Tempo,
+    InputElement::NoteRepeat,
+    InputElement::Lock,
+    InputElement::PadMode,
+    InputElement::Keyboard,
+    InputElement::Chords,
+    InputElement::Step,
+    InputElement::FixedVel,
+    InputElement::Scene,
+    InputElement::Pattern,
+    InputElement::Events,
+    InputElement::Variation,
+    InputElement::Duplicate,
+    InputElement::Select,
+    InputElement::Solo,
+    InputElement::Mute,
+    InputElement::Pitch,
+    InputElement::Mod,
+    InputElement::Perform,
+    InputElement::Shift,
+    InputElement::EncoderPush,
+    InputElement::EncoderUp,
+    InputElement::EncoderDown,
+    InputElement::EncoderLeft,
+    InputElement::EncoderRight,
+    InputElement::DisplayButton1,
+    InputElement::DisplayButton2,
+    InputElement::DisplayButton3,
+    InputElement::DisplayButton4,
+    InputElement::DisplayButton5,
+    InputElement::DisplayButton6,
+    InputElement::DisplayButton7,
+    InputElement::DisplayButton8,
+    InputElement::ChannelMidi,
+    InputElement::Arranger,
+    InputElement::BrowserPlugin,
+    InputElement::ArrowLeft,
+    InputElement::ArrowRight,
+    InputElement::FileSave,
+    InputElement::Settings,
+    InputElement::Macro,
+    InputElement::Plugin,
+    InputElement::Mixer,
+    InputElement::Sampling,
+    InputElement::Auto,
+    InputElement::PedalConnected,
+    InputElement::MicrophoneConnected,
+    InputElement::Knob1Touched,
+    InputElement::Knob2Touched,
+    InputElement::Knob3Touched,
+    InputElement::Knob4Touched,
+    InputElement::Knob5Touched,
+    InputElement::Knob6Touched,
+    InputElement::Knob7Touched,
+    InputElement::Knob8Touched,
+    InputElement::MainKnobTouched,
+];
+
+/// Elements reported as `KnobChanged`
+const KNOB_ELEMENTS: &[InputElement] = &[
+    InputElement::Knob1,
+    InputElement::Knob2,
+    InputElement::Knob3,
+    InputElement::Knob4,
+    InputElement::Knob5,
+    InputElement::Knob6,
+    InputElement::Knob7,
+    InputElement::Knob8,
+    InputElement::MainEncoder,
+];
+
+/// Elements reported as `AudioChanged`
+const AUDIO_ELEMENTS: &[InputElement] = &[
+    InputElement::MicGain,
+    InputElement::HeadphoneVolume,
+    InputElement::MasterVolume,
+];
+
+/// Timing for `ButtonHeld`, `ButtonDoublePressed` and `ButtonLongPressed`
+/// events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonGestureConfig {
+    /// Maximum time between two presses for a double press
+    pub double_press_window: Duration,
+    /// How long a button must be held for a long press
+    pub long_press_threshold: Duration,
+    /// How long a button must be held before `ButtonHeld` repeats with
+    /// every input packet
+    pub hold_threshold: Duration,
+}
+
+impl Default for ButtonGestureConfig {
+    fn default() -> Self {
+        Self {
+            double_press_window: Duration::from_millis(300),
+            long_press_threshold: Duration::from_millis(600),
+            hold_threshold: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Rate and threshold for `InputEvent::PadPressure` streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PadPressureConfig {
+    /// Minimum time between pressure events for one pad
+    pub min_interval: Duration,
+    /// Smallest pressure change worth reporting. Releases are always reported.
+    pub threshold: u16,
+}
+
+impl Default for PadPressureConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(10),
+            threshold: 16,
+        }
+    }
 }
 
 /// Input change tracker for delta detection
 #[derive(Debug, Clone)]
 pub struct InputTracker {
     previous_state: Option<InputState>,
-    held_buttons: HashMap<InputElement, u32>, // frame counter for held buttons
-    frame_count: u32,
+    held_buttons: HashMap<InputElement, Instant>, // when each held button was pressed
     is_first_update: bool,
+    touch_strip_gestures: TouchStripGestureRecognizer,
+    knob_gestures: KnobGestureRecognizer,
+    strip: StripController,
+    pad_pressure: [u16; 16],
+    pressure_config: Option<PadPressureConfig>,
+    // Last value and time sent per pad as InputEvent::PadPressure
+    reported_pressure: [(u16, Option<Instant>); 16],
+    button_gestures: ButtonGestureConfig,
+    pad_filter: PadHitFilter,
+    // Press that may become a double press
+    last_press: HashMap<InputElement, Instant>,
+    // Held buttons that haven't reported a long press yet
+    long_press_pending: HashMap<InputElement, Instant>,
+    // Pads whose hit hasn't been released, before filtering
+    pads_hit: [bool; 16],
+    // Pads seen released since the tracker started. Until then a release
+    // without its hit may be of a pad held before the first packet.
+    pads_seen_released: [bool; 16],
+    // Packets known to be lost that haven't been reported yet
+    lost_packets: u32,
+    // Report the full state with the next button packet
+    sync_requested: bool,
+    encoder_acceleration: EncoderAccelerator,
+    // Report skipped packets as ProtocolWarning events
+    protocol_warnings: bool,
 }
 
 /// Complete input state from Type 0x01 packets (buttons/knobs)
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct InputState {
     pub buttons: ButtonState,
     pub knobs: KnobState,
@@ -393,7 +853,7 @@ pub struct InputState {
 }
 
 /// Individual pad event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PadEvent {
     pub pad_number: u8,      // 0-15
     pub event_type: PadEventType,
@@ -401,15 +861,11 @@ pub struct PadEvent {
 }
 
 impl PadEvent {
-    /// Parse from raw 3-byte data
+    /// Parse from raw 3-byte data, see [`PadState::from_pad_packet`]. Phases
+    /// other than the four known ones are reported as aftertouch.
     pub fn from_raw(pad_number: u8, type_and_high: u8, low_byte: u8) -> Self {
-        let event_type = match type_and_high >> 4 {
-            0x1 => PadEventType::Hit,
-            0x2 => PadEventType::TouchRelease,
-            0x3 => PadEventType::HitRelease,
-            0x4 => PadEventType::Aftertouch,
-            _ => PadEventType::Aftertouch, // Default/unknown
-        };
+        let event_type =
+            PadEventType::from_phase_bits(type_and_high >> 4).unwrap_or(PadEventType::Aftertouch);
         
         // Combine 12-bit value: 4 high bits from type_and_high + 8 bits from low_byte
         let value = ((type_and_high as u16 & 0x0F) << 8) | (low_byte as u16);
@@ -444,7 +900,7 @@ impl PadEvent {
 }
 
 /// Represents pad input from Type 0x02 packets
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct PadState {
     pub events: Vec<PadEvent>,
 }
@@ -789,7 +1245,7 @@ impl InputState {
         knob_touch_pairs
             .into_iter()
             .filter(|(_, touch_element)| self.get_button(touch_element))
-            .map(|(knob_element, _)| (knob_element.clone(), self.get_value(&knob_element)))
+            .map(|(knob_element, _)| (knob_element, self.get_value(&knob_element)))
             .collect()
     }
 
@@ -803,13 +1259,13 @@ impl InputState {
 
         audio_elements
             .into_iter()
-            .map(|element| (element.clone(), self.get_value(&element)))
+            .map(|element| (element, self.get_value(&element)))
             .filter(|(_, value)| *value > 0)
             .collect()
     }
 
     /// Get touch strip data if any finger is active
-    pub fn get_touch_strip_data(&self) -> Option<((u8, u8, u8, u8), (u8, u8, u8, u8))> {
+    pub fn get_touch_strip_data(&self) -> Option<TouchStripRawData> {
         if self.touch_strip.finger_1.data_a > 0 || self.touch_strip.finger_2.data_a > 0 {
             Some((
                 (
@@ -836,15 +1292,162 @@ impl InputTracker {
         Self {
             previous_state: None,
             held_buttons: HashMap::new(),
-            frame_count: 0,
             is_first_update: true,
+            touch_strip_gestures: TouchStripGestureRecognizer::default(),
+            knob_gestures: KnobGestureRecognizer::default(),
+            strip: StripController::default(),
+            pad_pressure: [0; 16],
+            pressure_config: None,
+            reported_pressure: [(0, None); 16],
+            button_gestures: ButtonGestureConfig::default(),
+            pad_filter: PadHitFilter::default(),
+            last_press: HashMap::new(),
+            long_press_pending: HashMap::new(),
+            pads_hit: [false; 16],
+            pads_seen_released: [false; 16],
+            lost_packets: 0,
+            sync_requested: false,
+            encoder_acceleration: EncoderAccelerator::default(),
+            protocol_warnings: false,
+        }
+    }
+
+    /// Report the complete input state with the next button packet, e.g.
+    /// after a reconnect, a `PacketsDropped` event, or when an app starts
+    /// while buttons are already held.
+    ///
+    /// Every held button is reported as `ButtonPressed`, every pad still down
+    /// from a hit as a `Hit` with its current pressure, and every knob and
+    /// audio control as `KnobChanged`/`AudioChanged` with a delta of 0.
+    /// Anything not reported is released. The snapshot doesn't trigger
+    /// double or long presses.
+    pub fn request_state_sync(&mut self) {
+        self.sync_requested = true;
+    }
+
+    /// Add events for everything in the snapshot `events` doesn't already
+    /// report
+    fn report_state(&self, events: &mut Vec<InputEvent>, current: &InputState) {
+        let mut snapshot = Vec::new();
+
+        for element in BUTTON_ELEMENTS {
+            let reported = events.contains(&InputEvent::ButtonPressed(*element));
+            if current.get_button(element) && !reported {
+                snapshot.push(InputEvent::ButtonPressed(*element));
+            }
+        }
+
+        let value_reported = |element: &InputElement| {
+            events.iter().any(|event| match event {
+                InputEvent::KnobChanged { element: e, .. }
+                | InputEvent::AudioChanged { element: e, .. } => e == element,
+                _ => false,
+            })
+        };
+        for element in KNOB_ELEMENTS.iter().filter(|e| !value_reported(e)) {
+            snapshot.push(InputEvent::KnobChanged {
+                element: *element,
+                value: current.get_value(element),
+                delta: 0,
+            });
+        }
+        for element in AUDIO_ELEMENTS.iter().filter(|e| !value_reported(e)) {
+            snapshot.push(InputEvent::AudioChanged {
+                element: *element,
+                value: current.get_value(element),
+                delta: 0,
+            });
+        }
+
+        for (pad, (&hit, &pressure)) in self.pads_hit.iter().zip(&self.pad_pressure).enumerate() {
+            if hit && pressure > 0 {
+                snapshot.push(InputEvent::PadEvent {
+                    pad_number: pad as u8,
+                    event_type: PadEventType::Hit,
+                    value: pressure,
+                });
+            }
+        }
+
+        events.extend(snapshot);
+    }
+
+    /// Record input packets lost before they reached the tracker, e.g. by a
+    /// failed read. They are reported as `InputEvent::PacketsDropped` with
+    /// the next update.
+    pub fn note_lost_packets(&mut self, count: u32) {
+        self.lost_packets = self.lost_packets.saturating_add(count);
+    }
+
+    /// Report packets that couldn't be parsed as `ProtocolWarning` events
+    /// instead of skipping them silently
+    pub fn set_protocol_warnings(&mut self, enabled: bool) {
+        self.protocol_warnings = enabled;
+    }
+
+    pub fn protocol_warnings(&self) -> bool {
+        self.protocol_warnings
+    }
+
+    /// Event for a skipped packet, if protocol warnings are enabled
+    pub fn protocol_warning(&self, kind: ProtocolWarningKind, raw: &[u8]) -> Option<InputEvent> {
+        self.protocol_warnings.then(|| InputEvent::ProtocolWarning {
+            kind,
+            raw: raw.to_vec(),
+        })
+    }
+
+    fn report_lost_packets(&mut self, events: &mut Vec<InputEvent>) {
+        let count = std::mem::take(&mut self.lost_packets);
+        if count > 0 {
+            events.push(InputEvent::PacketsDropped { count });
         }
     }
 
+    /// Touch strip mode translation state
+    pub fn strip_controller(&self) -> &StripController {
+        &self.strip
+    }
+
+    pub fn strip_controller_mut(&mut self) -> &mut StripController {
+        &mut self.strip
+    }
+
+    /// Touch strip state of the last button packet
+    pub fn touch_strip(&self) -> TouchStripState {
+        self.previous_state
+            .map(|state| state.touch_strip)
+            .unwrap_or_default()
+    }
+
+    /// Configure the thresholds used for touch strip gesture recognition
+    pub fn set_touch_strip_gesture_config(&mut self, config: TouchStripGestureConfig) {
+        self.touch_strip_gestures.set_config(config);
+    }
+
+    /// Configure the thresholds used for knob gesture recognition
+    pub fn set_knob_gesture_config(&mut self, config: KnobGestureConfig) {
+        self.knob_gestures.set_config(config);
+    }
+
+    pub fn knob_gesture_config(&self) -> KnobGestureConfig {
+        self.knob_gestures.config()
+    }
+
+    /// Configure the timing of held, double-press and long-press events
+    pub fn set_button_gesture_config(&mut self, config: ButtonGestureConfig) {
+        self.button_gestures = config;
+    }
+
+    pub fn button_gesture_config(&self) -> ButtonGestureConfig {
+        self.button_gestures
+    }
+
     /// Update the tracker with a new input state and return all events
     pub fn update(&mut self, current_state: InputState) -> Vec<InputEvent> {
         let mut events = Vec::new();
-        self.frame_count += 1;
+        self.report_lost_packets(&mut events);
+        let now = Instant::now();
 
         let prev_state = self.previous_state.take().unwrap_or_default();
 
@@ -854,12 +1457,39 @@ impl InputTracker {
             &prev_state,
             &current_state,
             &mut self.held_buttons,
-            self.frame_count,
+            self.button_gestures.hold_threshold,
+            now,
         );
+        self.check_button_gestures(&mut events, now);
 
         // Check knob/value events - but skip on first update to avoid spurious events from initial hardware state
         if !self.is_first_update {
             Self::check_value_events_static(&mut events, &prev_state, &current_state);
+            self.check_encoder_turns(&mut events, now);
+        }
+
+        events.extend(
+            self.touch_strip_gestures
+                .update(&current_state.touch_strip, now)
+                .into_iter()
+                .map(InputEvent::TouchStripGesture),
+        );
+        events.extend(
+            self.knob_gestures
+                .update(&current_state, now)
+                .into_iter()
+                .map(InputEvent::KnobGesture),
+        );
+
+        if let Some(value) = self.strip.update(&current_state.touch_strip) {
+            events.push(InputEvent::StripChanged {
+                mode: self.strip.mode(),
+                value,
+            });
+        }
+
+        if std::mem::take(&mut self.sync_requested) {
+            self.report_state(&mut events, &current_state);
         }
 
         self.previous_state = Some(current_state);
@@ -867,21 +1497,143 @@ impl InputTracker {
         events
     }
 
+    /// Configure how turn speed scales `EncoderTurned` steps
+    pub fn set_encoder_acceleration(&mut self, config: EncoderAccelerationConfig) {
+        self.encoder_acceleration.set_config(config);
+    }
+
+    pub fn encoder_acceleration(&self) -> EncoderAccelerationConfig {
+        *self.encoder_acceleration.config()
+    }
+
+    /// Configure pad debouncing, crosstalk suppression and per-pad thresholds
+    pub fn set_pad_filter_config(&mut self, config: PadFilterConfig) {
+        self.pad_filter.set_config(config);
+    }
+
+    /// Emit `InputEvent::PadPressure` streams with this rate limiting, or
+    /// stop with `None` (the default)
+    pub fn set_pad_pressure_config(&mut self, config: Option<PadPressureConfig>) {
+        self.pressure_config = config;
+    }
+
+    /// Latest pressure of a pad (0-4095), 0 when released. Hits count as
+    /// the initial pressure.
+    pub fn pad_pressure(&self, pad_number: u8) -> u16 {
+        self.pad_pressure
+            .get(pad_number as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
     /// Update the tracker with pad events and return them as InputEvents
     pub fn update_pads(&mut self, pad_state: PadState) -> Vec<InputEvent> {
-        pad_state
-            .events
-            .into_iter()
-            .map(|event| InputEvent::PadEvent {
+        let now = Instant::now();
+
+        // A release without its hit means the packet carrying the hit was
+        // lost, once the pad is known to have been released before
+        let mut in_packet = [false; 16];
+        for event in &pad_state.events {
+            let pad = event.pad_number as usize;
+            let (Some(hit), Some(seen_released)) = (
+                self.pads_hit.get_mut(pad),
+                self.pads_seen_released.get_mut(pad),
+            ) else {
+                continue;
+            };
+            in_packet[pad] = true;
+            match event.event_type {
+                PadEventType::Hit => *hit = true,
+                PadEventType::HitRelease | PadEventType::TouchRelease => {
+                    if event.event_type == PadEventType::HitRelease && !*hit && *seen_released {
+                        self.lost_packets = self.lost_packets.saturating_add(1);
+                    }
+                    *hit = false;
+                    *seen_released = true;
+                }
+                PadEventType::Aftertouch => {}
+            }
+        }
+        // Pads without an entry aren't held
+        for (seen_released, in_packet) in self.pads_seen_released.iter_mut().zip(in_packet) {
+            *seen_released |= !in_packet;
+        }
+
+        let mut events = Vec::new();
+        self.report_lost_packets(&mut events);
+        for event in self.pad_filter.filter(pad_state.events, now) {
+            if let Some(pressure) = self.pad_pressure.get_mut(event.pad_number as usize) {
+                *pressure = match event.event_type {
+                    PadEventType::Hit | PadEventType::Aftertouch => event.value,
+                    PadEventType::HitRelease | PadEventType::TouchRelease => 0,
+                };
+            }
+            events.push(InputEvent::PadEvent {
                 pad_number: event.pad_number,
                 event_type: event.event_type,
                 value: event.value,
-            })
-            .collect()
+            });
+        }
+
+        if let Some(config) = self.pressure_config {
+            self.pressure_events(config, now, &mut events);
+        }
+        events
+    }
+
+    /// Report pressure that moved past the threshold, at most once per
+    /// `min_interval` per pad. Changes held back by the interval go out with
+    /// a later packet, since pads stream while pressed.
+    fn pressure_events(
+        &mut self,
+        config: PadPressureConfig,
+        now: Instant,
+        events: &mut Vec<InputEvent>,
+    ) {
+        for (pad, &value) in self.pad_pressure.iter().enumerate() {
+            let (reported, reported_at) = &mut self.reported_pressure[pad];
+            if value == *reported {
+                continue;
+            }
+
+            let released = value == 0;
+            let due = reported_at.is_none_or(|at| now.duration_since(at) >= config.min_interval);
+            if !released && (value.abs_diff(*reported) < config.threshold || !due) {
+                continue;
+            }
+
+            *reported = value;
+            *reported_at = Some(now);
+            events.push(InputEvent::PadPressure {
+                pad: pad as u8,
+                value,
+            });
+        }
     }
 }
 
 impl InputEvent {
+    /// Pad of a `PadEvent` or `PadPressure` event
+    pub fn pad(&self) -> Option<PadIndex> {
+        match self {
+            InputEvent::PadEvent { pad_number, .. } => PadIndex::new(*pad_number),
+            InputEvent::PadPressure { pad, .. } => PadIndex::new(*pad),
+            _ => None,
+        }
+    }
+
+    /// Normalized reading of an `AudioChanged` event
+    pub fn audio_value(&self) -> Option<AudioValue> {
+        match self {
+            InputEvent::AudioChanged {
+                element,
+                value,
+                delta,
+            } => Some(AudioValue::from_raw(*element, *value, *delta)),
+            _ => None,
+        }
+    }
+
     /// Get a human-readable description of this input event
     pub fn description(&self) -> String {
         match self {
@@ -920,6 +1672,53 @@ impl InputEvent {
                     event_str
                 )
             }
+            InputEvent::TouchStripGesture(gesture) => {
+                format!("Touch strip {}", gesture.description())
+            }
+            InputEvent::KnobGesture(gesture) => gesture.description(),
+            InputEvent::Action(ActionId(id)) => format!("Action {}", id),
+            InputEvent::StripChanged { mode, value } => {
+                format!("Touch strip {:?} → {:.3}", mode, value)
+            }
+            InputEvent::DeviceResumed => "Device resumed".to_string(),
+            InputEvent::PadPressure { pad, value } => {
+                format!("Pad {} pressure → {}", pad + 1, value)
+            }
+            InputEvent::ButtonDoublePressed(element) => {
+                format!("{} double-pressed", element.name())
+            }
+            InputEvent::ButtonLongPressed(element) => format!("{} long-pressed", element.name()),
+            InputEvent::PacketsDropped { count } => format!("{} input packet(s) dropped", count),
+            InputEvent::EncoderTurned { detents, steps } => {
+                format!("Main Encoder turned {:+} ({:+} steps)", detents, steps)
+            }
+            InputEvent::ProtocolWarning { kind, raw } => {
+                let bytes: Vec<String> = raw.iter().map(|byte| format!("{:02X}", byte)).collect();
+                match kind {
+                    ProtocolWarningKind::ShortPacket {
+                        packet_type,
+                        expected,
+                    } => format!(
+                        "Short 0x{:02X} packet ({} of {} bytes): {}",
+                        packet_type,
+                        raw.len(),
+                        expected,
+                        bytes.join(" ")
+                    ),
+                }
+            }
+            InputEvent::RawInput(packet) => {
+                let bytes: Vec<String> = packet
+                    .payload()
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect();
+                format!(
+                    "Packet type 0x{:02X}: {}",
+                    packet.packet_type,
+                    bytes.join(" ")
+                )
+            }
         }
     }
 
@@ -953,104 +1752,27 @@ impl InputTracker {
         events: &mut Vec<InputEvent>,
         prev: &InputState,
         current: &InputState,
-        held_buttons: &mut HashMap<InputElement, u32>,
-        frame_count: u32,
+        held_buttons: &mut HashMap<InputElement, Instant>,
+        hold_threshold: Duration,
+        now: Instant,
     ) {
-        let button_elements = [
-            InputElement::Play,
-            InputElement::Rec,
-            InputElement::Stop,
-            InputElement::Restart,
-            InputElement::Erase,
-            InputElement::Tap,
-            InputElement::Follow,
-            InputElement::GroupA,
-            InputElement::GroupB,
-            InputElement::GroupC,
-            InputElement::GroupD,
-            InputElement::GroupE,
-            InputElement::GroupF,
-            InputElement::GroupG,
-            InputElement::GroupH,
-            InputElement::Notes,
-            InputElement::Volume,
-            InputElement::Swing,
-            InputElement::Tempo,
-            InputElement::NoteRepeat,
-            InputElement::Lock,
-            InputElement::PadMode,
-            InputElement::Keyboard,
-            InputElement::Chords,
-            InputElement::Step,
-            InputElement::FixedVel,
-            InputElement::Scene,
-            InputElement::Pattern,
-            InputElement::Events,
-            InputElement::Variation,
-            InputElement::Duplicate,
-            InputElement::Select,
-            InputElement::Solo,
-            InputElement::Mute,
-            InputElement::Pitch,
-            InputElement::Mod,
-            InputElement::Perform,
-            InputElement::Shift,
-            InputElement::EncoderPush,
-            InputElement::EncoderUp,
-            InputElement::EncoderDown,
-            InputElement::EncoderLeft,
-            InputElement::EncoderRight,
-            InputElement::DisplayButton1,
-            InputElement::DisplayButton2,
-            InputElement::DisplayButton3,
-            InputElement::DisplayButton4,
-            InputElement::DisplayButton5,
-            InputElement::DisplayButton6,
-            InputElement::DisplayButton7,
-            InputElement::DisplayButton8,
-            InputElement::ChannelMidi,
-            InputElement::Arranger,
-            InputElement::BrowserPlugin,
-            InputElement::ArrowLeft,
-            InputElement::ArrowRight,
-            InputElement::FileSave,
-            InputElement::Settings,
-            InputElement::Macro,
-            InputElement::Plugin,
-            InputElement::Mixer,
-            InputElement::Sampling,
-            InputElement::Auto,
-            InputElement::PedalConnected,
-            InputElement::MicrophoneConnected,
-            InputElement::Knob1Touched,
-            InputElement::Knob2Touched,
-            InputElement::Knob3Touched,
-            InputElement::Knob4Touched,
-            InputElement::Knob5Touched,
-            InputElement::Knob6Touched,
-            InputElement::Knob7Touched,
-            InputElement::Knob8Touched,
-            InputElement::MainKnobTouched,
-        ];
-
-        for element in &button_elements {
+        for element in BUTTON_ELEMENTS {
             let prev_pressed = prev.get_button(element);
             let current_pressed = current.get_button(element);
 
             match (prev_pressed, current_pressed) {
                 (false, true) => {
-                    events.push(InputEvent::ButtonPressed(element.clone()));
-                    held_buttons.insert(element.clone(), frame_count);
+                    events.push(InputEvent::ButtonPressed(*element));
+                    held_buttons.insert(*element, now);
                 }
                 (true, false) => {
-                    events.push(InputEvent::ButtonReleased(element.clone()));
+                    events.push(InputEvent::ButtonReleased(*element));
                     held_buttons.remove(element);
                 }
                 (true, true) => {
                     if let Some(held_since) = held_buttons.get(element) {
-                        if frame_count - held_since > 30 {
-                            // ~0.5 seconds at 60fps
-                            events.push(InputEvent::ButtonHeld(element.clone()));
+                        if now.duration_since(*held_since) > hold_threshold {
+                            events.push(InputEvent::ButtonHeld(*element));
                         }
                     }
                 }
@@ -1059,51 +1781,95 @@ impl InputTracker {
         }
     }
 
+    /// Turn the presses and releases in `events` into double and long presses
+    fn check_button_gestures(&mut self, events: &mut Vec<InputEvent>, now: Instant) {
+        let config = self.button_gestures;
+        let mut gestures = Vec::new();
+
+        for event in events.iter() {
+            match event {
+                InputEvent::ButtonPressed(element) if has_button_gestures(element) => {
+                    let double = self
+                        .last_press
+                        .remove(element)
+                        .is_some_and(|at| now.duration_since(at) <= config.double_press_window);
+                    if double {
+                        gestures.push(InputEvent::ButtonDoublePressed(*element));
+                    } else {
+                        self.last_press.insert(*element, now);
+                    }
+                    self.long_press_pending.insert(*element, now);
+                }
+                InputEvent::ButtonReleased(element) => {
+                    // Packets may not arrive while a button is held still, so
+                    // a long press can first be noticed on release
+                    if let Some(since) = self.long_press_pending.remove(element) {
+                        if now.duration_since(since) >= config.long_press_threshold {
+                            gestures.push(InputEvent::ButtonLongPressed(*element));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.long_press_pending.retain(|element, since| {
+            let long = now.duration_since(*since) >= config.long_press_threshold;
+            if long {
+                gestures.push(InputEvent::ButtonLongPressed(*element));
+            }
+            !long
+        });
+        self.last_press
+            .retain(|_, at| now.duration_since(*at) <= config.double_press_window);
+
+        events.extend(gestures);
+    }
+
+    /// Follow each main encoder `KnobChanged` with an `EncoderTurned`
+    fn check_encoder_turns(&mut self, events: &mut Vec<InputEvent>, now: Instant) {
+        let delta = events.iter().find_map(|event| match event {
+            InputEvent::KnobChanged {
+                element: InputElement::MainEncoder,
+                delta,
+                ..
+            } => Some(*delta),
+            _ => None,
+        });
+        let Some(detents) = delta.map(encoder_detents).filter(|&detents| detents != 0) else {
+            return;
+        };
+        let steps = self.encoder_acceleration.update(detents, now);
+        events.push(InputEvent::EncoderTurned { detents, steps });
+    }
+
     fn check_value_events_static(
         events: &mut Vec<InputEvent>,
         prev: &InputState,
         current: &InputState,
     ) {
-        let knob_elements = [
-            InputElement::Knob1,
-            InputElement::Knob2,
-            InputElement::Knob3,
-            InputElement::Knob4,
-            InputElement::Knob5,
-            InputElement::Knob6,
-            InputElement::Knob7,
-            InputElement::Knob8,
-            InputElement::MainEncoder,
-        ];
-
-        let audio_elements = [
-            InputElement::MicGain,
-            InputElement::HeadphoneVolume,
-            InputElement::MasterVolume,
-        ];
-
-        for element in &knob_elements {
+        for element in KNOB_ELEMENTS {
             let prev_value = prev.get_value(element);
             let current_value = current.get_value(element);
 
             if prev_value != current_value {
                 let delta = current_value as i32 - prev_value as i32;
                 events.push(InputEvent::KnobChanged {
-                    element: element.clone(),
+                    element: *element,
                     value: current_value,
                     delta,
                 });
             }
         }
 
-        for element in &audio_elements {
+        for element in AUDIO_ELEMENTS {
             let prev_value = prev.get_value(element);
             let current_value = current.get_value(element);
 
             if prev_value != current_value {
                 let delta = current_value as i32 - prev_value as i32;
                 events.push(InputEvent::AudioChanged {
-                    element: element.clone(),
+                    element: *element,
                     value: current_value,
                     delta,
                 });
@@ -1142,7 +1908,20 @@ impl Default for InputTracker {
 }
 
 impl PadState {
-    /// Parse a Type 0x02 packet (up to 64 bytes) into pad hits
+    /// Parse a Type 0x02 packet (up to 64 bytes) into pad hits.
+    ///
+    /// After the report ID, the packet holds up to 21 three-byte entries:
+    ///
+    /// | Byte | Bits | Meaning |
+    /// |------|------|---------|
+    /// | 0    | 0-7  | Pad number (0-15); anything larger ends the list |
+    /// | 1    | 4-7  | Phase, see [`PadEventType::from_phase_bits`] |
+    /// | 1    | 0-3  | High 4 bits of the value |
+    /// | 2    | 0-7  | Low 8 bits of the value |
+    ///
+    /// The 12-bit value (0-4095) is the velocity for hits and the pressure
+    /// for aftertouch, see [`PadEvent::velocity`] and [`PadEvent::pressure`].
+    /// Unused entries are all zeros.
     pub fn from_pad_packet(data: &[u8]) -> Result<Self> {
         if data.is_empty() || data[0] != 0x02 {
             return Err(MK3Error::InvalidPacket);
@@ -1163,12 +1942,6 @@ impl PadState {
                 continue;
             }
 
-            // Debug output (commented out for production)
-            // println!(
-            //     "Pad Event: {:08b}, {:08b}, {:08b}",
-            //     pad_number, type_and_high, low_byte
-            // );
-
             // Check if this is a valid pad event (pad numbers 0-15)
             if pad_number <= 15 {
                 events.push(PadEvent::from_raw(pad_number, type_and_high, low_byte));
@@ -0,0 +1,158 @@
+/// Largest raw knob value (10-bit)
+const KNOB_MAX_RAW: u16 = 1023;
+
+/// How close (normalized) the knob must come to the parameter value to pick it up
+const PICKUP_TOLERANCE: f32 = 0.02;
+
+/// Curve used to map knob travel onto the output range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KnobTaper {
+    #[default]
+    Linear,
+    /// Equal knob travel gives equal ratios (e.g. 20Hz-20kHz). Needs a range
+    /// with `min` and `max` both above zero; otherwise behaves like `Linear`.
+    Logarithmic,
+}
+
+/// Maps raw knob values onto a parameter range, with optional soft takeover.
+///
+/// With soft takeover enabled, after [`set_value`](Self::set_value) (e.g. when
+/// switching parameter pages) the knob is ignored until it reaches or passes
+/// the parameter's current value, so the parameter doesn't jump to wherever
+/// the knob happens to be.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{InputElement, InputEvent, KnobMapper, KnobTaper, MaschineMK3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut cutoff = KnobMapper::new(20.0, 20000.0);
+/// cutoff.set_taper(KnobTaper::Logarithmic);
+/// cutoff.set_soft_takeover(true);
+/// cutoff.set_value(1000.0);
+///
+/// for event in device.poll_input_events()? {
+///     if let InputEvent::KnobChanged { element: InputElement::Knob1, value, .. } = event {
+///         if let Some(hz) = cutoff.update(value) {
+///             println!("Cutoff: {:.0} Hz", hz);
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct KnobMapper {
+    min: f32,
+    max: f32,
+    taper: KnobTaper,
+    soft_takeover: bool,
+    value: f32,
+    picked_up: bool,
+    last_position: Option<f32>,
+}
+
+impl KnobMapper {
+    /// Map the knob linearly onto `min..=max`, without soft takeover
+    pub fn new(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            taper: KnobTaper::Linear,
+            soft_takeover: false,
+            value: min,
+            picked_up: true,
+            last_position: None,
+        }
+    }
+
+    pub fn set_range(&mut self, min: f32, max: f32) {
+        self.min = min;
+        self.max = max;
+    }
+
+    pub fn set_taper(&mut self, taper: KnobTaper) {
+        self.taper = taper;
+    }
+
+    pub fn set_soft_takeover(&mut self, enabled: bool) {
+        self.soft_takeover = enabled;
+        if !enabled {
+            self.picked_up = true;
+        }
+    }
+
+    /// Current parameter value
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Whether the knob currently controls the value (always true without
+    /// soft takeover)
+    pub fn is_picked_up(&self) -> bool {
+        self.picked_up
+    }
+
+    /// Set the parameter value from elsewhere (preset load, page switch, UI).
+    /// With soft takeover the knob must catch up to this value before it
+    /// takes effect again.
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(self.min.min(self.max), self.min.max(self.max));
+        self.picked_up = !self.soft_takeover;
+    }
+
+    /// Map a raw 10-bit knob value onto the range, ignoring soft takeover
+    pub fn map_raw(&self, raw: u16) -> f32 {
+        let position = raw.min(KNOB_MAX_RAW) as f32 / KNOB_MAX_RAW as f32;
+        self.value_at(position)
+    }
+
+    /// Feed a raw knob value. Returns the new parameter value, or `None` while
+    /// soft takeover is waiting for the knob to reach the current value.
+    pub fn update(&mut self, raw: u16) -> Option<f32> {
+        let position = raw.min(KNOB_MAX_RAW) as f32 / KNOB_MAX_RAW as f32;
+        let previous = self.last_position.replace(position);
+
+        if !self.picked_up {
+            let target = self.position_of(self.value);
+            let reached = (position - target).abs() <= PICKUP_TOLERANCE;
+            let crossed = previous.is_some_and(|previous| {
+                (previous - target).signum() != (position - target).signum()
+            });
+
+            if !reached && !crossed {
+                return None;
+            }
+            self.picked_up = true;
+        }
+
+        self.value = self.value_at(position);
+        Some(self.value)
+    }
+
+    fn uses_log_taper(&self) -> bool {
+        self.taper == KnobTaper::Logarithmic && self.min > 0.0 && self.max > 0.0
+    }
+
+    fn value_at(&self, position: f32) -> f32 {
+        if self.uses_log_taper() {
+            self.min * (self.max / self.min).powf(position)
+        } else {
+            self.min + (self.max - self.min) * position
+        }
+    }
+
+    fn position_of(&self, value: f32) -> f32 {
+        if self.min == self.max {
+            return 0.0;
+        }
+        let position = if self.uses_log_taper() {
+            (value / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (value - self.min) / (self.max - self.min)
+        };
+        position.clamp(0.0, 1.0)
+    }
+}
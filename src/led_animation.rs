@@ -0,0 +1,142 @@
+use crate::output::{MaschineLEDColor, PadLedState};
+use std::time::{Duration, Instant};
+
+/// Hues of the palette in color-wheel order, red to pink
+const RAINBOW_HUES: u8 = 13;
+
+/// Pattern of an [`LedAnimation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedAnimationKind {
+    /// All LEDs fade up and down together in `color`
+    Pulse,
+    /// The palette hues scroll across the LEDs
+    Rainbow,
+    /// A single LED in `color` runs across the LEDs, with a dim tail
+    Chase,
+}
+
+/// LEDs an [`LedAnimation`] draws on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedTarget {
+    /// The 16 pads, in pad order
+    Pads,
+    /// The 25 touch strip LEDs, left to right
+    TouchStrip,
+    /// Pads and touch strip, each animated on its own
+    All,
+}
+
+impl LedTarget {
+    fn pads(&self) -> bool {
+        matches!(self, Self::Pads | Self::All)
+    }
+
+    fn touch_strip(&self) -> bool {
+        matches!(self, Self::TouchStrip | Self::All)
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        (self.pads() && other.pads()) || (self.touch_strip() && other.touch_strip())
+    }
+}
+
+/// Built-in LED animation, see
+/// [`MaschineMK3::start_led_animation`](crate::MaschineMK3::start_led_animation)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LedAnimation {
+    pub kind: LedAnimationKind,
+    pub target: LedTarget,
+    /// Color of `Pulse` and `Chase`; `Rainbow` ignores it
+    pub color: MaschineLEDColor,
+    /// Length of one cycle
+    pub period: Duration,
+}
+
+impl Default for LedAnimation {
+    fn default() -> Self {
+        Self {
+            kind: LedAnimationKind::Pulse,
+            target: LedTarget::Pads,
+            color: MaschineLEDColor::white(true),
+            period: Duration::from_secs(2),
+        }
+    }
+}
+
+impl LedAnimation {
+    pub fn new(kind: LedAnimationKind, target: LedTarget) -> Self {
+        Self {
+            kind,
+            target,
+            ..Self::default()
+        }
+    }
+
+    /// Draw the animation `elapsed` into its run onto `leds`
+    pub fn render(&self, elapsed: Duration, leds: &mut PadLedState) {
+        let period = self.period.as_secs_f32().max(f32::EPSILON);
+        let phase = (elapsed.as_secs_f32() / period).fract();
+        if self.target.pads() {
+            self.render_row(phase, &mut leds.pad_leds);
+        }
+        if self.target.touch_strip() {
+            self.render_row(phase, &mut leds.touch_strip_leds);
+        }
+    }
+
+    fn render_row(&self, phase: f32, row: &mut [MaschineLEDColor]) {
+        let len = row.len();
+        match self.kind {
+            LedAnimationKind::Pulse => {
+                let level = 0.5 - 0.5 * (phase * std::f32::consts::TAU).cos();
+                row.fill(self.color.scaled(level));
+            }
+            LedAnimationKind::Rainbow => {
+                for (index, led) in row.iter_mut().enumerate() {
+                    let position = (index as f32 / len as f32 + phase).fract();
+                    let hue = (position * RAINBOW_HUES as f32) as u8 % RAINBOW_HUES;
+                    *led = MaschineLEDColor::new(hue, true);
+                }
+            }
+            LedAnimationKind::Chase => {
+                let head = ((phase * len as f32) as usize).min(len - 1);
+                let tail = (head + len - 1) % len;
+                row.fill(MaschineLEDColor::black());
+                row[tail] = self.color.scaled(0.25);
+                row[head] = self.color;
+            }
+        }
+    }
+}
+
+/// Animations in progress, at most one per target LED
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LedAnimator {
+    running: Vec<(LedAnimation, Instant)>,
+}
+
+impl LedAnimator {
+    /// Start an animation, replacing any running on the same LEDs
+    pub(crate) fn start(&mut self, animation: LedAnimation, now: Instant) {
+        self.running
+            .retain(|(running, _)| !running.target.overlaps(&animation.target));
+        self.running.push((animation, now));
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.running.clear();
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// Draw the running animations onto `leds`. Returns whether they changed.
+    pub(crate) fn update(&self, leds: &mut PadLedState, now: Instant) -> bool {
+        let before = (leds.pad_leds, leds.touch_strip_leds);
+        for (animation, started) in &self.running {
+            animation.render(now.duration_since(*started), leds);
+        }
+        before != (leds.pad_leds, leds.touch_strip_leds)
+    }
+}
@@ -0,0 +1,278 @@
+use crate::input::InputElement;
+use crate::output::{LedBrightness, LedFrame, MaschineLEDColor};
+
+/// Brightness a single-color button shows for a dim color
+const DIM_BRIGHTNESS: LedBrightness = 63;
+
+/// What to set every LED of an [`LedGroup`] to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedValue {
+    /// Palette color; single-color buttons show it as full, half or no
+    /// brightness
+    Color(MaschineLEDColor),
+    /// Brightness (0-127); RGB LEDs show it as a gray
+    Brightness(LedBrightness),
+}
+
+impl LedValue {
+    /// Color for RGB LEDs and brightness for single-color LEDs
+    pub(crate) fn parts(self) -> (MaschineLEDColor, LedBrightness) {
+        match self {
+            LedValue::Color(color) => (color, color_brightness(color)),
+            LedValue::Brightness(brightness) => {
+                (MaschineLEDColor::from_brightness(brightness), brightness)
+            }
+        }
+    }
+}
+
+impl From<MaschineLEDColor> for LedValue {
+    fn from(color: MaschineLEDColor) -> Self {
+        Self::Color(color)
+    }
+}
+
+impl From<LedBrightness> for LedValue {
+    fn from(brightness: LedBrightness) -> Self {
+        Self::Brightness(brightness)
+    }
+}
+
+/// Named set of button, pad and touch strip LEDs that are lit together,
+/// e.g. a mode's buttons.
+///
+/// Built-in groups follow the controller's layout and can be looked up by
+/// name with [`builtin`](Self::builtin); apps build their own with
+/// [`new`](Self::new) and the `with_*` methods.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{InputElement, LedGroup, MaschineLEDColor, MaschineMK3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// device.set_group(&LedGroup::transport(), 0)?;
+/// device.set_group(&LedGroup::pad_row(0), MaschineLEDColor::blue(true))?;
+///
+/// let edit_mode = LedGroup::new("edit mode")
+///     .with_buttons([InputElement::Select, InputElement::Duplicate])
+///     .with_pads(12..16);
+/// device.set_group(&edit_mode, MaschineLEDColor::orange(true))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedGroup {
+    name: String,
+    buttons: Vec<InputElement>,
+    pads: Vec<u8>,
+    touch_strip: Vec<u8>,
+}
+
+impl LedGroup {
+    /// Names accepted by [`builtin`](Self::builtin)
+    pub const BUILTIN_NAMES: &'static [&'static str] = &[
+        "transport",
+        "mode row",
+        "groups",
+        "display buttons",
+        "pad modes",
+        "pad functions",
+        "encoder",
+        "pads",
+        "pads row 1",
+        "pads row 2",
+        "pads row 3",
+        "pads row 4",
+        "touch strip",
+    ];
+
+    /// Empty group
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            buttons: Vec::new(),
+            pads: Vec::new(),
+            touch_strip: Vec::new(),
+        }
+    }
+
+    /// Add button LEDs; buttons without an LED are ignored when lit
+    pub fn with_buttons(mut self, buttons: impl IntoIterator<Item = InputElement>) -> Self {
+        for button in buttons {
+            if !self.buttons.contains(&button) {
+                self.buttons.push(button);
+            }
+        }
+        self
+    }
+
+    /// Add pads (0-15)
+    pub fn with_pads(mut self, pads: impl IntoIterator<Item = u8>) -> Self {
+        extend_unique(&mut self.pads, pads);
+        self
+    }
+
+    /// Add touch strip LEDs (0-24, left to right)
+    pub fn with_touch_strip(mut self, leds: impl IntoIterator<Item = u8>) -> Self {
+        extend_unique(&mut self.touch_strip, leds);
+        self
+    }
+
+    /// Add every LED of another group
+    pub fn with_group(self, other: &LedGroup) -> Self {
+        self.with_buttons(other.buttons.iter().copied())
+            .with_pads(other.pads.iter().copied())
+            .with_touch_strip(other.touch_strip.iter().copied())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn buttons(&self) -> &[InputElement] {
+        &self.buttons
+    }
+
+    pub fn pads(&self) -> &[u8] {
+        &self.pads
+    }
+
+    pub fn touch_strip(&self) -> &[u8] {
+        &self.touch_strip
+    }
+
+    /// Built-in group by name (see [`BUILTIN_NAMES`](Self::BUILTIN_NAMES)),
+    /// ignoring case
+    pub fn builtin(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if let Some(row) = name.strip_prefix("pads row ") {
+            return match row.parse::<u8>() {
+                Ok(row @ 1..=4) => Some(Self::pad_row(row - 1)),
+                _ => None,
+            };
+        }
+        Some(match name.as_str() {
+            "transport" => Self::transport(),
+            "mode row" => Self::mode_row(),
+            "groups" => Self::groups(),
+            "display buttons" => Self::display_buttons(),
+            "pad modes" => Self::pad_modes(),
+            "pad functions" => Self::pad_functions(),
+            "encoder" => Self::encoder(),
+            "pads" => Self::all_pads(),
+            "touch strip" => Self::new("touch strip").with_touch_strip(0..25),
+            _ => return None,
+        })
+    }
+
+    /// Restart, Erase, Tap, Follow, Play, Rec and Stop
+    pub fn transport() -> Self {
+        use InputElement::*;
+        Self::new("transport").with_buttons([Restart, Erase, Tap, Follow, Play, Rec, Stop])
+    }
+
+    /// The mode buttons left of and between the displays, Channel to Macro
+    pub fn mode_row() -> Self {
+        use InputElement::*;
+        Self::new("mode row").with_buttons([
+            ChannelMidi,
+            Plugin,
+            Arranger,
+            Mixer,
+            BrowserPlugin,
+            Sampling,
+            ArrowLeft,
+            ArrowRight,
+            FileSave,
+            Settings,
+            Auto,
+            Macro,
+        ])
+    }
+
+    /// Group buttons A-H
+    pub fn groups() -> Self {
+        use InputElement::*;
+        Self::new("groups")
+            .with_buttons([GroupA, GroupB, GroupC, GroupD, GroupE, GroupF, GroupG, GroupH])
+    }
+
+    /// The eight buttons above the displays
+    pub fn display_buttons() -> Self {
+        use InputElement::*;
+        Self::new("display buttons").with_buttons([
+            DisplayButton1,
+            DisplayButton2,
+            DisplayButton3,
+            DisplayButton4,
+            DisplayButton5,
+            DisplayButton6,
+            DisplayButton7,
+            DisplayButton8,
+        ])
+    }
+
+    /// Pad Mode, Keyboard, Chords and Step
+    pub fn pad_modes() -> Self {
+        use InputElement::*;
+        Self::new("pad modes").with_buttons([PadMode, Keyboard, Chords, Step])
+    }
+
+    /// The column left of the pads, Scene to Mute
+    pub fn pad_functions() -> Self {
+        use InputElement::*;
+        Self::new("pad functions")
+            .with_buttons([Scene, Pattern, Events, Variation, Duplicate, Select, Solo, Mute])
+    }
+
+    /// The four direction LEDs around the main encoder
+    pub fn encoder() -> Self {
+        use InputElement::*;
+        Self::new("encoder").with_buttons([EncoderUp, EncoderLeft, EncoderRight, EncoderDown])
+    }
+
+    /// All 16 pads
+    pub fn all_pads() -> Self {
+        Self::new("pads").with_pads(0..16)
+    }
+
+    /// One row of four pads, 0 being the top row
+    pub fn pad_row(row: u8) -> Self {
+        let first = row.min(3) * 4;
+        Self::new(format!("pads row {}", row.min(3) + 1)).with_pads(first..first + 4)
+    }
+
+    /// Set every LED of the group in `frame`
+    pub fn apply(&self, frame: &mut LedFrame, value: impl Into<LedValue>) {
+        let value = value.into();
+        let (color, _) = value.parts();
+        for &button in &self.buttons {
+            frame.set_button_value(button, value);
+        }
+        for &pad in &self.pads {
+            frame.set_pad(pad, color);
+        }
+        for &led in &self.touch_strip {
+            frame.set_touch_strip(led, color);
+        }
+    }
+}
+
+/// Brightness of a single-color LED standing in for a palette color
+fn color_brightness(color: MaschineLEDColor) -> LedBrightness {
+    match (color.to_led_value(), color.bright) {
+        (0, _) => 0,
+        (_, true) => 127,
+        (_, false) => DIM_BRIGHTNESS,
+    }
+}
+
+fn extend_unique(list: &mut Vec<u8>, items: impl IntoIterator<Item = u8>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::error::{MK3Error, Result};
+use crate::input::InputElement;
+use crate::output::{LedCalibration, LedFrame, LedPalette, RgbColor};
+
+/// Curve of the transition into a [`Keyframe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slow, ends fast
+    EaseIn,
+    /// Starts fast, ends slow
+    EaseOut,
+    /// Slow at both ends
+    EaseInOut,
+    /// Holds the previous color, then jumps at the keyframe
+    Step,
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t` (0.0 - 1.0)
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// An LED a [`LedScene`] track drives
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SceneLed {
+    /// Pad 0-15
+    Pad(u8),
+    /// Touch strip LED 0-24, left to right
+    TouchStrip(u8),
+    /// A button; single-color LEDs show the color's brightness
+    Button(InputElement),
+}
+
+/// Color of an LED at a point in a scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyframe {
+    /// Clock ticks from the start of the scene
+    pub tick: u32,
+    pub color: RgbColor,
+    /// How the color moves from the previous keyframe to this one
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(tick: u32, color: RgbColor, easing: Easing) -> Self {
+        Self {
+            tick,
+            color,
+            easing,
+        }
+    }
+}
+
+/// Light show described by keyframes per LED, timed in clock ticks
+/// ([`TICKS_PER_BEAT`](crate::clock::TICKS_PER_BEAT) per beat).
+///
+/// Colors between keyframes are blended in the OKLab color space, so fades
+/// pass through even-looking in-between colors instead of going dark or
+/// muddy. [`compile`](Self::compile) renders one [`LedFrame`] per tick to
+/// play back from a [`Clock`](crate::Clock) callback.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{Clock, Easing, Keyframe, LedScene, MaschineMK3, RgbColor, SceneLed};
+/// use std::sync::{Arc, Mutex};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = Arc::new(Mutex::new(MaschineMK3::new()?));
+///
+/// // Two beats: pad 0 fades red to blue and back, looping
+/// let mut scene = LedScene::new(48).looping(true);
+/// scene.add_keyframe(SceneLed::Pad(0), Keyframe::new(0, RgbColor::red(), Easing::Linear))?;
+/// scene.add_keyframe(SceneLed::Pad(0), Keyframe::new(24, RgbColor::blue(), Easing::EaseInOut))?;
+///
+/// let frames = scene.compile(device.lock().unwrap().led_calibration());
+/// let mut clock = Clock::new(120.0);
+/// let playback = device.clone();
+/// clock.on_tick(move |tick| {
+///     let frame = frames[scene.frame_index(tick.tick)].clone();
+///     if let Ok(mut device) = playback.lock() {
+///         let _ = device.update_leds(|leds| *leds = frame);
+///     }
+/// });
+/// clock.start()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedScene {
+    length: u32,
+    looping: bool,
+    tracks: Vec<(SceneLed, Vec<Keyframe>)>,
+}
+
+impl LedScene {
+    /// Empty scene lasting `length` ticks (at least 1)
+    pub fn new(length: u32) -> Self {
+        Self {
+            length: length.max(1),
+            looping: false,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Play the scene over and over. Looping scenes also blend from the last
+    /// keyframe of each track back into the first.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Length in clock ticks
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    /// Add a keyframe to an LED's track, replacing one at the same tick.
+    /// Looping scenes take ticks below the length, others up to it.
+    pub fn add_keyframe(&mut self, led: SceneLed, keyframe: Keyframe) -> Result<()> {
+        let last_tick = if self.looping {
+            self.length - 1
+        } else {
+            self.length
+        };
+        if keyframe.tick > last_tick {
+            return Err(MK3Error::InvalidData(format!(
+                "Keyframe at tick {} is past the end of a {} tick scene",
+                keyframe.tick, self.length
+            )));
+        }
+
+        let track = match self
+            .tracks
+            .iter_mut()
+            .position(|(track_led, _)| *track_led == led)
+        {
+            Some(index) => &mut self.tracks[index].1,
+            None => {
+                self.tracks.push((led, Vec::new()));
+                &mut self.tracks.last_mut().expect("track was just added").1
+            }
+        };
+        match track.binary_search_by_key(&keyframe.tick, |existing| existing.tick) {
+            Ok(index) => track[index] = keyframe,
+            Err(index) => track.insert(index, keyframe),
+        }
+        Ok(())
+    }
+
+    /// Color of an LED `tick` ticks into the scene, or `None` if the scene
+    /// doesn't drive it. Fractional ticks give in-between colors.
+    pub fn color_at(&self, led: &SceneLed, tick: f32) -> Option<RgbColor> {
+        let (_, keyframes) = self.tracks.iter().find(|(track_led, _)| track_led == led)?;
+        Some(self.track_color(keyframes, tick))
+    }
+
+    /// Set the LEDs the scene drives to their colors at `tick`, converted
+    /// with `calibration`. Other LEDs in `frame` are left alone.
+    pub fn apply_at(&self, tick: f32, frame: &mut LedFrame, calibration: &LedCalibration) {
+        for (led, keyframes) in &self.tracks {
+            let color = self.track_color(keyframes, tick);
+            match led {
+                SceneLed::Pad(pad) => frame.set_pad(*pad, calibration.pad_color(color)),
+                SceneLed::TouchStrip(index) => {
+                    frame.set_touch_strip(*index, calibration.touch_strip_color(color))
+                }
+                SceneLed::Button(button) => {
+                    if !frame
+                        .buttons
+                        .set_color(button, calibration.button_color(color))
+                    {
+                        frame
+                            .buttons
+                            .set_brightness(button, calibration.button_brightness(color));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render one frame per tick, with LEDs the scene doesn't drive off.
+    /// Index the result with [`frame_index`](Self::frame_index).
+    pub fn compile(&self, calibration: &LedCalibration) -> Vec<LedFrame> {
+        (0..self.frame_count())
+            .map(|tick| {
+                let mut frame = LedFrame::default();
+                self.apply_at(tick as f32, &mut frame, calibration);
+                frame
+            })
+            .collect()
+    }
+
+    /// Index into [`compile`](Self::compile)'s frames for a clock tick:
+    /// wrapped for looping scenes, held on the last frame otherwise
+    pub fn frame_index(&self, tick: u64) -> usize {
+        let frames = self.frame_count() as u64;
+        if self.looping {
+            (tick % frames) as usize
+        } else {
+            tick.min(frames - 1) as usize
+        }
+    }
+
+    /// A looping scene's last tick runs into its first, a one-shot scene
+    /// ends on its length
+    fn frame_count(&self) -> u32 {
+        if self.looping {
+            self.length
+        } else {
+            self.length + 1
+        }
+    }
+
+    fn track_color(&self, keyframes: &[Keyframe], tick: f32) -> RgbColor {
+        let (Some(first), Some(last)) = (keyframes.first(), keyframes.last()) else {
+            return RgbColor::black();
+        };
+        let length = self.length as f32;
+        let tick = if self.looping {
+            tick.rem_euclid(length)
+        } else {
+            tick.clamp(0.0, length)
+        };
+
+        // Keyframes on either side; looping wraps from the last to the first
+        let next = keyframes.partition_point(|keyframe| keyframe.tick as f32 <= tick);
+        let (from, to, span_start, span_end) = match (next, keyframes.get(next)) {
+            (0, _) if self.looping => (last, first, last.tick as f32 - length, first.tick as f32),
+            (0, _) => return first.color,
+            (_, Some(to)) => (
+                &keyframes[next - 1],
+                to,
+                keyframes[next - 1].tick as f32,
+                to.tick as f32,
+            ),
+            (_, None) if self.looping => {
+                (last, first, last.tick as f32, first.tick as f32 + length)
+            }
+            (_, None) => return last.color,
+        };
+
+        let span = span_end - span_start;
+        if span <= 0.0 {
+            return to.color;
+        }
+        let t = to.easing.apply((tick - span_start) / span);
+        lerp_oklab(from.color, to.color, t)
+    }
+}
+
+/// Blend two colors `t` (0.0 - 1.0) of the way in OKLab
+fn lerp_oklab(from: RgbColor, to: RgbColor, t: f32) -> RgbColor {
+    let from = LedPalette::to_oklab(from.r, from.g, from.b);
+    let to = LedPalette::to_oklab(to.r, to.g, to.b);
+    let lab = [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t);
+    let (r, g, b) = LedPalette::from_oklab(lab);
+    RgbColor::new(r, g, b)
+}
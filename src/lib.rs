@@ -43,18 +43,124 @@
 //! # }
 //! ```
 
+pub mod action_map;
+pub mod animation;
+#[cfg(feature = "app")]
+pub mod app;
+pub mod clock;
+pub mod console;
+pub mod coords;
 pub mod device;
+pub mod device_group;
+pub mod display_handle;
+pub mod encoder;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod font;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod gesture;
+pub mod group_selector;
+pub mod health;
+mod identify;
 pub mod input;
+pub mod knob;
+pub mod led_animation;
+pub mod led_group;
+pub mod led_scene;
+pub mod list_navigator;
+pub mod midi_file;
+#[cfg(feature = "testing")]
+pub mod mock_input;
+pub mod note_repeat;
 pub mod output;
+#[cfg(feature = "verify-packets")]
+mod packet_verify;
+pub mod pad_filter;
+pub mod pad_flash;
+pub mod parameter_bank;
+pub mod platform;
+mod process_lock;
+pub mod recorder;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod selftest;
+pub mod settings;
+pub mod splash;
+#[cfg(feature = "sequencer")]
+pub mod sequencer;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod state;
+pub mod streamer;
+pub mod strip;
+pub mod strip_follow;
+pub mod topology;
 
-pub use device::MaschineMK3;
+pub use action_map::{ActionBinding, ActionId, ActionMap};
+pub use animation::{AnimationFrame, DisplayAnimator};
+pub use clock::{Clock, ClockSource, ClockTick, TapTempo};
+pub use console::DisplayConsole;
+pub use coords::{DisplayPoint, PadIndex};
+pub use device::{
+    ConnectionOptions, EventStream, InputStats, MaschineDevice, MaschineMK3, MonitoringStatus,
+    RetryPolicy, ShutdownBehavior, TransferTimeouts,
+};
+pub use device_group::DeviceGroup;
+pub use display_handle::DisplayHandle;
+pub use encoder::{AccelerationCurve, EncoderAccelerationConfig, EncoderAccelerator};
 pub use error::MK3Error;
+pub use font::TextStyle;
+#[cfg(feature = "gamepad")]
+pub use gamepad::{
+    GamepadAxis, GamepadButton, GamepadEvent, GamepadMapper, GamepadMapping, VirtualGamepad,
+};
+pub use gesture::{
+    KnobGesture, KnobGestureConfig, KnobGestureRecognizer, TouchStripGesture,
+    TouchStripGestureConfig, TouchStripGestureRecognizer,
+};
+pub use group_selector::{GroupEvent, GroupSelector};
+pub use health::{EndpointHealth, UsbEndpoint, UsbHealth};
 pub use input::{
-    AudioState, ButtonState, InputElement, InputEvent, InputState, InputTracker, KnobState, 
-    PadEvent, PadEventType, PadState, TouchStripState,
+    AudioDirection, AudioState, AudioValue, ButtonGestureConfig, ButtonState, InputElement,
+    InputEvent, InputState, InputTracker, KnobState, PadEvent, PadEventType, PadPressureConfig,
+    PadState, ProtocolWarningKind, RawInputPacket, TouchData, TouchStripState,
+};
+pub use knob::{KnobMapper, KnobTaper};
+pub use led_animation::{LedAnimation, LedAnimationKind, LedTarget};
+pub use led_group::{LedGroup, LedValue};
+pub use led_scene::{Easing, Keyframe, LedScene, SceneLed};
+pub use list_navigator::{ListEvent, ListNavigator};
+pub use midi_file::{MidiRecorder, MIDI_TICKS_PER_BEAT};
+#[cfg(feature = "testing")]
+pub use mock_input::MockInput;
+pub use note_repeat::{NoteRepeat, RepeatRate};
+pub use pad_filter::{PadFilterConfig, PadHitFilter};
+pub use pad_flash::PadFlashConfig;
+pub use parameter_bank::{BankEvent, ParameterBank};
+pub use recorder::{
+    OutputComparator, OutputMismatch, OutputRecorder, OutputRecording, RecordedPacket,
+};
+#[cfg(feature = "scripting")]
+pub use script::MacroScript;
+pub use selftest::{PadCalibration, SelfTestEvent, SelfTestReport, SelfTestStep};
+pub use settings::DeviceSettings;
+pub use splash::{SplashImage, SplashTransition};
+#[cfg(feature = "sequencer")]
+pub use sequencer::{Pattern, StepSequencer};
+#[cfg(feature = "simulator")]
+pub use simulator::{KeyMap, SimulatedControl, SimulatedMK3};
+pub use state::DeviceState;
+pub use streamer::{DisplayStreamer, StreamMode, StreamStats, StreamerConfig};
+pub use strip::{StripController, StripMode};
+pub use strip_follow::StripFollowConfig;
+pub use topology::{
+    Backend, DeviceTopology, DisplayAvailability, DisplayStatus, EndpointInfo, InterfaceInfo,
 };
 pub use output::{
-    ButtonLedState, DisplayGraphics, DisplayPacket, LedBrightness, MaschineLEDColor, PadLedState,
-    Rgb565, RgbColor,
+    ButtonLedPacket, ButtonLedState, ColorCalibration, ColorCorrection, ColorRamp,
+    ConversionOptions, DirtyStats, DisplayCommand, DisplayGraphics, DisplayPacket, DisplayRect,
+    Dithering, LedBrightness, LedCalibration, LedFrame, LedPalette, MaschineLEDColor, PadLedPacket,
+    PadLedState, RawPacket, Rgb565, RgbColor, PAD_LED_LAYOUT,
 };
\ No newline at end of file
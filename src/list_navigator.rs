@@ -0,0 +1,353 @@
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::encoder::encoder_detents;
+use crate::error::Result;
+use crate::font::{self, TextStyle, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::input::{InputElement, InputEvent};
+use crate::output::RgbColor;
+
+/// Glyph magnification of list rows (16x16 pixel characters)
+const TEXT_SCALE: usize = 2;
+
+/// Space above and below the text of a row
+const ROW_PADDING: usize = 2;
+
+/// Height of one row in pixels
+pub const ROW_HEIGHT: usize = GLYPH_HEIGHT * TEXT_SCALE + 2 * ROW_PADDING;
+
+/// Width of the scroll bar drawn when the list is longer than the screen
+const SCROLL_BAR_WIDTH: usize = 4;
+
+/// Left margin of the row text
+const TEXT_MARGIN: usize = 8;
+
+/// Something a [`ListNavigator`] did in response to input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEvent {
+    /// The highlight moved to `index`
+    Highlighted { index: usize },
+    /// Item `index` was chosen with an encoder push or right
+    Selected { index: usize },
+    /// Encoder left was pressed, typically to go up a level
+    Back,
+}
+
+/// Scrollable list on one display, navigated like the hardware browser.
+///
+/// Turning the 4D encoder or pushing it up and down moves the highlight,
+/// the arrow buttons jump a page, pushing the encoder or pushing it right
+/// selects the highlighted item and pushing it left asks to go back. The
+/// list scrolls to keep the highlight in view, with a scroll bar at the
+/// right edge when it's longer than the screen.
+///
+/// [`handle_events`](Self::handle_events) redraws the display when the view
+/// changed; [`draw`](Self::draw) renders into a caller's frame instead, e.g.
+/// a `Displays` buffer of the `app` framework.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{ListEvent, ListNavigator, MaschineMK3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let presets = vec!["Init".to_string(), "Bass".to_string(), "Lead".to_string()];
+/// let mut browser = ListNavigator::new(0, presets);
+/// browser.set_title(Some("Presets".to_string()));
+/// browser.present(&mut device)?;
+///
+/// loop {
+///     let events = device.poll_input_events()?;
+///     for event in browser.handle_events(&mut device, &events)? {
+///         if let ListEvent::Selected { index } = event {
+///             println!("Load {}", browser.items()[index]);
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ListNavigator {
+    display_id: u8,
+    items: Vec<String>,
+    title: Option<String>,
+    highlighted: usize,
+    /// First item in view
+    scroll: usize,
+    wrap: bool,
+    fg: RgbColor,
+    bg: RgbColor,
+    highlight_fg: RgbColor,
+    highlight_bg: RgbColor,
+    dirty: bool,
+}
+
+impl ListNavigator {
+    /// List on `display_id` with the first item highlighted
+    pub fn new(display_id: u8, items: Vec<String>) -> Self {
+        Self {
+            display_id,
+            items,
+            title: None,
+            highlighted: 0,
+            scroll: 0,
+            wrap: false,
+            fg: RgbColor::white(),
+            bg: RgbColor::black(),
+            highlight_fg: RgbColor::black(),
+            highlight_bg: RgbColor::new(255, 160, 0),
+            dirty: true,
+        }
+    }
+
+    pub fn display_id(&self) -> u8 {
+        self.display_id
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Replace the items, keeping the highlight where it is if it still
+    /// exists
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.highlighted = self.highlighted.min(self.items.len().saturating_sub(1));
+        self.scroll_to_highlight();
+        self.dirty = true;
+    }
+
+    /// Heading drawn above the list, taking one row
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+        self.scroll_to_highlight();
+        self.dirty = true;
+    }
+
+    /// Whether moving past either end continues at the other
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    pub fn set_colors(&mut self, fg: RgbColor, bg: RgbColor) {
+        self.fg = fg;
+        self.bg = bg;
+        self.dirty = true;
+    }
+
+    /// Colors of the highlighted row
+    pub fn set_highlight_colors(&mut self, fg: RgbColor, bg: RgbColor) {
+        self.highlight_fg = fg;
+        self.highlight_bg = bg;
+        self.dirty = true;
+    }
+
+    /// Index of the highlighted item; 0 when the list is empty
+    pub fn highlighted(&self) -> usize {
+        self.highlighted
+    }
+
+    pub fn highlighted_item(&self) -> Option<&str> {
+        self.items.get(self.highlighted).map(String::as_str)
+    }
+
+    /// Highlight an item and scroll it into view. Returns false if it
+    /// doesn't exist.
+    pub fn set_highlighted(&mut self, index: usize) -> bool {
+        if index >= self.items.len() {
+            return false;
+        }
+        if index != self.highlighted {
+            self.highlighted = index;
+            self.scroll_to_highlight();
+            self.dirty = true;
+        }
+        true
+    }
+
+    /// Items that fit on the screen at once
+    pub fn visible_rows(&self) -> usize {
+        let rows = MaschineMK3::DISPLAY_HEIGHT as usize / ROW_HEIGHT;
+        (rows - usize::from(self.title.is_some())).max(1)
+    }
+
+    /// Index of the first item in view
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll
+    }
+
+    /// Whether the view changed since it was last drawn
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Handle one input event without touching the device
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<ListEvent> {
+        match event {
+            InputEvent::KnobChanged {
+                element: InputElement::MainEncoder,
+                delta,
+                ..
+            } => self.move_by(encoder_detents(*delta) as isize),
+            InputEvent::ButtonPressed(InputElement::EncoderUp) => self.move_by(-1),
+            InputEvent::ButtonPressed(InputElement::EncoderDown) => self.move_by(1),
+            InputEvent::ButtonPressed(InputElement::ArrowLeft) => {
+                self.move_by(-(self.visible_rows() as isize))
+            }
+            InputEvent::ButtonPressed(InputElement::ArrowRight) => {
+                self.move_by(self.visible_rows() as isize)
+            }
+            InputEvent::ButtonPressed(InputElement::EncoderPush)
+            | InputEvent::ButtonPressed(InputElement::EncoderRight) => {
+                (self.highlighted < self.items.len()).then_some(ListEvent::Selected {
+                    index: self.highlighted,
+                })
+            }
+            InputEvent::ButtonPressed(InputElement::EncoderLeft) => Some(ListEvent::Back),
+            InputEvent::DeviceResumed => {
+                self.dirty = true;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a batch of events, redrawing the display if the view changed
+    pub fn handle_events<D: MaschineDevice>(
+        &mut self,
+        device: &mut D,
+        events: &[InputEvent],
+    ) -> Result<Vec<ListEvent>> {
+        let changes: Vec<ListEvent> = events
+            .iter()
+            .filter_map(|event| self.handle_event(event))
+            .collect();
+        self.present(device)?;
+        Ok(changes)
+    }
+
+    /// Send the list to its display if it changed
+    pub fn present<D: MaschineDevice>(&mut self, device: &mut D) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        device.send_display_rgb888(self.display_id, &self.render())?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Render the list into a full-screen RGB888 frame
+    pub fn render(&self) -> Vec<u8> {
+        let width = MaschineMK3::DISPLAY_WIDTH as usize;
+        let height = MaschineMK3::DISPLAY_HEIGHT as usize;
+        let mut frame = vec![0u8; width * height * 3];
+        self.draw(&mut frame);
+        frame
+    }
+
+    /// Draw the list over a full-screen RGB888 frame (480x272x3 bytes).
+    /// Doesn't clear [`is_dirty`](Self::is_dirty), see
+    /// [`mark_drawn`](Self::mark_drawn).
+    pub fn draw(&self, frame: &mut [u8]) {
+        let width = MaschineMK3::DISPLAY_WIDTH as usize;
+        let height = MaschineMK3::DISPLAY_HEIGHT as usize;
+        fill_rows(frame, 0, height, self.bg);
+
+        let mut y = 0;
+        if let Some(title) = &self.title {
+            let style = TextStyle {
+                fg: self.bg,
+                bg: None,
+                scale: TEXT_SCALE,
+            };
+            fill_rows(frame, 0, ROW_HEIGHT, self.fg);
+            font::draw_text(frame, width, TEXT_MARGIN, ROW_PADDING, title, &style);
+            y = ROW_HEIGHT;
+        }
+
+        let rows = self.visible_rows();
+        let scrolls = self.items.len() > rows;
+        let text_width = width - TEXT_MARGIN - if scrolls { SCROLL_BAR_WIDTH } else { 0 };
+        let max_chars = text_width / (GLYPH_WIDTH * TEXT_SCALE);
+
+        for (index, item) in self.items.iter().enumerate().skip(self.scroll).take(rows) {
+            let (fg, bg) = if index == self.highlighted {
+                (self.highlight_fg, self.highlight_bg)
+            } else {
+                (self.fg, self.bg)
+            };
+            if index == self.highlighted {
+                fill_rows(frame, y, ROW_HEIGHT, bg);
+            }
+            let style = TextStyle {
+                fg,
+                bg: None,
+                scale: TEXT_SCALE,
+            };
+            let text: String = item.chars().take(max_chars).collect();
+            font::draw_text(frame, width, TEXT_MARGIN, y + ROW_PADDING, &text, &style);
+            y += ROW_HEIGHT;
+        }
+
+        if scrolls {
+            // Thumb sized and placed by the share of the list in view
+            let top = usize::from(self.title.is_some()) * ROW_HEIGHT;
+            let track = height - top;
+            let thumb = (track * rows / self.items.len()).max(ROW_PADDING * 2);
+            let offset = (track - thumb) * self.scroll / (self.items.len() - rows);
+            let x = width - SCROLL_BAR_WIDTH;
+            for py in top + offset..top + offset + thumb {
+                let start = (py * width + x) * 3;
+                for pixel in frame[start..start + SCROLL_BAR_WIDTH * 3].chunks_exact_mut(3) {
+                    pixel.copy_from_slice(&[self.fg.r, self.fg.g, self.fg.b]);
+                }
+            }
+        }
+    }
+
+    /// Note that the current view was drawn by the caller through
+    /// [`draw`](Self::draw), so `present` has nothing to send
+    pub fn mark_drawn(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Move the highlight, clamping at the ends or wrapping around if
+    /// enabled
+    fn move_by(&mut self, steps: isize) -> Option<ListEvent> {
+        let len = self.items.len() as isize;
+        if len == 0 || steps == 0 {
+            return None;
+        }
+        let target = self.highlighted as isize + steps;
+        let target = if self.wrap {
+            target.rem_euclid(len)
+        } else {
+            target.clamp(0, len - 1)
+        } as usize;
+
+        if target == self.highlighted {
+            return None;
+        }
+        self.set_highlighted(target);
+        Some(ListEvent::Highlighted { index: target })
+    }
+
+    fn scroll_to_highlight(&mut self) {
+        let rows = self.visible_rows();
+        if self.highlighted < self.scroll {
+            self.scroll = self.highlighted;
+        } else if self.highlighted >= self.scroll + rows {
+            self.scroll = self.highlighted + 1 - rows;
+        }
+        self.scroll = self.scroll.min(self.items.len().saturating_sub(rows));
+    }
+}
+
+/// Fill full-width rows `y..y + height` of a 480-pixel-wide frame
+fn fill_rows(frame: &mut [u8], y: usize, height: usize, color: RgbColor) {
+    let row_len = MaschineMK3::DISPLAY_WIDTH as usize * 3;
+    let end = ((y + height) * row_len).min(frame.len());
+    let start = (y * row_len).min(end);
+    for pixel in frame[start..end].chunks_exact_mut(3) {
+        pixel.copy_from_slice(&[color.r, color.g, color.b]);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::clock::{MAX_BPM, MIN_BPM};
+use crate::error::Result;
+use crate::input::{InputElement, InputEvent, PadEventType};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Resolution of exported files in ticks per quarter note
+pub const MIDI_TICKS_PER_BEAT: u16 = 480;
+
+/// Knobs recorded as controllers, in controller number order
+const KNOBS: [InputElement; 8] = [
+    InputElement::Knob1,
+    InputElement::Knob2,
+    InputElement::Knob3,
+    InputElement::Knob4,
+    InputElement::Knob5,
+    InputElement::Knob6,
+    InputElement::Knob7,
+    InputElement::Knob8,
+];
+
+/// MIDI message with the time since the recording started
+#[derive(Debug, Clone, Copy)]
+struct TimedMessage {
+    time: Duration,
+    message: [u8; 3],
+}
+
+/// Records pad hits and knob moves as they are read and exports them as a
+/// standard MIDI file (format 0) for import into a DAW.
+///
+/// Pads play notes in the usual drum-rack layout, the bottom-left pad on
+/// the base note (C1 by default) and the top-right pad 15 notes above it,
+/// with the hit velocity scaled to 1-127. Knobs 1-8 send controllers 70-77
+/// by default. The file's tempo is the recorder's BPM, e.g. [`Clock::bpm`]
+/// of the session being recorded, so beats line up in the DAW.
+///
+/// [`Clock::bpm`]: crate::Clock::bpm
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineMK3, MidiRecorder};
+/// use std::time::{Duration, Instant};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut recorder = MidiRecorder::new(120.0);
+///
+/// let start = Instant::now();
+/// while start.elapsed() < Duration::from_secs(30) {
+///     for event in device.poll_input_events()? {
+///         recorder.record(&event);
+///     }
+/// }
+/// recorder.save("take.mid")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MidiRecorder {
+    bpm: f32,
+    channel: u8,
+    base_note: u8,
+    first_cc: u8,
+    started: Option<Instant>,
+    messages: Vec<TimedMessage>,
+    /// Pads whose note hasn't been released
+    held: [bool; 16],
+    /// Last controller value recorded per knob, to skip repeats
+    knob_values: [Option<u8>; 8],
+}
+
+impl MidiRecorder {
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            bpm: bpm.clamp(MIN_BPM, MAX_BPM),
+            channel: 0,
+            base_note: 36,
+            first_cc: 70,
+            started: None,
+            messages: Vec::new(),
+            held: [false; 16],
+            knob_values: [None; 8],
+        }
+    }
+
+    /// MIDI channel (0-15) of every message
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel.min(15);
+        self
+    }
+
+    /// Note of the bottom-left pad; notes above 127 are not recorded
+    pub fn with_base_note(mut self, note: u8) -> Self {
+        self.base_note = note.min(127);
+        self
+    }
+
+    /// Controller number (0-112) of knob 1, knobs 2-8 following on
+    pub fn with_first_cc(mut self, cc: u8) -> Self {
+        self.first_cc = cc.min(112);
+        self
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    /// Number of recorded MIDI messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Time from the first recorded event to the last
+    pub fn duration(&self) -> Duration {
+        self.messages
+            .last()
+            .map_or(Duration::ZERO, |message| message.time)
+    }
+
+    /// Discard everything recorded; the next event starts a new recording
+    pub fn clear(&mut self) {
+        self.started = None;
+        self.messages.clear();
+        self.held = [false; 16];
+        self.knob_values = [None; 8];
+    }
+
+    /// Record an event read just now. Returns whether it was recorded; only
+    /// pad hits and releases and moves of knobs 1-8 are.
+    pub fn record(&mut self, event: &InputEvent) -> bool {
+        self.record_at(event, Instant::now())
+    }
+
+    /// Record an event read at `at`. The first recorded event is the start
+    /// of the file; earlier times are recorded at the time of the last event.
+    pub fn record_at(&mut self, event: &InputEvent, at: Instant) -> bool {
+        let Some(message) = self.message_for(event) else {
+            return false;
+        };
+        let started = *self.started.get_or_insert(at);
+        let time = at.saturating_duration_since(started).max(self.duration());
+        self.messages.push(TimedMessage { time, message });
+        true
+    }
+
+    fn message_for(&mut self, event: &InputEvent) -> Option<[u8; 3]> {
+        match event {
+            InputEvent::PadEvent {
+                pad_number,
+                event_type,
+                value,
+            } => {
+                let note = self.pad_note(*pad_number)?;
+                let held = self.held.get_mut(*pad_number as usize)?;
+                match event_type {
+                    PadEventType::Hit => {
+                        *held = true;
+                        Some([0x90 | self.channel, note, pad_velocity(*value)])
+                    }
+                    PadEventType::HitRelease | PadEventType::TouchRelease if *held => {
+                        *held = false;
+                        Some([0x80 | self.channel, note, 0])
+                    }
+                    _ => None,
+                }
+            }
+            InputEvent::KnobChanged { element, value, .. } => {
+                let knob = KNOBS.iter().position(|knob| knob == element)?;
+                let cc_value = ((*value).min(1023) >> 3) as u8;
+                if self.knob_values[knob] == Some(cc_value) {
+                    return None;
+                }
+                self.knob_values[knob] = Some(cc_value);
+                Some([0xB0 | self.channel, self.first_cc + knob as u8, cc_value])
+            }
+            _ => None,
+        }
+    }
+
+    /// Note for a pad (0-15, numbered from the top left)
+    fn pad_note(&self, pad_number: u8) -> Option<u8> {
+        if pad_number >= 16 {
+            return None;
+        }
+        let row_from_bottom = 3 - pad_number / 4;
+        let note = self.base_note as u16 + (row_from_bottom * 4 + pad_number % 4) as u16;
+        u8::try_from(note).ok().filter(|note| *note <= 127)
+    }
+
+    /// Standard MIDI file holding the recording. Notes still held are
+    /// released at the time of the last event.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut track = Vec::new();
+
+        // Tempo in microseconds per quarter note
+        let tempo = (60_000_000.0 / self.bpm).round() as u32;
+        track.extend_from_slice(&[0x00, 0xFF, 0x51, 0x03]);
+        track.extend_from_slice(&tempo.to_be_bytes()[1..]);
+
+        let mut last_tick = 0;
+        for message in &self.messages {
+            let tick = self.ticks(message.time);
+            write_vlq(&mut track, tick - last_tick);
+            track.extend_from_slice(&message.message);
+            last_tick = tick;
+        }
+
+        for pad in (0..16).filter(|pad| self.held[*pad as usize]) {
+            if let Some(note) = self.pad_note(pad) {
+                write_vlq(&mut track, 0);
+                track.extend_from_slice(&[0x80 | self.channel, note, 0]);
+            }
+        }
+
+        // End of track
+        track.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+        let mut bytes = Vec::with_capacity(22 + track.len());
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        // Format 0, one track
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&MIDI_TICKS_PER_BEAT.to_be_bytes());
+        bytes.extend_from_slice(b"MTrk");
+        bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&track);
+        bytes
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Ticks at the recorder's tempo for a time since the start
+    fn ticks(&self, time: Duration) -> u32 {
+        let beats = time.as_secs_f64() * self.bpm as f64 / 60.0;
+        (beats * MIDI_TICKS_PER_BEAT as f64).round() as u32
+    }
+}
+
+/// MIDI velocity (1-127) for a 12-bit pad hit value
+fn pad_velocity(value: u16) -> u8 {
+    ((value.min(4095) as u32 * 127 + 2047) / 4095).max(1) as u8
+}
+
+/// Write a MIDI variable-length quantity
+fn write_vlq(bytes: &mut Vec<u8>, value: u32) {
+    // Seven bits per byte, most significant first, skipping leading zeros
+    let mut shift = 28;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        bytes.push(((value >> shift) & 0x7F) as u8 | 0x80);
+        shift -= 7;
+    }
+    bytes.push((value & 0x7F) as u8);
+}
@@ -0,0 +1,114 @@
+use crate::device::MaschineMK3;
+use crate::input::{InputElement, InputEvent, PadEventType};
+use std::collections::HashMap;
+
+/// Largest raw knob value (10-bit), as reported by the hardware
+const KNOB_MAX_RAW: u16 = 1023;
+
+/// Builds sequences of hardware-like input events and feeds them to a
+/// controller with [`MaschineMK3::inject_event`], for application tests
+/// run against a real device.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{InputElement, MaschineMK3, MockInput};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// MockInput::new()
+///     .hit_pad(0, 3000)
+///     .release_pad(0)
+///     .turn_knob(InputElement::Knob1, 40)
+///     .tap_button(InputElement::Play)
+///     .feed(&mut device);
+///
+/// let events = device.poll_input_events()?;
+/// assert_eq!(events.len(), 5);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockInput {
+    events: Vec<InputEvent>,
+    knob_values: HashMap<InputElement, u16>,
+}
+
+impl MockInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn press_button(&mut self, element: InputElement) -> &mut Self {
+        self.push(InputEvent::ButtonPressed(element))
+    }
+
+    pub fn release_button(&mut self, element: InputElement) -> &mut Self {
+        self.push(InputEvent::ButtonReleased(element))
+    }
+
+    /// Press and release a button
+    pub fn tap_button(&mut self, element: InputElement) -> &mut Self {
+        self.press_button(element).release_button(element)
+    }
+
+    /// Hit a pad with a velocity (0-4095)
+    pub fn hit_pad(&mut self, pad_number: u8, velocity: u16) -> &mut Self {
+        self.push(InputEvent::PadEvent {
+            pad_number,
+            event_type: PadEventType::Hit,
+            value: velocity.min(4095),
+        })
+    }
+
+    pub fn release_pad(&mut self, pad_number: u8) -> &mut Self {
+        self.push(InputEvent::PadEvent {
+            pad_number,
+            event_type: PadEventType::HitRelease,
+            value: 0,
+        })
+    }
+
+    /// Start a knob at a raw value (0-1023) without an event; knobs start
+    /// at 0 otherwise
+    pub fn set_knob(&mut self, element: InputElement, value: u16) -> &mut Self {
+        self.knob_values.insert(element, value.min(KNOB_MAX_RAW));
+        self
+    }
+
+    /// Move a knob by `delta` raw steps, clamped to the 10-bit range
+    pub fn turn_knob(&mut self, element: InputElement, delta: i32) -> &mut Self {
+        let value = self.knob_values.entry(element).or_insert(0);
+        let new_value = (*value as i32 + delta).clamp(0, KNOB_MAX_RAW as i32) as u16;
+        let delta = new_value as i32 - *value as i32;
+        if delta == 0 {
+            return self;
+        }
+        *value = new_value;
+
+        self.push(InputEvent::KnobChanged {
+            element,
+            value: new_value,
+            delta,
+        })
+    }
+
+    /// Queue an arbitrary event
+    pub fn push(&mut self, event: InputEvent) -> &mut Self {
+        self.events.push(event);
+        self
+    }
+
+    /// Events queued so far, in order
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    /// Inject the queued events into `device` and clear the queue. Knob
+    /// positions are kept for the next sequence.
+    pub fn feed(&mut self, device: &mut MaschineMK3) {
+        for event in self.events.drain(..) {
+            device.inject_event(event);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::clock::{Clock, ClockTick, TICKS_PER_BEAT};
+use crate::input::{InputElement, InputEvent, PadEventType};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+
+/// Note length between repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepeatRate {
+    Quarter,
+    Eighth,
+    #[default]
+    Sixteenth,
+    ThirtySecond,
+    QuarterTriplet,
+    EighthTriplet,
+    SixteenthTriplet,
+    ThirtySecondTriplet,
+}
+
+impl RepeatRate {
+    /// Length of one repeat in clock ticks
+    pub fn ticks(&self) -> u32 {
+        match self {
+            RepeatRate::Quarter => TICKS_PER_BEAT,
+            RepeatRate::Eighth => TICKS_PER_BEAT / 2,
+            RepeatRate::Sixteenth => TICKS_PER_BEAT / 4,
+            RepeatRate::ThirtySecond => TICKS_PER_BEAT / 8,
+            RepeatRate::QuarterTriplet => TICKS_PER_BEAT * 2 / 3,
+            RepeatRate::EighthTriplet => TICKS_PER_BEAT / 3,
+            RepeatRate::SixteenthTriplet => TICKS_PER_BEAT / 6,
+            RepeatRate::ThirtySecondTriplet => TICKS_PER_BEAT / 12,
+        }
+    }
+}
+
+/// Note repeat: while Note Repeat is active, held pads retrigger on the clock
+/// grid as synthesized `PadEvent` hits, using the pad's current pressure as
+/// velocity.
+///
+/// By default Note Repeat is active while its button is held; with
+/// [`set_latch`](Self::set_latch) each press toggles it instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{Clock, MaschineMK3, NoteRepeat, RepeatRate};
+/// use std::sync::{Arc, Mutex};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut clock = Clock::new(120.0);
+///
+/// let repeat = Arc::new(Mutex::new(NoteRepeat::new()));
+/// repeat.lock().unwrap().set_rate(RepeatRate::EighthTriplet);
+/// let repeats = NoteRepeat::attach(&repeat, &mut clock);
+/// clock.start()?;
+///
+/// loop {
+///     for event in device.poll_input_events()? {
+///         repeat.lock().unwrap().handle_event(&event);
+///     }
+///     for hit in repeats.try_iter() {
+///         println!("{}", hit.description());
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NoteRepeat {
+    active: bool,
+    latch: bool,
+    rate: RepeatRate,
+    swing: f32,
+    /// Current pressure of each held pad
+    held_pads: [Option<u16>; 16],
+}
+
+impl Default for NoteRepeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoteRepeat {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            latch: false,
+            rate: RepeatRate::default(),
+            swing: 0.0,
+            held_pads: [None; 16],
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    /// Toggle on each Note Repeat press instead of only while it is held
+    pub fn set_latch(&mut self, latch: bool) {
+        self.latch = latch;
+    }
+
+    pub fn rate(&self) -> RepeatRate {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: RepeatRate) {
+        self.rate = rate;
+    }
+
+    /// Delay every second repeat by up to half a repeat length (0.0 = straight,
+    /// 1.0 = maximum swing). Rounded to whole clock ticks, so fast rates have
+    /// only a few distinct swing amounts.
+    pub fn set_swing(&mut self, swing: f32) {
+        self.swing = swing.clamp(0.0, 1.0);
+    }
+
+    /// Track the Note Repeat button and pad holds/pressure
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::ButtonPressed(InputElement::NoteRepeat) => {
+                self.active = if self.latch { !self.active } else { true };
+            }
+            InputEvent::ButtonReleased(InputElement::NoteRepeat) if !self.latch => {
+                self.active = false;
+            }
+            InputEvent::PadEvent {
+                pad_number,
+                event_type,
+                value,
+            } => {
+                let Some(pad) = self.held_pads.get_mut(*pad_number as usize) else {
+                    return;
+                };
+                match event_type {
+                    PadEventType::Hit | PadEventType::Aftertouch => *pad = Some(*value),
+                    PadEventType::HitRelease | PadEventType::TouchRelease => *pad = None,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Repeated hits due on a clock tick
+    pub fn on_tick(&self, tick: ClockTick) -> Vec<InputEvent> {
+        if !self.active || !self.is_repeat_tick(tick.tick) {
+            return Vec::new();
+        }
+
+        self.held_pads
+            .iter()
+            .enumerate()
+            .filter_map(|(pad_number, pressure)| {
+                pressure.map(|value| InputEvent::PadEvent {
+                    pad_number: pad_number as u8,
+                    event_type: PadEventType::Hit,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// Drive note repeat from a clock. Repeated hits are sent to the returned
+    /// receiver from the clock's thread.
+    pub fn attach(repeat: &Arc<Mutex<NoteRepeat>>, clock: &mut Clock) -> Receiver<InputEvent> {
+        let (sender, receiver) = mpsc::channel();
+        let repeat = Arc::clone(repeat);
+
+        clock.on_tick(move |tick| {
+            let events = match repeat.lock() {
+                Ok(repeat) => repeat.on_tick(tick),
+                Err(_) => return,
+            };
+            for event in events {
+                let _ = sender.send(event);
+            }
+        });
+
+        receiver
+    }
+
+    /// Whether a repeat falls on `tick`, applying swing to every second repeat
+    fn is_repeat_tick(&self, tick: u64) -> bool {
+        let interval = self.rate.ticks() as u64;
+        let swing_ticks = (self.swing * interval as f32 / 2.0).round() as u64;
+        let position = tick % (interval * 2);
+        position == 0 || position == interval + swing_ticks
+    }
+}
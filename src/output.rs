@@ -1,8 +1,26 @@
+use crate::coords::{DisplayPoint, PadIndex};
+use crate::device::MaschineMK3;
+use crate::input::InputElement;
+use crate::led_group::{LedGroup, LedValue};
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 /// LED brightness levels (0-127 for most LEDs)
 pub type LedBrightness = u8;
 
+/// Type 0x80 button LED packet, see [`ButtonLedState::to_packet`]
+pub type ButtonLedPacket = [u8; ButtonLedState::PACKET_LEN];
+
+/// Type 0x81 pad and touch strip LED packet, see [`PadLedState::to_packet`]
+pub type PadLedPacket = [u8; PadLedState::PACKET_LEN];
+
+/// Scale a single-color LED brightness by `factor` (clamped to 0.0 - 1.0)
+pub fn scale_brightness(brightness: LedBrightness, factor: f32) -> LedBrightness {
+    (brightness as f32 * factor.clamp(0.0, 1.0)).round() as LedBrightness
+}
+
 /// RGB color for RGB LEDs
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct RgbColor {
     pub r: u8,
     pub g: u8,
@@ -36,7 +54,7 @@ impl RgbColor {
 }
 
 /// Maschine MK3 color mapping based on the hardware color palette
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MaschineLEDColor {
     pub index: u8,    // 0-16 color index
     pub bright: bool, // true for bright, false for dim
@@ -69,41 +87,9 @@ impl MaschineLEDColor {
     }
 
     /// Create a new MaschineColor from RGB values
-    /// Maps to the nearest color in the palette and determines brightness
+    /// Maps to the perceptually nearest bright or dim color in the standard palette
     pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
-        // Handle black/off case
-        if r == 0 && g == 0 && b == 0 {
-            return MaschineLEDColor {
-                index: 0,
-                bright: false,
-            };
-        }
-
-        // Find the closest color in the palette using Euclidean distance
-        let mut best_distance = f32::MAX;
-        let mut best_index = 0;
-
-        for (index, &(pr, pg, pb)) in Self::PALETTE.iter().enumerate() {
-            let distance = ((r as f32 - pr as f32).powi(2)
-                + (g as f32 - pg as f32).powi(2)
-                + (b as f32 - pb as f32).powi(2))
-            .sqrt();
-
-            if distance < best_distance {
-                best_distance = distance;
-                best_index = index;
-            }
-        }
-
-        // Determine brightness based on the maximum RGB component rather than luminance
-        // This ensures pure colors like red, green, blue are bright
-        let max_component = r.max(g).max(b);
-        let bright = max_component > 127;
-
-        MaschineLEDColor {
-            index: best_index as u8,
-            bright,
-        }
+        LedPalette::default().nearest(r, g, b)
     }
 
     /// Create a MaschineColor with specific color index and brightness
@@ -132,7 +118,7 @@ impl MaschineLEDColor {
             result += 4;
         }
 
-        result as u8
+        result
     }
 
     /// Predefined colors for common use
@@ -180,31 +166,260 @@ impl MaschineLEDColor {
         }
     }
 
+    /// Dim this color by `factor` (0.0 - 1.0). RGB LEDs only have bright and
+    /// dim levels, so factors below 0.5 select dim and 0.0 turns the LED off.
+    pub fn scaled(&self, factor: f32) -> Self {
+        if factor <= 0.0 {
+            Self::black()
+        } else if factor < 0.5 {
+            Self::new(self.index, false)
+        } else {
+            *self
+        }
+    }
+
     /// Get RGB values for this Maschine color (for preview/debugging)
     pub fn to_rgb(&self) -> (u8, u8, u8) {
-        // Special case: black/off
-        if self.index == 0 && !self.bright {
+        LedPalette::default().to_rgb(*self)
+    }
+}
+
+/// Mapping from pad velocity to LED color, for
+/// [`MaschineLEDColor::from_velocity`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorRamp {
+    /// Green for soft hits through yellow to red for hard ones
+    Classic,
+    /// Blue through cyan, green and yellow to red
+    Heatmap,
+    /// One color, dim for soft hits and bright for hard ones
+    SingleHue(MaschineLEDColor),
+}
+
+impl MaschineLEDColor {
+    /// Color for a 12-bit velocity (0-4095) along `ramp`. Velocity 0 is off;
+    /// soft hits get the dim variant.
+    pub fn from_velocity(velocity: u16, ramp: ColorRamp) -> Self {
+        if velocity == 0 {
+            return Self::black();
+        }
+
+        let t = velocity.min(4095) as f32 / 4095.0;
+        match ramp {
+            // Palette indices 5 (green) down to 0 (red)
+            ColorRamp::Classic => Self::new(5 - (t * 5.0).round() as u8, t >= 0.25),
+            // Palette indices 9 (blue) down to 0 (red)
+            ColorRamp::Heatmap => Self::new(9 - (t * 9.0).round() as u8, t >= 0.25),
+            ColorRamp::SingleHue(color) => Self::new(color.index, t >= 0.5),
+        }
+    }
+}
+
+impl From<RgbColor> for MaschineLEDColor {
+    fn from(rgb: RgbColor) -> Self {
+        Self::from_rgb(rgb.r, rgb.g, rgb.b)
+    }
+}
+
+/// How each of the 17 LED color indices looks, used to quantize RGB colors.
+///
+/// The hardware palette is fixed, but LEDs vary between units and differ from
+/// what a screen shows; supplying measured colors here makes
+/// [`nearest`](Self::nearest) pick the index that actually looks closest.
+///
+/// The protocol only exposes bright and dim per color (two LED values per
+/// index), so matching chooses between those two levels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedPalette {
+    colors: [(u8, u8, u8); 17],
+}
+
+impl Default for LedPalette {
+    fn default() -> Self {
+        Self::new(MaschineLEDColor::PALETTE)
+    }
+}
+
+impl LedPalette {
+    /// Create a palette from the bright appearance of each color index
+    pub fn new(colors: [(u8, u8, u8); 17]) -> Self {
+        Self { colors }
+    }
+
+    /// Override how one color index looks
+    pub fn set_color(&mut self, index: u8, rgb: (u8, u8, u8)) {
+        if let Some(color) = self.colors.get_mut(index as usize) {
+            *color = rgb;
+        }
+    }
+
+    /// Appearance of an LED color under this palette. Dim colors are taken
+    /// to be half as bright.
+    pub fn to_rgb(&self, color: MaschineLEDColor) -> (u8, u8, u8) {
+        if color.index == 0 && !color.bright {
             return (0, 0, 0);
         }
 
-        let (r, g, b) = Self::PALETTE[self.index as usize % 17];
-        if self.bright {
+        let (r, g, b) = self.colors[color.index as usize % 17];
+        if color.bright {
             (r, g, b)
         } else {
-            // Dim version - reduce brightness by ~50%
             (r / 2, g / 2, b / 2)
         }
     }
+
+    /// Find the LED color closest to an RGB color, comparing in the OKLab
+    /// color space so that e.g. dark orange maps to dim orange rather than red.
+    /// Pure black always maps to off.
+    pub fn nearest(&self, r: u8, g: u8, b: u8) -> MaschineLEDColor {
+        if r == 0 && g == 0 && b == 0 {
+            return MaschineLEDColor::black();
+        }
+
+        let target = Self::to_oklab(r, g, b);
+        let mut best = MaschineLEDColor::black();
+        let mut best_distance = f32::MAX;
+
+        for index in 0..17u8 {
+            for bright in [true, false] {
+                // Index 0 dim is the "off" value, so dim red can't be shown
+                if index == 0 && !bright {
+                    continue;
+                }
+
+                let candidate = MaschineLEDColor::new(index, bright);
+                let (cr, cg, cb) = self.to_rgb(candidate);
+                let lab = Self::to_oklab(cr, cg, cb);
+                let distance = (0..3).map(|i| (lab[i] - target[i]).powi(2)).sum::<f32>();
+
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = candidate;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Convert sRGB to OKLab (L, a, b)
+    pub(crate) fn to_oklab(r: u8, g: u8, b: u8) -> [f32; 3] {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(r), linear(g), linear(b));
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+
+    /// Convert OKLab back to sRGB, clamping colors outside the sRGB gamut
+    pub(crate) fn from_oklab(lab: [f32; 3]) -> (u8, u8, u8) {
+        let [lightness, a, b] = lab;
+        let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+        let encode = |c: f32| {
+            let c = c.clamp(0.0, 1.0);
+            let c = if c <= 0.003_130_8 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c * 255.0).round() as u8
+        };
+        (
+            encode(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            encode(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+            encode(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        )
+    }
 }
 
-impl From<RgbColor> for MaschineLEDColor {
-    fn from(rgb: RgbColor) -> Self {
-        Self::from_rgb(rgb.r, rgb.g, rgb.b)
+/// Per-channel correction applied to an RGB color before it is matched to
+/// the LED palette
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCalibration {
+    /// Exponent per channel (R, G, B); above 1.0 darkens mid-tones
+    pub gamma: [f32; 3],
+    /// Multiplier per channel, applied after gamma
+    pub gain: [f32; 3],
+}
+
+impl Default for ColorCalibration {
+    fn default() -> Self {
+        Self {
+            gamma: [1.0; 3],
+            gain: [1.0; 3],
+        }
+    }
+}
+
+impl ColorCalibration {
+    pub fn apply(&self, color: RgbColor) -> RgbColor {
+        let channel = |value: u8, i: usize| {
+            let corrected = (value as f32 / 255.0).powf(self.gamma[i]) * self.gain[i];
+            (corrected.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        RgbColor::new(channel(color.r, 0), channel(color.g, 1), channel(color.b, 2))
+    }
+}
+
+/// Color calibration for one unit's LEDs.
+///
+/// Pads, buttons and the touch strip use different LEDs behind different
+/// plastics, so each gets its own correction. RGB colors are corrected and
+/// then matched against `palette`, which can hold measured colors as well.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LedCalibration {
+    pub pads: ColorCalibration,
+    /// Group buttons, and the brightness of single-color button LEDs
+    pub buttons: ColorCalibration,
+    pub touch_strip: ColorCalibration,
+    pub palette: LedPalette,
+}
+
+impl LedCalibration {
+    pub fn pad_color(&self, color: RgbColor) -> MaschineLEDColor {
+        self.quantize(&self.pads, color)
+    }
+
+    pub fn button_color(&self, color: RgbColor) -> MaschineLEDColor {
+        self.quantize(&self.buttons, color)
+    }
+
+    pub fn touch_strip_color(&self, color: RgbColor) -> MaschineLEDColor {
+        self.quantize(&self.touch_strip, color)
+    }
+
+    /// Brightness (0-127) of a single-color button LED showing `color`,
+    /// from its brightest channel after correction
+    pub fn button_brightness(&self, color: RgbColor) -> LedBrightness {
+        let corrected = self.buttons.apply(color);
+        corrected.r.max(corrected.g).max(corrected.b) / 2
+    }
+
+    fn quantize(&self, calibration: &ColorCalibration, color: RgbColor) -> MaschineLEDColor {
+        let corrected = calibration.apply(color);
+        self.palette.nearest(corrected.r, corrected.g, corrected.b)
     }
 }
 
 /// State of all button LEDs (Type 0x80 packet)
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ButtonLedState {
     // Single-color LEDs
     pub channel_midi: LedBrightness,
@@ -273,17 +488,197 @@ pub struct ButtonLedState {
     pub nav_down: MaschineLEDColor,
 }
 
+/// Slot of each pad's LED among the 16 pad bytes of a Type 0x81 packet,
+/// indexed by pad number.
+///
+/// Pad numbers count from the top-left pad like the hits in Type 0x02
+/// packets, but the LEDs run from the bottom-left pad (the one labeled 1 on
+/// the controller) to the right and then up, row by row. The
+/// `pad_layout` example checks this on hardware.
+pub const PAD_LED_LAYOUT: [u8; 16] = [12, 13, 14, 15, 8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3];
+
+// Every LED slot belongs to exactly one pad
+const _: () = {
+    let mut used = [false; 16];
+    let mut pad = 0;
+    while pad < 16 {
+        let slot = PAD_LED_LAYOUT[pad] as usize;
+        assert!(
+            slot < 16 && !used[slot],
+            "PAD_LED_LAYOUT must use every slot once"
+        );
+        used[slot] = true;
+        pad += 1;
+    }
+};
+
 /// State of pad and touch strip LEDs (Type 0x81 packet)
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct PadLedState {
     pub touch_strip_leds: [MaschineLEDColor; 25], // 25 RGB LEDs on touch strip
-    pub pad_leds: [MaschineLEDColor; 16],         // 16 RGB pad LEDs
+    /// 16 RGB pad LEDs by pad number, mapped to the packet's order with
+    /// [`PAD_LED_LAYOUT`]
+    pub pad_leds: [MaschineLEDColor; 16],
 }
 
 impl ButtonLedState {
-    /// Convert to Type 0x80 packet (62 bytes)
-    pub fn to_packet(&self) -> Vec<u8> {
-        let mut packet = vec![0u8; 63];
+    /// Length of a Type 0x80 packet, including the type byte
+    pub const PACKET_LEN: usize = 63;
+
+    /// Mutable references to every single-color LED
+    pub fn single_color_leds_mut(&mut self) -> [&mut LedBrightness; 49] {
+        [
+            &mut self.channel_midi, &mut self.plugin_instance, &mut self.arranger, &mut self.mixer,
+            &mut self.sampler, &mut self.arrow_left, &mut self.arrow_right, &mut self.file_save,
+            &mut self.settings, &mut self.auto, &mut self.macro_set, &mut self.display_button_1,
+            &mut self.display_button_2, &mut self.display_button_3, &mut self.display_button_4,
+            &mut self.display_button_5, &mut self.display_button_6, &mut self.display_button_7,
+            &mut self.display_button_8, &mut self.volume, &mut self.swing, &mut self.note_repeat,
+            &mut self.tempo, &mut self.lock, &mut self.pitch, &mut self.mod_, &mut self.perform,
+            &mut self.notes, &mut self.restart, &mut self.erase, &mut self.tap, &mut self.follow,
+            &mut self.play, &mut self.rec, &mut self.stop, &mut self.shift, &mut self.fixed_vel,
+            &mut self.pad_mode, &mut self.keyboard, &mut self.chords, &mut self.step,
+            &mut self.scene, &mut self.pattern, &mut self.events, &mut self.variation,
+            &mut self.duplicate, &mut self.select, &mut self.solo, &mut self.mute,
+        ]
+    }
+
+    /// Mutable references to every RGB LED
+    pub fn rgb_leds_mut(&mut self) -> [&mut MaschineLEDColor; 13] {
+        [
+            &mut self.browser_plugin, &mut self.group_a, &mut self.group_b, &mut self.group_c,
+            &mut self.group_d, &mut self.group_e, &mut self.group_f, &mut self.group_g,
+            &mut self.group_h, &mut self.nav_up, &mut self.nav_left, &mut self.nav_right,
+            &mut self.nav_down,
+        ]
+    }
+
+    /// Set every LED to a brightness; RGB LEDs get a grayscale color
+    pub fn set_all_brightness(&mut self, brightness: LedBrightness) {
+        for led in self.single_color_leds_mut() {
+            *led = brightness;
+        }
+        for led in self.rgb_leds_mut() {
+            *led = MaschineLEDColor::from_brightness(brightness);
+        }
+    }
+
+    /// Copy of this state with every LED dimmed by `factor` (0.0 - 1.0)
+    pub fn scaled(&self, factor: f32) -> ButtonLedState {
+        let mut scaled = self.clone();
+        for led in scaled.single_color_leds_mut() {
+            *led = scale_brightness(*led, factor);
+        }
+        for led in scaled.rgb_leds_mut() {
+            *led = led.scaled(factor);
+        }
+        scaled
+    }
+
+    /// Set the LED for an element by brightness. RGB LEDs are set to a
+    /// grayscale color. Returns false if the element has no LED.
+    pub fn set_brightness(&mut self, element: &InputElement, brightness: LedBrightness) -> bool {
+        match element {
+            InputElement::Play => self.play = brightness,
+            InputElement::Rec => self.rec = brightness,
+            InputElement::Stop => self.stop = brightness,
+            InputElement::Restart => self.restart = brightness,
+            InputElement::Erase => self.erase = brightness,
+            InputElement::Tap => self.tap = brightness,
+            InputElement::Follow => self.follow = brightness,
+            InputElement::ChannelMidi => self.channel_midi = brightness,
+            InputElement::Arranger => self.arranger = brightness,
+            InputElement::ArrowLeft => self.arrow_left = brightness,
+            InputElement::ArrowRight => self.arrow_right = brightness,
+            InputElement::FileSave => self.file_save = brightness,
+            InputElement::Settings => self.settings = brightness,
+            InputElement::Macro => self.macro_set = brightness,
+            InputElement::Auto => self.auto = brightness,
+            InputElement::Plugin => self.plugin_instance = brightness,
+            InputElement::Mixer => self.mixer = brightness,
+            InputElement::Sampling => self.sampler = brightness,
+            InputElement::Volume => self.volume = brightness,
+            InputElement::Swing => self.swing = brightness,
+            InputElement::NoteRepeat => self.note_repeat = brightness,
+            InputElement::Tempo => self.tempo = brightness,
+            InputElement::Lock => self.lock = brightness,
+            InputElement::Pitch => self.pitch = brightness,
+            InputElement::Mod => self.mod_ = brightness,
+            InputElement::Perform => self.perform = brightness,
+            InputElement::Notes => self.notes = brightness,
+            InputElement::Shift => self.shift = brightness,
+            InputElement::FixedVel => self.fixed_vel = brightness,
+            InputElement::PadMode => self.pad_mode = brightness,
+            InputElement::Keyboard => self.keyboard = brightness,
+            InputElement::Chords => self.chords = brightness,
+            InputElement::Step => self.step = brightness,
+            InputElement::Scene => self.scene = brightness,
+            InputElement::Pattern => self.pattern = brightness,
+            InputElement::Events => self.events = brightness,
+            InputElement::Variation => self.variation = brightness,
+            InputElement::Duplicate => self.duplicate = brightness,
+            InputElement::Select => self.select = brightness,
+            InputElement::Solo => self.solo = brightness,
+            InputElement::Mute => self.mute = brightness,
+            InputElement::DisplayButton1 => self.display_button_1 = brightness,
+            InputElement::DisplayButton2 => self.display_button_2 = brightness,
+            InputElement::DisplayButton3 => self.display_button_3 = brightness,
+            InputElement::DisplayButton4 => self.display_button_4 = brightness,
+            InputElement::DisplayButton5 => self.display_button_5 = brightness,
+            InputElement::DisplayButton6 => self.display_button_6 = brightness,
+            InputElement::DisplayButton7 => self.display_button_7 = brightness,
+            InputElement::DisplayButton8 => self.display_button_8 = brightness,
+            // For RGB LEDs, convert brightness to grayscale color
+            InputElement::GroupA => self.group_a = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupB => self.group_b = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupC => self.group_c = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupD => self.group_d = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupE => self.group_e = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupF => self.group_f = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupG => self.group_g = MaschineLEDColor::from_brightness(brightness),
+            InputElement::GroupH => self.group_h = MaschineLEDColor::from_brightness(brightness),
+            InputElement::BrowserPlugin => {
+                self.browser_plugin = MaschineLEDColor::from_brightness(brightness)
+            }
+            InputElement::EncoderUp => self.nav_up = MaschineLEDColor::from_brightness(brightness),
+            InputElement::EncoderLeft => {
+                self.nav_left = MaschineLEDColor::from_brightness(brightness)
+            }
+            InputElement::EncoderRight => {
+                self.nav_right = MaschineLEDColor::from_brightness(brightness)
+            }
+            InputElement::EncoderDown => {
+                self.nav_down = MaschineLEDColor::from_brightness(brightness)
+            }
+            _ => return false, // Elements that don't have LEDs
+        }
+        true
+    }
+
+    /// Set the color of an RGB LED. Returns false if the element has no RGB LED.
+    pub fn set_color(&mut self, element: &InputElement, color: MaschineLEDColor) -> bool {
+        match element {
+            InputElement::GroupA => self.group_a = color,
+            InputElement::GroupB => self.group_b = color,
+            InputElement::GroupC => self.group_c = color,
+            InputElement::GroupD => self.group_d = color,
+            InputElement::GroupE => self.group_e = color,
+            InputElement::GroupF => self.group_f = color,
+            InputElement::GroupG => self.group_g = color,
+            InputElement::GroupH => self.group_h = color,
+            InputElement::BrowserPlugin => self.browser_plugin = color,
+            InputElement::EncoderUp => self.nav_up = color,
+            InputElement::EncoderLeft => self.nav_left = color,
+            InputElement::EncoderRight => self.nav_right = color,
+            InputElement::EncoderDown => self.nav_down = color,
+            _ => return false, // Elements that don't have RGB LEDs
+        }
+        true
+    }
+
+    /// Convert to Type 0x80 packet (63 bytes, built on the stack)
+    pub fn to_packet(&self) -> ButtonLedPacket {
+        let mut packet = [0u8; Self::PACKET_LEN];
         packet[0] = 0x80; // Packet type
 
         // Single-color LEDs (according to documentation order)
@@ -360,9 +755,46 @@ impl ButtonLedState {
 }
 
 impl PadLedState {
-    /// Convert to Type 0x81 packet (42 bytes)
-    pub fn to_packet(&self) -> Vec<u8> {
-        let mut packet = vec![0u8; 42];
+    /// Length of a Type 0x81 packet, including the type byte
+    pub const PACKET_LEN: usize = 42;
+
+    /// Pad at a grid position: row 0 is the top row, column 0 the left
+    /// column. `None` outside the 4x4 grid.
+    pub const fn grid_pad(row: u8, col: u8) -> Option<PadIndex> {
+        PadIndex::from_grid(row, col)
+    }
+
+    /// Set the pad at a grid position (see [`grid_pad`](Self::grid_pad)).
+    /// Returns false if the position is outside the grid.
+    pub fn set_by_grid(&mut self, row: u8, col: u8, color: MaschineLEDColor) -> bool {
+        match Self::grid_pad(row, col) {
+            Some(pad) => {
+                self.set_pad(pad, color);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn pad(&self, pad: PadIndex) -> MaschineLEDColor {
+        self.pad_leds[pad.get() as usize]
+    }
+
+    pub fn set_pad(&mut self, pad: PadIndex, color: MaschineLEDColor) {
+        self.pad_leds[pad.get() as usize] = color;
+    }
+
+    /// Copy of this state with every LED dimmed by `factor` (0.0 - 1.0)
+    pub fn scaled(&self, factor: f32) -> PadLedState {
+        PadLedState {
+            touch_strip_leds: self.touch_strip_leds.map(|led| led.scaled(factor)),
+            pad_leds: self.pad_leds.map(|led| led.scaled(factor)),
+        }
+    }
+
+    /// Convert to Type 0x81 packet (42 bytes, built on the stack)
+    pub fn to_packet(&self) -> PadLedPacket {
+        let mut packet = [0u8; Self::PACKET_LEN];
         packet[0] = 0x81; // Packet type
 
         // Touch strip LEDs (25 RGB, bytes 1-26, simplified to single byte per LED)
@@ -372,19 +804,290 @@ impl PadLedState {
             }
         }
 
-        // Pad LEDs (16 RGB, bytes 27-42, simplified to single byte per LED)
-        for (i, led) in self.pad_leds.iter().enumerate() {
-            if i + 26 < packet.len() {
-                packet[i + 26] = led.to_led_value();
-            }
+        // Pad LEDs (16 RGB, bytes 26-41, in the LEDs' own order)
+        for (led, &slot) in self.pad_leds.iter().zip(&PAD_LED_LAYOUT) {
+            packet[26 + slot as usize] = led.to_led_value();
         }
 
         packet
     }
 }
 
+/// Staged button, pad and touch strip LED changes, applied in one write by
+/// `MaschineMK3::update_leds`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LedFrame {
+    pub buttons: ButtonLedState,
+    pub pads: PadLedState,
+}
+
+impl LedFrame {
+    /// Set a button LED brightness (RGB LEDs get a grayscale color)
+    pub fn set_button(&mut self, element: InputElement, brightness: LedBrightness) {
+        self.buttons.set_brightness(&element, brightness);
+    }
+
+    /// Set an RGB button LED color
+    pub fn set_button_color(&mut self, element: InputElement, color: MaschineLEDColor) {
+        self.buttons.set_color(&element, color);
+    }
+
+    /// Set a button LED to a color or brightness, whichever kind of LED it
+    /// has
+    pub fn set_button_value(&mut self, element: InputElement, value: impl Into<LedValue>) {
+        let (color, brightness) = value.into().parts();
+        if !self.buttons.set_color(&element, color) {
+            self.buttons.set_brightness(&element, brightness);
+        }
+    }
+
+    /// Set a pad LED color (pads 0-15, out of range pads are ignored)
+    pub fn set_pad(&mut self, pad_number: u8, color: MaschineLEDColor) {
+        if let Some(led) = self.pads.pad_leds.get_mut(pad_number as usize) {
+            *led = color;
+        }
+    }
+
+    /// Set every pad LED to the same color
+    pub fn set_all_pads(&mut self, color: MaschineLEDColor) {
+        self.pads.pad_leds = [color; 16];
+    }
+
+    /// Set a touch strip LED color (LEDs 0-24, left to right)
+    pub fn set_touch_strip(&mut self, index: u8, color: MaschineLEDColor) {
+        if let Some(led) = self.pads.touch_strip_leds.get_mut(index as usize) {
+            *led = color;
+        }
+    }
+
+    /// Set every LED of a group to a color or brightness
+    pub fn set_group(&mut self, group: &LedGroup, value: impl Into<LedValue>) {
+        group.apply(self, value);
+    }
+
+    /// Turn every LED off
+    pub fn clear(&mut self) {
+        *self = LedFrame::default();
+    }
+}
+
+/// Levels of the linear-light lookup used when encoding corrected colors
+const CORRECTION_LEVELS: usize = 4096;
+
+/// Color correction for a display: a 3x3 matrix applied in linear light,
+/// then the panel's gamma.
+///
+/// Input is taken as sRGB. The matrix maps linear sRGB to what the panel
+/// needs to show the same color, e.g. to cancel a blue cast; the result is
+/// encoded with `gamma` per channel instead of the sRGB curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCorrection {
+    /// Row-major; `matrix[0]` gives the output red from input R, G, B
+    pub matrix: [[f32; 3]; 3],
+    /// Panel gamma per channel (R, G, B)
+    pub gamma: [f32; 3],
+}
+
+impl Default for ColorCorrection {
+    /// Identity matrix with a gamma of 2.2, close to passing sRGB through
+    fn default() -> Self {
+        Self {
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            gamma: [2.2; 3],
+        }
+    }
+}
+
+impl ColorCorrection {
+    /// Correct a single color
+    pub fn apply(&self, color: RgbColor) -> RgbColor {
+        let input = [
+            srgb_to_linear(color.r),
+            srgb_to_linear(color.g),
+            srgb_to_linear(color.b),
+        ];
+        let channel = |i: usize| {
+            let linear = self.mix(i, input);
+            (linear.powf(1.0 / self.gamma[i].max(f32::EPSILON)) * 255.0).round() as u8
+        };
+        RgbColor::new(channel(0), channel(1), channel(2))
+    }
+
+    /// Output channel `i` in linear light, clamped to 0.0-1.0
+    fn mix(&self, i: usize, input: [f32; 3]) -> f32 {
+        let row = self.matrix[i];
+        (row[0] * input[0] + row[1] * input[1] + row[2] * input[2]).clamp(0.0, 1.0)
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// A [`ColorCorrection`] with lookup tables, for correcting whole frames
+#[derive(Debug, Clone)]
+pub(crate) struct CorrectionTable {
+    correction: ColorCorrection,
+    decode: [f32; 256],
+    encode: [Vec<u8>; 3],
+}
+
+impl CorrectionTable {
+    pub(crate) fn new(correction: ColorCorrection) -> Self {
+        let decode = std::array::from_fn(|value| srgb_to_linear(value as u8));
+        let encode = std::array::from_fn(|channel| {
+            let gamma = correction.gamma[channel].max(f32::EPSILON);
+            (0..CORRECTION_LEVELS)
+                .map(|level| {
+                    let linear = level as f32 / (CORRECTION_LEVELS - 1) as f32;
+                    (linear.powf(1.0 / gamma) * 255.0).round() as u8
+                })
+                .collect()
+        });
+
+        Self {
+            correction,
+            decode,
+            encode,
+        }
+    }
+
+    pub(crate) fn correction(&self) -> &ColorCorrection {
+        &self.correction
+    }
+
+    /// Correct one RGB888 pixel
+    pub(crate) fn apply(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let input = rgb.map(|value| self.decode[value as usize]);
+        std::array::from_fn(|i| {
+            let level = (self.correction.mix(i, input) * (CORRECTION_LEVELS - 1) as f32).round();
+            self.encode[i][level as usize]
+        })
+    }
+}
+
+/// 4x4 Bayer matrix, thresholds 0-15
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// How RGB888 colors are reduced to the 5 bits per channel the displays
+/// keep, see [`ConversionOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dithering {
+    /// Drop the low bits; smooth gradients show bands
+    #[default]
+    None,
+    /// 4x4 Bayer pattern tied to the screen position, so it stays put in
+    /// animations and dirty-region updates
+    Ordered,
+    /// Error diffusion; the finest result for still images, but the noise
+    /// shifts whenever anything above or to the left changes
+    FloydSteinberg,
+}
+
+/// Options for converting RGB888 data to the displays' RGB565X
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConversionOptions {
+    pub dithering: Dithering,
+}
+
+impl ConversionOptions {
+    pub fn new(dithering: Dithering) -> Self {
+        Self { dithering }
+    }
+
+    /// Convert row-major RGB888 data, `width` pixels per row, as if it were
+    /// drawn at the top left of a display
+    pub fn convert(&self, rgb_data: &[u8], width: usize) -> Vec<Rgb565> {
+        let mut pixels = Vec::with_capacity(rgb_data.len() / 3);
+        let mut converter = Rgb565Converter::new(*self, None);
+        for (y, row) in rgb_data.chunks(width.max(1) * 3).enumerate() {
+            converter.convert_row(row, 0, y, &mut pixels);
+        }
+        pixels
+    }
+}
+
+/// Row-by-row RGB888 to RGB565X conversion with color correction and
+/// dithering. Rows must be fed top to bottom and all be the same width.
+#[derive(Debug)]
+pub(crate) struct Rgb565Converter<'a> {
+    correction: Option<&'a CorrectionTable>,
+    dithering: Dithering,
+    /// Floyd-Steinberg error in 1/16ths for the current and the next row,
+    /// with a pixel of padding at each end
+    errors: [Vec<[i16; 3]>; 2],
+}
+
+impl<'a> Rgb565Converter<'a> {
+    pub(crate) fn new(options: ConversionOptions, correction: Option<&'a CorrectionTable>) -> Self {
+        Self {
+            correction,
+            dithering: options.dithering,
+            errors: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// Convert one row of pixels starting at display position (`x`, `y`)
+    pub(crate) fn convert_row(
+        &mut self,
+        rgb_data: &[u8],
+        x: usize,
+        y: usize,
+        pixels: &mut Vec<Rgb565>,
+    ) {
+        let width = rgb_data.len() / 3;
+        if self.dithering == Dithering::FloydSteinberg && self.errors[0].len() != width + 2 {
+            self.errors = [vec![[0; 3]; width + 2], vec![[0; 3]; width + 2]];
+        }
+
+        for (i, chunk) in rgb_data.chunks_exact(3).enumerate() {
+            let mut rgb = [chunk[0], chunk[1], chunk[2]];
+            if let Some(table) = self.correction {
+                rgb = table.apply(rgb);
+            }
+
+            let rgb = match self.dithering {
+                Dithering::None => rgb,
+                Dithering::Ordered => {
+                    // Spread the threshold over the 8 levels truncation drops
+                    let threshold = BAYER_4X4[y % 4][(x + i) % 4] / 2;
+                    rgb.map(|value| value.saturating_add(threshold))
+                }
+                Dithering::FloydSteinberg => self.diffuse(i + 1, rgb),
+            };
+            pixels.push(Rgb565::new(rgb[0], rgb[1], rgb[2]));
+        }
+
+        if self.dithering == Dithering::FloydSteinberg {
+            self.errors.swap(0, 1);
+            self.errors[1].fill([0; 3]);
+        }
+    }
+
+    /// Quantize a pixel at padded index `j` and push the rounding error on
+    /// to its unvisited neighbours
+    fn diffuse(&mut self, j: usize, rgb: [u8; 3]) -> [u8; 3] {
+        std::array::from_fn(|c| {
+            let value = (rgb[c] as i16 + self.errors[0][j][c] / 16).clamp(0, 255);
+            let quantized = ((value + 4) & !7).min(248);
+            let error = value - quantized;
+
+            self.errors[0][j + 1][c] += error * 7;
+            self.errors[1][j - 1][c] += error * 3;
+            self.errors[1][j][c] += error * 5;
+            self.errors[1][j + 1][c] += error;
+            quantized as u8
+        })
+    }
+}
+
 /// RGB565X pixel format for displays (CORRECTED)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct Rgb565 {
     pub value: u16,
 }
@@ -413,6 +1116,19 @@ impl Rgb565 {
         Self::new(color.r, color.g, color.b)
     }
 
+    /// The color this pixel shows, undoing the channel rotation. Each channel
+    /// has 5 bits, widened back to 8, so for pixels made with
+    /// [`new`](Self::new) converting the result again gives the same pixel.
+    pub fn to_rgb(&self) -> RgbColor {
+        let widen = |bits: u16| ((bits << 3) | (bits >> 2)) as u8;
+        let value = self.value;
+        RgbColor {
+            r: widen(((value >> 13) << 2) | (value & 0x3)),
+            g: widen((value >> 8) & 0x1F),
+            b: widen((value >> 3) & 0x1F),
+        }
+    }
+
     pub fn black() -> Self {
         Self::new(0, 0, 0)
     }
@@ -521,7 +1237,7 @@ impl DisplayGraphics {
             for x in 0..width {
                 let checker_x = (x / square_size) % 2;
                 let checker_y = (y / square_size) % 2;
-                let color = if (checker_x + checker_y) % 2 == 0 {
+                let color = if (checker_x + checker_y).is_multiple_of(2) {
                     color1
                 } else {
                     color2
@@ -556,6 +1272,118 @@ impl DisplayGraphics {
         pixels
     }
 
+    /// Simple logo: a 4x4 grid of pads in rainbow colors, centered on a dark
+    /// background
+    pub fn logo(width: u16, height: u16) -> Vec<Rgb565> {
+        let mut pixels = vec![Rgb565::new(16, 16, 16); width as usize * height as usize];
+
+        let cell = (width.min(height) as usize * 3 / 4) / 4;
+        let gap = (cell / 6).max(1);
+        let grid = cell * 4;
+        let left = (width as usize).saturating_sub(grid) / 2;
+        let top = (height as usize).saturating_sub(grid) / 2;
+
+        // Too small for the pads to show between their gaps
+        if cell <= 2 * gap {
+            return pixels;
+        }
+
+        for pad in 0..16 {
+            let color = Rgb565::from_hsv(pad as f32 * 360.0 / 16.0, 0.8, 0.9);
+            let pad_x = left + (pad % 4) * cell;
+            let pad_y = top + (pad / 4) * cell; // Pads are numbered from the top-left
+
+            for y in pad_y + gap..pad_y + cell - gap {
+                let row = y * width as usize;
+                pixels[row + pad_x + gap..row + pad_x + cell - gap].fill(color);
+            }
+        }
+
+        pixels
+    }
+
+    /// Render a waveform into a region-sized buffer, for use with
+    /// `write_display_region`. Samples are expected in the -1.0..=1.0 range and
+    /// are bucketed so each column shows the min/max envelope of its samples.
+    pub fn waveform(samples: &[f32], rect: DisplayRect, color: Rgb565) -> Vec<Rgb565> {
+        let width = rect.width as usize;
+        let height = rect.height as usize;
+        let mut pixels = vec![Rgb565::black(); rect.area()];
+
+        if samples.is_empty() || width == 0 || height == 0 {
+            return pixels;
+        }
+
+        let to_row = |sample: f32| {
+            let normalized = (1.0 - sample.clamp(-1.0, 1.0)) / 2.0; // 0.0 = top, 1.0 = bottom
+            ((normalized * (height - 1) as f32).round() as usize).min(height - 1)
+        };
+
+        for x in 0..width {
+            let start = x * samples.len() / width;
+            let end = ((x + 1) * samples.len() / width).max(start + 1).min(samples.len());
+
+            let (min, max) = samples[start..end]
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+
+            for y in to_row(max)..=to_row(min) {
+                pixels[y * width + x] = color;
+            }
+        }
+
+        pixels
+    }
+
+    /// Render a level meter into a region-sized buffer, for use with
+    /// `write_display_region`. The meter spans -60 dB to 0 dB and is drawn
+    /// vertically when the region is taller than it is wide, horizontally
+    /// otherwise. Unlit segments are drawn in a dim version of their color.
+    pub fn level_meter(db: f32, rect: DisplayRect) -> Vec<Rgb565> {
+        const MIN_DB: f32 = -60.0;
+
+        let width = rect.width as usize;
+        let height = rect.height as usize;
+        let vertical = height > width;
+        let length = if vertical { height } else { width };
+        let mut pixels = vec![Rgb565::black(); rect.area()];
+
+        if length == 0 {
+            return pixels;
+        }
+
+        let level = ((db.max(MIN_DB) - MIN_DB) / -MIN_DB).clamp(0.0, 1.0);
+        let lit = (level * length as f32).round() as usize;
+
+        for position in 0..length {
+            let position_db = MIN_DB - MIN_DB * (position as f32 + 0.5) / length as f32;
+            let (r, g, b) = if position_db >= -3.0 {
+                (255, 0, 0)
+            } else if position_db >= -12.0 {
+                (255, 200, 0)
+            } else {
+                (0, 255, 0)
+            };
+            let color = if position < lit {
+                Rgb565::new(r, g, b)
+            } else {
+                Rgb565::new(r / 6, g / 6, b / 6)
+            };
+
+            if vertical {
+                // Fill from the bottom up
+                let y = height - 1 - position;
+                pixels[y * width..(y + 1) * width].fill(color);
+            } else {
+                for y in 0..height {
+                    pixels[y * width + position] = color;
+                }
+            }
+        }
+
+        pixels
+    }
+
     fn lerp_color(color1: Rgb565, color2: Rgb565, t: f32) -> Rgb565 {
         // Extract RGB components from RGB565
         let r1 = ((color1.value >> 11) & 0x1F) as f32 * 8.0;
@@ -574,8 +1402,220 @@ impl DisplayGraphics {
     }
 }
 
+/// Rectangular area of a display in pixel coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DisplayRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl DisplayRect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Rectangle covering an entire 480x272 display
+    pub fn full_screen() -> Self {
+        Self::new(0, 0, 480, 272)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Number of pixels covered by this rectangle
+    pub fn area(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Rectangle of `width` x `height` pixels with its top-left corner at
+    /// `origin`; an error unless it is non-empty and fits on the 480x272
+    /// display
+    pub fn checked(origin: DisplayPoint, width: u16, height: u16) -> crate::error::Result<Self> {
+        let rect = Self::new(origin.x, origin.y, width, height);
+        if !rect.is_on_screen() {
+            return Err(crate::error::MK3Error::InvalidData(format!(
+                "Region {:?} is outside the {}x{} display",
+                rect,
+                MaschineMK3::DISPLAY_WIDTH,
+                MaschineMK3::DISPLAY_HEIGHT
+            )));
+        }
+        Ok(rect)
+    }
+
+    /// Top-left corner
+    pub fn origin(&self) -> DisplayPoint {
+        DisplayPoint {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    /// Whether the rectangle is non-empty and lies within the display
+    pub fn is_on_screen(&self) -> bool {
+        !self.is_empty()
+            && self.right() <= MaschineMK3::DISPLAY_WIDTH
+            && self.bottom() <= MaschineMK3::DISPLAY_HEIGHT
+    }
+
+    pub fn contains(&self, point: DisplayPoint) -> bool {
+        (self.x..self.right()).contains(&point.x) && (self.y..self.bottom()).contains(&point.y)
+    }
+
+    /// Exclusive right edge, saturating at `u16::MAX`
+    pub fn right(&self) -> u16 {
+        self.x.saturating_add(self.width)
+    }
+
+    /// Exclusive bottom edge, saturating at `u16::MAX`
+    pub fn bottom(&self) -> u16 {
+        self.y.saturating_add(self.height)
+    }
+
+    /// Smallest rectangle containing both rectangles
+    pub fn union(&self, other: &DisplayRect) -> DisplayRect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        DisplayRect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Clip this rectangle so it lies within `bounds`
+    pub fn clipped_to(&self, bounds: &DisplayRect) -> DisplayRect {
+        let x = self.x.max(bounds.x);
+        let y = self.y.max(bounds.y);
+        let right = self.right().min(bounds.right());
+        let bottom = self.bottom().min(bounds.bottom());
+
+        if right <= x || bottom <= y {
+            return DisplayRect::new(x, y, 0, 0);
+        }
+        DisplayRect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Widen the rectangle so it starts on an even column and has an even width.
+    /// The transmit command counts pixels in pairs, so odd-sized regions would
+    /// drop their last pixel.
+    pub fn aligned_to_pixel_pairs(&self) -> DisplayRect {
+        let x = self.x & !1;
+        let right = (self.right() + 1) & !1;
+        DisplayRect::new(x, self.y, right - x, self.height)
+    }
+}
+
+/// What the last dirty-region write to a display actually transferred
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirtyStats {
+    /// Region sent, or `None` if the frame was unchanged and nothing was sent
+    pub region: Option<DisplayRect>,
+    /// Whether the whole screen was sent
+    pub full_frame: bool,
+    pub pixels_sent: usize,
+    /// Pixel payload in bytes (RGB565, excluding packet headers)
+    pub bytes_sent: usize,
+    /// Whether the packet was reused from the display's packet cache
+    /// instead of being converted again
+    pub from_cache: bool,
+}
+
+impl DirtyStats {
+    pub(crate) fn for_region(region: DisplayRect) -> Self {
+        Self {
+            region: Some(region),
+            full_frame: region == DisplayRect::full_screen(),
+            pixels_sent: region.area(),
+            bytes_sent: region.area() * 2,
+            from_cache: false,
+        }
+    }
+}
+
+/// Packets kept per display unless changed with
+/// `MaschineMK3::set_display_packet_cache`
+pub const DEFAULT_PACKET_CACHE_ENTRIES: usize = 4;
+
+/// Display packets built from recently sent regions, most recently used
+/// first, keyed by a hash of the region and its pixels
+#[derive(Debug)]
+pub(crate) struct PacketCache {
+    capacity: usize,
+    entries: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl Default for PacketCache {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_PACKET_CACHE_ENTRIES,
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl PacketCache {
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Key for `rect` of a row-major frame `frame_width` pixels wide, with
+    /// `bpp` bytes per pixel
+    pub(crate) fn key(rect: DisplayRect, frame: &[u8], frame_width: usize, bpp: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (rect.x, rect.y, rect.width, rect.height, bpp).hash(&mut hasher);
+        let row_bytes = rect.width as usize * bpp;
+        for row in rect.y as usize..rect.bottom() as usize {
+            let start = (row * frame_width + rect.x as usize) * bpp;
+            hasher.write(&frame[start..start + row_bytes]);
+        }
+        hasher.finish()
+    }
+
+    /// Remove and return the packet stored under `key`; put it back with
+    /// [`insert`](Self::insert) once it has been sent
+    pub(crate) fn take(&mut self, key: u64) -> Option<Vec<u8>> {
+        let index = self.entries.iter().position(|(k, _)| *k == key)?;
+        self.entries.remove(index).map(|(_, packet)| packet)
+    }
+
+    pub(crate) fn insert(&mut self, key: u64, packet: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.truncate(self.capacity - 1);
+        self.entries.push_front((key, packet));
+    }
+}
+
 /// Display command for the MK3 displays
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DisplayCommand {
     /// Transmit pixels directly
     TransmitPixels { pixels: Vec<Rgb565> },
@@ -591,7 +1631,15 @@ pub enum DisplayCommand {
     EndTransmission,
 }
 
+/// Display packet header length in bytes
+const DISPLAY_HEADER_LEN: usize = 16;
+
+/// Bytes a region packet adds to its pixel data: header, transmit command,
+/// blit and end command
+const REGION_PACKET_OVERHEAD: usize = DISPLAY_HEADER_LEN + 12;
+
 /// Display packet builder for Type 0x84 packets
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DisplayPacket {
     display_id: u8, // 0 = left, 1 = right
     x_start: u16,
@@ -643,10 +1691,244 @@ impl DisplayPacket {
         packet
     }
 
+    /// Create a packet that updates a single region of a display
+    pub fn region(display_id: u8, rect: DisplayRect, pixels: Vec<Rgb565>) -> Self {
+        let mut packet = Self::new(display_id, rect.x, rect.y, rect.width, rect.height);
+        packet.add_pixels(pixels);
+        packet.add_blit();
+        packet.finish();
+        packet
+    }
+
+    /// Decode a Type 0x84 packet, e.g. from a USB capture. The inverse of
+    /// [`to_packet`](Self::to_packet).
+    ///
+    /// The header and every command must be complete, and the packet must
+    /// stop right after the end command (0x40). Display number and region
+    /// aren't range checked; [`RawPacket`] does that before sending.
+    pub fn parse(data: &[u8]) -> crate::error::Result<Self> {
+        Self::decode(data).map_err(crate::error::MK3Error::InvalidData)
+    }
+
+    fn decode(data: &[u8]) -> std::result::Result<Self, String> {
+        if data.len() < DISPLAY_HEADER_LEN {
+            return Err(format!(
+                "Display packet is {} bytes, shorter than its {}-byte header",
+                data.len(),
+                DISPLAY_HEADER_LEN
+            ));
+        }
+        if data[0] != 0x84 || data[1] != 0x00 || data[3] != 0x60 {
+            return Err(format!(
+                "Not a display packet header: {:02X} {:02X} {:02X} {:02X}",
+                data[0], data[1], data[2], data[3]
+            ));
+        }
+
+        let field = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        let mut packet = Self::new(data[2], field(8), field(10), field(12), field(14));
+        let pixel = |bytes: &[u8]| Rgb565 {
+            value: u16::from_le_bytes([bytes[0], bytes[1]]),
+        };
+
+        let mut offset = DISPLAY_HEADER_LEN;
+        loop {
+            let Some(command) = data.get(offset..offset + 4) else {
+                return Err(format!(
+                    "Display packet ends at byte {} without an end command",
+                    data.len()
+                ));
+            };
+            let count = u32::from_be_bytes([0, command[1], command[2], command[3]]);
+            let payload_len = match command[0] {
+                0x00 => count as usize * 4,
+                0x01 => 4,
+                0x03 | 0x40 => 0,
+                other => {
+                    return Err(format!(
+                        "Unknown display command 0x{:02X} at byte {}",
+                        other, offset
+                    ))
+                }
+            };
+            let start = offset + 4;
+            let Some(payload) = data.get(start..start + payload_len) else {
+                return Err(format!(
+                    "Display command 0x{:02X} needs {} bytes but the packet has {}",
+                    command[0],
+                    start + payload_len,
+                    data.len()
+                ));
+            };
+
+            match command[0] {
+                0x00 => packet.add_pixels(payload.chunks_exact(2).map(pixel).collect()),
+                0x01 => packet.add_repeat(pixel(&payload[0..2]), pixel(&payload[2..4]), count),
+                0x03 => packet.add_blit(),
+                _ => {
+                    packet.finish();
+                    break;
+                }
+            }
+            offset = start + payload_len;
+        }
+
+        let end = offset + 4;
+        if end != data.len() {
+            return Err(format!("{} bytes after the end command", data.len() - end));
+        }
+        Ok(packet)
+    }
+
+    /// Display the packet draws to (0 = left, 1 = right)
+    pub fn display_id(&self) -> u8 {
+        self.display_id
+    }
+
+    /// Region of the display the packet draws to
+    pub fn rect(&self) -> DisplayRect {
+        DisplayRect::new(self.x_start, self.y_start, self.width, self.height)
+    }
+
+    pub fn commands(&self) -> &[DisplayCommand] {
+        &self.commands
+    }
+
+    /// Split into packets for bands of whole rows of the same region, each
+    /// at most `max_len` bytes once encoded, for USB controllers that stall
+    /// on large transfers. Each band is blitted on its own.
+    ///
+    /// A band holds at least one row (two if the region's width is odd, to
+    /// keep pixel counts even), so a very small `max_len` can be exceeded.
+    /// A packet that already fits, or whose commands don't cover its region
+    /// exactly, is returned unchanged.
+    pub fn split_rows(&self, max_len: usize) -> Vec<DisplayPacket> {
+        let width = self.width as usize;
+        if self.encoded_len() <= max_len || width == 0 || self.pixel_count() != self.rect().area()
+        {
+            return vec![self.clone()];
+        }
+
+        let mut rows = (max_len.saturating_sub(REGION_PACKET_OVERHEAD) / (width * 2)).max(1);
+        if width % 2 == 1 {
+            rows = (rows & !1).max(2);
+        }
+
+        self.pixels()
+            .chunks(rows * width)
+            .enumerate()
+            .map(|(band, pixels)| {
+                let y = self.y_start + (band * rows) as u16;
+                let height = (pixels.len() / width) as u16;
+                let rect = DisplayRect::new(self.x_start, y, self.width, height);
+                Self::region(self.display_id, rect, pixels.to_vec())
+            })
+            .collect()
+    }
+
+    /// Length of [`to_packet`](Self::to_packet)'s output
+    fn encoded_len(&self) -> usize {
+        let commands: usize = self
+            .commands
+            .iter()
+            .map(|command| match command {
+                DisplayCommand::TransmitPixels { pixels } => 4 + pixels.len() * 2,
+                DisplayCommand::RepeatPixels { .. } => 8,
+                DisplayCommand::Blit | DisplayCommand::EndTransmission => 4,
+            })
+            .sum();
+        DISPLAY_HEADER_LEN + commands
+    }
+
+    /// Pixels the packet draws, in order, with repeats expanded
+    fn pixels(&self) -> Vec<Rgb565> {
+        let mut pixels = Vec::with_capacity(self.pixel_count());
+        for command in &self.commands {
+            match command {
+                DisplayCommand::TransmitPixels { pixels: transmitted } => {
+                    pixels.extend_from_slice(transmitted)
+                }
+                DisplayCommand::RepeatPixels {
+                    pixel1,
+                    pixel2,
+                    count,
+                } => {
+                    for _ in 0..*count {
+                        pixels.extend_from_slice(&[*pixel1, *pixel2]);
+                    }
+                }
+                DisplayCommand::Blit | DisplayCommand::EndTransmission => {}
+            }
+        }
+        pixels
+    }
+
+    /// Pixels the transmit and repeat commands cover
+    pub fn pixel_count(&self) -> usize {
+        self.commands
+            .iter()
+            .map(|command| match command {
+                DisplayCommand::TransmitPixels { pixels } => pixels.len(),
+                DisplayCommand::RepeatPixels { count, .. } => *count as usize * 2,
+                DisplayCommand::Blit | DisplayCommand::EndTransmission => 0,
+            })
+            .sum()
+    }
+
+    /// Encode a region packet straight from a pixel iterator, without
+    /// collecting the pixels first. Same bytes as
+    /// [`region`](Self::region)`(..).to_packet()`; errors if the iterator
+    /// doesn't yield exactly `rect.area()` pixels. `rect.x` and `rect.width`
+    /// should be even, see [`DisplayRect::aligned_to_pixel_pairs`].
+    pub fn encode_region(
+        display_id: u8,
+        rect: DisplayRect,
+        pixels: impl IntoIterator<Item = Rgb565>,
+    ) -> crate::error::Result<Vec<u8>> {
+        let area = rect.area();
+        let mut packet = Vec::with_capacity(REGION_PACKET_OVERHEAD + area * 2);
+        Self::new(display_id, rect.x, rect.y, rect.width, rect.height).push_header(&mut packet);
+
+        let half_pixels = (area / 2) as u32;
+        packet.extend_from_slice(&[
+            0x00,
+            (half_pixels >> 16) as u8,
+            (half_pixels >> 8) as u8,
+            (half_pixels & 0xFF) as u8,
+        ]);
+        let mut pixels = pixels.into_iter();
+        let mut count = 0;
+        for pixel in pixels.by_ref().take(area) {
+            packet.extend_from_slice(&pixel.value.to_le_bytes());
+            count += 1;
+        }
+        if count < area {
+            return Err(crate::error::MK3Error::InvalidData(format!(
+                "Expected {} pixels, got {}",
+                area, count
+            )));
+        }
+        if pixels.next().is_some() {
+            return Err(crate::error::MK3Error::InvalidData(format!(
+                "Expected {} pixels, got more",
+                area
+            )));
+        }
+
+        packet.extend_from_slice(&[0x03, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00]);
+        Ok(packet)
+    }
+
     /// Build the complete display packet (CORRECTED)
     pub fn to_packet(&self) -> Vec<u8> {
         let mut packet = Vec::new();
+        self.push_header(&mut packet);
+        self.push_commands(&mut packet);
+        packet
+    }
 
+    /// The 16-byte header
+    fn push_header(&self, packet: &mut Vec<u8>) {
         // Header (16 bytes total) - CORRECTED FORMAT
         packet.extend_from_slice(&[
             0x84,
@@ -666,7 +1948,10 @@ impl DisplayPacket {
             (self.height >> 8) as u8,    // Height MSB
             (self.height & 0xFF) as u8,  // Height LSB
         ]);
+    }
 
+    /// Command bytes, following the header
+    fn push_commands(&self, packet: &mut Vec<u8>) {
         // Add commands
         for command in &self.commands {
             match command {
@@ -710,7 +1995,94 @@ impl DisplayPacket {
                 }
             }
         }
+    }
+}
 
-        packet
+/// Raw Type 0x84 display packet, checked to be well formed before it can be
+/// sent with [`MaschineMK3::send_raw_packet`](crate::MaschineMK3::send_raw_packet).
+///
+/// The header has to name display 0 or 1 and a non-empty region inside the
+/// 480x272 screen. The commands have to be transmit (0x00), repeat (0x01)
+/// and blit (0x03) with their payloads fully present, together covering
+/// exactly the region's pixels, followed by the end command (0x40) and
+/// nothing after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPacket {
+    data: Vec<u8>,
+}
+
+impl RawPacket {
+    /// Validate `data` as a display packet
+    pub fn new(data: Vec<u8>) -> crate::error::Result<Self> {
+        Self::validate(&data).map_err(crate::error::MK3Error::InvalidData)?;
+        Ok(Self { data })
+    }
+
+    pub fn display_id(&self) -> u8 {
+        self.data[2]
+    }
+
+    /// Region of the display the packet draws to
+    pub fn rect(&self) -> DisplayRect {
+        let field = |offset: usize| u16::from_be_bytes([self.data[offset], self.data[offset + 1]]);
+        DisplayRect::new(field(8), field(10), field(12), field(14))
     }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub(crate) fn validate(data: &[u8]) -> std::result::Result<(), String> {
+        let packet = DisplayPacket::decode(data)?;
+        if data[4..8] != [0; 4] {
+            return Err("Reserved header bytes 4-7 must be zero".to_string());
+        }
+        if packet.display_id() > 1 {
+            return Err(format!("Invalid display {}", packet.display_id()));
+        }
+
+        let rect = packet.rect();
+        let screen = DisplayRect::full_screen();
+        if rect.is_empty() || rect.right() > screen.width || rect.bottom() > screen.height {
+            return Err(format!(
+                "Region {:?} is outside the {}x{} display",
+                rect, screen.width, screen.height
+            ));
+        }
+        if packet.pixel_count() != rect.area() {
+            return Err(format!(
+                "Commands cover {} pixels but the region has {}",
+                packet.pixel_count(),
+                rect.area()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Encode a full 480x272 RGB888 display frame as a PNG file
+#[cfg(feature = "png")]
+pub(crate) fn save_rgb888_png(
+    path: &std::path::Path,
+    rgb_data: &[u8],
+) -> crate::error::Result<()> {
+    use crate::error::MK3Error;
+
+    let screen = DisplayRect::full_screen();
+    let png_error = |e: png::EncodingError| match e {
+        png::EncodingError::IoError(e) => MK3Error::Io(e),
+        e => MK3Error::InvalidData(format!("PNG encode error: {}", e)),
+    };
+
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, screen.width as u32, screen.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(rgb_data).map_err(png_error)?;
+    writer.finish().map_err(png_error)
 }
@@ -0,0 +1,59 @@
+use crate::health::UsbEndpoint;
+use crate::output::{ButtonLedState, PadLedState, RawPacket};
+
+/// Largest value of an LED byte: 7-bit brightness for single-color LEDs,
+/// and the palette bytes stay below it
+const MAX_LED_VALUE: u8 = 127;
+
+/// Check an outgoing packet against the protocol before it is sent.
+/// Malformed packets panic in debug builds and are logged in release builds,
+/// then sent anyway.
+pub(crate) fn verify_output(endpoint: UsbEndpoint, data: &[u8]) {
+    let result = match endpoint {
+        UsbEndpoint::Led => check_led_packet(data),
+        UsbEndpoint::Display => RawPacket::validate(data),
+        UsbEndpoint::Input => Ok(()),
+    };
+    if let Err(problem) = result {
+        if cfg!(debug_assertions) {
+            panic!(
+                "Malformed {} packet ({} bytes): {}",
+                endpoint.name(),
+                data.len(),
+                problem
+            );
+        }
+        log::error!(
+            "Malformed {} packet ({} bytes): {}",
+            endpoint.name(),
+            data.len(),
+            problem
+        );
+    }
+}
+
+/// A Type 0x80 or 0x81 packet of its full length, with every LED byte in
+/// range
+fn check_led_packet(data: &[u8]) -> Result<(), String> {
+    let expected = match data.first() {
+        Some(0x80) => ButtonLedState::PACKET_LEN,
+        Some(0x81) => PadLedState::PACKET_LEN,
+        Some(other) => return Err(format!("Unknown LED packet type 0x{:02X}", other)),
+        None => return Err("Empty LED packet".to_string()),
+    };
+    if data.len() != expected {
+        return Err(format!(
+            "Type 0x{:02X} packets are {} bytes",
+            data[0], expected
+        ));
+    }
+    match data[1..].iter().position(|&value| value > MAX_LED_VALUE) {
+        Some(index) => Err(format!(
+            "LED byte {} is {}, above {}",
+            index + 1,
+            data[index + 1],
+            MAX_LED_VALUE
+        )),
+        None => Ok(()),
+    }
+}
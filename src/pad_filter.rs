@@ -0,0 +1,155 @@
+use crate::input::{PadEvent, PadEventType};
+use std::time::{Duration, Instant};
+
+/// Settings for dropping duplicate and ghost pad hits
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadFilterConfig {
+    /// A pad hit again this soon after its last accepted hit is treated as a
+    /// duplicate
+    pub debounce: Duration,
+    /// How long after a hit its neighbors are checked for crosstalk
+    pub crosstalk_window: Duration,
+    /// A neighbor hit within `crosstalk_window` is a ghost if its velocity is
+    /// below this fraction of the original hit's
+    pub crosstalk_ratio: f32,
+    /// Minimum hit velocity per pad (0-4095); softer hits are dropped
+    pub thresholds: [u16; 16],
+}
+
+impl Default for PadFilterConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(20),
+            crosstalk_window: Duration::from_millis(5),
+            crosstalk_ratio: 0.5,
+            thresholds: [0; 16],
+        }
+    }
+}
+
+impl PadFilterConfig {
+    /// Pass every pad event through unchanged
+    pub fn disabled() -> Self {
+        Self {
+            debounce: Duration::ZERO,
+            crosstalk_window: Duration::ZERO,
+            crosstalk_ratio: 0.0,
+            thresholds: [0; 16],
+        }
+    }
+}
+
+/// Drops duplicate hits, crosstalk from neighboring pads and hits below a
+/// pad's threshold, along with the aftertouch and release that follow them
+#[derive(Debug, Clone, Default)]
+pub struct PadHitFilter {
+    config: PadFilterConfig,
+    /// Time and velocity of the last accepted hit per pad
+    last_hit: [Option<(Instant, u16)>; 16],
+    /// Pads with an accepted hit that hasn't been released
+    active: [bool; 16],
+    /// Pads whose current hit was dropped, so its follow-up is dropped too
+    suppressed: [bool; 16],
+}
+
+impl PadHitFilter {
+    pub fn new(config: PadFilterConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &PadFilterConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: PadFilterConfig) {
+        self.config = config;
+    }
+
+    /// Filter the events of one pad packet received at `now`
+    pub fn filter(&mut self, events: Vec<PadEvent>, now: Instant) -> Vec<PadEvent> {
+        // The ghost can arrive in the same packet as, or even before, the
+        // hit that caused it, so compare against every hit in the packet
+        let mut packet_hits = [0u16; 16];
+        for event in &events {
+            if event.event_type == PadEventType::Hit {
+                if let Some(velocity) = packet_hits.get_mut(event.pad_number as usize) {
+                    *velocity = (*velocity).max(event.value);
+                }
+            }
+        }
+
+        events
+            .into_iter()
+            .filter(|event| self.accept(event, &packet_hits, now))
+            .collect()
+    }
+
+    fn accept(&mut self, event: &PadEvent, packet_hits: &[u16; 16], now: Instant) -> bool {
+        let pad = event.pad_number as usize;
+        if pad >= 16 {
+            return true;
+        }
+
+        match event.event_type {
+            PadEventType::Hit => {
+                let duplicate = self.last_hit[pad]
+                    .is_some_and(|(at, _)| now.duration_since(at) < self.config.debounce);
+                if duplicate && self.active[pad] {
+                    // Repeated hit without a release; the release that
+                    // follows belongs to the accepted hit
+                    return false;
+                }
+
+                if duplicate
+                    || event.value < self.config.thresholds[pad]
+                    || self.is_crosstalk(pad, event.value, packet_hits, now)
+                {
+                    self.suppressed[pad] = true;
+                    return false;
+                }
+
+                self.last_hit[pad] = Some((now, event.value));
+                self.active[pad] = true;
+                self.suppressed[pad] = false;
+                true
+            }
+            PadEventType::Aftertouch => !self.suppressed[pad],
+            PadEventType::HitRelease | PadEventType::TouchRelease => {
+                self.active[pad] = false;
+                !std::mem::take(&mut self.suppressed[pad])
+            }
+        }
+    }
+
+    /// Whether a neighbor was hit hard enough, recently enough, to explain
+    /// this hit
+    fn is_crosstalk(
+        &self,
+        pad: usize,
+        velocity: u16,
+        packet_hits: &[u16; 16],
+        now: Instant,
+    ) -> bool {
+        let limit = |neighbor_velocity: u16| neighbor_velocity as f32 * self.config.crosstalk_ratio;
+
+        neighbors(pad).any(|neighbor| {
+            let recent = self.last_hit[neighbor].is_some_and(|(at, neighbor_velocity)| {
+                now.duration_since(at) < self.config.crosstalk_window
+                    && (velocity as f32) < limit(neighbor_velocity)
+            });
+            recent || (velocity as f32) < limit(packet_hits[neighbor])
+        })
+    }
+}
+
+/// Pads touching `pad` on the 4x4 grid, including diagonals
+fn neighbors(pad: usize) -> impl Iterator<Item = usize> {
+    let (row, col) = ((pad / 4) as i32, (pad % 4) as i32);
+    (-1..=1)
+        .flat_map(move |dr| (-1..=1).map(move |dc| (row + dr, col + dc)))
+        .filter(move |&(r, c)| (r, c) != (row, col) && (0..4).contains(&r) && (0..4).contains(&c))
+        .map(|(r, c)| (r * 4 + c) as usize)
+}
@@ -0,0 +1,118 @@
+use crate::input::{InputEvent, PadEventType};
+use crate::output::{ColorRamp, MaschineLEDColor};
+use std::time::{Duration, Instant};
+
+/// Flash pads in a velocity color when hit, see
+/// [`MaschineMK3::set_pad_hit_flash`](crate::MaschineMK3::set_pad_hit_flash)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PadFlashConfig {
+    pub ramp: ColorRamp,
+    /// How long the pad stays at full brightness
+    pub hold: Duration,
+    /// How long the dim tail lasts before the pad returns to its own color
+    pub decay: Duration,
+}
+
+impl Default for PadFlashConfig {
+    fn default() -> Self {
+        Self {
+            ramp: ColorRamp::Classic,
+            hold: Duration::from_millis(80),
+            decay: Duration::from_millis(160),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Flash {
+    started: Instant,
+    color: MaschineLEDColor,
+    /// What the pad showed before the flash
+    base: MaschineLEDColor,
+    /// What the flash last wrote, to notice the app overwriting it
+    shown: MaschineLEDColor,
+}
+
+/// Pad flashes in progress
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PadFlasher {
+    flashes: [Option<Flash>; 16],
+}
+
+impl PadFlasher {
+    /// Start flashes for hits in `events` and advance running ones. Returns
+    /// whether `pads` changed.
+    pub(crate) fn update(
+        &mut self,
+        config: &PadFlashConfig,
+        pads: &mut [MaschineLEDColor; 16],
+        events: &[InputEvent],
+        now: Instant,
+    ) -> bool {
+        let mut changed = false;
+
+        for event in events {
+            let InputEvent::PadEvent {
+                pad_number,
+                event_type: PadEventType::Hit,
+                value,
+            } = event
+            else {
+                continue;
+            };
+            let Some(pad) = pads.get(*pad_number as usize) else {
+                continue;
+            };
+
+            // A re-hit keeps the color from before the first flash
+            let base = match self.flashes[*pad_number as usize] {
+                Some(flash) if flash.shown == *pad => flash.base,
+                _ => *pad,
+            };
+            let color = MaschineLEDColor::from_velocity(*value, config.ramp);
+            self.flashes[*pad_number as usize] = Some(Flash {
+                started: now,
+                color,
+                base,
+                shown: *pad,
+            });
+        }
+
+        for (pad, slot) in pads.iter_mut().zip(self.flashes.iter_mut()) {
+            let Some(flash) = slot else {
+                continue;
+            };
+            if *pad != flash.shown {
+                // The app set this pad during the flash; leave it alone
+                *slot = None;
+                continue;
+            }
+
+            let elapsed = now.duration_since(flash.started);
+            let color = if elapsed < config.hold {
+                flash.color
+            } else if elapsed < config.hold + config.decay {
+                flash.color.scaled(0.25)
+            } else {
+                let base = flash.base;
+                *slot = None;
+                base
+            };
+
+            if let Some(flash) = slot {
+                flash.shown = color;
+            }
+            if *pad != color {
+                *pad = color;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Whether any flash still needs updates
+    pub(crate) fn is_active(&self) -> bool {
+        self.flashes.iter().any(Option::is_some)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::device::MaschineDevice;
+use crate::error::Result;
+use crate::input::{InputElement, InputEvent};
+use crate::knob::KnobMapper;
+use crate::output::{LedBrightness, LedFrame};
+
+/// Knobs per page
+pub const BANK_SLOTS: usize = 8;
+
+const DISPLAY_BUTTONS: [InputElement; BANK_SLOTS] = [
+    InputElement::DisplayButton1,
+    InputElement::DisplayButton2,
+    InputElement::DisplayButton3,
+    InputElement::DisplayButton4,
+    InputElement::DisplayButton5,
+    InputElement::DisplayButton6,
+    InputElement::DisplayButton7,
+    InputElement::DisplayButton8,
+];
+
+const KNOBS: [InputElement; BANK_SLOTS] = [
+    InputElement::Knob1,
+    InputElement::Knob2,
+    InputElement::Knob3,
+    InputElement::Knob4,
+    InputElement::Knob5,
+    InputElement::Knob6,
+    InputElement::Knob7,
+    InputElement::Knob8,
+];
+
+/// Something a [`ParameterBank`] did in response to input
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankEvent {
+    /// A different page was selected
+    PageChanged { page: usize },
+    /// A knob changed the parameter in `slot` (0-7) of `page`
+    ParameterChanged {
+        page: usize,
+        slot: usize,
+        value: f32,
+    },
+}
+
+/// Eight knob parameters per page, any number of pages.
+///
+/// The display buttons above the screens select pages, eight at a time; with
+/// more than eight pages the arrow buttons step to the previous and next
+/// page. Every parameter uses soft takeover, so after a page switch a knob
+/// only takes effect once it reaches the value its new parameter already
+/// has. The display button of the current page is lit brightly and those of
+/// the other pages in view dimly.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{BankEvent, MaschineMK3, ParameterBank};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut bank = ParameterBank::new(3);
+/// if let Some(cutoff) = bank.parameter_mut(0, 0) {
+///     cutoff.set_range(20.0, 20000.0);
+/// }
+/// bank.show_page_leds(&mut device)?;
+///
+/// loop {
+///     let events = device.poll_input_events()?;
+///     for change in bank.handle_events(&mut device, &events)? {
+///         if let BankEvent::ParameterChanged { page, slot, value } = change {
+///             println!("Page {} slot {}: {:.2}", page, slot, value);
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParameterBank {
+    pages: Vec<[KnobMapper; BANK_SLOTS]>,
+    page: usize,
+    selected_brightness: LedBrightness,
+    available_brightness: LedBrightness,
+}
+
+impl ParameterBank {
+    /// Bank of `pages` pages (at least one) of parameters ranging 0.0-1.0
+    pub fn new(pages: usize) -> Self {
+        Self {
+            pages: (0..pages.max(1)).map(|_| new_page()).collect(),
+            page: 0,
+            selected_brightness: 127,
+            available_brightness: 16,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Currently selected page
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    /// Select a page. Returns false if it doesn't exist.
+    ///
+    /// Every knob on the new page has to pick up its parameter again before
+    /// it takes effect.
+    pub fn set_page(&mut self, page: usize) -> bool {
+        let Some(parameters) = self.pages.get_mut(page) else {
+            return false;
+        };
+        for parameter in parameters.iter_mut() {
+            parameter.set_value(parameter.value());
+        }
+        self.page = page;
+        true
+    }
+
+    /// Add a page at the end and return its index
+    pub fn add_page(&mut self) -> usize {
+        self.pages.push(new_page());
+        self.pages.len() - 1
+    }
+
+    /// Mapping of one parameter, to change its range or taper
+    pub fn parameter(&self, page: usize, slot: usize) -> Option<&KnobMapper> {
+        self.pages.get(page)?.get(slot)
+    }
+
+    pub fn parameter_mut(&mut self, page: usize, slot: usize) -> Option<&mut KnobMapper> {
+        self.pages.get_mut(page)?.get_mut(slot)
+    }
+
+    pub fn value(&self, page: usize, slot: usize) -> Option<f32> {
+        self.parameter(page, slot).map(KnobMapper::value)
+    }
+
+    /// Set a parameter from elsewhere (preset load, UI). If it's on the
+    /// current page its knob has to pick it up again.
+    pub fn set_value(&mut self, page: usize, slot: usize, value: f32) -> bool {
+        match self.parameter_mut(page, slot) {
+            Some(parameter) => {
+                parameter.set_value(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// LED brightness of the current page's display button and of the other
+    /// pages in view (127 and 16 by default)
+    pub fn set_page_led_brightness(&mut self, selected: LedBrightness, available: LedBrightness) {
+        self.selected_brightness = selected;
+        self.available_brightness = available;
+    }
+
+    /// Handle one input event without touching the device. Display buttons
+    /// and arrows switch pages; knobs update the current page.
+    pub fn handle_event(&mut self, event: &InputEvent) -> Option<BankEvent> {
+        match event {
+            InputEvent::ButtonPressed(element) => {
+                let target = if let Some(index) = slot_of(&DISPLAY_BUTTONS, element) {
+                    self.first_page_in_view() + index
+                } else if *element == InputElement::ArrowLeft && self.has_more_than_a_view() {
+                    self.page.checked_sub(1)?
+                } else if *element == InputElement::ArrowRight && self.has_more_than_a_view() {
+                    self.page + 1
+                } else {
+                    return None;
+                };
+
+                if target == self.page || !self.set_page(target) {
+                    return None;
+                }
+                Some(BankEvent::PageChanged { page: target })
+            }
+            InputEvent::KnobChanged { element, value, .. } => {
+                let slot = slot_of(&KNOBS, element)?;
+                let value = self.pages[self.page][slot].update(*value)?;
+                Some(BankEvent::ParameterChanged {
+                    page: self.page,
+                    slot,
+                    value,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a batch of events, relighting the page LEDs if the page
+    /// changed
+    pub fn handle_events<D: MaschineDevice>(
+        &mut self,
+        device: &mut D,
+        events: &[InputEvent],
+    ) -> Result<Vec<BankEvent>> {
+        let changes: Vec<BankEvent> = events
+            .iter()
+            .filter_map(|event| self.handle_event(event))
+            .collect();
+
+        if changes
+            .iter()
+            .any(|change| matches!(change, BankEvent::PageChanged { .. }))
+        {
+            self.show_page_leds(device)?;
+        }
+        Ok(changes)
+    }
+
+    /// Light the display buttons (and arrows, with more than eight pages)
+    /// for the current page
+    pub fn show_page_leds<D: MaschineDevice>(&self, device: &mut D) -> Result<()> {
+        device.update_leds(|leds| self.write_page_leds(leds))
+    }
+
+    /// Write the page indicator LEDs into `leds`
+    pub fn write_page_leds(&self, leds: &mut LedFrame) {
+        let first = self.first_page_in_view();
+        for (index, button) in DISPLAY_BUTTONS.iter().enumerate() {
+            let page = first + index;
+            let brightness = if page == self.page {
+                self.selected_brightness
+            } else if page < self.pages.len() {
+                self.available_brightness
+            } else {
+                0
+            };
+            leds.set_button(*button, brightness);
+        }
+
+        if self.has_more_than_a_view() {
+            let lit = |available: bool| {
+                if available {
+                    self.available_brightness
+                } else {
+                    0
+                }
+            };
+            leds.set_button(InputElement::ArrowLeft, lit(self.page > 0));
+            leds.set_button(
+                InputElement::ArrowRight,
+                lit(self.page + 1 < self.pages.len()),
+            );
+        }
+    }
+
+    /// The display buttons show the group of eight pages containing the
+    /// current one
+    fn first_page_in_view(&self) -> usize {
+        self.page / BANK_SLOTS * BANK_SLOTS
+    }
+
+    fn has_more_than_a_view(&self) -> bool {
+        self.pages.len() > BANK_SLOTS
+    }
+}
+
+/// Page of 0.0-1.0 parameters with soft takeover
+fn new_page() -> [KnobMapper; BANK_SLOTS] {
+    std::array::from_fn(|_| {
+        let mut mapper = KnobMapper::new(0.0, 1.0);
+        mapper.set_soft_takeover(true);
+        mapper
+    })
+}
+
+fn slot_of(elements: &[InputElement; BANK_SLOTS], element: &InputElement) -> Option<usize> {
+    elements.iter().position(|candidate| candidate == element)
+}
@@ -0,0 +1,323 @@
+//! Linux permission diagnostics and udev rule generation.
+//!
+//! Opening the controller as a regular user fails with a bare `Access` error
+//! when the udev rule is missing or the user isn't in the device node's
+//! group. [`check_permissions`] works out which of these is the cause.
+
+use crate::device::{PRODUCT_ID, VENDOR_ID};
+use rusb::UsbContext;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Where [`generate_udev_rule`]'s output is usually installed
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/99-maschine-mk3.rules";
+
+/// Group the generated udev rule grants access to
+pub const UDEV_RULE_GROUP: &str = "audio";
+
+/// Directories udev reads rules from
+const UDEV_RULE_DIRS: [&str; 4] = [
+    "/etc/udev/rules.d",
+    "/run/udev/rules.d",
+    "/lib/udev/rules.d",
+    "/usr/lib/udev/rules.d",
+];
+
+/// A problem preventing access to the controller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionIssue {
+    /// No Maschine MK3 is connected
+    DeviceNotFound,
+    /// The device node can't be opened for reading and writing
+    NodeNotAccessible {
+        path: PathBuf,
+        mode: u32,
+        group: Option<String>,
+    },
+    /// The user isn't a member of the group owning the device node
+    NotInGroup { group: String },
+    /// The user was added to the group, but this session predates it. Logging
+    /// out and back in fixes it.
+    GroupNotActive { group: String },
+    /// No udev rule mentions the controller's vendor and product ID
+    MissingUdevRule,
+}
+
+impl PermissionIssue {
+    /// Suggested fix to show the user
+    pub fn hint(&self) -> String {
+        match self {
+            PermissionIssue::DeviceNotFound => {
+                "Connect the Maschine MK3 and check `lsusb | grep 17cc:1600`".to_string()
+            }
+            PermissionIssue::NodeNotAccessible { path, .. } => format!(
+                "Install the udev rule and replug the controller so {} is accessible",
+                path.display()
+            ),
+            PermissionIssue::NotInGroup { group } => {
+                format!(
+                    "Run `sudo usermod -a -G {} $USER`, then log out and in",
+                    group
+                )
+            }
+            PermissionIssue::GroupNotActive { group } => {
+                format!(
+                    "Log out and back in so membership of '{}' takes effect",
+                    group
+                )
+            }
+            PermissionIssue::MissingUdevRule => format!(
+                "Save generate_udev_rule() to {} and run \
+                 `sudo udevadm control --reload-rules && sudo udevadm trigger`",
+                UDEV_RULE_PATH
+            ),
+        }
+    }
+}
+
+/// Result of [`check_permissions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionReport {
+    /// USB device node of the controller, e.g. `/dev/bus/usb/001/004`
+    pub device_node: Option<PathBuf>,
+    /// Mode bits of the device node
+    pub node_mode: Option<u32>,
+    /// Group owning the device node
+    pub node_group: Option<String>,
+    /// Whether the node could be opened for reading and writing
+    pub node_accessible: bool,
+    /// First udev rule file mentioning the controller
+    pub udev_rule: Option<PathBuf>,
+    pub issues: Vec<PermissionIssue>,
+}
+
+impl PermissionReport {
+    /// Whether nothing should prevent opening the controller
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check the controller's device node permissions, the user's group
+/// membership and the installed udev rules
+pub fn check_permissions() -> PermissionReport {
+    let mut report = PermissionReport {
+        device_node: find_device_node(),
+        node_mode: None,
+        node_group: None,
+        node_accessible: false,
+        udev_rule: find_udev_rule(),
+        issues: Vec::new(),
+    };
+
+    if report.udev_rule.is_none() {
+        report.issues.push(PermissionIssue::MissingUdevRule);
+    }
+
+    let Some(path) = report.device_node.clone() else {
+        report.issues.insert(0, PermissionIssue::DeviceNotFound);
+        return report;
+    };
+
+    let metadata = fs::metadata(&path).ok();
+    report.node_mode = metadata.as_ref().map(|metadata| metadata.mode() & 0o7777);
+    let node_gid = metadata.as_ref().map(|metadata| metadata.gid());
+    report.node_group = node_gid.and_then(group_name);
+    report.node_accessible = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .is_ok();
+
+    if report.node_accessible {
+        return report;
+    }
+
+    report.issues.push(PermissionIssue::NodeNotAccessible {
+        path,
+        mode: report.node_mode.unwrap_or(0),
+        group: report.node_group.clone(),
+    });
+
+    // Group access only helps if the node grants its group read/write
+    let group_writable = report.node_mode.is_some_and(|mode| mode & 0o060 == 0o060);
+    if let (Some(gid), Some(group), true) = (node_gid, report.node_group.clone(), group_writable) {
+        if !active_groups().contains(&gid) {
+            if configured_members(&group).contains(&current_user().unwrap_or_default()) {
+                report
+                    .issues
+                    .push(PermissionIssue::GroupNotActive { group });
+            } else {
+                report.issues.push(PermissionIssue::NotInGroup { group });
+            }
+        }
+    }
+
+    report
+}
+
+/// udev rule giving members of [`UDEV_RULE_GROUP`] access to the controller's
+/// USB and hidraw nodes
+pub fn generate_udev_rule() -> String {
+    format!(
+        "# Native Instruments Maschine MK3 (VID: 0x{vid:04x}, PID: 0x{pid:04x})\n\
+         SUBSYSTEM==\"usb\", ATTRS{{idVendor}}==\"{vid:04x}\", ATTRS{{idProduct}}==\"{pid:04x}\", \
+         GROUP=\"{group}\", MODE=\"0664\"\n\
+         KERNEL==\"hidraw*\", ATTRS{{idVendor}}==\"{vid:04x}\", ATTRS{{idProduct}}==\"{pid:04x}\", \
+         GROUP=\"{group}\", MODE=\"0664\"\n",
+        vid = VENDOR_ID,
+        pid = PRODUCT_ID,
+        group = UDEV_RULE_GROUP,
+    )
+}
+
+fn find_device_node() -> Option<PathBuf> {
+    let context = rusb::Context::new().ok()?;
+    let devices = context.devices().ok()?;
+    let device = devices.iter().find(|device| {
+        device
+            .device_descriptor()
+            .is_ok_and(|desc| desc.vendor_id() == VENDOR_ID && desc.product_id() == PRODUCT_ID)
+    })?;
+    Some(PathBuf::from(format!(
+        "/dev/bus/usb/{:03}/{:03}",
+        device.bus_number(),
+        device.address()
+    )))
+}
+
+fn find_udev_rule() -> Option<PathBuf> {
+    let vid = format!("{:04x}", VENDOR_ID);
+    let pid = format!("{:04x}", PRODUCT_ID);
+
+    UDEV_RULE_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rules"))
+        .find(|path| rule_mentions(path, &vid, &pid))
+}
+
+fn rule_mentions(path: &Path, vid: &str, pid: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|rules| {
+        rules.lines().any(|line| {
+            let line = line.trim().to_ascii_lowercase();
+            !line.starts_with('#') && line.contains(vid) && line.contains(pid)
+        })
+    })
+}
+
+/// Primary and supplementary group IDs of this process, as granted at login
+fn active_groups() -> Vec<u32> {
+    let primary =
+        proc_status_field("Gid:").and_then(|gids| gids.split_whitespace().nth(1)?.parse().ok());
+    let supplementary = proc_status_field("Groups:").unwrap_or_default();
+    supplementary
+        .split_whitespace()
+        .filter_map(|gid| gid.parse().ok())
+        .chain(primary)
+        .collect()
+}
+
+fn current_user() -> Option<String> {
+    let uid: u32 = proc_status_field("Uid:")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    fs::read_to_string("/etc/passwd")
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            (fields.nth(1)?.parse::<u32>().ok()? == uid).then(|| name.to_string())
+        })
+}
+
+fn proc_status_field(field: &str) -> Option<String> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix(field)
+                .map(|value| value.trim().to_string())
+        })
+}
+
+fn group_name(gid: u32) -> Option<String> {
+    group_entries()
+        .into_iter()
+        .find(|(_, entry_gid, _)| *entry_gid == gid)
+        .map(|(name, _, _)| name)
+}
+
+/// Users listed as members of `group` in /etc/group
+fn configured_members(group: &str) -> Vec<String> {
+    group_entries()
+        .into_iter()
+        .find(|(name, _, _)| name == group)
+        .map(|(_, _, members)| members)
+        .unwrap_or_default()
+}
+
+/// (name, gid, members) for each /etc/group entry
+fn group_entries() -> Vec<(String, u32, Vec<String>)> {
+    let Ok(groups) = fs::read_to_string("/etc/group") else {
+        return Vec::new();
+    };
+    groups
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?.to_string();
+            let gid = fields.nth(1)?.parse().ok()?;
+            let members = fields
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .filter(|member| !member.is_empty())
+                .map(str::to_string)
+                .collect();
+            Some((name, gid, members))
+        })
+        .collect()
+}
+
+/// Real-time priority of the input thread in low-latency mode: above every
+/// normal thread, below the audio threads of JACK and PipeWire
+const INPUT_THREAD_RT_PRIORITY: libc::c_int = 10;
+
+/// Nice value the input thread falls back to without real-time rights
+const INPUT_THREAD_NICE: libc::c_int = -10;
+
+/// Move the calling thread to the `SCHED_FIFO` real-time class, or failing
+/// that lower its nice value. Both need the user's `rtprio` or `nice` limit
+/// raised in `/etc/security/limits.conf`, or `CAP_SYS_NICE`.
+pub(crate) fn raise_thread_priority() -> std::io::Result<()> {
+    let param = libc::sched_param {
+        sched_priority: INPUT_THREAD_RT_PRIORITY,
+    };
+    // SAFETY: only changes the scheduling of the calling thread
+    let error =
+        unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if error == 0 {
+        return Ok(());
+    }
+
+    // SAFETY: as above; on Linux a thread id selects a single thread
+    let niced = unsafe {
+        libc::setpriority(
+            libc::PRIO_PROCESS,
+            libc::gettid() as libc::id_t,
+            INPUT_THREAD_NICE,
+        )
+    };
+    if niced == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::from_raw_os_error(error))
+    }
+}
@@ -0,0 +1,6 @@
+//! Platform-specific setup diagnostics and thread tuning
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+#[cfg(windows)]
+pub mod windows;
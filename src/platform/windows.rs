@@ -0,0 +1,249 @@
+//! Windows driver detection and WinUSB setup.
+//!
+//! The display interface (5) only works through libusb when WinUSB is bound
+//! to it; NI's own driver claims it by default. [`display_driver_status`]
+//! reports which driver is bound so applications can guide users through the
+//! switch, and with the `winusb-install` feature [`install_winusb`] performs
+//! it directly.
+
+use crate::device::{DISPLAY_INTERFACE, PRODUCT_ID, VENDOR_ID};
+use crate::error::{MK3Error, Result};
+use ::windows::core::w;
+use ::windows::Win32::Devices::DeviceAndDriverInstallation::{
+    SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInfo, SetupDiGetClassDevsW,
+    SetupDiGetDeviceInstanceIdW, SetupDiGetDeviceRegistryPropertyW, DIGCF_ALLCLASSES,
+    DIGCF_PRESENT, HDEVINFO, SPDRP_SERVICE, SP_DEVINFO_DATA,
+};
+use ::windows::Win32::Foundation::{ERROR_NO_MORE_ITEMS, HWND};
+use ::windows::Win32::System::Threading::{
+    GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_TIME_CRITICAL,
+};
+
+/// Windows service name of the WinUSB driver
+const WINUSB_SERVICE: &str = "WinUSB";
+
+/// Driver bound to one interface of the controller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriverStatus {
+    /// WinUSB is bound; libusb can claim the interface
+    WinUsb,
+    /// Another driver is bound, usually Native Instruments' own. `service` is
+    /// its Windows service name.
+    OtherDriver { service: String },
+    /// The interface is present but has no driver installed
+    NoDriver,
+    /// No Maschine MK3 is connected
+    NotConnected,
+}
+
+impl DriverStatus {
+    pub fn is_winusb(&self) -> bool {
+        *self == DriverStatus::WinUsb
+    }
+}
+
+/// Raise the calling thread to time-critical priority
+pub(crate) fn raise_thread_priority() -> std::io::Result<()> {
+    // SAFETY: the pseudo handle of the current thread needs no closing
+    unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_TIME_CRITICAL) }
+        .map_err(std::io::Error::from)
+}
+
+/// Driver bound to the display interface
+pub fn display_driver_status() -> Result<DriverStatus> {
+    interface_driver_status(DISPLAY_INTERFACE)
+}
+
+/// Driver bound to `interface` of the first connected controller
+pub fn interface_driver_status(interface: u8) -> Result<DriverStatus> {
+    let instance_prefix = format!(
+        "USB\\VID_{:04X}&PID_{:04X}&MI_{:02X}\\",
+        VENDOR_ID, PRODUCT_ID, interface
+    );
+
+    let devices = DeviceInfoList::present_usb_devices()?;
+    for index in 0.. {
+        let mut info = SP_DEVINFO_DATA {
+            cbSize: std::mem::size_of::<SP_DEVINFO_DATA>() as u32,
+            ..Default::default()
+        };
+        // SAFETY: `info` is a correctly sized SP_DEVINFO_DATA and `devices`
+        // is a live device information set
+        if let Err(e) = unsafe { SetupDiEnumDeviceInfo(devices.0, index, &mut info) } {
+            if e.code() == ERROR_NO_MORE_ITEMS.to_hresult() {
+                break;
+            }
+            return Err(MK3Error::Io(e.into()));
+        }
+
+        let instance_id = devices.instance_id(&info)?;
+        if !instance_id.to_ascii_uppercase().starts_with(&instance_prefix) {
+            continue;
+        }
+
+        return Ok(match devices.service(&info) {
+            Some(service) if service.eq_ignore_ascii_case(WINUSB_SERVICE) => DriverStatus::WinUsb,
+            Some(service) => DriverStatus::OtherDriver { service },
+            None => DriverStatus::NoDriver,
+        });
+    }
+
+    Ok(DriverStatus::NotConnected)
+}
+
+/// Owned SetupAPI device information set
+struct DeviceInfoList(HDEVINFO);
+
+impl DeviceInfoList {
+    fn present_usb_devices() -> Result<Self> {
+        // SAFETY: the enumerator is a static wide string and no window is
+        // passed
+        let handle = unsafe {
+            SetupDiGetClassDevsW(
+                None,
+                w!("USB"),
+                HWND::default(),
+                DIGCF_PRESENT | DIGCF_ALLCLASSES,
+            )
+        }
+        .map_err(|e| MK3Error::Io(e.into()))?;
+        Ok(Self(handle))
+    }
+
+    fn instance_id(&self, info: &SP_DEVINFO_DATA) -> Result<String> {
+        let mut buffer = [0u16; 512];
+        // SAFETY: `info` came from SetupDiEnumDeviceInfo on this set and the
+        // buffer length is passed alongside it
+        unsafe { SetupDiGetDeviceInstanceIdW(self.0, info, Some(&mut buffer[..]), None) }
+            .map_err(|e| MK3Error::Io(e.into()))?;
+        Ok(wide_to_string(&buffer))
+    }
+
+    /// Service name of the bound driver, if any
+    fn service(&self, info: &SP_DEVINFO_DATA) -> Option<String> {
+        let mut buffer = [0u8; 512];
+        // SAFETY: as in `instance_id`; the property is a REG_SZ written into
+        // `buffer` up to its length
+        unsafe {
+            SetupDiGetDeviceRegistryPropertyW(
+                self.0,
+                info,
+                SPDRP_SERVICE,
+                None,
+                Some(&mut buffer[..]),
+                None,
+            )
+        }
+        .ok()?;
+
+        let wide: Vec<u16> = buffer
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let service = wide_to_string(&wide);
+        (!service.is_empty()).then_some(service)
+    }
+}
+
+impl Drop for DeviceInfoList {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from SetupDiGetClassDevsW and is destroyed
+        // once
+        let _ = unsafe { SetupDiDestroyDeviceInfoList(self.0) };
+    }
+}
+
+/// Decode a NUL-terminated UTF-16 buffer
+fn wide_to_string(wide: &[u16]) -> String {
+    let end = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..end])
+}
+
+/// Hardware ID of a controller interface, as matched by an INF file
+pub fn hardware_id(interface: u8) -> String {
+    format!(
+        "USB\\VID_{:04X}&PID_{:04X}&MI_{:02X}",
+        VENDOR_ID, PRODUCT_ID, interface
+    )
+}
+
+/// INF file binding WinUSB to the display interface.
+///
+/// Windows only installs INF files with a signed catalog, so this is a
+/// starting point for a package signed by the distributing application (the
+/// catalog is expected next to the INF as `maschine3_winusb.cat`).
+pub fn winusb_inf() -> String {
+    let hardware_id = hardware_id(DISPLAY_INTERFACE);
+    format!(
+        r#"[Version]
+Signature = "$Windows NT$"
+Class = USBDevice
+ClassGUID = {{88BAE032-5A81-49f0-BC3D-A4FF138216D6}}
+Provider = %ManufacturerName%
+CatalogFile = maschine3_winusb.cat
+DriverVer = 01/01/2024,1.0.0.0
+
+[Manufacturer]
+%ManufacturerName% = Standard,NTamd64,NTarm64
+
+[Standard.NTamd64]
+%DeviceName% = USB_Install, {hardware_id}
+
+[Standard.NTarm64]
+%DeviceName% = USB_Install, {hardware_id}
+
+[USB_Install]
+Include = winusb.inf
+Needs = WINUSB.NT
+
+[USB_Install.Services]
+Include = winusb.inf
+Needs = WINUSB.NT.Services
+
+[USB_Install.HW]
+AddReg = Dev_AddReg
+
+[Dev_AddReg]
+HKR,,DeviceInterfaceGUIDs,0x10000,"{{6C3A1E52-9B0D-4F7E-8D21-3E5A7C9B1F04}}"
+
+[Strings]
+ManufacturerName = "maschine3-hal"
+DeviceName = "Maschine MK3 Display (WinUSB)"
+"#
+    )
+}
+
+/// Bind WinUSB to the display interface using the INF at `inf_path` (see
+/// [`winusb_inf`]), replacing whatever driver is bound now. Needs an elevated
+/// process. Returns `true` if Windows asks for a reboot to finish.
+#[cfg(feature = "winusb-install")]
+pub fn install_winusb(inf_path: &std::path::Path) -> Result<bool> {
+    use ::windows::Win32::Devices::DeviceAndDriverInstallation::{
+        UpdateDriverForPlugAndPlayDevicesW, INSTALLFLAG_FORCE,
+    };
+    use ::windows::core::PCWSTR;
+    use ::windows::Win32::Foundation::BOOL;
+    use std::os::windows::ffi::OsStrExt;
+
+    let inf_path = std::path::absolute(inf_path)?;
+    let inf_path: Vec<u16> = inf_path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let hardware_id: Vec<u16> = hardware_id(DISPLAY_INTERFACE)
+        .encode_utf16()
+        .chain(Some(0))
+        .collect();
+
+    let mut reboot_required = BOOL::default();
+    // SAFETY: both strings are NUL-terminated and outlive the call
+    unsafe {
+        UpdateDriverForPlugAndPlayDevicesW(
+            HWND::default(),
+            PCWSTR(hardware_id.as_ptr()),
+            PCWSTR(inf_path.as_ptr()),
+            INSTALLFLAG_FORCE,
+            Some(&mut reboot_required as *mut BOOL),
+        )
+    }
+    .map_err(|e| MK3Error::Io(e.into()))?;
+
+    Ok(reboot_required.as_bool())
+}
@@ -0,0 +1,74 @@
+use crate::error::{MK3Error, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Exclusive lock on a controller shared by every process using this crate.
+///
+/// The lock is an advisory file lock in the temp directory, so it is
+/// released when the process exits, even if it crashes. The file holds the
+/// holder's process ID and name for the error other processes get.
+#[derive(Debug)]
+pub(crate) struct ProcessLock {
+    _file: File,
+}
+
+impl ProcessLock {
+    /// Take the lock called `name`, or fail with [`MK3Error::DeviceBusy`]
+    /// if another process holds it
+    pub(crate) fn acquire(name: &str) -> Result<Self> {
+        let path = Self::path(name);
+        // A lock file left by another user may not be writable; locking
+        // works on a read-only handle too, it just can't record the holder
+        let (mut file, writable) = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+        {
+            Ok(file) => (file, true),
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                (File::open(&path)?, false)
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(MK3Error::DeviceBusy(Self::holder(&mut file)));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        if writable {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            writeln!(file, "{}", Self::describe_current_process())?;
+        }
+        Ok(Self { _file: file })
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("maschine3-hal-{}.lock", name))
+    }
+
+    /// Who holds the lock, as recorded in the file
+    fn holder(file: &mut File) -> String {
+        let mut contents = String::new();
+        // Windows locks keep other handles from reading the file
+        match file.read_to_string(&mut contents) {
+            Ok(_) if !contents.trim().is_empty() => contents.trim().to_string(),
+            _ => "another process".to_string(),
+        }
+    }
+
+    fn describe_current_process() -> String {
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "unknown".to_string());
+        format!("process {} ({})", std::process::id(), name)
+    }
+}
@@ -0,0 +1,419 @@
+use crate::error::{MK3Error, Result};
+use crate::health::UsbEndpoint;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Start of every recording file
+const MAGIC: &[u8; 4] = b"MK3R";
+
+/// Format version written after the magic
+const VERSION: u8 = 1;
+
+/// One LED or display packet as it was sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedPacket {
+    /// Time since the first packet of the recording
+    pub time: Duration,
+    pub endpoint: UsbEndpoint,
+    pub data: Vec<u8>,
+}
+
+/// Packets captured by an [`OutputRecorder`], in the order they were sent.
+///
+/// Files hold each packet's endpoint, the microseconds since the previous
+/// packet and its bytes, with a packet identical to the previous one on
+/// the same endpoint stored as a reference to it, so idle LED refreshes
+/// and repeated frames take a few bytes each.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputRecording {
+    packets: Vec<RecordedPacket>,
+}
+
+impl OutputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn packets(&self) -> &[RecordedPacket] {
+        &self.packets
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Time of the last packet
+    pub fn duration(&self) -> Duration {
+        self.packets
+            .last()
+            .map_or(Duration::ZERO, |packet| packet.time)
+    }
+
+    /// Packets sent to one endpoint
+    pub fn endpoint_packets(
+        &self,
+        endpoint: UsbEndpoint,
+    ) -> impl Iterator<Item = &RecordedPacket> + '_ {
+        self.packets
+            .iter()
+            .filter(move |packet| packet.endpoint == endpoint)
+    }
+
+    /// Append a packet, e.g. to build an expected recording by hand. Times
+    /// must not go backwards and are saved to whole microseconds.
+    pub fn push(&mut self, packet: RecordedPacket) -> Result<()> {
+        if packet.time < self.duration() {
+            return Err(MK3Error::InvalidData(format!(
+                "Packet at {:?} is earlier than the last one at {:?}",
+                packet.time,
+                self.duration()
+            )));
+        }
+        self.packets.push(packet);
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_varint(&mut writer, self.packets.len() as u64)?;
+
+        let mut previous: [Option<&[u8]>; 3] = [None; 3];
+        let mut last_time = Duration::ZERO;
+        for packet in &self.packets {
+            let slot = &mut previous[endpoint_code(packet.endpoint) as usize];
+            writer.write_all(&[endpoint_code(packet.endpoint)])?;
+            let delta = packet.time.as_micros() - last_time.as_micros();
+            write_varint(&mut writer, delta as u64)?;
+
+            // Zero length repeats the endpoint's previous packet
+            if *slot == Some(packet.data.as_slice()) {
+                write_varint(&mut writer, 0)?;
+            } else {
+                write_varint(&mut writer, packet.data.len() as u64)?;
+                writer.write_all(&packet.data)?;
+            }
+            *slot = Some(packet.data.as_slice());
+            last_time = packet.time;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(MK3Error::InvalidData("Not an output recording".to_string()));
+        }
+        if header[4] != VERSION {
+            return Err(MK3Error::InvalidData(format!(
+                "Unsupported recording version {}",
+                header[4]
+            )));
+        }
+
+        let count = read_varint(&mut reader)?;
+        let mut previous: [Option<Vec<u8>>; 3] = Default::default();
+        let mut recording = Self::new();
+        let mut time = Duration::ZERO;
+        for index in 0..count {
+            let mut code = [0u8; 1];
+            reader.read_exact(&mut code)?;
+            let endpoint = endpoint_from_code(code[0])?;
+            time += Duration::from_micros(read_varint(&mut reader)?);
+
+            let slot = &mut previous[code[0] as usize];
+            let data = match read_varint(&mut reader)? {
+                0 => slot.clone().ok_or_else(|| {
+                    MK3Error::InvalidData(format!(
+                        "Packet {} repeats a packet that was never sent",
+                        index
+                    ))
+                })?,
+                len => {
+                    let mut data = Vec::new();
+                    (&mut reader).take(len).read_to_end(&mut data)?;
+                    if data.len() as u64 != len {
+                        return Err(MK3Error::InvalidData(format!(
+                            "Packet {} is truncated",
+                            index
+                        )));
+                    }
+                    data
+                }
+            };
+            *slot = Some(data.clone());
+            recording.packets.push(RecordedPacket {
+                time,
+                endpoint,
+                data,
+            });
+        }
+        Ok(recording)
+    }
+}
+
+fn endpoint_code(endpoint: UsbEndpoint) -> u8 {
+    match endpoint {
+        UsbEndpoint::Input => 0,
+        UsbEndpoint::Led => 1,
+        UsbEndpoint::Display => 2,
+    }
+}
+
+fn endpoint_from_code(code: u8) -> Result<UsbEndpoint> {
+    match code {
+        0 => Ok(UsbEndpoint::Input),
+        1 => Ok(UsbEndpoint::Led),
+        2 => Ok(UsbEndpoint::Display),
+        _ => Err(MK3Error::InvalidData(format!(
+            "Unknown endpoint {} in recording",
+            code
+        ))),
+    }
+}
+
+/// LEB128: seven bits per byte, low bits first
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> Result<()> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[byte])?;
+            return Ok(());
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7F) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(MK3Error::InvalidData(
+        "Length in recording is too long".to_string(),
+    ))
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    started: Option<Instant>,
+    recording: OutputRecording,
+}
+
+/// Captures every LED and display packet a device sends, with timestamps,
+/// for golden-file tests of animations and UI rendering.
+///
+/// The recorder is a shared handle: install a clone on a device with
+/// [`MaschineMK3::set_output_recorder`](crate::MaschineMK3::set_output_recorder)
+/// and read what was captured from the original. Times count from the first
+/// packet, so recordings of the same output line up wherever they started.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineMK3, OutputComparator, OutputRecorder};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let recorder = OutputRecorder::new();
+/// device.set_output_recorder(Some(recorder.clone()));
+///
+/// device.clear_display(0, 0, 0, 0)?;
+///
+/// // Written the first time, compared against on later runs
+/// OutputComparator::new().check_golden(&recorder.recording(), "tests/golden/clear.mk3r")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OutputRecorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl OutputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything captured so far
+    pub fn recording(&self) -> OutputRecording {
+        self.state
+            .lock()
+            .map(|state| state.recording.clone())
+            .unwrap_or_default()
+    }
+
+    /// Everything captured so far, starting a new recording
+    pub fn take(&self) -> OutputRecording {
+        self.state
+            .lock()
+            .map(|mut state| std::mem::take(&mut *state).recording)
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        self.take();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().map_or(0, |state| state.recording.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn record(&self, endpoint: UsbEndpoint, data: &[u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        // Whole microseconds, as stored in files
+        let started = *state.started.get_or_insert_with(Instant::now);
+        state.recording.packets.push(RecordedPacket {
+            time: Duration::from_micros(started.elapsed().as_micros() as u64),
+            endpoint,
+            data: data.to_vec(),
+        });
+    }
+}
+
+/// First difference between two recordings, see [`OutputComparator`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OutputMismatch {
+    #[error("{actual} packets recorded, {expected} expected")]
+    PacketCount { actual: usize, expected: usize },
+
+    #[error("packet {index} went to the {} endpoint, {} expected", .actual.name(), .expected.name())]
+    Endpoint {
+        index: usize,
+        actual: UsbEndpoint,
+        expected: UsbEndpoint,
+    },
+
+    #[error("{} packet {index} differs from byte {offset}", .endpoint.name())]
+    Data {
+        index: usize,
+        endpoint: UsbEndpoint,
+        offset: usize,
+    },
+
+    #[error("packet {index} sent at {actual:?}, {expected:?} expected")]
+    Timing {
+        index: usize,
+        actual: Duration,
+        expected: Duration,
+    },
+}
+
+/// Compares recordings packet by packet, e.g. against a golden file.
+///
+/// Timing is ignored unless a tolerance is set, since real transfers
+/// jitter; packet order, endpoints and bytes must match exactly.
+#[derive(Debug, Clone, Default)]
+pub struct OutputComparator {
+    timing_tolerance: Option<Duration>,
+}
+
+impl OutputComparator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also require each packet to be sent within `tolerance` of its
+    /// expected time
+    pub fn with_timing_tolerance(mut self, tolerance: Duration) -> Self {
+        self.timing_tolerance = Some(tolerance);
+        self
+    }
+
+    /// First difference between `actual` and `expected`, if any
+    pub fn compare(
+        &self,
+        actual: &OutputRecording,
+        expected: &OutputRecording,
+    ) -> Option<OutputMismatch> {
+        let pairs = actual.packets.iter().zip(&expected.packets).enumerate();
+        for (index, (actual, expected)) in pairs {
+            if actual.endpoint != expected.endpoint {
+                return Some(OutputMismatch::Endpoint {
+                    index,
+                    actual: actual.endpoint,
+                    expected: expected.endpoint,
+                });
+            }
+            if actual.data != expected.data {
+                let offset = actual
+                    .data
+                    .iter()
+                    .zip(&expected.data)
+                    .position(|(a, b)| a != b)
+                    .unwrap_or_else(|| actual.data.len().min(expected.data.len()));
+                return Some(OutputMismatch::Data {
+                    index,
+                    endpoint: actual.endpoint,
+                    offset,
+                });
+            }
+            if let Some(tolerance) = self.timing_tolerance {
+                if actual.time.abs_diff(expected.time) > tolerance {
+                    return Some(OutputMismatch::Timing {
+                        index,
+                        actual: actual.time,
+                        expected: expected.time,
+                    });
+                }
+            }
+        }
+
+        (actual.len() != expected.len()).then_some(OutputMismatch::PacketCount {
+            actual: actual.len(),
+            expected: expected.len(),
+        })
+    }
+
+    /// Compare `actual` with the recording in a golden file. If the file
+    /// doesn't exist yet it is written from `actual`, so deleting a golden
+    /// file regenerates it on the next run.
+    pub fn check_golden<P: AsRef<Path>>(&self, actual: &OutputRecording, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return actual.save(path);
+        }
+
+        let expected = OutputRecording::load(path)?;
+        match self.compare(actual, &expected) {
+            Some(mismatch) => Err(MK3Error::InvalidData(format!(
+                "Output differs from {}: {}",
+                path.display(),
+                mismatch
+            ))),
+            None => Ok(()),
+        }
+    }
+}
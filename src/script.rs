@@ -0,0 +1,449 @@
+//! Macro scripts in [Rhai](https://rhai.rs) mapping input events to LED and
+//! display actions.
+//!
+//! A script defines `fn on_event(event)`, which is called for every event
+//! with a map describing it. `this` is a map kept between events, for state
+//! the script tracks itself:
+//!
+//! ```text
+//! // my_controller.rhai
+//! fn on_event(event) {
+//!     switch event.type {
+//!         "pad_hit" => pad(event.pad, "green"),
+//!         "pad_release" => pad(event.pad, "off"),
+//!         "press" if event.element == "Play" => toggle("Play"),
+//!         "press" if event.element == "GroupA" => {
+//!             led("GroupA", "dim blue");
+//!             pads("off");
+//!         }
+//!         "knob" if event.element == "Knob1" => {
+//!             this.level = ((this.level ?? 0) + event.delta).max(0).min(255);
+//!             display(0, 0, 0, this.level);
+//!         }
+//!         "resume" => clear(),
+//!     }
+//! }
+//! ```
+//!
+//! Event fields, besides `type` and `description` (as in
+//! [`InputEvent::description`]):
+//! - `press`, `release`, `hold`, `double`, `long`: `element`
+//! - `knob`, `audio`: `element`, `value`, `delta`
+//! - `pad_hit`, `pad_release`, `pad_aftertouch`: `pad` (1-16), `value`
+//! - `strip`: `value`
+//! - `encoder`: `detents`, `steps`
+//! - `resume` after the controller reconnects
+//! - `other` for every other event
+//!
+//! Functions:
+//! - `led(element, 0-127)` for single-color buttons, or
+//!   `led(element, color)` for RGB buttons
+//! - `toggle(element)` switches a button LED between off and full
+//! - `pad(1-16, color)` and `pads(color)`
+//! - `clear()` turns every LED off
+//! - `display(0|1, r, g, b)` fills a display
+//! - `print(text)` logs at info level
+//!
+//! Events name elements by their identifier (`"GroupA"`, `"Knob1"`); the
+//! functions take any name [`InputElement::from_name`] accepts. Colors are
+//! `red`, `orange`, `yellow`, `green`, `cyan`, `blue`, `purple`, `magenta`,
+//! `pink`, `white` and `off`, optionally prefixed with `dim `. LED changes
+//! made for one event are sent as a single update once `on_event` returns.
+
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::error::{MK3Error, Result};
+use crate::input::{InputElement, InputEvent, PadEventType};
+use crate::output::{LedFrame, MaschineLEDColor};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST, INT};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Rhai operations a script may run per event, so a runaway loop can't
+/// stall input polling
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    ButtonBrightness(InputElement, u8),
+    ButtonColor(InputElement, MaschineLEDColor),
+    Toggle(InputElement),
+    Pad(u8, MaschineLEDColor),
+    AllPads(MaschineLEDColor),
+    Clear,
+    Display { display: u8, rgb: [u8; 3] },
+}
+
+/// Actions queued by the script's calls, applied once `on_event` returns
+type ActionQueue = Arc<Mutex<Vec<Action>>>;
+
+/// A compiled macro script, run against input events with
+/// [`handle_event`](Self::handle_event) or installed on a device with
+/// [`MaschineMK3::load_script`]
+pub struct MacroScript {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// `this` in `on_event`
+    state: Dynamic,
+    actions: ActionQueue,
+    /// Buttons currently lit by `toggle`
+    toggled: HashSet<InputElement>,
+}
+
+impl fmt::Debug for MacroScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MacroScript")
+            .field("state", &self.state)
+            .field("toggled", &self.toggled)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MacroScript {
+    /// Compile a script and run its top-level statements. Errors name the
+    /// offending line.
+    pub fn parse(source: &str) -> Result<Self> {
+        let actions = ActionQueue::default();
+        let engine = build_engine(&actions);
+
+        let ast = engine.compile(source).map_err(|e| script_error(&e))?;
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == "on_event" && function.params.len() == 1)
+        {
+            return Err(MK3Error::InvalidData(
+                "script doesn't define fn on_event(event)".to_string(),
+            ));
+        }
+
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| script_error(&e))?;
+        // Only on_event drives the controller
+        take_actions(&actions);
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            state: Dynamic::from(Map::new()),
+            actions,
+            toggled: HashSet::new(),
+        })
+    }
+
+    /// Read and compile a script file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let source = std::fs::read_to_string(path.as_ref())?;
+        Self::parse(&source).map_err(|e| match e {
+            MK3Error::InvalidData(message) => {
+                MK3Error::InvalidData(format!("{}: {}", path.as_ref().display(), message))
+            }
+            e => e,
+        })
+    }
+
+    /// Call `on_event` for `event` and apply what it did. LED changes from
+    /// one event are sent as a single update; nothing is sent if the script
+    /// fails.
+    pub fn handle_event<D: MaschineDevice>(
+        &mut self,
+        device: &mut D,
+        event: &InputEvent,
+    ) -> Result<()> {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            "on_event",
+            (event_map(event),),
+        );
+        let actions = take_actions(&self.actions);
+        // on_event may end in any value, e.g. a switch expression's
+        if let Err(e) = result {
+            return Err(script_error(&e));
+        }
+
+        self.apply(device, actions)
+    }
+
+    fn apply<D: MaschineDevice>(&mut self, device: &mut D, actions: Vec<Action>) -> Result<()> {
+        let mut leds = device.led_frame();
+        let mut leds_changed = false;
+
+        for action in actions {
+            match action {
+                Action::Display { display, rgb } => {
+                    let frame = rgb.repeat(
+                        MaschineMK3::DISPLAY_WIDTH as usize * MaschineMK3::DISPLAY_HEIGHT as usize,
+                    );
+                    device.send_display_rgb888(display, &frame)?;
+                    continue;
+                }
+                Action::Toggle(element) => {
+                    let lit = !self.toggled.remove(&element);
+                    if lit {
+                        self.toggled.insert(element);
+                    }
+                    leds.set_button(element, if lit { 127 } else { 0 });
+                }
+                Action::Clear => {
+                    // Toggled buttons are off now too
+                    self.toggled.clear();
+                    leds = LedFrame::default();
+                }
+                Action::ButtonBrightness(element, brightness) => {
+                    leds.set_button(element, brightness)
+                }
+                Action::ButtonColor(element, color) => leds.set_button_color(element, color),
+                Action::Pad(pad, color) => leds.set_pad(pad, color),
+                Action::AllPads(color) => leds.set_all_pads(color),
+            }
+            leds_changed = true;
+        }
+
+        if leds_changed {
+            device.set_led_frame(leds)?;
+        }
+        Ok(())
+    }
+}
+
+fn script_error(error: &dyn fmt::Display) -> MK3Error {
+    MK3Error::InvalidData(format!("script: {}", error))
+}
+
+fn take_actions(actions: &ActionQueue) -> Vec<Action> {
+    // Only a queue: a panic while it was held leaves nothing inconsistent
+    std::mem::take(&mut *actions.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// An engine whose LED and display functions queue into `actions`
+fn build_engine(actions: &ActionQueue) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| log::info!("script: {}", text));
+    engine.on_debug(|text, _, position| log::debug!("script {}: {}", position, text));
+
+    let queue = |actions: &ActionQueue| {
+        let actions = Arc::clone(actions);
+        move |action: Action| {
+            actions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(action);
+        }
+    };
+
+    let push = queue(actions);
+    engine.register_fn(
+        "led",
+        move |element: &str, brightness: INT| -> std::result::Result<(), Box<EvalAltResult>> {
+            let element = parse_element(element)?;
+            if element.has_color() {
+                return Err(
+                    format!("{} is an RGB button, use led(element, color)", element).into(),
+                );
+            }
+            let brightness = u8::try_from(brightness)
+                .ok()
+                .filter(|brightness| *brightness <= 127)
+                .ok_or_else(|| format!("brightness must be 0-127, got {}", brightness))?;
+            push(Action::ButtonBrightness(element, brightness));
+            Ok(())
+        },
+    );
+
+    let push = queue(actions);
+    engine.register_fn(
+        "led",
+        move |element: &str, color: &str| -> std::result::Result<(), Box<EvalAltResult>> {
+            let element = parse_element(element)?;
+            let action = if element.has_color() {
+                Action::ButtonColor(element, parse_color(color)?)
+            } else {
+                match color {
+                    "on" => Action::ButtonBrightness(element, 127),
+                    "off" => Action::ButtonBrightness(element, 0),
+                    _ => {
+                        return Err(format!(
+                            "{} is a single-color button, use on, off or 0-127",
+                            element
+                        )
+                        .into())
+                    }
+                }
+            };
+            push(action);
+            Ok(())
+        },
+    );
+
+    let push = queue(actions);
+    engine.register_fn(
+        "toggle",
+        move |element: &str| -> std::result::Result<(), Box<EvalAltResult>> {
+            push(Action::Toggle(parse_element(element)?));
+            Ok(())
+        },
+    );
+
+    let push = queue(actions);
+    engine.register_fn(
+        "pad",
+        move |pad: INT, color: &str| -> std::result::Result<(), Box<EvalAltResult>> {
+            push(Action::Pad(parse_pad(pad)?, parse_color(color)?));
+            Ok(())
+        },
+    );
+
+    let push = queue(actions);
+    engine.register_fn(
+        "pads",
+        move |color: &str| -> std::result::Result<(), Box<EvalAltResult>> {
+            push(Action::AllPads(parse_color(color)?));
+            Ok(())
+        },
+    );
+
+    let push = queue(actions);
+    engine.register_fn("clear", move || push(Action::Clear));
+
+    let push = queue(actions);
+    engine.register_fn(
+        "display",
+        move |display: INT,
+              r: INT,
+              g: INT,
+              b: INT|
+              -> std::result::Result<(), Box<EvalAltResult>> {
+            let display = match display {
+                0 => 0,
+                1 => 1,
+                _ => return Err(format!("display must be 0 or 1, got {}", display).into()),
+            };
+            let component = |value: INT| {
+                u8::try_from(value).map_err(|_| format!("expected 0-255, got {}", value))
+            };
+            push(Action::Display {
+                display,
+                rgb: [component(r)?, component(g)?, component(b)?],
+            });
+            Ok(())
+        },
+    );
+
+    engine
+}
+
+/// The map `on_event` is called with
+fn event_map(event: &InputEvent) -> Map {
+    let mut map = Map::new();
+    let mut set = |key: &str, value: Dynamic| {
+        map.insert(key.into(), value);
+    };
+
+    let kind = match event {
+        InputEvent::ButtonPressed(element)
+        | InputEvent::ButtonReleased(element)
+        | InputEvent::ButtonHeld(element)
+        | InputEvent::ButtonDoublePressed(element)
+        | InputEvent::ButtonLongPressed(element) => {
+            set("element", element.to_string().into());
+            match event {
+                InputEvent::ButtonPressed(_) => "press",
+                InputEvent::ButtonReleased(_) => "release",
+                InputEvent::ButtonHeld(_) => "hold",
+                InputEvent::ButtonDoublePressed(_) => "double",
+                _ => "long",
+            }
+        }
+        InputEvent::KnobChanged {
+            element,
+            value,
+            delta,
+        }
+        | InputEvent::AudioChanged {
+            element,
+            value,
+            delta,
+        } => {
+            set("element", element.to_string().into());
+            set("value", INT::from(*value).into());
+            set("delta", INT::from(*delta).into());
+            match event {
+                InputEvent::KnobChanged { .. } => "knob",
+                _ => "audio",
+            }
+        }
+        InputEvent::PadEvent {
+            pad_number,
+            event_type,
+            value,
+        } => {
+            // Pads are numbered 1-16 in scripts
+            set("pad", INT::from(*pad_number + 1).into());
+            set("value", INT::from(*value).into());
+            match event_type {
+                PadEventType::Hit => "pad_hit",
+                PadEventType::HitRelease | PadEventType::TouchRelease => "pad_release",
+                PadEventType::Aftertouch => "pad_aftertouch",
+            }
+        }
+        InputEvent::StripChanged { value, .. } => {
+            set("value", Dynamic::from_float((*value).into()));
+            "strip"
+        }
+        InputEvent::EncoderTurned { detents, steps } => {
+            set("detents", INT::from(*detents).into());
+            set("steps", INT::from(*steps).into());
+            "encoder"
+        }
+        InputEvent::DeviceResumed => "resume",
+        _ => "other",
+    };
+    set("type", kind.into());
+    set("description", event.description().into());
+    map
+}
+
+fn parse_element(name: &str) -> std::result::Result<InputElement, String> {
+    InputElement::from_name(name).ok_or_else(|| format!("unknown element '{}'", name))
+}
+
+/// Pads are numbered 1-16 in scripts
+fn parse_pad(pad: INT) -> std::result::Result<u8, String> {
+    match pad {
+        1..=16 => Ok(pad as u8 - 1),
+        _ => Err(format!("pad must be 1-16, got {}", pad)),
+    }
+}
+
+fn parse_color(name: &str) -> std::result::Result<MaschineLEDColor, String> {
+    let (bright, name) = match name.strip_prefix("dim ") {
+        Some(name) => (false, name.trim()),
+        None => (true, name.trim()),
+    };
+
+    let color = match name {
+        "red" => MaschineLEDColor::red(bright),
+        "orange" => MaschineLEDColor::orange(bright),
+        "yellow" => MaschineLEDColor::yellow(bright),
+        "green" => MaschineLEDColor::green(bright),
+        "cyan" => MaschineLEDColor::cyan(bright),
+        "blue" => MaschineLEDColor::blue(bright),
+        "purple" => MaschineLEDColor::purple(bright),
+        "magenta" => MaschineLEDColor::magenta(bright),
+        "pink" => MaschineLEDColor::pink(bright),
+        "white" => MaschineLEDColor::white(bright),
+        "off" | "black" => MaschineLEDColor::black(),
+        _ => return Err(format!("unknown color '{}'", name)),
+    };
+    Ok(color)
+}
@@ -0,0 +1,358 @@
+use crate::device::MaschineMK3;
+use crate::error::Result;
+use crate::input::{InputElement, InputEvent, PadEventType};
+use crate::output::MaschineLEDColor;
+use crate::strip::STRIP_LED_COUNT;
+use std::time::{Duration, Instant};
+
+/// How long each step waits for the prompted control before it fails
+pub const SELFTEST_STEP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Hits collected per pad to measure its velocity range
+pub const SELFTEST_PAD_HITS: u32 = 3;
+
+/// One control the self-test asks the user to operate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfTestStep {
+    /// Press and release a button
+    Button(InputElement),
+    /// Touch and turn a knob. Knob 1-8 and the main encoder must also report
+    /// their touch sensor.
+    Knob(InputElement),
+    /// Hit a pad `SELFTEST_PAD_HITS` times, from soft to hard
+    Pad(u8),
+    /// Slide a finger along the touch strip
+    TouchStrip,
+}
+
+impl SelfTestStep {
+    /// Every step in the order the self-test runs them
+    pub fn all() -> Vec<SelfTestStep> {
+        let mut steps: Vec<SelfTestStep> = InputElement::ALL
+            .iter()
+            .filter(|element| is_tested_button(element))
+            .cloned()
+            .map(SelfTestStep::Button)
+            .collect();
+
+        steps.extend(
+            [
+                InputElement::Knob1,
+                InputElement::Knob2,
+                InputElement::Knob3,
+                InputElement::Knob4,
+                InputElement::Knob5,
+                InputElement::Knob6,
+                InputElement::Knob7,
+                InputElement::Knob8,
+                InputElement::MainEncoder,
+                InputElement::MicGain,
+                InputElement::HeadphoneVolume,
+                InputElement::MasterVolume,
+            ]
+            .into_iter()
+            .map(SelfTestStep::Knob),
+        );
+        steps.extend((0..16).map(SelfTestStep::Pad));
+        steps.push(SelfTestStep::TouchStrip);
+        steps
+    }
+
+    /// Instruction to show the user
+    pub fn prompt(&self) -> String {
+        match self {
+            SelfTestStep::Button(element) => format!("Press {}", element.name()),
+            SelfTestStep::Knob(element) => format!("Touch and turn {}", element.name()),
+            SelfTestStep::Pad(pad) => format!(
+                "Hit pad {} {} times, from soft to hard",
+                pad + 1,
+                SELFTEST_PAD_HITS
+            ),
+            SelfTestStep::TouchStrip => "Slide along the touch strip".to_string(),
+        }
+    }
+}
+
+/// Progress reported while the self-test runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelfTestEvent {
+    /// Waiting for the user to operate `step` (`index` counts from 0)
+    Prompt {
+        step: SelfTestStep,
+        index: usize,
+        total: usize,
+    },
+    /// The prompted control reported as expected
+    Passed(SelfTestStep),
+    /// No matching input arrived within `SELFTEST_STEP_TIMEOUT`
+    TimedOut(SelfTestStep),
+    /// A different control reported while waiting for `expected`. Either the
+    /// user touched the wrong control or its packet bit is mapped differently
+    /// on this hardware revision.
+    Unexpected {
+        expected: SelfTestStep,
+        event: InputEvent,
+    },
+    /// Velocity of a hit counted towards a pad step
+    PadHit { pad: u8, velocity: u16 },
+}
+
+/// Velocity range measured for one pad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PadCalibration {
+    pub min_velocity: u16,
+    pub max_velocity: u16,
+    /// Highest aftertouch pressure seen
+    pub max_pressure: u16,
+    pub hits: u32,
+}
+
+/// Result of [`MaschineMK3::run_selftest`]
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub passed: Vec<SelfTestStep>,
+    pub failed: Vec<SelfTestStep>,
+    /// Controls that reported while a different one was expected
+    pub unexpected: Vec<(SelfTestStep, InputEvent)>,
+    /// Measured velocity range per pad, `None` if the pad never reported
+    pub pads: [Option<PadCalibration>; 16],
+}
+
+impl SelfTestReport {
+    /// Whether every step passed without unexpected input
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Buttons with a physical control; jack detection is excluded
+fn is_tested_button(element: &InputElement) -> bool {
+    element.id() < InputElement::Knob1.id()
+        && !matches!(
+            element,
+            InputElement::PedalConnected | InputElement::MicrophoneConnected
+        )
+}
+
+/// Touch sensor reported alongside a knob, if it has one
+fn touch_sensor(knob: &InputElement) -> Option<InputElement> {
+    match knob {
+        InputElement::Knob1 => Some(InputElement::Knob1Touched),
+        InputElement::Knob2 => Some(InputElement::Knob2Touched),
+        InputElement::Knob3 => Some(InputElement::Knob3Touched),
+        InputElement::Knob4 => Some(InputElement::Knob4Touched),
+        InputElement::Knob5 => Some(InputElement::Knob5Touched),
+        InputElement::Knob6 => Some(InputElement::Knob6Touched),
+        InputElement::Knob7 => Some(InputElement::Knob7Touched),
+        InputElement::Knob8 => Some(InputElement::Knob8Touched),
+        InputElement::MainEncoder => Some(InputElement::MainKnobTouched),
+        _ => None,
+    }
+}
+
+fn is_touch_sensor(element: &InputElement) -> bool {
+    InputElement::ALL
+        .iter()
+        .any(|knob| touch_sensor(knob).as_ref() == Some(element))
+}
+
+/// Progress within the current step
+#[derive(Default)]
+struct StepProgress {
+    turned: bool,
+    touched: bool,
+    pad_hits: u32,
+}
+
+pub(crate) fn run<F>(device: &mut MaschineMK3, mut report: F) -> Result<SelfTestReport>
+where
+    F: FnMut(SelfTestEvent),
+{
+    let saved = device.save_state();
+    let steps = SelfTestStep::all();
+    let mut result = SelfTestReport::default();
+
+    for (index, step) in steps.iter().enumerate() {
+        report(SelfTestEvent::Prompt {
+            step: step.clone(),
+            index,
+            total: steps.len(),
+        });
+        highlight(device, step)?;
+
+        let deadline = Instant::now() + SELFTEST_STEP_TIMEOUT;
+        let mut progress = StepProgress::default();
+        let mut passed = false;
+
+        while !passed && Instant::now() < deadline {
+            for event in device.poll_input_events()? {
+                match check_event(step, &event, &mut progress, &mut result) {
+                    Some(true) => passed = true,
+                    Some(false) => {}
+                    None => {
+                        result.unexpected.push((step.clone(), event.clone()));
+                        report(SelfTestEvent::Unexpected {
+                            expected: step.clone(),
+                            event: event.clone(),
+                        });
+                    }
+                }
+
+                if let (
+                    SelfTestStep::Pad(pad),
+                    InputEvent::PadEvent {
+                        pad_number,
+                        event_type: PadEventType::Hit,
+                        value,
+                    },
+                ) = (step, &event)
+                {
+                    if pad == pad_number {
+                        report(SelfTestEvent::PadHit {
+                            pad: *pad,
+                            velocity: *value,
+                        });
+                    }
+                }
+            }
+        }
+
+        if passed {
+            result.passed.push(step.clone());
+            report(SelfTestEvent::Passed(step.clone()));
+        } else {
+            result.failed.push(step.clone());
+            report(SelfTestEvent::TimedOut(step.clone()));
+        }
+    }
+
+    saved.restore(device)?;
+    Ok(result)
+}
+
+/// Light the control under test, if it has an LED
+fn highlight(device: &mut MaschineMK3, step: &SelfTestStep) -> Result<()> {
+    device.update_leds(|leds| {
+        leds.buttons.set_all_brightness(0);
+        leds.set_all_pads(MaschineLEDColor::black());
+        for index in 0..STRIP_LED_COUNT as u8 {
+            leds.set_touch_strip(index, MaschineLEDColor::black());
+        }
+
+        match step {
+            SelfTestStep::Button(element) => leds.set_button(*element, 127),
+            SelfTestStep::Pad(pad) => leds.set_pad(*pad, MaschineLEDColor::white(true)),
+            SelfTestStep::TouchStrip => {
+                for index in 0..STRIP_LED_COUNT as u8 {
+                    leds.set_touch_strip(index, MaschineLEDColor::white(false));
+                }
+            }
+            SelfTestStep::Knob(_) => {}
+        }
+    })
+}
+
+/// Apply one event to the current step. Returns `Some(true)` once the step
+/// is complete, `Some(false)` for expected or ignorable input, and `None` for
+/// input from a different control.
+fn check_event(
+    step: &SelfTestStep,
+    event: &InputEvent,
+    progress: &mut StepProgress,
+    result: &mut SelfTestReport,
+) -> Option<bool> {
+    match (step, event) {
+        // Releases, holds, press and knob gestures and touch sensors on the
+        // way to a knob are noise
+        (
+            _,
+            InputEvent::ButtonReleased(_)
+            | InputEvent::ButtonHeld(_)
+            | InputEvent::ButtonDoublePressed(_)
+            | InputEvent::ButtonLongPressed(_)
+            | InputEvent::KnobGesture(_),
+        ) => Some(false),
+        (SelfTestStep::Button(expected), InputEvent::ButtonPressed(element)) => {
+            if element == expected {
+                Some(true)
+            } else if is_touch_sensor(element) {
+                Some(false)
+            } else {
+                None
+            }
+        }
+        (SelfTestStep::Knob(knob), InputEvent::ButtonPressed(element)) => {
+            if touch_sensor(knob).as_ref() == Some(element) {
+                progress.touched = true;
+                Some(progress.turned)
+            } else if is_touch_sensor(element) {
+                Some(false)
+            } else {
+                None
+            }
+        }
+        (
+            SelfTestStep::Knob(knob),
+            InputEvent::KnobChanged { element, .. } | InputEvent::AudioChanged { element, .. },
+        ) => {
+            if element != knob {
+                return None;
+            }
+            progress.turned = true;
+            Some(progress.touched || touch_sensor(knob).is_none())
+        }
+        (
+            SelfTestStep::Pad(pad),
+            InputEvent::PadEvent {
+                pad_number,
+                event_type,
+                value,
+            },
+        ) => {
+            if pad_number != pad {
+                return None;
+            }
+            let calibration = result.pads[*pad as usize].get_or_insert(PadCalibration {
+                min_velocity: u16::MAX,
+                max_velocity: 0,
+                max_pressure: 0,
+                hits: 0,
+            });
+            match event_type {
+                PadEventType::Hit => {
+                    calibration.min_velocity = calibration.min_velocity.min(*value);
+                    calibration.max_velocity = calibration.max_velocity.max(*value);
+                    calibration.hits += 1;
+                    progress.pad_hits += 1;
+                }
+                PadEventType::Aftertouch => {
+                    calibration.max_pressure = calibration.max_pressure.max(*value);
+                }
+                PadEventType::HitRelease | PadEventType::TouchRelease => {}
+            }
+            Some(progress.pad_hits >= SELFTEST_PAD_HITS)
+        }
+        // Pad releases and aftertouch after a step completes are harmless
+        (
+            _,
+            InputEvent::PadEvent {
+                event_type:
+                    PadEventType::HitRelease | PadEventType::TouchRelease | PadEventType::Aftertouch,
+                ..
+            },
+        ) => Some(false),
+        (
+            SelfTestStep::TouchStrip,
+            InputEvent::TouchStripGesture(_) | InputEvent::StripChanged { .. },
+        ) => Some(true),
+        // The strip reports while fingers rest near it; don't flag that
+        (_, InputEvent::TouchStripGesture(_) | InputEvent::StripChanged { .. }) => Some(false),
+        (
+            _,
+            InputEvent::DeviceResumed
+            | InputEvent::PadPressure { .. }
+            | InputEvent::PacketsDropped { .. },
+        ) => Some(false),
+        _ => None,
+    }
+}
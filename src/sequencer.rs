@@ -0,0 +1,207 @@
+use crate::input::{InputElement, InputEvent, PadEventType};
+use crate::output::{LedFrame, MaschineLEDColor};
+
+/// Steps per pattern, one per pad
+pub const STEP_COUNT: usize = 16;
+
+/// Pattern pages, one per group button (A-H)
+pub const PAGE_COUNT: usize = 8;
+
+const PAGE_BUTTONS: [InputElement; PAGE_COUNT] = [
+    InputElement::GroupA,
+    InputElement::GroupB,
+    InputElement::GroupC,
+    InputElement::GroupD,
+    InputElement::GroupE,
+    InputElement::GroupF,
+    InputElement::GroupG,
+    InputElement::GroupH,
+];
+
+/// One page of 16 on/off steps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub steps: [bool; STEP_COUNT],
+}
+
+impl Pattern {
+    pub fn toggle(&mut self, step: usize) {
+        if let Some(active) = self.steps.get_mut(step) {
+            *active = !*active;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.steps.contains(&true)
+    }
+}
+
+/// 16-step grid sequencer mapped onto the pads.
+///
+/// Pad hits toggle steps (pad 0 top-left is step 1), group buttons A-H select
+/// the pattern page, and [`tick`](Self::tick) advances the playhead, typically
+/// from a clock callback. [`render`](Self::render) draws the current page and
+/// playhead into an [`LedFrame`].
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineMK3, StepSequencer};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut sequencer = StepSequencer::new();
+///
+/// loop {
+///     for event in device.poll_input_events()? {
+///         sequencer.handle_event(&event);
+///     }
+///
+///     // A real app ticks from its clock; 125ms is a 16th note at 120 BPM
+///     let step = sequencer.tick();
+///     if sequencer.pattern().steps[step] {
+///         println!("Trigger step {}", step + 1);
+///     }
+///     device.update_leds(|leds| sequencer.render(leds))?;
+///     std::thread::sleep(Duration::from_millis(125));
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StepSequencer {
+    pages: [Pattern; PAGE_COUNT],
+    page: usize,
+    playhead: Option<usize>,
+    step_color: MaschineLEDColor,
+    playhead_color: MaschineLEDColor,
+    page_color: MaschineLEDColor,
+}
+
+impl Default for StepSequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StepSequencer {
+    pub fn new() -> Self {
+        Self {
+            pages: [Pattern::default(); PAGE_COUNT],
+            page: 0,
+            playhead: None,
+            step_color: MaschineLEDColor::blue(true),
+            playhead_color: MaschineLEDColor::white(true),
+            page_color: MaschineLEDColor::orange(true),
+        }
+    }
+
+    /// Index of the page being edited and played (0 = group A)
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        if page < PAGE_COUNT {
+            self.page = page;
+        }
+    }
+
+    /// Pattern on the current page
+    pub fn pattern(&self) -> &Pattern {
+        &self.pages[self.page]
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.pages[self.page]
+    }
+
+    /// All pattern pages
+    pub fn pages(&self) -> &[Pattern; PAGE_COUNT] {
+        &self.pages
+    }
+
+    /// Current playhead position, or `None` before the first tick
+    pub fn playhead(&self) -> Option<usize> {
+        self.playhead
+    }
+
+    /// Advance the playhead by one step and return the new step index
+    pub fn tick(&mut self) -> usize {
+        let step = match self.playhead {
+            Some(step) => (step + 1) % STEP_COUNT,
+            None => 0,
+        };
+        self.playhead = Some(step);
+        step
+    }
+
+    /// Move the playhead back to before the first step
+    pub fn reset(&mut self) {
+        self.playhead = None;
+    }
+
+    pub fn set_colors(
+        &mut self,
+        step: MaschineLEDColor,
+        playhead: MaschineLEDColor,
+        page: MaschineLEDColor,
+    ) {
+        self.step_color = step;
+        self.playhead_color = playhead;
+        self.page_color = page;
+    }
+
+    /// Apply pad hits (toggle steps) and group button presses (select page).
+    /// Returns `true` if the sequencer state changed.
+    pub fn handle_event(&mut self, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::PadEvent {
+                pad_number,
+                event_type: PadEventType::Hit,
+                ..
+            } if (*pad_number as usize) < STEP_COUNT => {
+                self.pattern_mut().toggle(*pad_number as usize);
+                true
+            }
+            InputEvent::ButtonPressed(element) => {
+                match PAGE_BUTTONS.iter().position(|button| button == element) {
+                    Some(page) => {
+                        self.page = page;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Draw the current page onto the pads and the page selection onto the
+    /// group buttons. Other LEDs in the frame are left untouched.
+    pub fn render(&self, leds: &mut LedFrame) {
+        let pattern = self.pattern();
+        for step in 0..STEP_COUNT {
+            let color = if self.playhead == Some(step) {
+                self.playhead_color
+            } else if pattern.steps[step] {
+                self.step_color
+            } else {
+                MaschineLEDColor::black()
+            };
+            leds.set_pad(step as u8, color);
+        }
+
+        // Selected page bright, other pages with content dim
+        for (page, button) in PAGE_BUTTONS.iter().enumerate() {
+            let color = if page == self.page {
+                self.page_color
+            } else if !self.pages[page].is_empty() {
+                MaschineLEDColor::new(self.page_color.index, false)
+            } else {
+                MaschineLEDColor::black()
+            };
+            leds.set_button_color(*button, color);
+        }
+    }
+}
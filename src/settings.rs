@@ -0,0 +1,316 @@
+use crate::error::{MK3Error, Result};
+use crate::output::{ColorCalibration, ColorCorrection};
+use crate::pad_filter::PadFilterConfig;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory under the platform config dir that holds the settings files
+const CONFIG_DIR_NAME: &str = "maschine3-hal";
+
+/// Extension of a settings file
+const SETTINGS_EXTENSION: &str = "conf";
+
+/// Preferences for one controller, kept on the host per serial number.
+///
+/// [`MaschineMK3`](crate::MaschineMK3) loads the file for its serial at
+/// connection and applies it, and [`set_settings`] applies new settings and
+/// writes them back, so units in a multi-device setup keep their own
+/// calibration and brightness. `profile` and `values` are free for apps to
+/// store their own per-unit choices in.
+///
+/// The file is plain text, one `key = value` per line with `#` comments.
+/// Unknown keys are ignored so files written by newer versions still load.
+///
+/// [`set_settings`]: crate::MaschineMK3::set_settings
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::MaschineMK3;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let mut settings = device.settings().clone();
+/// settings.led_brightness = 0.5;
+/// settings.values.insert("layout".to_string(), "drums".to_string());
+/// device.set_settings(settings)?; // kept for the next connection
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSettings {
+    /// LED master brightness, see `MaschineMK3::set_led_master_brightness`
+    pub led_brightness: f32,
+    /// LED color calibration; the palette isn't stored
+    pub pad_calibration: ColorCalibration,
+    pub button_calibration: ColorCalibration,
+    pub touch_strip_calibration: ColorCalibration,
+    /// Color correction per display, see
+    /// `MaschineMK3::set_display_color_correction`
+    pub display_correction: [Option<ColorCorrection>; 2],
+    /// Pad debouncing, crosstalk suppression and per-pad velocity thresholds
+    pub pad_filter: PadFilterConfig,
+    /// Name of the app profile this unit uses
+    pub profile: Option<String>,
+    /// App-defined entries. Keys must not contain `=`, whitespace or line
+    /// breaks, values must not contain line breaks.
+    pub values: BTreeMap<String, String>,
+}
+
+impl Default for DeviceSettings {
+    /// What a controller uses when nothing was saved
+    fn default() -> Self {
+        Self {
+            led_brightness: 1.0,
+            pad_calibration: ColorCalibration::default(),
+            button_calibration: ColorCalibration::default(),
+            touch_strip_calibration: ColorCalibration::default(),
+            display_correction: [None; 2],
+            pad_filter: PadFilterConfig::default(),
+            profile: None,
+            values: BTreeMap::new(),
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// Parse a settings file's contents. Keys that aren't set keep their
+    /// default.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut settings = Self::default();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            settings.parse_line(line).map_err(|message| {
+                MK3Error::InvalidData(format!("line {}: {}", index + 1, message))
+            })?;
+        }
+        Ok(settings)
+    }
+
+    /// Read and parse a settings file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let source = std::fs::read_to_string(path.as_ref())?;
+        Self::parse(&source).map_err(|e| match e {
+            MK3Error::InvalidData(message) => {
+                MK3Error::InvalidData(format!("{}: {}", path.as_ref().display(), message))
+            }
+            e => e,
+        })
+    }
+
+    /// Write the settings to a file, creating its directory if needed
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = self.to_text()?;
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Settings in the file format. Fails if a profile or value can't be
+    /// stored on one line.
+    pub fn to_text(&self) -> Result<String> {
+        let mut text = String::new();
+        // Writing to a String can't fail
+        let mut line = |key: &str, value: String| {
+            let _ = writeln!(text, "{} = {}", key, value);
+        };
+
+        line("led_brightness", self.led_brightness.to_string());
+        let calibrations = [
+            ("pads", &self.pad_calibration),
+            ("buttons", &self.button_calibration),
+            ("touch_strip", &self.touch_strip_calibration),
+        ];
+        for (name, calibration) in calibrations {
+            line(&format!("{}.gamma", name), join(&calibration.gamma));
+            line(&format!("{}.gain", name), join(&calibration.gain));
+        }
+        for (display, correction) in self.display_correction.iter().enumerate() {
+            if let Some(correction) = correction {
+                line(
+                    &format!("display{}.matrix", display),
+                    join(correction.matrix.as_flattened()),
+                );
+                line(&format!("display{}.gamma", display), join(&correction.gamma));
+            }
+        }
+
+        let filter = &self.pad_filter;
+        line("pad_filter.debounce_ms", millis(filter.debounce));
+        line("pad_filter.crosstalk_window_ms", millis(filter.crosstalk_window));
+        line("pad_filter.crosstalk_ratio", filter.crosstalk_ratio.to_string());
+        line("pad_filter.thresholds", join(&filter.thresholds));
+
+        if let Some(profile) = &self.profile {
+            check_value("profile", profile)?;
+            line("profile", profile.clone());
+        }
+        for (key, value) in &self.values {
+            if key.is_empty() || key.contains(|c: char| c == '=' || c.is_whitespace()) {
+                return Err(MK3Error::InvalidData(format!(
+                    "Settings key {:?} must not be empty or contain '=' or whitespace",
+                    key
+                )));
+            }
+            check_value(key, value)?;
+            line(&format!("value.{}", key), value.clone());
+        }
+        Ok(text)
+    }
+
+    /// Platform directory the settings files are kept in: `%APPDATA%` on
+    /// Windows, `~/Library/Application Support` on macOS and
+    /// `$XDG_CONFIG_HOME` (or `~/.config`) elsewhere, each with a
+    /// `maschine3-hal` subdirectory
+    pub fn config_dir() -> Option<PathBuf> {
+        platform_config_dir().map(|dir| dir.join(CONFIG_DIR_NAME))
+    }
+
+    /// Settings file of the controller with serial number `serial`
+    pub fn path_for_serial(serial: &str) -> Option<PathBuf> {
+        let name: String = serial
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let name = if name.is_empty() { "unknown" } else { &name };
+        Self::config_dir().map(|dir| dir.join(format!("{}.{}", name, SETTINGS_EXTENSION)))
+    }
+
+    fn parse_line(&mut self, line: &str) -> std::result::Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'key = value', got {:?}", line))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        if let Some(name) = key.strip_prefix("value.") {
+            self.values.insert(name.to_string(), value.to_string());
+            return Ok(());
+        }
+
+        match key {
+            "led_brightness" => self.led_brightness = parse_number(key, value)?,
+            "pads.gamma" => self.pad_calibration.gamma = parse_list(key, value)?,
+            "pads.gain" => self.pad_calibration.gain = parse_list(key, value)?,
+            "buttons.gamma" => self.button_calibration.gamma = parse_list(key, value)?,
+            "buttons.gain" => self.button_calibration.gain = parse_list(key, value)?,
+            "touch_strip.gamma" => self.touch_strip_calibration.gamma = parse_list(key, value)?,
+            "touch_strip.gain" => self.touch_strip_calibration.gain = parse_list(key, value)?,
+            "display0.matrix" | "display1.matrix" => {
+                let values: [f32; 9] = parse_list(key, value)?;
+                let correction = self.correction_mut(key);
+                for (row, chunk) in correction.matrix.iter_mut().zip(values.chunks_exact(3)) {
+                    row.copy_from_slice(chunk);
+                }
+            }
+            "display0.gamma" | "display1.gamma" => {
+                self.correction_mut(key).gamma = parse_list(key, value)?;
+            }
+            "pad_filter.debounce_ms" => {
+                self.pad_filter.debounce = parse_millis(key, value)?;
+            }
+            "pad_filter.crosstalk_window_ms" => {
+                self.pad_filter.crosstalk_window = parse_millis(key, value)?;
+            }
+            "pad_filter.crosstalk_ratio" => {
+                self.pad_filter.crosstalk_ratio = parse_number(key, value)?;
+            }
+            "pad_filter.thresholds" => self.pad_filter.thresholds = parse_list(key, value)?,
+            "profile" => self.profile = Some(value.to_string()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Correction of the display named by a `displayN.` key, created with
+    /// defaults on first use
+    fn correction_mut(&mut self, key: &str) -> &mut ColorCorrection {
+        let display = usize::from(key.starts_with("display1"));
+        self.display_correction[display].get_or_insert_with(ColorCorrection::default)
+    }
+}
+
+fn check_value(key: &str, value: &str) -> Result<()> {
+    if value.contains(['\n', '\r']) || value.trim() != value {
+        return Err(MK3Error::InvalidData(format!(
+            "Settings value for {:?} must be one line without surrounding whitespace",
+            key
+        )));
+    }
+    Ok(())
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn millis(duration: Duration) -> String {
+    (duration.as_secs_f64() * 1000.0).to_string()
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> std::result::Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {:?} for {}", value, key))
+}
+
+fn parse_millis(key: &str, value: &str) -> std::result::Result<Duration, String> {
+    let ms: f64 = parse_number(key, value)?;
+    if !ms.is_finite() || ms < 0.0 {
+        return Err(format!("{} must be a positive number of milliseconds", key));
+    }
+    Ok(Duration::from_secs_f64(ms / 1000.0))
+}
+
+/// Exactly `N` whitespace-separated numbers
+fn parse_list<T: std::str::FromStr + Copy + Default, const N: usize>(
+    key: &str,
+    value: &str,
+) -> std::result::Result<[T; N], String> {
+    let mut list = [T::default(); N];
+    let mut items = value.split_whitespace();
+    for slot in list.iter_mut() {
+        let item = items
+            .next()
+            .ok_or_else(|| format!("{} needs {} numbers", key, N))?;
+        *slot = parse_number(key, item)?;
+    }
+    if items.next().is_some() {
+        return Err(format!("{} needs {} numbers", key, N));
+    }
+    Ok(list)
+}
+
+#[cfg(windows)]
+fn platform_config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_config_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
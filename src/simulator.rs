@@ -0,0 +1,351 @@
+use crate::device::{MaschineDevice, MaschineMK3};
+use crate::error::{MK3Error, Result};
+use crate::input::{InputElement, InputEvent, PadEventType};
+use crate::health::UsbEndpoint;
+use crate::output::{save_rgb888_png, ConversionOptions, DisplayPacket, LedFrame};
+use crate::recorder::OutputRecorder;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Largest raw knob value (10-bit), as reported by the hardware
+const KNOB_MAX_RAW: u16 = 1023;
+
+/// Velocity of simulated pad hits unless changed with `set_pad_velocity`
+const DEFAULT_PAD_VELOCITY: u16 = 3072;
+
+/// Knob movement per simulated key press
+const KNOB_KEY_STEP: i32 = 32;
+
+/// What a key drives on the simulated controller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulatedControl {
+    /// Pressed while the key is down
+    Button(InputElement),
+    /// Hit on key down, released on key up (pads 0-15, top-left first)
+    Pad(u8),
+    /// Turned by `delta` raw steps on each key down
+    Knob { element: InputElement, delta: i32 },
+}
+
+/// Keyboard bindings for [`SimulatedMK3`].
+///
+/// The default layout puts the pads on `1234` / `qwer` / `asdf` / `zxcv`
+/// (matching the 4x4 grid), Play on space, Rec on `R`, Stop on `S`, Tap on
+/// `T`, Shift on `` ` ``, groups A-H on `A`-`H`, and knobs 1-4 on `yuio`
+/// (up) and `hjkl` (down).
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<char, SimulatedControl>,
+}
+
+impl KeyMap {
+    /// Map with no bindings
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    pub fn bind(&mut self, key: char, control: SimulatedControl) {
+        self.bindings.insert(key, control);
+    }
+
+    pub fn unbind(&mut self, key: char) {
+        self.bindings.remove(&key);
+    }
+
+    pub fn get(&self, key: char) -> Option<&SimulatedControl> {
+        self.bindings.get(&key)
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut map = Self::empty();
+
+        for (pad, key) in "1234qwerasdfzxcv".chars().enumerate() {
+            map.bind(key, SimulatedControl::Pad(pad as u8));
+        }
+
+        let buttons = [
+            (' ', InputElement::Play),
+            ('R', InputElement::Rec),
+            ('S', InputElement::Stop),
+            ('T', InputElement::Tap),
+            ('`', InputElement::Shift),
+            ('A', InputElement::GroupA),
+            ('B', InputElement::GroupB),
+            ('C', InputElement::GroupC),
+            ('D', InputElement::GroupD),
+            ('E', InputElement::GroupE),
+            ('F', InputElement::GroupF),
+            ('G', InputElement::GroupG),
+            ('H', InputElement::GroupH),
+        ];
+        for (key, element) in buttons {
+            map.bind(key, SimulatedControl::Button(element));
+        }
+
+        let knobs = [
+            InputElement::Knob1,
+            InputElement::Knob2,
+            InputElement::Knob3,
+            InputElement::Knob4,
+        ];
+        for ((up, down), element) in "yuio".chars().zip("hjkl".chars()).zip(knobs) {
+            map.bind(
+                up,
+                SimulatedControl::Knob {
+                    element,
+                    delta: KNOB_KEY_STEP,
+                },
+            );
+            map.bind(
+                down,
+                SimulatedControl::Knob {
+                    element,
+                    delta: -KNOB_KEY_STEP,
+                },
+            );
+        }
+
+        map
+    }
+}
+
+/// Software Maschine MK3 for developing without hardware or in CI.
+///
+/// Input comes from [`key_down`](Self::key_down)/[`key_up`](Self::key_up)
+/// through a [`KeyMap`], or from events pushed directly. Display frames are
+/// kept in memory and, with [`set_png_output`](Self::set_png_output), written
+/// to `display0.png` / `display1.png` on every update.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineDevice, MaschineLEDColor, SimulatedMK3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = SimulatedMK3::new();
+/// device.set_png_output(Some("frames"));
+///
+/// device.key_down('1');
+/// for event in device.poll_input_events()? {
+///     println!("{}", event.description());
+/// }
+/// device.update_leds(|leds| leds.set_pad(0, MaschineLEDColor::red(true)))?;
+/// device.send_display_rgb888(0, &vec![0x40; 480 * 272 * 3])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimulatedMK3 {
+    leds: LedFrame,
+    displays: [Option<Vec<u8>>; 2],
+    events: VecDeque<InputEvent>,
+    key_map: KeyMap,
+    held_keys: HashSet<char>,
+    knob_values: HashMap<InputElement, u16>,
+    pad_velocity: u16,
+    png_output: Option<PathBuf>,
+    output_recorder: Option<OutputRecorder>,
+}
+
+impl Default for SimulatedMK3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedMK3 {
+    pub fn new() -> Self {
+        Self {
+            leds: LedFrame::default(),
+            displays: [None, None],
+            events: VecDeque::new(),
+            key_map: KeyMap::default(),
+            held_keys: HashSet::new(),
+            knob_values: HashMap::new(),
+            pad_velocity: DEFAULT_PAD_VELOCITY,
+            png_output: None,
+            output_recorder: None,
+        }
+    }
+
+    pub fn key_map(&self) -> &KeyMap {
+        &self.key_map
+    }
+
+    pub fn key_map_mut(&mut self) -> &mut KeyMap {
+        &mut self.key_map
+    }
+
+    /// Velocity (0-4095) used for pad hits from keys
+    pub fn set_pad_velocity(&mut self, velocity: u16) {
+        self.pad_velocity = velocity.min(4095);
+    }
+
+    /// Write each display to `display<N>.png` in `dir` whenever it changes,
+    /// or stop writing with `None`
+    pub fn set_png_output<P: AsRef<Path>>(&mut self, dir: Option<P>) {
+        self.png_output = dir.map(|dir| dir.as_ref().to_path_buf());
+    }
+
+    /// Handle a key press. Auto-repeat is ignored for buttons and pads but
+    /// turns knobs further.
+    pub fn key_down(&mut self, key: char) {
+        let Some(control) = self.key_map.get(key).cloned() else {
+            return;
+        };
+        let repeat = !self.held_keys.insert(key);
+
+        match control {
+            SimulatedControl::Button(element) if !repeat => self.press_button(element),
+            SimulatedControl::Pad(pad) if !repeat => self.hit_pad(pad, self.pad_velocity),
+            SimulatedControl::Knob { element, delta } => self.turn_knob(element, delta),
+            _ => {}
+        }
+    }
+
+    pub fn key_up(&mut self, key: char) {
+        if !self.held_keys.remove(&key) {
+            return;
+        }
+
+        match self.key_map.get(key).cloned() {
+            Some(SimulatedControl::Button(element)) => self.release_button(element),
+            Some(SimulatedControl::Pad(pad)) => self.release_pad(pad),
+            _ => {}
+        }
+    }
+
+    pub fn press_button(&mut self, element: InputElement) {
+        self.events.push_back(InputEvent::ButtonPressed(element));
+    }
+
+    pub fn release_button(&mut self, element: InputElement) {
+        self.events.push_back(InputEvent::ButtonReleased(element));
+    }
+
+    pub fn hit_pad(&mut self, pad_number: u8, velocity: u16) {
+        self.events.push_back(InputEvent::PadEvent {
+            pad_number,
+            event_type: PadEventType::Hit,
+            value: velocity.min(4095),
+        });
+    }
+
+    pub fn release_pad(&mut self, pad_number: u8) {
+        self.events.push_back(InputEvent::PadEvent {
+            pad_number,
+            event_type: PadEventType::HitRelease,
+            value: 0,
+        });
+    }
+
+    /// Move a knob by `delta` raw steps, clamped to the 10-bit range
+    pub fn turn_knob(&mut self, element: InputElement, delta: i32) {
+        let value = self.knob_values.entry(element).or_insert(0);
+        let new_value = (*value as i32 + delta).clamp(0, KNOB_MAX_RAW as i32) as u16;
+        let delta = new_value as i32 - *value as i32;
+        if delta == 0 {
+            return;
+        }
+        *value = new_value;
+
+        self.events.push_back(InputEvent::KnobChanged {
+            element,
+            value: new_value,
+            delta,
+        });
+    }
+
+    /// Queue an arbitrary event for the next `poll_input_events`
+    /// Record the packets the hardware would be sent: both LED packets for
+    /// every frame and a full-screen packet for every display frame
+    pub fn set_output_recorder(&mut self, recorder: Option<OutputRecorder>) {
+        self.output_recorder = recorder;
+    }
+
+    pub fn push_event(&mut self, event: InputEvent) {
+        self.events.push_back(event);
+    }
+
+    /// Current LED state
+    pub fn leds(&self) -> &LedFrame {
+        &self.leds
+    }
+
+    /// Last frame sent to a display
+    pub fn display_frame(&self, display_num: u8) -> Option<&[u8]> {
+        self.displays.get(display_num as usize)?.as_deref()
+    }
+
+    /// Save the last frame sent to a display as a 480x272 PNG
+    pub fn save_display_png<P: AsRef<Path>>(&self, display_num: u8, path: P) -> Result<()> {
+        let Some(rgb_data) = self.display_frame(display_num) else {
+            return Err(MK3Error::InvalidData(format!(
+                "No frame sent to display {}",
+                display_num
+            )));
+        };
+        save_rgb888_png(path.as_ref(), rgb_data)
+    }
+}
+
+impl MaschineDevice for SimulatedMK3 {
+    /// Drain queued events. Unlike the hardware this never blocks.
+    fn poll_input_events(&mut self) -> Result<Vec<InputEvent>> {
+        Ok(self.events.drain(..).collect())
+    }
+
+    fn led_frame(&self) -> LedFrame {
+        self.leds.clone()
+    }
+
+    fn set_led_frame(&mut self, frame: LedFrame) -> Result<()> {
+        if let Some(recorder) = &self.output_recorder {
+            recorder.record(UsbEndpoint::Led, &frame.buttons.to_packet());
+            recorder.record(UsbEndpoint::Led, &frame.pads.to_packet());
+        }
+        self.leds = frame;
+        Ok(())
+    }
+
+    fn send_display_rgb888(&mut self, display_num: u8, rgb_data: &[u8]) -> Result<()> {
+        if display_num > 1 {
+            return Err(MK3Error::InvalidData(
+                "Display number must be 0 or 1".to_string(),
+            ));
+        }
+
+        let frame_len =
+            MaschineMK3::DISPLAY_WIDTH as usize * MaschineMK3::DISPLAY_HEIGHT as usize * 3;
+        if rgb_data.len() != frame_len {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes, got {}",
+                frame_len,
+                rgb_data.len()
+            )));
+        }
+
+        self.displays[display_num as usize] = Some(rgb_data.to_vec());
+
+        if let Some(recorder) = &self.output_recorder {
+            let width = MaschineMK3::DISPLAY_WIDTH as usize;
+            let pixels = ConversionOptions::default().convert(rgb_data, width);
+            let packet = DisplayPacket::full_screen_optimized(display_num, pixels);
+            recorder.record(UsbEndpoint::Display, &packet.to_packet());
+        }
+
+        if let Some(dir) = &self.png_output {
+            let path = dir.join(format!("display{}.png", display_num));
+            save_rgb888_png(&path, rgb_data)?;
+        }
+        Ok(())
+    }
+
+    fn framebuffer_snapshot(&self, display_num: u8) -> Option<Vec<u8>> {
+        self.display_frame(display_num).map(<[u8]>::to_vec)
+    }
+}
@@ -0,0 +1,238 @@
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames per second of splash fades
+pub(crate) const FADE_FPS: u32 = 30;
+
+const WIDTH: usize = MaschineMK3::DISPLAY_WIDTH as usize;
+const HEIGHT: usize = MaschineMK3::DISPLAY_HEIGHT as usize;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const BMP_SIGNATURE: &[u8] = b"BM";
+
+/// How [`MaschineMK3::show_splash`](crate::MaschineMK3::show_splash) brings
+/// the image up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplashTransition {
+    /// Show the image at once
+    #[default]
+    Cut,
+    /// Ramp the image up from black over the given time
+    FadeIn(Duration),
+}
+
+/// Startup image for both displays, fitted to 480x272.
+///
+/// Images of another size are scaled to fit with their aspect ratio kept,
+/// centered on black.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{MaschineMK3, ShutdownBehavior, SplashImage, SplashTransition};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut device = MaschineMK3::new()?;
+/// let splash = SplashImage::load("assets/splash.bmp")?;
+/// device.show_splash(&splash, SplashTransition::FadeIn(Duration::from_millis(800)))?;
+/// device.set_shutdown_behavior(ShutdownBehavior::FadeOut(Duration::from_millis(500)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplashImage {
+    rgb: Vec<u8>,
+}
+
+impl SplashImage {
+    /// Image from `width * height * 3` bytes of row-major RGB888
+    pub fn from_rgb888(width: usize, height: usize, rgb: &[u8]) -> Result<Self> {
+        if width == 0 || height == 0 || rgb.len() != width * height * 3 {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes for a {}x{} image, got {}",
+                width * height * 3,
+                width,
+                height,
+                rgb.len()
+            )));
+        }
+        Ok(Self {
+            rgb: fit_to_display(width, height, rgb),
+        })
+    }
+
+    /// Decode an uncompressed 24 or 32-bit BMP file
+    pub fn from_bmp(bytes: &[u8]) -> Result<Self> {
+        let (width, height, rgb) = decode_bmp(bytes)?;
+        Self::from_rgb888(width, height, &rgb)
+    }
+
+    /// Decode a PNG file; transparent areas are shown on black
+    #[cfg(feature = "png")]
+    pub fn from_png(bytes: &[u8]) -> Result<Self> {
+        let (width, height, rgb) = decode_png(bytes)?;
+        Self::from_rgb888(width, height, &rgb)
+    }
+
+    /// Read a BMP or (with the `png` feature) PNG file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref())?;
+        if bytes.starts_with(BMP_SIGNATURE) {
+            return Self::from_bmp(&bytes);
+        }
+        if bytes.starts_with(PNG_SIGNATURE) {
+            #[cfg(feature = "png")]
+            return Self::from_png(&bytes);
+            #[cfg(not(feature = "png"))]
+            return Err(MK3Error::InvalidData(
+                "PNG splash images need the `png` feature".to_string(),
+            ));
+        }
+        Err(MK3Error::InvalidData(format!(
+            "{} is not a BMP or PNG file",
+            path.as_ref().display()
+        )))
+    }
+
+    /// The fitted 480x272 RGB888 frame
+    pub fn rgb888(&self) -> &[u8] {
+        &self.rgb
+    }
+}
+
+/// Number of frames in a fade lasting `duration`, at least 1
+pub(crate) fn fade_steps(duration: Duration) -> u32 {
+    ((duration.as_secs_f32() * FADE_FPS as f32).ceil() as u32).max(1)
+}
+
+/// Copy of an RGB888 frame at `level` (0.0 - 1.0) brightness
+pub(crate) fn faded(rgb: &[u8], level: f32) -> Vec<u8> {
+    let level = level.clamp(0.0, 1.0);
+    rgb.iter()
+        .map(|&value| (value as f32 * level).round() as u8)
+        .collect()
+}
+
+/// Nearest-neighbour scale to fit 480x272, keeping the aspect ratio
+fn fit_to_display(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    if width == WIDTH && height == HEIGHT {
+        return rgb.to_vec();
+    }
+
+    // Fit to whichever side is tighter
+    let (out_width, out_height) = if width * HEIGHT > height * WIDTH {
+        (WIDTH, (height * WIDTH / width).max(1))
+    } else {
+        ((width * HEIGHT / height).max(1), HEIGHT)
+    };
+    let left = (WIDTH - out_width) / 2;
+    let top = (HEIGHT - out_height) / 2;
+
+    let mut frame = vec![0u8; WIDTH * HEIGHT * 3];
+    for y in 0..out_height {
+        let src_y = y * height / out_height;
+        for x in 0..out_width {
+            let src_x = x * width / out_width;
+            let src = (src_y * width + src_x) * 3;
+            let dst = ((top + y) * WIDTH + left + x) * 3;
+            frame[dst..dst + 3].copy_from_slice(&rgb[src..src + 3]);
+        }
+    }
+    frame
+}
+
+fn decode_bmp(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>)> {
+    let invalid = |message: &str| MK3Error::InvalidData(format!("Invalid BMP: {}", message));
+    let u16_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| invalid("truncated header"))
+    };
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| invalid("truncated header"))
+    };
+
+    if !bytes.starts_with(BMP_SIGNATURE) {
+        return Err(invalid("missing BM signature"));
+    }
+    let data_offset = u32_at(10)? as usize;
+    let width = u32_at(18)? as i32;
+    let height = u32_at(22)? as i32;
+    let bits = u16_at(28)?;
+    let compression = u32_at(30)?;
+
+    // 32-bit files often use BI_BITFIELDS with the standard BGRA masks
+    const BI_RGB: u32 = 0;
+    const BI_BITFIELDS: u32 = 3;
+    let bytes_per_pixel = match (bits, compression) {
+        (24, BI_RGB) => 3,
+        (32, BI_RGB | BI_BITFIELDS) => 4,
+        _ => {
+            return Err(invalid(
+                "only uncompressed 24 and 32-bit images are supported",
+            ))
+        }
+    };
+    if width <= 0 || height == 0 {
+        return Err(invalid("empty image"));
+    }
+
+    // Rows are stored bottom-up unless the height is negative
+    let (width, rows) = (width as usize, height.unsigned_abs() as usize);
+    let bottom_up = height > 0;
+    let stride = (width * bytes_per_pixel).div_ceil(4) * 4;
+    let pixels = bytes
+        .get(data_offset..)
+        .filter(|pixels| pixels.len() >= stride * rows)
+        .ok_or_else(|| invalid("truncated pixel data"))?;
+
+    let mut rgb = Vec::with_capacity(width * rows * 3);
+    for y in 0..rows {
+        let row = if bottom_up { rows - 1 - y } else { y };
+        let row = &pixels[row * stride..row * stride + width * bytes_per_pixel];
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            rgb.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+    }
+    Ok((width, rows, rgb))
+}
+
+#[cfg(feature = "png")]
+fn decode_png(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>)> {
+    let png_error = |e: png::DecodingError| MK3Error::InvalidData(format!("Invalid PNG: {}", e));
+
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(png_error)?;
+    let mut buffer = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(png_error)?;
+    let data = &buffer[..info.buffer_size()];
+
+    // Blend alpha against black, the color around the fitted image
+    let blend = |value: u8, alpha: u8| (value as u16 * alpha as u16 / 255) as u8;
+    let rgb = match info.color_type {
+        png::ColorType::Rgb => data.to_vec(),
+        png::ColorType::Rgba => data
+            .chunks_exact(4)
+            .flat_map(|p| [blend(p[0], p[3]), blend(p[1], p[3]), blend(p[2], p[3])])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&v| [v, v, v]).collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|p| [blend(p[0], p[1]); 3])
+            .collect(),
+        png::ColorType::Indexed => {
+            return Err(MK3Error::InvalidData(
+                "Invalid PNG: palette was not expanded".to_string(),
+            ))
+        }
+    };
+    Ok((info.width as usize, info.height as usize, rgb))
+}
@@ -0,0 +1,59 @@
+use crate::device::MaschineMK3;
+use crate::error::Result;
+use crate::output::{ButtonLedState, PadLedState};
+
+/// Snapshot of everything the host has written to the device: button LED
+/// brightness and colors, pad and touch strip LED colors, the master LED
+/// brightness, and the last frame sent to each display.
+///
+/// The controller forgets all of this on a USB reset. Take a snapshot with
+/// [`MaschineMK3::save_state`] and apply it to the new connection with
+/// [`restore`](Self::restore).
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::MaschineMK3;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = MaschineMK3::new()?;
+/// let state = device.save_state();
+/// drop(device);
+///
+/// // ... device was unplugged and plugged back in ...
+/// let mut device = MaschineMK3::new()?;
+/// state.restore(&mut device)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeviceState {
+    pub buttons: ButtonLedState,
+    pub pads: PadLedState,
+    /// See [`MaschineMK3::set_led_master_brightness`]
+    pub led_master_brightness: f32,
+    /// Last RGB888 frame sent to each display through the dirty-region path,
+    /// `None` if that display was never written that way
+    pub displays: [Option<Vec<u8>>; 2],
+}
+
+impl Default for DeviceState {
+    fn default() -> Self {
+        Self {
+            buttons: ButtonLedState::default(),
+            pads: PadLedState::default(),
+            led_master_brightness: 1.0,
+            displays: [None, None],
+        }
+    }
+}
+
+impl DeviceState {
+    /// Resend the saved LED state and display frames to a device.
+    ///
+    /// Everything is sent in full, regardless of what the device believes
+    /// it already sent.
+    pub fn restore(&self, device: &mut MaschineMK3) -> Result<()> {
+        device.restore_state(self)
+    }
+}
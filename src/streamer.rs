@@ -0,0 +1,449 @@
+use crate::device::MaschineMK3;
+use crate::error::{MK3Error, Result};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Consecutive near-full-screen dirty frames before auto mode stops diffing
+const FULL_FRAME_STREAK: u32 = 5;
+/// How long auto mode stays in full-frame mode before trying dirty regions again
+const DIRTY_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest pause between frames after repeated transfer failures
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+/// Weight of the newest sample in the throughput and transfer-time averages
+const SMOOTHING: f64 = 0.2;
+
+/// How a [`DisplayStreamer`] transfers frames
+///
+/// The MK3 displays only accept uncompressed RGB565, so there is no
+/// compressed mode yet; this enum is non-exhaustive so one can be added if
+/// the firmware turns out to support it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum StreamMode {
+    /// Pick between the modes below from what each frame actually changes
+    #[default]
+    Auto,
+    /// Always send the whole screen, skipping the diff against the last frame
+    FullFrame,
+    /// Send only the area that changed since the last frame
+    DirtyRegion,
+}
+
+/// Settings for a [`DisplayStreamer`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamerConfig {
+    /// Highest rate frames are sent at per display
+    pub target_fps: f32,
+    pub mode: StreamMode,
+    /// In auto mode, dirty regions covering at least this fraction of the
+    /// screen count as full frames
+    pub full_frame_threshold: f32,
+}
+
+impl Default for StreamerConfig {
+    fn default() -> Self {
+        Self {
+            target_fps: 30.0,
+            mode: StreamMode::Auto,
+            full_frame_threshold: 0.8,
+        }
+    }
+}
+
+/// Counters and measurements for one display of a [`DisplayStreamer`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StreamStats {
+    /// Frames passed to [`submit`](DisplayStreamer::submit)
+    pub submitted: u64,
+    /// Frames transferred to the display
+    pub sent: u64,
+    /// Frames replaced by a newer one before they could be sent
+    pub dropped: u64,
+    /// Transfers that failed
+    pub errors: u64,
+    /// Mode used for the last frame, `Auto` until one has been sent
+    pub mode: StreamMode,
+    /// Rate frames are currently limited to, below `target_fps` when the
+    /// bus can't keep up
+    pub fps: f32,
+    /// Average pixel payload throughput of recent transfers
+    pub bytes_per_second: f64,
+    /// Duration of the last transfer
+    pub last_transfer: Duration,
+}
+
+/// Scheduling and mode state for one display, owned by the streaming thread
+#[derive(Debug)]
+struct Lane {
+    next_send_at: Instant,
+    /// Shortest gap between frames the bus currently sustains
+    min_interval: Duration,
+    /// Average transfer time in seconds
+    transfer_secs: f64,
+    /// Mode auto mode has settled on
+    auto_mode: StreamMode,
+    /// Mode the previous frame was sent with
+    last_mode: Option<StreamMode>,
+    full_streak: u32,
+    probe_at: Instant,
+    /// The next dirty frame is resent in full, so it says nothing about how
+    /// much the content changes
+    skip_measurement: bool,
+}
+
+impl Lane {
+    fn new(now: Instant) -> Self {
+        Self {
+            next_send_at: now,
+            min_interval: Duration::ZERO,
+            transfer_secs: 0.0,
+            auto_mode: StreamMode::DirtyRegion,
+            last_mode: None,
+            full_streak: 0,
+            probe_at: now,
+            skip_measurement: false,
+        }
+    }
+
+    fn mode(&mut self, config: &StreamerConfig, now: Instant) -> StreamMode {
+        match config.mode {
+            StreamMode::Auto => {
+                if self.auto_mode == StreamMode::FullFrame && now >= self.probe_at {
+                    self.auto_mode = StreamMode::DirtyRegion;
+                    self.full_streak = 0;
+                }
+                self.auto_mode
+            }
+            mode => mode,
+        }
+    }
+
+    fn interval(&self, config: &StreamerConfig) -> Duration {
+        frame_interval(config.target_fps).max(self.min_interval)
+    }
+
+    /// Account for a dirty-region frame that covered `coverage` of the screen
+    fn measure_coverage(&mut self, config: &StreamerConfig, coverage: f32, now: Instant) {
+        if std::mem::take(&mut self.skip_measurement) || config.mode != StreamMode::Auto {
+            return;
+        }
+        if coverage < config.full_frame_threshold {
+            self.full_streak = 0;
+            return;
+        }
+        self.full_streak += 1;
+        if self.full_streak >= FULL_FRAME_STREAK {
+            self.auto_mode = StreamMode::FullFrame;
+            self.probe_at = now + DIRTY_PROBE_INTERVAL;
+        }
+    }
+
+    fn record_success(&mut self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        self.transfer_secs = if self.transfer_secs == 0.0 {
+            secs
+        } else {
+            self.transfer_secs + SMOOTHING * (secs - self.transfer_secs)
+        };
+        // Recover gradually after a backoff, but never below what a transfer
+        // actually takes
+        let transfer = Duration::from_secs_f64(self.transfer_secs);
+        self.min_interval = self.min_interval.mul_f32(0.9).max(transfer);
+    }
+
+    fn record_failure(&mut self) {
+        self.min_interval = (self.min_interval * 2)
+            .max(Duration::from_millis(10))
+            .min(MAX_BACKOFF);
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: StreamerConfig,
+    pending: [Option<Vec<u8>>; 2],
+    stats: [StreamStats; 2],
+    stop: bool,
+}
+
+/// Streams frames to the displays from a background thread, however fast
+/// they are produced.
+///
+/// [`submit`](Self::submit) never blocks on USB: a frame waits until its
+/// display is due, and a newer frame replaces one that hasn't been sent yet.
+/// That caps each display at `target_fps` and lowers the rate further when
+/// transfers take longer than the frame interval or start failing, so a
+/// visualizer that renders as fast as it can doesn't overrun the bulk
+/// endpoint.
+///
+/// In [`StreamMode::Auto`] frames go through the dirty-region path while
+/// they change only part of the screen. Once most frames change nearly all
+/// of it, the streamer sends full frames and skips the diff, checking again
+/// every second whether dirty regions would pay off.
+///
+/// # Example
+///
+/// ```no_run
+/// use maschine3_hal::{DisplayStreamer, MaschineMK3, StreamerConfig};
+/// use std::sync::{Arc, Mutex};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let device = Arc::new(Mutex::new(MaschineMK3::new()?));
+/// let streamer = DisplayStreamer::new(Arc::clone(&device), StreamerConfig::default());
+///
+/// for i in 0..1000u32 {
+///     let frame = vec![(i % 256) as u8; 480 * 272 * 3];
+///     streamer.submit(0, frame)?;
+/// }
+/// println!("{:?}", streamer.stats(0)?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DisplayStreamer {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DisplayStreamer {
+    pub fn new(device: Arc<Mutex<MaschineMK3>>, config: StreamerConfig) -> Self {
+        let shared = Arc::new((
+            Mutex::new(Shared {
+                config,
+                pending: [None, None],
+                stats: [StreamStats::default(); 2],
+                stop: false,
+            }),
+            Condvar::new(),
+        ));
+
+        let thread_shared = Arc::clone(&shared);
+        let thread = thread::spawn(move || stream(device, thread_shared));
+
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Queue a 480x272 RGB888 frame for a display, replacing any frame still
+    /// waiting to be sent there
+    pub fn submit(&self, display_id: u8, frame: Vec<u8>) -> Result<()> {
+        check_display_id(display_id)?;
+
+        let frame_bytes =
+            MaschineMK3::DISPLAY_WIDTH as usize * MaschineMK3::DISPLAY_HEIGHT as usize * 3;
+        if frame.len() != frame_bytes {
+            return Err(MK3Error::InvalidData(format!(
+                "Expected {} RGB bytes, got {}",
+                frame_bytes,
+                frame.len()
+            )));
+        }
+
+        let (lock, wakeup) = &*self.shared;
+        let mut shared = lock_shared(lock)?;
+        let replaced = shared.pending[display_id as usize].replace(frame).is_some();
+        let stats = &mut shared.stats[display_id as usize];
+        stats.submitted += 1;
+        if replaced {
+            stats.dropped += 1;
+        }
+        wakeup.notify_one();
+        Ok(())
+    }
+
+    pub fn config(&self) -> Result<StreamerConfig> {
+        Ok(lock_shared(&self.shared.0)?.config)
+    }
+
+    /// Change the target rate or mode; applies from the next frame
+    pub fn set_config(&self, config: StreamerConfig) -> Result<()> {
+        let (lock, wakeup) = &*self.shared;
+        lock_shared(lock)?.config = config;
+        wakeup.notify_one();
+        Ok(())
+    }
+
+    pub fn stats(&self, display_id: u8) -> Result<StreamStats> {
+        check_display_id(display_id)?;
+        Ok(lock_shared(&self.shared.0)?.stats[display_id as usize])
+    }
+
+    /// Whether a frame is still waiting to be sent to a display
+    pub fn is_pending(&self, display_id: u8) -> bool {
+        lock_shared(&self.shared.0)
+            .map(|shared| {
+                shared
+                    .pending
+                    .get(display_id as usize)
+                    .is_some_and(Option::is_some)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Stop the streaming thread. Frames not sent yet are discarded; the
+    /// displays keep showing the last frame that was.
+    pub fn stop(&mut self) -> Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+
+        let (lock, wakeup) = &*self.shared;
+        lock_shared(lock)?.stop = true;
+        wakeup.notify_one();
+        thread
+            .join()
+            .map_err(|_| MK3Error::InvalidData("Failed to join streaming thread".to_string()))
+    }
+}
+
+impl Drop for DisplayStreamer {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn check_display_id(display_id: u8) -> Result<()> {
+    if display_id > 1 {
+        return Err(MK3Error::InvalidData(
+            "Display number must be 0 or 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn lock_shared(lock: &Mutex<Shared>) -> Result<MutexGuard<'_, Shared>> {
+    lock.lock()
+        .map_err(|_| MK3Error::InvalidData("Streamer state lock poisoned".to_string()))
+}
+
+fn frame_interval(fps: f32) -> Duration {
+    if fps > 0.0 {
+        Duration::from_secs_f32(1.0 / fps)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// Body of the streaming thread
+fn stream(device: Arc<Mutex<MaschineMK3>>, shared: Arc<(Mutex<Shared>, Condvar)>) {
+    let (lock, wakeup) = &*shared;
+    let mut lanes = [Lane::new(Instant::now()), Lane::new(Instant::now())];
+
+    loop {
+        // Wait for a display that has a frame and is due
+        let (display_id, frame, config) = {
+            let Ok(mut state) = lock.lock() else {
+                return;
+            };
+            loop {
+                if state.stop {
+                    return;
+                }
+
+                let now = Instant::now();
+                let due = (0..2)
+                    .filter(|&id| state.pending[id].is_some())
+                    .min_by_key(|&id| lanes[id].next_send_at);
+                match due {
+                    Some(id) if lanes[id].next_send_at <= now => {
+                        let frame = state.pending[id].take().unwrap_or_default();
+                        break (id, frame, state.config);
+                    }
+                    Some(id) => {
+                        let timeout = lanes[id].next_send_at - now;
+                        match wakeup.wait_timeout(state, timeout) {
+                            Ok((guard, _)) => state = guard,
+                            Err(_) => return,
+                        }
+                    }
+                    None => match wakeup.wait(state) {
+                        Ok(guard) => state = guard,
+                        Err(_) => return,
+                    },
+                }
+            }
+        };
+
+        let lane = &mut lanes[display_id];
+        let started = Instant::now();
+        let mode = lane.mode(&config, started);
+        let result = send_frame(&device, display_id as u8, &frame, mode, lane);
+        let finished = Instant::now();
+        let elapsed = finished - started;
+
+        match result {
+            Ok(Some(coverage)) => {
+                lane.record_success(elapsed);
+                lane.measure_coverage(&config, coverage, finished);
+            }
+            Ok(None) => lane.record_success(elapsed),
+            Err(_) => {
+                // A frame that failed is dropped; the next one carries the
+                // same content anyway. Dirty-region writes drop their cache
+                // on failure, so the next one resends the whole screen.
+                lane.record_failure();
+                lane.skip_measurement = true;
+            }
+        }
+        lane.last_mode = Some(mode);
+        lane.next_send_at = started + lane.interval(&config);
+
+        let Ok(mut state) = lock.lock() else {
+            return;
+        };
+        let stats = &mut state.stats[display_id];
+        stats.mode = mode;
+        stats.fps = 1.0 / lane.interval(&config).as_secs_f32().max(f32::EPSILON);
+        stats.last_transfer = elapsed;
+        match result {
+            Ok(coverage) => {
+                stats.sent += 1;
+                let full_bytes =
+                    MaschineMK3::DISPLAY_WIDTH as f64 * MaschineMK3::DISPLAY_HEIGHT as f64 * 2.0;
+                let bytes = coverage.map_or(full_bytes, |c| c as f64 * full_bytes);
+                let rate = bytes / elapsed.as_secs_f64().max(1e-6);
+                stats.bytes_per_second = if stats.bytes_per_second == 0.0 {
+                    rate
+                } else {
+                    stats.bytes_per_second + SMOOTHING * (rate - stats.bytes_per_second)
+                };
+            }
+            Err(_) => stats.errors += 1,
+        }
+    }
+}
+
+/// Send one frame in `mode`. For dirty-region frames, returns the fraction of
+/// the screen that was sent.
+fn send_frame(
+    device: &Mutex<MaschineMK3>,
+    display_id: u8,
+    frame: &[u8],
+    mode: StreamMode,
+    lane: &mut Lane,
+) -> Result<Option<f32>> {
+    let mut device = device
+        .lock()
+        .map_err(|_| MK3Error::InvalidData("Device lock poisoned".to_string()))?;
+
+    match mode {
+        StreamMode::DirtyRegion => {
+            if lane.last_mode == Some(StreamMode::FullFrame) {
+                // Full frames bypass the dirty-region cache, so it no longer
+                // matches the screen
+                device.invalidate_display(display_id)?;
+                lane.skip_measurement = true;
+            }
+            device.write_display_framebuffer_rgb888_dirty(display_id, frame)?;
+            let stats = device.dirty_stats(display_id)?;
+            let screen = MaschineMK3::DISPLAY_WIDTH as f32 * MaschineMK3::DISPLAY_HEIGHT as f32;
+            Ok(Some(stats.pixels_sent as f32 / screen))
+        }
+        _ => {
+            device.send_display_rgb888(display_id, frame)?;
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::input::TouchStripState;
+use crate::output::MaschineLEDColor;
+
+/// Number of LEDs on the touch strip
+pub const STRIP_LED_COUNT: usize = 25;
+
+/// How touch strip positions are interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StripMode {
+    /// No translation; only raw data and gestures are reported
+    #[default]
+    Raw,
+    /// Bipolar -1.0..=1.0 around the strip center, snapping back to 0.0 on release
+    PitchBend,
+    /// Unipolar 0.0..=1.0, holding its value on release
+    ModWheel,
+    /// Bipolar -1.0 (left) ..= 1.0 (right), holding its value on release
+    Crossfader,
+}
+
+impl StripMode {
+    /// Whether the value returns to 0.0 when the finger is lifted
+    pub fn snaps_back(&self) -> bool {
+        matches!(self, StripMode::PitchBend)
+    }
+
+    pub fn is_bipolar(&self) -> bool {
+        matches!(self, StripMode::PitchBend | StripMode::Crossfader)
+    }
+}
+
+/// Translates touch strip input into a mode-specific value and matching LED
+/// feedback
+#[derive(Debug, Clone)]
+pub struct StripController {
+    mode: StripMode,
+    value: f32,
+    feedback_color: MaschineLEDColor,
+}
+
+impl Default for StripController {
+    fn default() -> Self {
+        Self::new(StripMode::Raw)
+    }
+}
+
+impl StripController {
+    pub fn new(mode: StripMode) -> Self {
+        Self {
+            mode,
+            value: 0.0,
+            feedback_color: MaschineLEDColor::white(true),
+        }
+    }
+
+    pub fn mode(&self) -> StripMode {
+        self.mode
+    }
+
+    /// Switch to a new mode, resetting the value to 0.0
+    pub fn set_mode(&mut self, mode: StripMode) {
+        self.mode = mode;
+        self.value = 0.0;
+    }
+
+    /// Current value in the mode's range
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Color used for lit LEDs in the feedback pattern
+    pub fn set_feedback_color(&mut self, color: MaschineLEDColor) {
+        self.feedback_color = color;
+    }
+
+    /// Update from the latest touch strip state. Returns the new value if it changed.
+    pub fn update(&mut self, strip: &TouchStripState) -> Option<f32> {
+        if self.mode == StripMode::Raw {
+            return None;
+        }
+
+        let value = match strip.finger_1.normalized_position() {
+            Some(position) if self.mode.is_bipolar() => position * 2.0 - 1.0,
+            Some(position) => position,
+            None if self.mode.snaps_back() => 0.0,
+            None => self.value,
+        };
+
+        if (value - self.value).abs() > f32::EPSILON {
+            self.value = value;
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// LED pattern reflecting the current value, or `None` in raw mode
+    pub fn led_feedback(&self) -> Option<[MaschineLEDColor; STRIP_LED_COUNT]> {
+        let mut leds = [MaschineLEDColor::black(); STRIP_LED_COUNT];
+        let last = STRIP_LED_COUNT - 1;
+        let center = last / 2;
+        let to_index =
+            |normalized: f32| (normalized.clamp(0.0, 1.0) * last as f32).round() as usize;
+
+        match self.mode {
+            StripMode::Raw => return None,
+            StripMode::PitchBend => {
+                // Fill from the center towards the finger
+                let index = to_index((self.value + 1.0) / 2.0);
+                for led in &mut leds[index.min(center)..=index.max(center)] {
+                    *led = self.feedback_color;
+                }
+            }
+            StripMode::ModWheel => {
+                let lit = (self.value.clamp(0.0, 1.0) * STRIP_LED_COUNT as f32).round() as usize;
+                for led in &mut leds[..lit] {
+                    *led = self.feedback_color;
+                }
+            }
+            StripMode::Crossfader => {
+                leds[center] = MaschineLEDColor::new(self.feedback_color.index, false);
+                leds[to_index((self.value + 1.0) / 2.0)] = self.feedback_color;
+            }
+        }
+
+        Some(leds)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::input::TouchStripState;
+use crate::output::MaschineLEDColor;
+use crate::strip::STRIP_LED_COUNT;
+use std::time::{Duration, Instant};
+
+/// Light the touch strip LEDs under the fingers, see
+/// [`MaschineMK3::set_touch_strip_follow`](crate::MaschineMK3::set_touch_strip_follow)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StripFollowConfig {
+    pub color: MaschineLEDColor,
+    /// LEDs lit on each side of a finger
+    pub spread: u8,
+    /// How long an LED glows after the finger has left it, bright for the
+    /// first half and dim for the rest; zero for no trail
+    pub trail: Duration,
+}
+
+impl Default for StripFollowConfig {
+    fn default() -> Self {
+        Self {
+            color: MaschineLEDColor::white(true),
+            spread: 1,
+            trail: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Finger positions for the follow mode, recorded by whichever thread
+/// reads input and drawn by the device
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StripFollower {
+    config: Option<StripFollowConfig>,
+    /// LEDs under a finger in the last packet
+    touched: [bool; STRIP_LED_COUNT],
+    /// When a finger left each LED, while its trail lasts
+    released: [Option<Instant>; STRIP_LED_COUNT],
+}
+
+impl StripFollower {
+    pub(crate) fn config(&self) -> Option<StripFollowConfig> {
+        self.config
+    }
+
+    /// Switch the follow mode on or off, forgetting earlier touches
+    pub(crate) fn set_config(&mut self, config: Option<StripFollowConfig>) {
+        *self = Self {
+            config,
+            ..Self::default()
+        };
+    }
+
+    /// Whether a trail is still fading, so the LEDs change without input
+    pub(crate) fn is_fading(&self) -> bool {
+        self.config.is_some() && self.released.iter().any(Option::is_some)
+    }
+
+    /// Record the fingers of the latest touch strip state
+    pub(crate) fn touch(&mut self, strip: &TouchStripState, now: Instant) {
+        let Some(config) = self.config else {
+            return;
+        };
+
+        let last = STRIP_LED_COUNT - 1;
+        let spread = config.spread as usize;
+        let mut touched = [false; STRIP_LED_COUNT];
+        for finger in [&strip.finger_1, &strip.finger_2] {
+            if let Some(position) = finger.normalized_position() {
+                let center = (position * last as f32).round() as usize;
+                let lit = center.saturating_sub(spread)..=(center + spread).min(last);
+                touched[lit].fill(true);
+            }
+        }
+
+        for ((was, is), released) in self.touched.iter().zip(&touched).zip(&mut self.released) {
+            if *is {
+                *released = None;
+            } else if *was {
+                *released = Some(now);
+            }
+        }
+        self.touched = touched;
+    }
+
+    /// Draw the fingers and their trails at `now`. Returns whether `leds`
+    /// changed.
+    pub(crate) fn update(
+        &mut self,
+        leds: &mut [MaschineLEDColor; STRIP_LED_COUNT],
+        now: Instant,
+    ) -> bool {
+        let Some(config) = self.config else {
+            return false;
+        };
+
+        let mut changed = false;
+        let trails = self.touched.iter().zip(&mut self.released);
+        for (led, (touched, released)) in leds.iter_mut().zip(trails) {
+            let color = match released {
+                _ if *touched => config.color,
+                Some(at) if now.duration_since(*at) < config.trail => {
+                    let fade = now.duration_since(*at).as_secs_f32() / config.trail.as_secs_f32();
+                    config.color.scaled(1.0 - fade)
+                }
+                _ => {
+                    *released = None;
+                    MaschineLEDColor::black()
+                }
+            };
+            if *led != color {
+                *led = color;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
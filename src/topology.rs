@@ -0,0 +1,144 @@
+use rusb::{Direction, TransferType};
+
+/// How a class of traffic reaches the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Direct USB transfers through libusb
+    Rusb,
+    /// The OS HID driver through hidapi (Windows only)
+    Hid,
+}
+
+/// One endpoint of a USB interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointInfo {
+    pub address: u8,
+    pub direction: Direction,
+    pub transfer_type: TransferType,
+}
+
+/// One USB interface (first alternate setting)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    pub number: u8,
+    pub class_code: u8,
+    pub sub_class_code: u8,
+    pub protocol_code: u8,
+    pub endpoints: Vec<EndpointInfo>,
+    /// Whether this connection claimed the interface
+    pub claimed: bool,
+}
+
+/// Whether frames can be sent to the displays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayAvailability {
+    /// Display data goes to the bulk endpoint of `interface`
+    Available { interface: u8 },
+    /// The display interface couldn't be claimed. `reason` is the underlying
+    /// error and `hint` suggests a fix to show the user.
+    Unavailable { reason: String, hint: String },
+}
+
+impl DisplayAvailability {
+    pub fn is_available(&self) -> bool {
+        matches!(self, DisplayAvailability::Available { .. })
+    }
+}
+
+/// Whether the displays can be used, and why not, from
+/// [`MaschineMK3::display_status`](crate::MaschineMK3::display_status)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayStatus {
+    /// Display data goes to the bulk endpoint of `interface`
+    Available { interface: u8 },
+    /// Claiming the display interface failed with `error`, e.g. because
+    /// another process holds it. `hint` suggests a fix to show the user.
+    InterfaceClaimFailed { error: String, hint: String },
+    /// A driver other than the one libusb needs is bound to the display
+    /// interface: Native Instruments' instead of WinUSB on Windows, or a
+    /// kernel driver that wasn't detached on Linux. `driver` names it when
+    /// known.
+    WrongDriver {
+        driver: Option<String>,
+        hint: String,
+    },
+    /// The display interface wasn't claimed, see
+    /// [`ConnectionOptions::claim_display`](crate::ConnectionOptions::claim_display)
+    NotAttempted,
+}
+
+impl DisplayStatus {
+    pub fn is_available(&self) -> bool {
+        matches!(self, DisplayStatus::Available { .. })
+    }
+
+    /// Suggested fix to show the user, if the displays are unavailable
+    pub fn hint(&self) -> Option<&str> {
+        match self {
+            DisplayStatus::Available { .. } => None,
+            DisplayStatus::InterfaceClaimFailed { hint, .. }
+            | DisplayStatus::WrongDriver { hint, .. } => Some(hint),
+            DisplayStatus::NotAttempted => {
+                Some("Call retry_display_claim to claim the display interface")
+            }
+        }
+    }
+
+    /// Why the displays are unavailable, if they are
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            DisplayStatus::Available { .. } => None,
+            DisplayStatus::InterfaceClaimFailed { error, .. } => Some(error.clone()),
+            DisplayStatus::WrongDriver {
+                driver: Some(driver),
+                ..
+            } => Some(format!(
+                "The display interface is bound to the {} driver",
+                driver
+            )),
+            DisplayStatus::WrongDriver { driver: None, .. } => {
+                Some("The display interface is bound to another driver".to_string())
+            }
+            DisplayStatus::NotAttempted => Some("The display interface wasn't claimed".to_string()),
+        }
+    }
+}
+
+impl From<&DisplayStatus> for DisplayAvailability {
+    fn from(status: &DisplayStatus) -> Self {
+        match status {
+            DisplayStatus::Available { interface } => DisplayAvailability::Available {
+                interface: *interface,
+            },
+            _ => DisplayAvailability::Unavailable {
+                reason: status.reason().unwrap_or_default(),
+                hint: status.hint().unwrap_or_default().to_string(),
+            },
+        }
+    }
+}
+
+/// USB layout of the connected controller and how this connection uses it,
+/// from [`MaschineMK3::topology`](crate::MaschineMK3::topology)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceTopology {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub bus_number: u8,
+    pub address: u8,
+    pub interfaces: Vec<InterfaceInfo>,
+    /// Backend used to read buttons, knobs and pads
+    pub input_backend: Backend,
+    /// Backend used to write LED packets
+    pub led_backend: Backend,
+    pub display: DisplayAvailability,
+}
+
+impl DeviceTopology {
+    /// Look up an interface by number
+    pub fn interface(&self, number: u8) -> Option<&InterfaceInfo> {
+        self.interfaces
+            .iter()
+            .find(|interface| interface.number == number)
+    }
+}